@@ -1,8 +1,12 @@
 use cli::{self, package};
 use colored::*;
 use error::DefaultResult;
-use holochain_container_api::{config::*, container::Container, logger::LogRules};
-use holochain_core_types::agent::AgentId;
+use holochain_container_api::{
+    config::*,
+    container::Container,
+    logger::{LogFormat, LogRules},
+};
+use holochain_core_types::{agent::AgentId, cas::content::AddressableContent};
 use std::{env, fs};
 
 const LOCAL_STORAGE_PATH: &str = ".hc";
@@ -25,12 +29,15 @@ pub fn run(package: bool, port: u16, persist: bool, networked: bool) -> DefaultR
         name: agent.nick,
         public_address: agent.key,
         key_file: "hc_run.key".into(),
+        keystore_passphrase: None,
     };
 
+    let dna_file = String::from(package::DEFAULT_BUNDLE_FILE_NAME);
+    let dna = Container::load_dna(&dna_file).map_err(|err| format_err!("{}", err))?;
     let dna_config = DnaConfiguration {
         id: DNA_CONFIG_ID.into(),
-        file: package::DEFAULT_BUNDLE_FILE_NAME.into(),
-        hash: "Qm328wyq38924ybogus".into(),
+        hash: dna.address().to_string(),
+        file: dna_file,
     };
 
     let storage = if persist {
@@ -38,6 +45,7 @@ pub fn run(package: bool, port: u16, persist: bool, networked: bool) -> DefaultR
 
         StorageConfiguration::File {
             path: LOCAL_STORAGE_PATH.into(),
+            shared: false,
         }
     } else {
         StorageConfiguration::Memory
@@ -48,6 +56,10 @@ pub fn run(package: bool, port: u16, persist: bool, networked: bool) -> DefaultR
         dna: DNA_CONFIG_ID.into(),
         agent: AGENT_CONFIG_ID.into(),
         storage,
+        network: None,
+        priority: 0,
+        validation_timeout_ms: None,
+        prioritized_calls: false,
     };
 
     let interface_config = InterfaceConfiguration {
@@ -57,6 +69,18 @@ pub fn run(package: bool, port: u16, persist: bool, networked: bool) -> DefaultR
         instances: vec![InstanceReferenceConfiguration {
             id: INSTANCE_CONFIG_ID.into(),
         }],
+        all_instances: false,
+        auth_token: None,
+        public_info: false,
+        max_requests_per_second: None,
+        validate_zome_function_inputs: false,
+        ping_interval_ms: None,
+        max_request_bytes: default_max_request_bytes(),
+        cors: None,
+        worker_threads: None,
+        publish_confirmation_timeout_ms: None,
+        read_only: false,
+        read_only_functions: Vec::new(),
     };
 
     // temporary log rules, should come from a configuration
@@ -64,6 +88,9 @@ pub fn run(package: bool, port: u16, persist: bool, networked: bool) -> DefaultR
     let logger_config = LoggerConfiguration {
         logger_type: "debug".to_string(),
         rules,
+        format: LogFormat::Text,
+        file: None,
+        max_size_bytes: None,
     };
 
     let n3h_path = env::var("HC_N3H_PATH").ok();
@@ -88,6 +115,10 @@ pub fn run(package: bool, port: u16, persist: bool, networked: bool) -> DefaultR
             n3h_persistence_path: n3h_persistence_path
                 .unwrap_or_else(|| default_n3h_persistence_path()),
             n3h_ipc_uri: Default::default(),
+            reuse_network: false,
+            spawn_retries: default_spawn_retries(),
+            spawn_retry_delay_ms: default_spawn_retry_delay_ms(),
+            n3h_ipc_socket: default_n3h_ipc_socket(),
         })
     } else {
         None
@@ -109,7 +140,9 @@ pub fn run(package: bool, port: u16, persist: bool, networked: bool) -> DefaultR
         .load_config()
         .map_err(|err| format_err!("{}", err))?;
 
-    container.start_all_interfaces();
+    container
+        .start_all_interfaces()
+        .map_err(|err| format_err!("{}", err))?;
     container.start_all_instances()?;
 
     println!(
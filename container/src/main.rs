@@ -14,23 +14,33 @@
 /// If called without arguments, this executable tries to load a configuration from
 /// ~/.holochain/container_config.toml.
 /// A custom config can be provided with the --config, -c flag.
+/// If that points to a directory, all TOML files in it get merged into one config.
 extern crate clap;
 extern crate holochain_container_api;
 extern crate holochain_core_types;
 extern crate structopt;
 
 use holochain_container_api::{
-    config::{load_configuration, Configuration},
+    config::{load_configuration_from_dir, load_container_configuration, Configuration},
     container::Container,
 };
 use holochain_core_types::error::HolochainError;
-use std::{fs::File, io::prelude::*, path::PathBuf};
+use std::{
+    fs::File,
+    io::prelude::*,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
 use structopt::StructOpt;
 
+const ADMIN_COMMAND_POLL_INTERVAL_MS: u64 = 100;
+
 #[derive(StructOpt, Debug)]
 #[structopt(name = "hcc")]
 struct Opt {
-    /// Path to the toml configuration file for the container
+    /// Path to the toml configuration file for the container, or to a directory
+    /// of toml files that get merged
     #[structopt(short = "c", long = "config", parse(from_os_str))]
     config: Option<PathBuf>,
 }
@@ -42,30 +52,53 @@ fn main() {
         .config
         .unwrap_or(PathBuf::from(r"~/.holochain/container_config.toml"));
     let config_path_str = config_path.to_str().unwrap();
-    println!("Using config path: {}", config_path_str);
     match bootstrap_from_config(config_path_str) {
         Ok(mut container) => {
+            let stdio = container.config().has_stdio_interface();
+            print_status(stdio, format!("Using config path: {}", config_path_str));
             if container.instances().len() > 0 {
-                println!(
-                    "Successfully loaded {} instance configurations",
-                    container.instances().len()
+                print_status(
+                    stdio,
+                    format!(
+                        "Successfully loaded {} instance configurations",
+                        container.instances().len()
+                    ),
                 );
-                println!("Starting all of them...");
+                print_status(stdio, "Starting all of them...".to_string());
                 container
                     .start_all_instances()
                     .expect("Could not start instances!");
-                println!("Starting interfaces...");
-                container.start_all_interfaces();
-                println!("Done.");
-                loop {}
+                print_status(stdio, "Starting interfaces...".to_string());
+                container
+                    .start_all_interfaces()
+                    .expect("Could not start interfaces!");
+                print_status(stdio, "Done.".to_string());
+                loop {
+                    container.process_admin_commands();
+                    container.recover_poisoned_instances();
+                    thread::sleep(Duration::from_millis(ADMIN_COMMAND_POLL_INTERVAL_MS));
+                }
             } else {
-                println!("No instance started, bailing...");
+                print_status(stdio, "No instance started, bailing...".to_string());
             }
         }
-        Err(error) => println!("Error while trying to boot from config: {:?}", error),
+        // The config might have a stdio interface, so stdout is not safe to use
+        Err(error) => eprintln!(
+            "Using config path: {}\nError while trying to boot from config: {:?}",
+            config_path_str, error
+        ),
     };
 }
 
+/// Prints the message to stdout, unless a stdio interface uses stdout for its responses
+fn print_status(stdio: bool, message: String) {
+    if stdio {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
 #[cfg_attr(tarpaulin, skip)]
 fn bootstrap_from_config(path: &str) -> Result<Container, HolochainError> {
     let config = load_config_file(&String::from(path))?;
@@ -79,8 +112,18 @@ fn bootstrap_from_config(path: &str) -> Result<Container, HolochainError> {
 
 #[cfg_attr(tarpaulin, skip)]
 fn load_config_file(path: &String) -> Result<Configuration, HolochainError> {
-    let mut f = File::open(path)?;
-    let mut contents = String::new();
-    f.read_to_string(&mut contents)?;
-    Ok(load_configuration::<Configuration>(&contents)?)
+    let path = Path::new(path);
+    let (mut config, config_dir) = if path.is_dir() {
+        (load_configuration_from_dir(path)?, path)
+    } else {
+        let mut f = File::open(path)?;
+        let mut contents = String::new();
+        f.read_to_string(&mut contents)?;
+        let config = load_container_configuration(&contents)?;
+        (config, path.parent().unwrap_or(Path::new("")))
+    };
+    // Relative paths in the config, including base_dir itself, are relative to the config
+    let base_dir = config_dir.join(config.base_dir.clone().unwrap_or_default());
+    config.base_dir = Some(base_dir.to_string_lossy().to_string());
+    Ok(config)
 }
@@ -0,0 +1,42 @@
+extern crate chrono;
+
+use std::{path::Path, process::Command};
+
+/// Runs git with the given arguments and returns its trimmed output if it succeeded
+fn git(args: &[&str]) -> Option<String> {
+    Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|output| output.trim().to_string())
+}
+
+/// Provides the build info "info/version" reports as the environment variables
+/// HC_BUILD_GIT_COMMIT ("unknown" if git is not available) and HC_BUILD_TIMESTAMP (RFC 3339).
+fn main() {
+    let git_commit = git(&["rev-parse", "HEAD"]).unwrap_or_else(|| String::from("unknown"));
+    println!("cargo:rustc-env=HC_BUILD_GIT_COMMIT={}", git_commit);
+
+    // Checking out or committing moves HEAD or the branch it points to,
+    // which has to rebuild with the new commit hash.
+    if let Some(git_dir) = git(&["rev-parse", "--git-dir"]) {
+        let git_dir = Path::new(&git_dir);
+        println!("cargo:rerun-if-changed={}", git_dir.join("HEAD").display());
+        println!(
+            "cargo:rerun-if-changed={}",
+            git_dir.join("packed-refs").display()
+        );
+        if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            println!(
+                "cargo:rerun-if-changed={}",
+                git_dir.join(head_ref).display()
+            );
+        }
+    }
+    println!(
+        "cargo:rustc-env=HC_BUILD_TIMESTAMP={}",
+        chrono::Utc::now().to_rfc3339()
+    );
+}
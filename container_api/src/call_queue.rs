@@ -0,0 +1,100 @@
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    sync::{Condvar, Mutex},
+};
+
+/// Orders the zome calls waiting for an instance, see the `prioritized_calls` instance
+/// config. Calls wait for their turn before acquiring the instance's lock, calls with a
+/// higher priority first and calls of the same priority in the order they arrived.
+/// Calls that don't specify a priority have priority 0, so without priorities the
+/// queue is FIFO.
+#[derive(Default)]
+pub struct CallQueue {
+    state: Mutex<QueueState>,
+    turn_changed: Condvar,
+}
+
+#[derive(Default)]
+struct QueueState {
+    /// Whether a call currently has its turn
+    busy: bool,
+    waiting: BinaryHeap<(i32, Reverse<u64>)>,
+    next_ticket: u64,
+}
+
+/// The turn of a call, which ends once this gets dropped
+pub struct CallTurn<'a> {
+    queue: &'a CallQueue,
+}
+
+impl CallQueue {
+    pub fn new() -> Self {
+        CallQueue::default()
+    }
+
+    /// Blocks until all calls that got queued with a higher priority, or with the same
+    /// priority but earlier, have had their turn.
+    pub fn wait_for_turn(&self, priority: i32) -> CallTurn {
+        let mut state = self.state.lock().unwrap();
+        let key = (priority, Reverse(state.next_ticket));
+        state.next_ticket += 1;
+        state.waiting.push(key);
+        while state.busy || state.waiting.peek() != Some(&key) {
+            state = self.turn_changed.wait(state).unwrap();
+        }
+        state.waiting.pop();
+        state.busy = true;
+        CallTurn { queue: self }
+    }
+
+    /// Number of calls waiting for their turn
+    pub fn waiting_calls(&self) -> usize {
+        self.state.lock().unwrap().waiting.len()
+    }
+}
+
+impl<'a> Drop for CallTurn<'a> {
+    fn drop(&mut self) {
+        self.queue.state.lock().unwrap().busy = false;
+        self.queue.turn_changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::{
+        sync::{mpsc::channel, Arc},
+        thread,
+        time::Duration,
+    };
+
+    #[test]
+    fn calls_get_their_turn_by_priority_then_arrival() {
+        let queue = Arc::new(CallQueue::new());
+        let (order_tx, order_rx) = channel();
+        let first_turn = queue.wait_for_turn(0);
+
+        let mut handles = Vec::new();
+        for (name, priority) in vec![("bulk 1", 0), ("bulk 2", 0), ("interactive", 5)] {
+            let queue = queue.clone();
+            let order_tx = order_tx.clone();
+            handles.push(thread::spawn(move || {
+                let _turn = queue.wait_for_turn(priority);
+                order_tx.send(name).unwrap();
+            }));
+            // queue them one after the other
+            while queue.waiting_calls() < handles.len() {
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+        drop(first_turn);
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let order: Vec<&str> = order_rx.try_iter().collect();
+        assert_eq!(order, vec!["interactive", "bulk 1", "bulk 2"]);
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Counts the zome calls running on the instances of a container, by instance ID, and
+/// keeps track of the instances that refuse new calls. Unlike an instance's `active` flag,
+/// both work without taking the instance's lock, which a running call holds.
+/// This lets `Container::shutdown_graceful()` wait for calls to finish and stop an
+/// instance that stays busy for too long.
+#[derive(Default)]
+pub struct CallsInFlight {
+    instances: Mutex<HashMap<String, InstanceCalls>>,
+}
+
+#[derive(Default)]
+struct InstanceCalls {
+    running: usize,
+    refusing: bool,
+}
+
+/// A running call, which ends once this gets dropped
+pub struct CallInFlight {
+    calls: Arc<CallsInFlight>,
+    instance_id: String,
+}
+
+impl CallsInFlight {
+    pub fn new() -> Self {
+        CallsInFlight::default()
+    }
+
+    /// Counts a call to the given instance as running until the returned value gets
+    /// dropped, or returns None if the instance refuses calls
+    pub fn begin(calls: &Arc<CallsInFlight>, instance_id: &str) -> Option<CallInFlight> {
+        let mut instances = calls.instances.lock().unwrap();
+        let instance = instances.entry(instance_id.to_string()).or_default();
+        if instance.refusing {
+            return None;
+        }
+        instance.running += 1;
+        Some(CallInFlight {
+            calls: calls.clone(),
+            instance_id: instance_id.to_string(),
+        })
+    }
+
+    /// Number of calls currently running on the given instance
+    pub fn running(&self, instance_id: &str) -> usize {
+        self.instances
+            .lock()
+            .unwrap()
+            .get(instance_id)
+            .map(|instance| instance.running)
+            .unwrap_or(0)
+    }
+
+    /// Makes the given instance fail all calls that are not running yet,
+    /// as if it was stopped
+    pub fn refuse(&self, instance_id: &str) {
+        self.instances
+            .lock()
+            .unwrap()
+            .entry(instance_id.to_string())
+            .or_default()
+            .refusing = true;
+    }
+
+    /// Lets the given instance take calls again
+    pub fn accept(&self, instance_id: &str) {
+        if let Some(instance) = self.instances.lock().unwrap().get_mut(instance_id) {
+            instance.refusing = false;
+        }
+    }
+
+    /// Forgets the given instance once it is not running any calls anymore
+    pub fn remove_instance(&self, instance_id: &str) {
+        let mut instances = self.instances.lock().unwrap();
+        if instances
+            .get(instance_id)
+            .map(|instance| instance.running == 0)
+            .unwrap_or(false)
+        {
+            instances.remove(instance_id);
+        }
+    }
+}
+
+impl Drop for CallInFlight {
+    fn drop(&mut self) {
+        if let Some(instance) = self
+            .calls
+            .instances
+            .lock()
+            .unwrap()
+            .get_mut(&self.instance_id)
+        {
+            instance.running -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_running_calls_and_refuses_new_ones() {
+        let calls = Arc::new(CallsInFlight::new());
+        let first = CallsInFlight::begin(&calls, "instance-1").unwrap();
+        let second = CallsInFlight::begin(&calls, "instance-1").unwrap();
+        assert_eq!(calls.running("instance-1"), 2);
+        assert_eq!(calls.running("instance-2"), 0);
+
+        calls.refuse("instance-1");
+        assert!(CallsInFlight::begin(&calls, "instance-1").is_none());
+        assert!(CallsInFlight::begin(&calls, "instance-2").is_some());
+
+        drop(first);
+        assert_eq!(calls.running("instance-1"), 1);
+        calls.remove_instance("instance-1");
+        drop(second);
+        assert_eq!(calls.running("instance-1"), 0);
+
+        calls.accept("instance-1");
+        assert!(CallsInFlight::begin(&calls, "instance-1").is_some());
+    }
+}
@@ -1,4 +1,7 @@
-use crate::logger::LogRules;
+use crate::{
+    logger::{LogFormat, LogRules},
+    signal_broadcaster::SignalDelivery,
+};
 /// Container Configuration
 /// This module provides structs that represent the different aspects of how
 /// a container can be configured.
@@ -13,14 +16,22 @@ use crate::logger::LogRules;
 use boolinator::*;
 use directories;
 use holochain_core_types::{
-    agent::AgentId,
+    agent::{AgentId, KeyBuffer},
     dna::Dna,
     error::{HcResult, HolochainError},
     json::JsonString,
 };
-use petgraph::{algo::toposort, graph::DiGraph, prelude::NodeIndex};
+use petgraph::{graph::DiGraph, prelude::NodeIndex, visit::Dfs, Direction};
 use serde::Deserialize;
-use std::{collections::HashMap, convert::TryFrom, env, fs::File, io::prelude::*};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    convert::TryFrom,
+    env,
+    fs::{self, File},
+    io::prelude::*,
+    path::{Path, PathBuf},
+};
 use toml;
 
 /// Main container configuration struct
@@ -45,12 +56,113 @@ pub struct Configuration {
     /// List of bridges between instances. Optional.
     #[serde(default)]
     pub bridges: Vec<Bridge>,
+    /// List of bridges to instances of other containers, reached through their interfaces.
+    /// Optional.
+    #[serde(default)]
+    pub remote_bridges: Vec<RemoteBridge>,
     /// Configures how logging should behave
     #[serde(default)]
     pub logger: LoggerConfiguration,
     /// Configuration options for the network module n3h
     #[serde(default)]
     pub network: Option<NetworkConfig>,
+    /// If set, a mismatch between a DNA's configured hash and the hash of the loaded
+    /// DNA only produces a warning instead of failing instantiation.
+    /// Meant for development setups where the hash is left blank. Default is false.
+    #[serde(default)]
+    pub warn_on_dna_hash_mismatch: bool,
+    /// If set, zome functions returning a `ZomeFunctionError` get answered with a
+    /// JSON-RPC error object carrying its code, message and data on all interfaces,
+    /// instead of a successful response containing the serialized error. Default is false.
+    #[serde(default)]
+    pub structured_zome_errors: bool,
+    /// Time in milliseconds an instance has to be running before it is reported as ready,
+    /// to give it time to sync DHT data after joining the network. Default is 0.
+    #[serde(default)]
+    pub readiness_grace_ms: u64,
+    /// If set, the WASM of every zome gets compiled when loading a DNA, so that broken
+    /// WASM fails `Container::load_config()` instead of the first zome call. Default is false.
+    #[serde(default)]
+    pub validate_wasm_on_load: bool,
+    /// Configures how signals of instances get passed on
+    #[serde(default)]
+    pub signals: SignalConfiguration,
+    /// How many instances `Container::start_all_instances()` starts in parallel.
+    /// Bridge callees still get started before their callers.
+    /// Default is 1, i.e. one after another.
+    #[serde(default = "default_instance_start_concurrency")]
+    pub instance_start_concurrency: usize,
+    /// If set, every instance counts how its entry look-ups went (cache and local hits,
+    /// network look-ups, timeouts and latencies), reported by the "metrics/get_entry"
+    /// admin method.
+    /// Default is false, which leaves look-ups uninstrumented.
+    #[serde(default)]
+    pub metrics: bool,
+    /// If set, the container counts how the zome calls of its instances went (calls, errors
+    /// and latencies per function), reported by the "metrics/instances" admin method and
+    /// metrics interfaces.
+    /// Default is false, which leaves calls uninstrumented.
+    #[serde(default)]
+    pub zome_call_metrics: bool,
+    /// If set, every instance caches up to this many resolved entries, which default
+    /// (local first) entry look-ups get served from. Live entries only get served from
+    /// the cache for a few seconds since they may get updated elsewhere.
+    /// Default is None, i.e. no caching.
+    #[serde(default)]
+    pub entry_cache_size: Option<usize>,
+    /// What happens to an instance once a call panicked while holding its lock,
+    /// see `Container::recover_poisoned_instances()`. Default is `mark_failed`.
+    #[serde(default)]
+    pub panic_policy: PanicPolicy,
+    /// Directory that relative paths of this configuration get resolved against:
+    /// DNA files, key files, passphrase files, file storage paths, TLS certificates and keys,
+    /// domain socket files and the n3h paths of the network.
+    /// The container binary sets it to the directory of the config file if it is not set,
+    /// otherwise relative paths are relative to the working directory.
+    /// Configurations applied with `Container::reload_config()` keep the current one if they
+    /// don't set their own. Optional.
+    #[serde(default)]
+    pub base_dir: Option<String>,
+    /// Raw values of the strings that got environment variables interpolated when loading,
+    /// keyed by their interpolated value, so that `Container::config_toml()` can write
+    /// the `${VAR}` references instead of the secrets they may stand for.
+    #[serde(skip)]
+    pub interpolated_values: HashMap<String, String>,
+}
+
+pub fn default_instance_start_concurrency() -> usize {
+    1
+}
+
+/// How the container deals with an instance whose lock got poisoned by a panic.
+/// Any further call to such an instance would panic as well.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PanicPolicy {
+    /// The instance gets removed from the container and its interfaces and reported by
+    /// `Container::failed_instances()`, all other instances keep running.
+    MarkFailed,
+    /// The instance gets replaced by a new one created from its configuration.
+    /// With file storage, the new instance continues with the data of the failed one.
+    Restart,
+}
+
+impl Default for PanicPolicy {
+    fn default() -> PanicPolicy {
+        PanicPolicy::MarkFailed
+    }
+}
+
+/// Configuration of the container-wide signal fan-out.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, PartialEq)]
+pub struct SignalConfiguration {
+    /// Number of recent signals kept to be replayed to new subscribers. Default is 0.
+    #[serde(default)]
+    pub history_size: usize,
+    /// What happens to signals when the consumer of the container's signal channel falls
+    /// behind: "drop" (default), "unbounded" or "block", see `SignalDelivery`.
+    #[serde(default)]
+    pub delivery: SignalDelivery,
 }
 
 /// There might be different kinds of loggers in the future.
@@ -62,12 +174,50 @@ pub struct LoggerConfiguration {
     pub logger_type: String,
     #[serde(default)]
     pub rules: LogRules,
-    //    pub file: Option<String>,
+    /// Either "text" (default) or "json", see `LogFormat`
+    #[serde(default)]
+    pub format: LogFormat,
+    /// If set, log lines get appended to this file instead of printed to stdout
+    #[serde(default)]
+    pub file: Option<String>,
+    /// Size in bytes after which the log file gets moved to "<file>.1" and a new one is
+    /// started. Without it the log file grows without limit.
+    #[serde(default)]
+    pub max_size_bytes: Option<u64>,
+}
+/// Fails naming the first ID that is used by more than one of the given configs
+fn check_unique_ids<'a, I: Iterator<Item = &'a String>>(kind: &str, ids: I) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for id in ids {
+        if !seen.insert(id) {
+            return Err(format!("Duplicate {} configuration ID \"{}\"", kind, id));
+        }
+    }
+    Ok(())
 }
+
 impl Configuration {
     /// This function basically checks if self is a semantically valid configuration.
     /// This mainly means checking for consistency between config structs that reference others.
     pub fn check_consistency(&self) -> Result<(), String> {
+        check_unique_ids("agent", self.agents.iter().map(|agent| &agent.id))?;
+        check_unique_ids("DNA", self.dnas.iter().map(|dna| &dna.id))?;
+        check_unique_ids(
+            "instance",
+            self.instances.iter().map(|instance| &instance.id),
+        )?;
+        check_unique_ids(
+            "interface",
+            self.interfaces.iter().map(|interface| &interface.id),
+        )?;
+        for ref agent in self.agents.iter() {
+            KeyBuffer::with_corrected(&agent.public_address).map_err(|error| {
+                format!(
+                    "Public address of agent \"{}\" is not a valid agent key: {}",
+                    agent.id, error
+                )
+            })?;
+        }
         for ref instance in self.instances.iter() {
             self.agent_by_id(&instance.agent).is_some().ok_or_else(|| {
                 format!(
@@ -82,7 +232,108 @@ impl Configuration {
                 )
             })?;
         }
+        let mut instances_by_storage_path: HashMap<PathBuf, &InstanceConfiguration> =
+            HashMap::new();
+        for instance in self.instances.iter() {
+            if let StorageConfiguration::File { ref path, shared } = instance.storage {
+                let path = self.resolve_path(&instance.resolve_storage_path(path));
+                if let Some(other) =
+                    instances_by_storage_path.insert(PathBuf::from(&path), instance)
+                {
+                    let other_shared = match other.storage {
+                        StorageConfiguration::File { shared, .. } => shared,
+                        StorageConfiguration::Memory => false,
+                    };
+                    if !shared || !other_shared {
+                        return Err(format!(
+                            "Instances \"{}\" and \"{}\" both store their data in \"{}\", set `shared = true` on both to keep them apart",
+                            other.id, instance.id, path
+                        ));
+                    }
+                }
+            }
+        }
+
+        if let Some(ref network) = self.network {
+            network.check_n3h_ipc_socket()?;
+            if network.reuse_network && network.n3h_ipc_uri.is_none() {
+                return Err(String::from(
+                    "Network config sets `reuse_network` but no `n3h_ipc_uri` to reuse",
+                ));
+            }
+        }
+
+        let stdio_interfaces: Vec<&String> = self
+            .interfaces
+            .iter()
+            .filter(|interface| interface.driver == InterfaceDriver::Stdio)
+            .map(|interface| &interface.id)
+            .collect();
+        if stdio_interfaces.len() > 1 {
+            return Err(format!(
+                "Interfaces \"{}\" and \"{}\" both use stdio, there can only be one",
+                stdio_interfaces[0], stdio_interfaces[1]
+            ));
+        }
+        if let Some(interface_id) = stdio_interfaces.first() {
+            let prompting_agent = self
+                .agents
+                .iter()
+                .find(|agent| agent.keystore_passphrase == Some(PassphraseSource::Prompt));
+            if let Some(agent) = prompting_agent {
+                return Err(format!(
+                    "Agent \"{}\" prompts for its passphrase, which can't be combined with the stdio interface \"{}\"",
+                    agent.id, interface_id
+                ));
+            }
+        }
+
+        let mut interfaces_by_port: HashMap<u16, &String> = HashMap::new();
         for ref interface in self.interfaces.iter() {
+            if let Some(port) = interface.driver.port() {
+                if let Some(other_id) = interfaces_by_port.insert(port, &interface.id) {
+                    return Err(format!(
+                        "Interfaces \"{}\" and \"{}\" both use port {}",
+                        other_id, interface.id, port
+                    ));
+                }
+            }
+            let driver = self.resolve_driver_paths(&interface.driver);
+            if let InterfaceDriver::WebsocketTls {
+                ref cert_path,
+                ref key_path,
+                ..
+            } = driver
+            {
+                for path in [cert_path, key_path].iter() {
+                    File::open(path).map_err(|error| {
+                        format!(
+                            "TLS file \"{}\" of interface \"{}\" is not readable: {}",
+                            path, interface.id, error
+                        )
+                    })?;
+                }
+            }
+            if let InterfaceDriver::Metrics { .. } = interface.driver {
+                self.zome_call_metrics.ok_or_else(|| {
+                    format!(
+                        "Metrics interface \"{}\" needs zome_call_metrics to be enabled",
+                        interface.id
+                    )
+                })?;
+            }
+            if let InterfaceDriver::DomainSocket { ref file } = driver {
+                let parent_exists = Path::new(file)
+                    .parent()
+                    .map(|parent| parent.as_os_str().is_empty() || parent.is_dir())
+                    .unwrap_or(false);
+                parent_exists.ok_or_else(|| {
+                    format!(
+                        "Parent directory of socket \"{}\" of interface \"{}\" does not exist",
+                        file, interface.id
+                    )
+                })?;
+            }
             for ref instance in interface.instances.iter() {
                 self.instance_by_id(&instance.id).is_some().ok_or_else(|| {
                     format!(
@@ -112,6 +363,56 @@ impl Configuration {
                 })?;
         }
 
+        for ref bridge in self.remote_bridges.iter() {
+            self.instance_by_id(&bridge.caller_id)
+                .is_some()
+                .ok_or_else(|| {
+                    format!(
+                        "Instance configuration \"{}\" not found, mentioned in remote bridge",
+                        bridge.caller_id
+                    )
+                })?;
+            bridge.callee_url.starts_with("http://").ok_or_else(|| {
+                format!(
+                    "Remote bridge callee URL \"{}\" is not an http:// URL",
+                    bridge.callee_url
+                )
+            })?;
+            let handle_taken = self
+                .bridge_dependencies(bridge.caller_id.clone())
+                .iter()
+                .any(|local| local.handle == bridge.handle)
+                || self
+                    .remote_bridge_dependencies(bridge.caller_id.clone())
+                    .iter()
+                    .filter(|remote| remote.handle == bridge.handle)
+                    .count()
+                    > 1;
+            (!handle_taken).ok_or_else(|| {
+                format!(
+                    "Instance \"{}\" has more than one bridge with handle \"{}\"",
+                    bridge.caller_id, bridge.handle
+                )
+            })?;
+        }
+
+        if let Some(cycle) = self.bridge_cycle() {
+            return Err(format!(
+                "Cyclic dependency in bridge configuration: {}",
+                cycle.join(" -> ")
+            ));
+        }
+
+        if let Some(ref file) = self.logger.file {
+            let parent_exists = Path::new(file)
+                .parent()
+                .map(|parent| parent.as_os_str().is_empty() || parent.is_dir())
+                .unwrap_or(false);
+            parent_exists.ok_or_else(|| {
+                format!("Parent directory of log file \"{}\" does not exist", file)
+            })?;
+        }
+
         let _ = self.instance_ids_sorted_by_bridge_dependencies()?;
 
         Ok(())
@@ -137,6 +438,55 @@ impl Configuration {
         self.interfaces.iter().find(|ic| &ic.id == id).cloned()
     }
 
+    /// Whether an interface talks over stdin and stdout, which then must not be logged to
+    pub fn has_stdio_interface(&self) -> bool {
+        self.interfaces
+            .iter()
+            .any(|interface| interface.driver == InterfaceDriver::Stdio)
+    }
+
+    /// Resolves the given path against `base_dir` if it is relative.
+    /// Absolute paths and all paths without a `base_dir` are returned unchanged.
+    pub fn resolve_path(&self, path: &str) -> String {
+        match self.base_dir {
+            Some(ref base_dir) if Path::new(path).is_relative() => {
+                Path::new(base_dir).join(path).to_string_lossy().to_string()
+            }
+            _ => path.to_string(),
+        }
+    }
+
+    /// The given interface driver with its TLS and socket file paths resolved,
+    /// see `resolve_path()`.
+    pub fn resolve_driver_paths(&self, driver: &InterfaceDriver) -> InterfaceDriver {
+        match driver {
+            InterfaceDriver::WebsocketTls {
+                port,
+                cert_path,
+                key_path,
+            } => InterfaceDriver::WebsocketTls {
+                port: *port,
+                cert_path: self.resolve_path(cert_path),
+                key_path: self.resolve_path(key_path),
+            },
+            InterfaceDriver::DomainSocket { file } => InterfaceDriver::DomainSocket {
+                file: self.resolve_path(file),
+            },
+            other => other.clone(),
+        }
+    }
+
+    /// The given passphrase source with the path of a passphrase file resolved,
+    /// see `resolve_path()`.
+    pub fn resolve_passphrase_source(&self, source: &PassphraseSource) -> PassphraseSource {
+        match source {
+            PassphraseSource::File { path } => PassphraseSource::File {
+                path: self.resolve_path(path),
+            },
+            other => other.clone(),
+        }
+    }
+
     /// Returns all defined instance IDs
     pub fn instance_ids(&self) -> Vec<String> {
         self.instances
@@ -147,10 +497,14 @@ impl Configuration {
 
     /// This function uses the petgraph crate to model the bridge connections in this config
     /// as a graph and then create a topological sorting of the nodes, which are instances.
-    /// The sorting gets reversed to get those instances first that do NOT depend on others
+    /// The sorting has those instances first that do NOT depend on others
     /// such that this ordering of instances can be used to spawn them and simultaneously create
     /// initialize the bridges and be able to assert that any callee already exists (which makes
     /// this task much easier).
+    /// Among instances that can be placed next, those with a higher `priority` come first,
+    /// and those configured first if their priorities are equal. Callees count with the
+    /// highest priority of their (transitive) callers, so that a high priority instance
+    /// does not wait for instances of lower priority to get placed before its callees.
     pub fn instance_ids_sorted_by_bridge_dependencies(
         &self,
     ) -> Result<Vec<String>, HolochainError> {
@@ -193,14 +547,54 @@ impl Configuration {
             graph.add_edge(node_a.clone(), node_b.clone(), "");
         }
 
-        // Sort with petgraph::algo::toposort
-        let mut sorted_nodes = toposort(&graph, None).map_err(|_cycle_error| {
-            HolochainError::ConfigError("Cyclic dependency in bridge configuration".to_string())
-        })?;
+        // Nodes got added in the order of self.instances,
+        // so the node index is the position in the config.
+        let mut priorities: Vec<i32> = self
+            .instances
+            .iter()
+            .map(|instance| instance.priority)
+            .collect();
+        for node in graph.node_indices() {
+            let mut dfs = Dfs::new(&graph, node);
+            while let Some(callee) = dfs.next(&graph) {
+                priorities[callee.index()] =
+                    priorities[callee.index()].max(self.instances[node.index()].priority);
+            }
+        }
 
-        // REVERSE order because we want to get the instance with NO dependencies first
-        // since that is the instance we should spawn first.
-        sorted_nodes.reverse();
+        // Topological sorting by Kahn's algorithm,
+        // placing an instance once all its callees (edge targets) are placed.
+        let sort_key = |node: NodeIndex<u32>| (priorities[node.index()], Reverse(node.index()));
+        let mut missing_callees: HashMap<_, _> = graph
+            .node_indices()
+            .map(|node| {
+                let callees = graph.neighbors_directed(node, Direction::Outgoing).count();
+                (node, callees)
+            })
+            .collect();
+        let mut placeable: BinaryHeap<_> = missing_callees
+            .iter()
+            .filter(|(_, callees)| **callees == 0)
+            .map(|(node, _)| sort_key(*node))
+            .collect();
+        let mut sorted_nodes = Vec::new();
+        while let Some((_, Reverse(index))) = placeable.pop() {
+            let node = NodeIndex::new(index);
+            sorted_nodes.push(node);
+            for caller in graph.neighbors_directed(node, Direction::Incoming) {
+                let callees = missing_callees.get_mut(&caller).unwrap();
+                *callees -= 1;
+                if *callees == 0 {
+                    placeable.push(sort_key(caller));
+                }
+            }
+        }
+        // Instances on a cycle never get all their callees placed
+        if sorted_nodes.len() < graph.node_count() {
+            return Err(HolochainError::ConfigError(
+                "Cyclic dependency in bridge configuration".to_string(),
+            ));
+        }
 
         // Map sorted vector of node indices back to instance ids
         Ok(sorted_nodes
@@ -210,6 +604,42 @@ impl Configuration {
             .collect())
     }
 
+    /// Searches the bridges for a cycle of instances that (transitively) call each other.
+    /// Returns the instance IDs along the first cycle found, starting and ending with
+    /// the same instance, e.g. `["app1", "app2", "app1"]`.
+    fn bridge_cycle(&self) -> Option<Vec<String>> {
+        fn visit(
+            config: &Configuration,
+            id: &String,
+            path: &mut Vec<String>,
+            finished: &mut HashSet<String>,
+        ) -> Option<Vec<String>> {
+            if let Some(position) = path.iter().position(|visited| visited == id) {
+                let mut cycle = path[position..].to_vec();
+                cycle.push(id.clone());
+                return Some(cycle);
+            }
+            if finished.contains(id) {
+                return None;
+            }
+            path.push(id.clone());
+            for bridge in config.bridges.iter().filter(|b| &b.caller_id == id) {
+                if let Some(cycle) = visit(config, &bridge.callee_id, path, finished) {
+                    return Some(cycle);
+                }
+            }
+            path.pop();
+            finished.insert(id.clone());
+            None
+        }
+
+        let mut finished = HashSet::new();
+        self.instances
+            .iter()
+            .filter_map(|instance| visit(self, &instance.id, &mut Vec::new(), &mut finished))
+            .next()
+    }
+
     pub fn bridge_dependencies(&self, caller_instance_id: String) -> Vec<Bridge> {
         self.bridges
             .iter()
@@ -217,15 +647,41 @@ impl Configuration {
             .cloned()
             .collect()
     }
+
+    pub fn remote_bridge_dependencies(&self, caller_instance_id: String) -> Vec<RemoteBridge> {
+        self.remote_bridges
+            .iter()
+            .filter(|bridge| bridge.caller_id == caller_instance_id)
+            .cloned()
+            .collect()
+    }
 }
 
 /// An agent has a name/ID and is defined by a private key that resides in a file
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
 pub struct AgentConfiguration {
     pub id: String,
     pub name: String,
     pub public_address: String,
     pub key_file: String,
+    /// If set, `key_file` is an encrypted keystore that gets decrypted with the passphrase
+    /// from this source when creating the agent's instances, so they can sign.
+    /// Creating the instances fails if the key file is missing or can't be decrypted.
+    #[serde(default)]
+    pub keystore_passphrase: Option<PassphraseSource>,
+}
+
+/// Where the container gets the passphrase of an agent's keystore from.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum PassphraseSource {
+    /// The value of an environment variable
+    Env { variable: String },
+    /// The first line of a file
+    File { path: String },
+    /// Asks for the passphrase on the terminal, without echo.
+    /// The container asks only once per agent and can't have a stdio interface then.
+    Prompt,
 }
 
 impl From<AgentConfiguration> for AgentId {
@@ -237,7 +693,7 @@ impl From<AgentConfiguration> for AgentId {
 
 /// A DNA is represented by a DNA file.
 /// A hash has to be provided for sanity check.
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
 pub struct DnaConfiguration {
     pub id: String,
     pub file: String,
@@ -256,12 +712,57 @@ impl TryFrom<DnaConfiguration> for Dna {
 
 /// An instance combines a DNA with an agent.
 /// Each instance has its own storage configuration.
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
 pub struct InstanceConfiguration {
     pub id: String,
     pub dna: String,
     pub agent: String,
     pub storage: StorageConfiguration,
+    /// Network this instance uses instead of the container's, see `NetworkConfig`.
+    /// Default is None, i.e. the container's network.
+    #[serde(default)]
+    pub network: Option<InstanceNetworkConfiguration>,
+    /// Instances with a higher priority get started before others, unless bridges require
+    /// a different order since callees always get started before their callers.
+    /// Default is 0.
+    #[serde(default)]
+    pub priority: i32,
+    /// If set, validating an entry fails once the DNA's validation callback runs longer than
+    /// this many milliseconds, which fails the commit instead of letting it hang.
+    /// The callback gets aborted at that point.
+    /// Default is no limit.
+    #[serde(default)]
+    pub validation_timeout_ms: Option<u64>,
+    /// Zome calls wait for their turn in a queue before calling into this instance,
+    /// ordered by the priority clients of interfaces can pass as `_call_priority` param.
+    /// Bridge calls, prepared calls and `Container::call_instance_function()` queue up
+    /// as well, with priority 0. Calls of the same priority keep their order.
+    /// Without `worker_threads`, an interface handles one call at a time anyway, so the
+    /// queue only orders calls coming from different interfaces, bridges or the embedder.
+    /// Default is false, i.e. calls race for the instance and `_call_priority` is passed
+    /// on to the zome function like any other param.
+    #[serde(default)]
+    pub prioritized_calls: bool,
+}
+
+impl InstanceConfiguration {
+    /// Replaces "{instance_id}" and "{agent_id}" in the given storage path with the IDs of
+    /// this instance and its agent configuration.
+    pub fn resolve_storage_path(&self, path: &str) -> String {
+        path.replace("{instance_id}", &self.id)
+            .replace("{agent_id}", &self.agent)
+    }
+}
+
+/// Mock networks instances can be put on instead of the container's network,
+/// mostly for testing.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InstanceNetworkConfiguration {
+    /// A mock network no other instance is on
+    UniqueMock,
+    /// A mock network shared by all instances configured with the same name
+    NamedMock { name: String },
 }
 
 /// This configures the Content Addressable Storage (CAS) that
@@ -271,11 +772,19 @@ pub struct InstanceConfiguration {
 /// * file
 ///
 /// Projected are various DB adapters.
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum StorageConfiguration {
     Memory,
-    File { path: String },
+    /// If `shared` is set, several instances can use the same `path`:
+    /// each instance then keeps its data in a subdirectory named after its ID.
+    /// "{instance_id}" and "{agent_id}" in `path` get replaced by the IDs of the instance and
+    /// its agent, e.g. `path = "/data/{instance_id}"` gives every instance its own directory.
+    File {
+        path: String,
+        #[serde(default)]
+        shared: bool,
+    },
 }
 
 /// Here, interfaces are user facing and make available zome functions to
@@ -286,28 +795,166 @@ pub enum StorageConfiguration {
 /// * Unix domain sockets
 /// very soon.
 ///
-/// Every interface lists the instances that are made available here.
-/// An admin flag will enable container functions for programmatically changing the configuration
-/// (i.e. installing apps)
-#[derive(Deserialize, Serialize, Clone)]
+/// Every interface lists the instances that are made available here,
+/// or sets `all_instances` to make all instances of the container available.
+/// The admin flag additionally exposes the privileged admin/* methods (i.e. inspecting and
+/// dumping instance state) on this interface. Non-admin interfaces only get zome function
+/// calls and the info and query methods.
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
 pub struct InterfaceConfiguration {
     pub id: String,
     pub driver: InterfaceDriver,
     #[serde(default)]
     pub admin: bool,
+    #[serde(default)]
     pub instances: Vec<InstanceReferenceConfiguration>,
+    /// Serve all instances of the container instead of only the listed ones,
+    /// including instances that get added at runtime. Default is false.
+    #[serde(default)]
+    pub all_instances: bool,
+    /// If set, clients have to present this token, either as `Authorization: Bearer <token>`
+    /// header or, for websocket interfaces, as `token` query parameter when connecting.
+    #[serde(default)]
+    pub auth_token: Option<String>,
+    /// Lets clients call "info/instances" without the auth token.
+    /// Only applies to HTTP interfaces since websocket clients get authorized per connection.
+    #[serde(default)]
+    pub public_info: bool,
+    /// If set, each client may make at most this many calls per second, further calls get
+    /// rejected with a "Too many requests" error. Websocket connections are limited one by
+    /// one, HTTP clients share the limit. Default is no limit.
+    #[serde(default)]
+    pub max_requests_per_second: Option<u32>,
+    /// Checks the params of zome calls against the inputs the DNA declares for the called
+    /// function and rejects mismatching calls with an "Invalid params" error.
+    #[serde(default)]
+    pub validate_zome_function_inputs: bool,
+    /// Websocket interfaces only: if set, every connection gets a ping frame in this
+    /// interval, which lets the interface drop connections to clients that are gone.
+    /// The websocket server handles pongs itself, so clients that stay connected but don't
+    /// answer pings are not noticed.
+    #[serde(default)]
+    pub ping_interval_ms: Option<u64>,
+    /// HTTP and websocket interfaces only: requests bigger than this get rejected before
+    /// they are parsed. HTTP clients get a 413 response, websocket connections sending a
+    /// bigger message get closed with status 1009.
+    #[serde(default = "default_max_request_bytes")]
+    pub max_request_bytes: usize,
+    /// HTTP interfaces only: lets browsers call the interface from pages of the allowed
+    /// origins and rejects requests from pages of any other origin.
+    /// Without it, requests from any origin get served, but without CORS headers.
+    #[serde(default)]
+    pub cors: Option<CorsConfiguration>,
+    /// If set, calls get handled on this many threads, so that a slow call does not hold up
+    /// the calls of other clients. Calls to the same instance still wait for each other.
+    /// Default is handling calls one after the other on the interface's server thread.
+    #[serde(default)]
+    pub worker_threads: Option<usize>,
+    /// If set, zome calls only return once every entry they published got held by one of the
+    /// container's instances of another agent, or once this many milliseconds passed.
+    /// Holds by instances of the calling instance's own agent don't count, and calls of
+    /// instances whose agent has no other instance in the container don't wait at all.
+    /// Calls return their result either way. Default is returning right after the call.
+    #[serde(default)]
+    pub publish_confirmation_timeout_ms: Option<u64>,
+    /// Only lets clients call the zome functions listed in `read_only_functions`, calls of
+    /// other zome functions get rejected with an error. Meant for public interfaces that
+    /// may query but never commit. Default is false.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Zome functions that don't commit anything, named "{zome}/{function}".
+    /// The only ones a `read_only` interface lets clients call.
+    #[serde(default)]
+    pub read_only_functions: Vec<String>,
+}
+
+impl InterfaceConfiguration {
+    /// Whether the instance with the given ID is available on this interface
+    pub fn serves_instance(&self, id: &str) -> bool {
+        self.all_instances || self.instances.iter().any(|instance| instance.id == id)
+    }
+}
+
+/// Big enough for any zome call, but keeps a single request from exhausting the memory
+pub fn default_max_request_bytes() -> usize {
+    10 * 1024 * 1024
+}
+
+/// Cross-origin resource sharing settings of an HTTP interface
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct CorsConfiguration {
+    /// Origins like "https://example.org" whose pages may call the interface.
+    /// Any origin is only allowed if "*" is listed.
+    pub allowed_origins: Vec<String>,
+    /// Methods announced to preflight requests, "POST" and "OPTIONS" by default
+    #[serde(default = "default_cors_allowed_methods")]
+    pub allowed_methods: Vec<String>,
+    /// Request headers pages may send, "Content-Type" and "Authorization" by default
+    #[serde(default = "default_cors_allowed_headers")]
+    pub allowed_headers: Vec<String>,
+}
+
+pub fn default_cors_allowed_methods() -> Vec<String> {
+    vec![String::from("POST"), String::from("OPTIONS")]
+}
+
+pub fn default_cors_allowed_headers() -> Vec<String> {
+    vec![String::from("Content-Type"), String::from("Authorization")]
+}
+
+impl CorsConfiguration {
+    /// The value of the Access-Control-Allow-Origin header for a request from the given
+    /// origin, None if that origin is not allowed
+    pub fn allow_origin(&self, origin: &str) -> Option<String> {
+        if self.allowed_origins.iter().any(|allowed| allowed == "*") {
+            Some(String::from("*"))
+        } else if self.allowed_origins.iter().any(|allowed| allowed == origin) {
+            Some(origin.to_string())
+        } else {
+            None
+        }
+    }
 }
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum InterfaceDriver {
     Websocket { port: u16 },
+    /// Websocket served over TLS (WSS) with the given PEM encoded certificate (chain) and key
+    WebsocketTls {
+        port: u16,
+        cert_path: String,
+        key_path: String,
+    },
     Http { port: u16 },
+    /// Unix domain socket at the given filesystem path
     DomainSocket { file: String },
+    /// Newline-delimited JSON-RPC over the container's stdin and stdout
+    Stdio,
+    /// Zome call metrics in the Prometheus text format at `GET /metrics`,
+    /// see `zome_call_metrics`. Only listens on `bind_address`, 127.0.0.1 by default.
+    Metrics {
+        port: u16,
+        #[serde(default)]
+        bind_address: Option<String>,
+    },
     Custom(toml::value::Value),
 }
 
-#[derive(Deserialize, Serialize, Clone)]
+impl InterfaceDriver {
+    /// The TCP port this driver binds, if any
+    pub fn port(&self) -> Option<u16> {
+        match self {
+            InterfaceDriver::Websocket { port }
+            | InterfaceDriver::WebsocketTls { port, .. }
+            | InterfaceDriver::Http { port }
+            | InterfaceDriver::Metrics { port, .. } => Some(*port),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
 pub struct InstanceReferenceConfiguration {
     pub id: String,
 }
@@ -331,6 +978,39 @@ pub struct Bridge {
     pub handle: String,
 }
 
+impl Bridge {
+    /// Whether the given instance is the caller or the callee of this bridge
+    pub fn involves(&self, instance_id: &str) -> bool {
+        self.caller_id == instance_id || self.callee_id == instance_id
+    }
+}
+
+/// A remote bridge lets an instance call zome functions of an instance running in another
+/// container, through an HTTP interface of that container.
+/// Calls get proxied as JSON-RPC requests, so the other container can be restarted
+/// independently. The callee has to be reachable when the caller gets instantiated
+/// since that is when the callee's zome functions get looked up.
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
+pub struct RemoteBridge {
+    /// ID of the local instance that calls the remote one.
+    pub caller_id: String,
+
+    /// URL of the HTTP interface of the other container, e.g. "http://127.0.0.1:4000".
+    pub callee_url: String,
+
+    /// ID of the instance in the other container, which has to be exposed
+    /// through the interface at `callee_url`.
+    pub callee_instance: String,
+
+    /// The caller's local handle of this bridge, like `Bridge::handle`.
+    pub handle: String,
+
+    /// Auth token of the remote interface, sent as `Authorization: Bearer <token>`.
+    /// Optional.
+    #[serde(default)]
+    pub auth_token: Option<String>,
+}
+
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
 pub struct NetworkConfig {
     /// List of URIs that point to other nodes to bootstrap p2p connections.
@@ -351,12 +1031,64 @@ pub struct NetworkConfig {
     /// configs above. Default is None.
     #[serde(default)]
     pub n3h_ipc_uri: Option<String>,
+    /// If set, `Container::spawn_network()` returns `n3h_ipc_uri` instead of spawning
+    /// a n3h child process, so that many containers can share one n3h process.
+    /// Requires `n3h_ipc_uri` to be set. Default is false.
+    #[serde(default)]
+    pub reuse_network: bool,
+    /// How often `Container::spawn_network()` retries spawning n3h if it fails.
+    /// Default is 3.
+    #[serde(default = "default_spawn_retries")]
+    pub spawn_retries: u32,
+    /// How long to wait before the first retry of spawning n3h.
+    /// The delay doubles with every further retry. Default is 500.
+    #[serde(default = "default_spawn_retry_delay_ms")]
+    pub spawn_retry_delay_ms: u64,
+    /// Socket n3h binds its IPC interface to, either "tcp://<host>:<port>" where port can be
+    /// "*" to pick a free one, or "ipc://<path>". Default is "tcp://127.0.0.1:*".
+    #[serde(default = "default_n3h_ipc_socket")]
+    pub n3h_ipc_socket: String,
+}
+
+impl NetworkConfig {
+    /// Checks that `n3h_ipc_socket` is a well-formed tcp:// or ipc:// URI
+    pub fn check_n3h_ipc_socket(&self) -> Result<(), String> {
+        let uri = &self.n3h_ipc_socket;
+        let well_formed = if uri.starts_with("tcp://") {
+            let mut parts = uri["tcp://".len()..].rsplitn(2, ':');
+            let port = parts.next().unwrap_or("");
+            let host = parts.next().unwrap_or("");
+            !host.is_empty() && (port == "*" || port.parse::<u16>().is_ok())
+        } else if uri.starts_with("ipc://") {
+            uri.len() > "ipc://".len()
+        } else {
+            false
+        };
+        well_formed.ok_or_else(|| {
+            format!(
+                "Invalid n3h IPC socket \"{}\", expected \"tcp://<host>:<port>\" or \"ipc://<path>\"",
+                uri
+            )
+        })
+    }
 }
 
 pub fn default_n3h_mode() -> String {
     String::from("HACK")
 }
 
+pub fn default_n3h_ipc_socket() -> String {
+    String::from("tcp://127.0.0.1:*")
+}
+
+pub fn default_spawn_retries() -> u32 {
+    3
+}
+
+pub fn default_spawn_retry_delay_ms() -> u64 {
+    500
+}
+
 pub fn default_n3h_path() -> String {
     if let Some(user_dirs) = directories::UserDirs::new() {
         user_dirs
@@ -375,6 +1107,114 @@ pub fn default_n3h_persistence_path() -> String {
     env::temp_dir().to_string_lossy().to_string()
 }
 
+/// Describes what changes between two configurations, as far as running instances and
+/// interfaces are concerned.
+///
+/// An instance counts as changed if its own configuration, its agent or DNA configuration
+/// or its (remote) bridges differ, or if it bridges to a changed or removed instance, since bridges
+/// get bound during instantiation.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ConfigDiff {
+    pub added_instances: Vec<String>,
+    pub removed_instances: Vec<String>,
+    pub changed_instances: Vec<String>,
+    pub added_interfaces: Vec<String>,
+    pub removed_interfaces: Vec<String>,
+    pub changed_interfaces: Vec<String>,
+    pub added_bridges: Vec<Bridge>,
+    pub removed_bridges: Vec<Bridge>,
+}
+
+impl ConfigDiff {
+    pub fn between(old: &Configuration, new: &Configuration) -> Self {
+        let mut diff = ConfigDiff::default();
+
+        for instance in new.instances.iter() {
+            match old.instance_by_id(&instance.id) {
+                None => diff.added_instances.push(instance.id.clone()),
+                Some(old_instance) => {
+                    if old_instance != *instance
+                        || old.agent_by_id(&instance.agent) != new.agent_by_id(&instance.agent)
+                        || old.dna_by_id(&instance.dna) != new.dna_by_id(&instance.dna)
+                        || old.bridge_dependencies(instance.id.clone())
+                            != new.bridge_dependencies(instance.id.clone())
+                        || old.remote_bridge_dependencies(instance.id.clone())
+                            != new.remote_bridge_dependencies(instance.id.clone())
+                    {
+                        diff.changed_instances.push(instance.id.clone());
+                    }
+                }
+            }
+        }
+        diff.removed_instances = old
+            .instances
+            .iter()
+            .filter(|instance| new.instance_by_id(&instance.id).is_none())
+            .map(|instance| instance.id.clone())
+            .collect();
+
+        // Callers hold on to their callee instances, so they have to be re-created as well.
+        loop {
+            let dependent_callers: Vec<String> = new
+                .bridges
+                .iter()
+                .filter(|bridge| {
+                    diff.changed_instances.contains(&bridge.callee_id)
+                        && !diff.changed_instances.contains(&bridge.caller_id)
+                        && !diff.added_instances.contains(&bridge.caller_id)
+                })
+                .map(|bridge| bridge.caller_id.clone())
+                .collect();
+            if dependent_callers.is_empty() {
+                break;
+            }
+            for caller in dependent_callers {
+                if !diff.changed_instances.contains(&caller) {
+                    diff.changed_instances.push(caller);
+                }
+            }
+        }
+
+        for interface in new.interfaces.iter() {
+            match old.interface_by_id(&interface.id) {
+                None => diff.added_interfaces.push(interface.id.clone()),
+                Some(ref old_interface) if old_interface != interface => {
+                    diff.changed_interfaces.push(interface.id.clone())
+                }
+                Some(_) => (),
+            }
+        }
+        diff.removed_interfaces = old
+            .interfaces
+            .iter()
+            .filter(|interface| new.interface_by_id(&interface.id).is_none())
+            .map(|interface| interface.id.clone())
+            .collect();
+
+        diff.added_bridges = new
+            .bridges
+            .iter()
+            .filter(|bridge| !old.bridges.contains(bridge))
+            .cloned()
+            .collect();
+        diff.removed_bridges = old
+            .bridges
+            .iter()
+            .filter(|bridge| !new.bridges.contains(bridge))
+            .cloned()
+            .collect();
+
+        diff
+    }
+
+    /// True if the instance with the given ID gets stopped or (re-)created by this diff
+    pub fn touches_instance(&self, id: &String) -> bool {
+        self.added_instances.contains(id)
+            || self.removed_instances.contains(id)
+            || self.changed_instances.contains(id)
+    }
+}
+
 /// Use this function to load a `Configuration` from a string.
 pub fn load_configuration<'a, T>(toml: &'a str) -> HcResult<T>
 where
@@ -385,29 +1225,207 @@ where
     })
 }
 
-#[cfg(test)]
-pub mod tests {
-    use super::*;
-    use crate::config::{load_configuration, Configuration, NetworkConfig};
-    use holochain_core::context::mock_network_config;
+/// Loads the configuration of a container from a string, like `load_configuration()`
+/// but replacing `${VAR}` tokens in string values with the value of the environment
+/// variable `VAR`, see `interpolate_env_vars()`.
+pub fn load_container_configuration(toml: &str) -> HcResult<Configuration> {
+    let value = toml::from_str::<toml::Value>(toml).map_err(|e| {
+        HolochainError::IoError(format!("Could not serialize toml: {}", e.to_string()))
+    })?;
+    container_configuration_from_value(value)
+}
 
-    pub fn example_serialized_network_config() -> String {
-        String::from(mock_network_config())
+/// Loads a container's `Configuration` that is split across several TOML sources, given
+/// as pairs of a name (used in error messages) and the TOML string.
+/// Top-level arrays like `[[agents]]`, `[[dnas]]`, `[[instances]]`, `[[interfaces]]` and
+/// `[[bridges]]` get concatenated in the order of the sources. Any other top-level key,
+/// like `network` or `logger`, may only be set in one of them.
+/// Environment variables get interpolated like in `load_container_configuration()`.
+pub fn load_merged_configuration(sources: &[(&str, &str)]) -> HcResult<Configuration> {
+    let mut merged = toml::value::Table::new();
+    // Name of the source that set each key, to point at both sources of a conflict
+    let mut origins: HashMap<String, &str> = HashMap::new();
+    for &(name, toml) in sources.iter() {
+        let value = toml::from_str::<toml::Value>(toml).map_err(|e| {
+            HolochainError::IoError(format!("Could not serialize toml of {}: {}", name, e))
+        })?;
+        let table = match value {
+            toml::Value::Table(table) => table,
+            _ => unreachable!("TOML documents are tables"),
+        };
+        for (key, value) in table {
+            if !merged.contains_key(&key) {
+                origins.insert(key.clone(), name);
+                merged.insert(key, value);
+                continue;
+            }
+            match (merged.get_mut(&key).unwrap(), value) {
+                (&mut toml::Value::Array(ref mut merged_values), toml::Value::Array(values)) => {
+                    merged_values.extend(values)
+                }
+                _ => {
+                    return Err(HolochainError::ConfigError(format!(
+                        "Could not merge configuration: \"{}\" is set in both {} and {}",
+                        key, origins[&key], name
+                    )));
+                }
+            }
+        }
     }
+    container_configuration_from_value(toml::Value::Table(merged))
+}
 
-    #[test]
-    fn test_agent_load() {
-        let toml = r#"
-    [[agents]]
-    id = "bob"
-    name = "Holo Tester 1"
-    public_address = "HoloTester1-------------------------------------------------------------------------AHi1"
-    key_file="file/to/serialize"
+/// Loads a container's `Configuration` from all `*.toml` files in the given directory,
+/// merged in the order of their file names, see `load_merged_configuration()`.
+pub fn load_configuration_from_dir(dir: &Path) -> HcResult<Configuration> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.retain(|path| {
+        path.is_file()
+            && path
+                .extension()
+                .map(|extension| extension == "toml")
+                .unwrap_or(false)
+    });
+    paths.sort();
+    let mut files = Vec::new();
+    for path in paths.iter() {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        files.push((path.to_string_lossy().to_string(), contents));
+    }
+    let sources: Vec<(&str, &str)> = files
+        .iter()
+        .map(|(name, toml)| (name.as_str(), toml.as_str()))
+        .collect();
+    load_merged_configuration(&sources)
+}
+
+/// The one place where container configurations get their environment variables
+/// interpolated before being deserialized
+fn container_configuration_from_value(mut value: toml::Value) -> HcResult<Configuration> {
+    let mut interpolated_values = HashMap::new();
+    interpolate_env_vars(&mut value, &mut interpolated_values)?;
+    let mut config = value
+        .try_into::<Configuration>()
+        .map_err(|e| HolochainError::IoError(format!("Could not serialize toml: {}", e)))?;
+    config.interpolated_values = interpolated_values;
+    Ok(config)
+}
+
+/// Replaces `${VAR}` tokens in all string values (also those nested in tables and arrays)
+/// with the value of the process environment variable `VAR`.
+/// `$${` stands for a literal `${`.
+/// Fails naming the variable if it is not set.
+/// Every string that changed gets its raw value recorded in `interpolated_values`.
+fn interpolate_env_vars(
+    value: &mut toml::Value,
+    interpolated_values: &mut HashMap<String, String>,
+) -> HcResult<()> {
+    match *value {
+        toml::Value::String(ref mut string) => {
+            let interpolated = interpolate_env_vars_in(string)?;
+            if interpolated != *string {
+                let raw = std::mem::replace(string, interpolated.clone());
+                interpolated_values.insert(interpolated, raw);
+            }
+        }
+        toml::Value::Array(ref mut values) => {
+            for value in values.iter_mut() {
+                interpolate_env_vars(value, interpolated_values)?;
+            }
+        }
+        toml::Value::Table(ref mut table) => {
+            for (_, value) in table.iter_mut() {
+                interpolate_env_vars(value, interpolated_values)?;
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Reverses `interpolate_env_vars()` for a serialized configuration: string values that
+/// are still the result of an interpolation get replaced with their raw value.
+pub fn restore_env_var_references(
+    value: &mut toml::Value,
+    interpolated_values: &HashMap<String, String>,
+) {
+    match *value {
+        toml::Value::String(ref mut string) => {
+            if let Some(raw) = interpolated_values.get(string) {
+                *string = raw.clone();
+            }
+        }
+        toml::Value::Array(ref mut values) => {
+            for value in values.iter_mut() {
+                restore_env_var_references(value, interpolated_values);
+            }
+        }
+        toml::Value::Table(ref mut table) => {
+            for (_, value) in table.iter_mut() {
+                restore_env_var_references(value, interpolated_values);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn interpolate_env_vars_in(string: &str) -> HcResult<String> {
+    let mut result = String::new();
+    let mut rest = string;
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            result.push_str(&rest[..start - 1]);
+            result.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        result.push_str(&rest[..start]);
+        let end = rest[start..].find('}').ok_or_else(|| {
+            HolochainError::ConfigError(format!(
+                "Unterminated environment variable reference in config value \"{}\"",
+                string
+            ))
+        })?;
+        let name = &rest[start + 2..start + end];
+        let var = env::var(name).map_err(|_| {
+            HolochainError::ConfigError(format!(
+                "Environment variable {} referenced in config is not set",
+                name
+            ))
+        })?;
+        result.push_str(&var);
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::config::{load_configuration, Configuration, NetworkConfig};
+    use holochain_core::context::mock_network_config;
+
+    pub fn example_serialized_network_config() -> String {
+        String::from(mock_network_config())
+    }
+
+    #[test]
+    fn test_agent_load() {
+        let toml = r#"
+    [[agents]]
+    id = "bob"
+    name = "Holo Tester 1"
+    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
+    key_file="file/to/serialize"
 
     [[agents]]
     id="alex"
     name = "Holo Tester 1"
-    public_address = "HoloTester1-------------------------------------------------------------------------AHi1"
+    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
     key_file="another/file"
 
     [[dnas]]
@@ -437,7 +1455,7 @@ pub mod tests {
     [[agents]]
     id="agent"
     name = "Holo Tester 1"
-    public_address = "HoloTester1-------------------------------------------------------------------------AHi1"
+    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
     key_file="whatever"
 
     [[dnas]]
@@ -458,7 +1476,7 @@ pub mod tests {
     [[agents]]
     id = "test agent"
     name = "Holo Tester 1"
-    public_address = "HoloTester1-------------------------------------------------------------------------AHi1"
+    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
     key_file = "holo_tester.key"
 
     [[dnas]]
@@ -484,9 +1502,13 @@ pub mod tests {
 
     [[interfaces]]
     id = "app spec http interface"
+    max_request_bytes = 1024
+    worker_threads = 4
     [interfaces.driver]
     type = "http"
     port = 4000
+    [interfaces.cors]
+    allowed_origins = ["https://app.example.org"]
     [[interfaces.instances]]
     id = "app spec instance"
 
@@ -507,6 +1529,23 @@ pub mod tests {
         let config = load_configuration::<Configuration>(toml).unwrap();
 
         assert_eq!(config.check_consistency(), Ok(()));
+        assert_eq!(
+            config.interfaces[0].max_request_bytes,
+            default_max_request_bytes()
+        );
+        assert_eq!(config.interfaces[1].max_request_bytes, 1024);
+        assert_eq!(config.interfaces[0].cors, None);
+        assert_eq!(config.interfaces[0].worker_threads, None);
+        assert!(!config.interfaces[0].all_instances);
+        assert_eq!(config.interfaces[1].worker_threads, Some(4));
+        assert_eq!(
+            config.interfaces[1].cors,
+            Some(CorsConfiguration {
+                allowed_origins: vec![String::from("https://app.example.org")],
+                allowed_methods: default_cors_allowed_methods(),
+                allowed_headers: default_cors_allowed_headers(),
+            })
+        );
         let dnas = config.dnas;
         let dna_config = dnas.get(0).expect("expected at least 1 DNA");
         assert_eq!(dna_config.id, "app spec rust");
@@ -529,6 +1568,10 @@ pub mod tests {
                 n3h_mode: String::from("HACK"),
                 n3h_persistence_path: String::from("/Users/cnorris/.holochain/n3h_persistence"),
                 n3h_ipc_uri: None,
+                reuse_network: false,
+                spawn_retries: 3,
+                spawn_retry_delay_ms: 500,
+                n3h_ipc_socket: String::from("tcp://127.0.0.1:*"),
             }
         );
     }
@@ -539,7 +1582,7 @@ pub mod tests {
     [[agents]]
     id = "test agent"
     name = "Holo Tester 1"
-    public_address = "HoloTester1-------------------------------------------------------------------------AHi1"
+    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
     key_file = "holo_tester.key"
 
     [[dnas]]
@@ -603,17 +1646,62 @@ pub mod tests {
         assert_eq!(instance_config.agent, "test agent");
         assert_eq!(config.logger.logger_type, "debug");
         assert_eq!(config.logger.rules.rules.len(), 1);
+        assert_eq!(config.logger.format, LogFormat::Text);
 
         assert_eq!(config.network, None);
     }
 
+    #[test]
+    fn test_check_n3h_ipc_socket() {
+        let toml = r#"
+    n3h_ipc_socket = "tcp://127.0.0.1:*"
+    "#;
+        let mut network = load_configuration::<NetworkConfig>(toml).unwrap();
+        assert_eq!(network.check_n3h_ipc_socket(), Ok(()));
+
+        for valid in ["tcp://0.0.0.0:5555", "ipc:///tmp/n3h.sock"].iter() {
+            network.n3h_ipc_socket = valid.to_string();
+            assert_eq!(network.check_n3h_ipc_socket(), Ok(()));
+        }
+        for invalid in [
+            "127.0.0.1:5555",
+            "tcp://127.0.0.1",
+            "tcp://:5555",
+            "tcp://127.0.0.1:99999",
+            "ipc://",
+        ]
+        .iter()
+        {
+            network.n3h_ipc_socket = invalid.to_string();
+            assert!(network.check_n3h_ipc_socket().is_err());
+        }
+    }
+
+    #[test]
+    fn test_inconsistent_config_reuse_network_without_uri() {
+        let toml = r#"
+    [network]
+    reuse_network = true
+    "#;
+        let mut config = load_configuration::<Configuration>(toml).unwrap();
+        assert_eq!(
+            config.check_consistency(),
+            Err(String::from(
+                "Network config sets `reuse_network` but no `n3h_ipc_uri` to reuse"
+            ))
+        );
+
+        config.network.as_mut().unwrap().n3h_ipc_uri = Some(String::from("tcp://127.0.0.1:12345"));
+        assert_eq!(config.check_consistency(), Ok(()));
+    }
+
     #[test]
     fn test_inconsistent_config() {
         let toml = r#"
     [[agents]]
     id = "test agent"
     name = "Holo Tester 1"
-    public_address = "HoloTester1-------------------------------------------------------------------------AHi1"
+    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
     key_file = "holo_tester.key"
 
     [[dnas]]
@@ -638,13 +1726,82 @@ pub mod tests {
     }
 
     #[test]
-    fn test_inconsistent_config_interface_1() {
+    fn test_inconsistent_config_agent_public_address() {
         let toml = r#"
     [[agents]]
     id = "test agent"
     name = "Holo Tester 1"
     public_address = "HoloTester1-------------------------------------------------------------------------AHi1"
     key_file = "holo_tester.key"
+    "#;
+
+        let config = load_configuration::<Configuration>(toml).unwrap();
+        let result = config.check_consistency();
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .starts_with("Public address of agent \"test agent\" is not a valid agent key"));
+    }
+
+    #[test]
+    fn test_inconsistent_config_duplicate_ids() {
+        let agent = r#"
+    [[agents]]
+    id = "test agent"
+    name = "Holo Tester 1"
+    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
+    key_file = "holo_tester.key"
+    "#;
+        let dna = r#"
+    [[dnas]]
+    id = "app spec rust"
+    file = "app_spec.hcpkg"
+    hash = "Qm328wyq38924y"
+    "#;
+        let instance = r#"
+    [[instances]]
+    id = "app spec instance"
+    dna = "app spec rust"
+    agent = "test agent"
+    [instances.storage]
+    type = "memory"
+    "#;
+        let interface = r#"
+    [[interfaces]]
+    id = "app spec interface"
+    instances = []
+    [interfaces.driver]
+    type = "websocket"
+    port = 8888
+    "#;
+        let sections = [agent, dna, instance, interface];
+
+        let config = load_configuration::<Configuration>(&sections.concat()).unwrap();
+        assert_eq!(config.check_consistency(), Ok(()));
+
+        for (index, expected) in [
+            "Duplicate agent configuration ID \"test agent\"",
+            "Duplicate DNA configuration ID \"app spec rust\"",
+            "Duplicate instance configuration ID \"app spec instance\"",
+            "Duplicate interface configuration ID \"app spec interface\"",
+        ]
+        .iter()
+        .enumerate()
+        {
+            let toml = format!("{}{}", sections.concat(), sections[index]);
+            let config = load_configuration::<Configuration>(&toml).unwrap();
+            assert_eq!(config.check_consistency(), Err(expected.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_inconsistent_config_interface_1() {
+        let toml = r#"
+    [[agents]]
+    id = "test agent"
+    name = "Holo Tester 1"
+    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
+    key_file = "holo_tester.key"
 
     [[dnas]]
     id = "app spec rust"
@@ -679,6 +1836,216 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_inconsistent_config_interface_port_conflict() {
+        let toml = r#"
+    [[agents]]
+    id = "test agent"
+    name = "Holo Tester 1"
+    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
+    key_file = "holo_tester.key"
+
+    [[dnas]]
+    id = "app spec rust"
+    file = "app_spec.hcpkg"
+    hash = "Qm328wyq38924y"
+
+    [[interfaces]]
+    id = "websocket interface"
+    instances = []
+    [interfaces.driver]
+    type = "websocket"
+    port = 8888
+
+    [[interfaces]]
+    id = "http interface"
+    instances = []
+    [interfaces.driver]
+    type = "http"
+    port = 8888
+    "#;
+
+        let mut config = load_configuration::<Configuration>(toml).unwrap();
+        assert_eq!(
+            config.check_consistency(),
+            Err(
+                "Interfaces \"websocket interface\" and \"http interface\" both use port 8888"
+                    .to_string()
+            )
+        );
+
+        config.interfaces[1].driver = InterfaceDriver::Http { port: 8889 };
+        assert_eq!(config.check_consistency(), Ok(()));
+    }
+
+    #[test]
+    fn test_inconsistent_config_interface_tls_files() {
+        let toml = r#"
+    [[agents]]
+    id = "test agent"
+    name = "Holo Tester 1"
+    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
+    key_file = "holo_tester.key"
+
+    [[dnas]]
+    id = "app spec rust"
+    file = "app_spec.hcpkg"
+    hash = "Qm328wyq38924y"
+
+    [[instances]]
+    id = "app spec instance"
+    dna = "app spec rust"
+    agent = "test agent"
+    [instances.storage]
+    type = "memory"
+
+    [[interfaces]]
+    id = "app spec interface"
+    [interfaces.driver]
+    type = "websockettls"
+    port = 8443
+    cert_path = "non-existent-cert.pem"
+    key_path = "non-existent-key.pem"
+    [[interfaces.instances]]
+    id = "app spec instance"
+    "#;
+
+        let config = load_configuration::<Configuration>(toml).unwrap();
+
+        let result = config.check_consistency();
+        assert!(result.is_err());
+        assert!(result
+            .err()
+            .unwrap()
+            .starts_with("TLS file \"non-existent-cert.pem\" of interface \"app spec interface\" is not readable"));
+    }
+
+    #[test]
+    fn test_inconsistent_config_interface_domain_socket() {
+        let toml = r#"
+    [[agents]]
+    id = "test agent"
+    name = "Holo Tester 1"
+    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
+    key_file = "holo_tester.key"
+
+    [[dnas]]
+    id = "app spec rust"
+    file = "app_spec.hcpkg"
+    hash = "Qm328wyq38924y"
+
+    [[instances]]
+    id = "app spec instance"
+    dna = "app spec rust"
+    agent = "test agent"
+    [instances.storage]
+    type = "memory"
+
+    [[interfaces]]
+    id = "app spec interface"
+    [interfaces.driver]
+    type = "domainsocket"
+    file = "/non-existent-dir/holochain.sock"
+    [[interfaces.instances]]
+    id = "app spec instance"
+    "#;
+
+        let mut config = load_configuration::<Configuration>(toml).unwrap();
+
+        assert_eq!(
+            config.check_consistency(),
+            Err("Parent directory of socket \"/non-existent-dir/holochain.sock\" of interface \"app spec interface\" does not exist".to_string())
+        );
+
+        config.interfaces[0].driver = InterfaceDriver::DomainSocket {
+            file: String::from("/tmp/holochain.sock"),
+        };
+        assert_eq!(config.check_consistency(), Ok(()));
+
+        config.interfaces[0].driver = InterfaceDriver::DomainSocket {
+            file: String::from("tmp/holochain.sock"),
+        };
+        config.base_dir = Some(String::from("/"));
+        assert_eq!(config.check_consistency(), Ok(()));
+        assert!(
+            config.resolve_driver_paths(&config.interfaces[0].driver)
+                == InterfaceDriver::DomainSocket {
+                    file: String::from("/tmp/holochain.sock"),
+                }
+        );
+    }
+
+    #[test]
+    fn test_inconsistent_config_stdio_interfaces() {
+        let toml = r#"
+    [[agents]]
+    id = "test agent"
+    name = "Holo Tester 1"
+    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
+    key_file = "holo_tester.key"
+
+    [[dnas]]
+    id = "app spec rust"
+    file = "app_spec.hcpkg"
+    hash = "Qm328wyq38924y"
+
+    [[interfaces]]
+    id = "stdio interface"
+    [interfaces.driver]
+    type = "stdio"
+
+    [[interfaces]]
+    id = "other stdio interface"
+    [interfaces.driver]
+    type = "stdio"
+    "#;
+
+        let mut config = load_configuration::<Configuration>(toml).unwrap();
+        assert_eq!(
+            config.check_consistency(),
+            Err("Interfaces \"stdio interface\" and \"other stdio interface\" both use stdio, there can only be one".to_string())
+        );
+
+        config.interfaces[1].driver = InterfaceDriver::Http { port: 8889 };
+        assert_eq!(config.check_consistency(), Ok(()));
+
+        config.interfaces[1].driver = InterfaceDriver::Metrics {
+            port: 8889,
+            bind_address: None,
+        };
+        assert_eq!(
+            config.check_consistency(),
+            Err(
+                "Metrics interface \"other stdio interface\" needs zome_call_metrics to be enabled"
+                    .to_string()
+            )
+        );
+        config.zome_call_metrics = true;
+        assert_eq!(config.check_consistency(), Ok(()));
+
+        config.agents[0].keystore_passphrase = Some(PassphraseSource::Prompt);
+        assert_eq!(
+            config.check_consistency(),
+            Err("Agent \"test agent\" prompts for its passphrase, which can't be combined with the stdio interface \"stdio interface\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_inconsistent_config_log_file() {
+        let mut config = Configuration::default();
+        config.logger.file = Some(String::from("/nonexistent/holochain/container.log"));
+        assert_eq!(
+            config.check_consistency(),
+            Err(
+                "Parent directory of log file \"/nonexistent/holochain/container.log\" does not exist"
+                    .to_string()
+            )
+        );
+
+        config.logger.file = Some(String::from("container.log"));
+        assert_eq!(config.check_consistency(), Ok(()));
+    }
+
     #[test]
     fn test_invalid_toml_1() {
         let toml = &format!(
@@ -686,7 +2053,7 @@ pub mod tests {
     [[agents]]
     id = "test agent"
     name = "Holo Tester 1"
-    public_address = "HoloTester1-------------------------------------------------------------------------AHi1"
+    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
     key_file = "holo_tester.key"
 
     [[dnas]]
@@ -723,13 +2090,122 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_load_configuration_env_vars() {
+        env::set_var("HC_TEST_CONFIG_KEY_DIR", "/etc/holochain/keys");
+        env::set_var("HC_TEST_CONFIG_DNA", "app_spec");
+        let toml = r#"
+    [[agents]]
+    id = "test agent"
+    name = "Holo Tester"
+    public_address = "HoloTester-------------------------------------------------------------------------AHi1"
+    key_file = "${HC_TEST_CONFIG_KEY_DIR}/holo_tester.key"
+
+    [[dnas]]
+    id = "app spec rust"
+    file = "${HC_TEST_CONFIG_DNA}/${HC_TEST_CONFIG_DNA}.hcpkg"
+    hash = "$${HC_TEST_CONFIG_DNA}"
+    "#;
+        let config = load_container_configuration(toml).unwrap();
+        assert_eq!(
+            config.agents[0].key_file,
+            "/etc/holochain/keys/holo_tester.key"
+        );
+        assert_eq!(config.dnas[0].file, "app_spec/app_spec.hcpkg");
+        assert_eq!(config.dnas[0].hash, "${HC_TEST_CONFIG_DNA}");
+
+        // Other configurations are left alone
+        let config = load_configuration::<Configuration>(toml).unwrap();
+        assert_eq!(
+            config.agents[0].key_file,
+            "${HC_TEST_CONFIG_KEY_DIR}/holo_tester.key"
+        );
+
+        let toml = toml.replace("HC_TEST_CONFIG_DNA", "HC_TEST_CONFIG_UNSET");
+        assert_eq!(
+            load_container_configuration(&toml).map(|_| ()),
+            Err(HolochainError::ConfigError(String::from(
+                "Environment variable HC_TEST_CONFIG_UNSET referenced in config is not set"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_load_merged_configuration() {
+        let agents = r#"
+    [[agents]]
+    id = "test agent"
+    name = "Holo Tester"
+    public_address = "HoloTester-------------------------------------------------------------------------AHi1"
+    key_file = "holo_tester.key"
+
+    [logger]
+    type = "debug"
+    "#;
+        let dnas = r#"
+    [[dnas]]
+    id = "app spec rust"
+    file = "app_spec.hcpkg"
+    hash = "Qm328wyq38924y"
+
+    [[instances]]
+    id = "app spec instance"
+    dna = "app spec rust"
+    agent = "test agent"
+    [instances.storage]
+    type = "memory"
+    "#;
+        let more_instances = r#"
+    [[instances]]
+    id = "app spec instance 2"
+    dna = "app spec rust"
+    agent = "test agent"
+    [instances.storage]
+    type = "memory"
+    "#;
+        let config = load_merged_configuration(&[
+            ("agents.toml", agents),
+            ("dnas.toml", dnas),
+            ("instances.toml", more_instances),
+        ])
+        .unwrap();
+        assert_eq!(config.agents.len(), 1);
+        assert_eq!(config.dnas.len(), 1);
+        assert_eq!(
+            config
+                .instances
+                .iter()
+                .map(|instance| instance.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["app spec instance", "app spec instance 2"]
+        );
+        assert_eq!(config.logger.logger_type, "debug");
+        assert_eq!(config.check_consistency(), Ok(()));
+
+        let other_logger = r#"
+    [logger]
+    type = "simple"
+    "#;
+        assert_eq!(
+            load_merged_configuration(&[
+                ("agents.toml", agents),
+                ("dnas.toml", dnas),
+                ("logger.toml", other_logger),
+            ])
+            .map(|_| ()),
+            Err(HolochainError::ConfigError(String::from(
+                "Could not merge configuration: \"logger\" is set in both agents.toml and logger.toml"
+            )))
+        );
+    }
+
     fn bridges_config(bridges: &str) -> String {
         format!(
             r#"
     [[agents]]
     id = "test agent"
     name = "Holo Tester 1"
-    public_address = "HoloTester1-------------------------------------------------------------------------AHi1"
+    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
     key_file = "holo_tester.key"
 
     [[dnas]]
@@ -743,7 +2219,7 @@ pub mod tests {
     agent = "test agent"
     [instances.storage]
     type = "file"
-    path = "app_spec_storage"
+    path = "app_spec_storage/app1"
 
     [[instances]]
     id = "app2"
@@ -751,7 +2227,7 @@ pub mod tests {
     agent = "test agent"
     [instances.storage]
     type = "file"
-    path = "app_spec_storage"
+    path = "app_spec_storage/app2"
 
     [[instances]]
     id = "app3"
@@ -759,7 +2235,7 @@ pub mod tests {
     agent = "test agent"
     [instances.storage]
     type = "file"
-    path = "app_spec_storage"
+    path = "app_spec_storage/app3"
 
     {}
     "#, bridges)
@@ -799,6 +2275,45 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_bridge_config_with_priorities() {
+        let toml = bridges_config(
+            r#"
+    [[bridges]]
+    caller_id = "app1"
+    callee_id = "app2"
+    handle = "happ-store"
+    "#,
+        );
+        let sorted = |toml: &str| {
+            load_configuration::<Configuration>(toml)
+                .expect("Config should be syntactically correct")
+                .instance_ids_sorted_by_bridge_dependencies()
+                .unwrap()
+        };
+
+        // Without priorities, instances keep their configured order where bridges allow it
+        assert_eq!(sorted(&toml), vec!["app2", "app1", "app3"]);
+
+        let app3_first = toml.replace("id = \"app3\"", "id = \"app3\"\n    priority = 10");
+        assert_eq!(sorted(&app3_first), vec!["app3", "app2", "app1"]);
+
+        // app1 still has to wait for its callee app2
+        let app1_first = toml.replace("id = \"app1\"", "id = \"app1\"\n    priority = 10");
+        assert_eq!(sorted(&app1_first), vec!["app2", "app1", "app3"]);
+
+        // app2 inherits the priority of its caller app1, so both get ahead of app3
+        let app1_before_app3 = toml
+            .replace("id = \"app1\"", "id = \"app1\"\n    priority = 2")
+            .replace("id = \"app3\"", "id = \"app3\"\n    priority = 1");
+        assert_eq!(sorted(&app1_before_app3), vec!["app2", "app1", "app3"]);
+
+        let app3_before_app1 = toml
+            .replace("id = \"app1\"", "id = \"app1\"\n    priority = 1")
+            .replace("id = \"app3\"", "id = \"app3\"\n    priority = 2");
+        assert_eq!(sorted(&app3_before_app1), vec!["app3", "app2", "app1"]);
+    }
+
     #[test]
     fn test_bridge_cycle() {
         let toml = bridges_config(
@@ -823,7 +2338,73 @@ pub mod tests {
             .expect("Config should be syntactically correct");
         assert_eq!(
             config.check_consistency(),
-            Err("Cyclic dependency in bridge configuration".to_string())
+            Err(
+                "Cyclic dependency in bridge configuration: app1 -> app2 -> app3 -> app1"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_bridge_cycle_two_instances() {
+        let toml = bridges_config(
+            r#"
+    [[bridges]]
+    caller_id = "app2"
+    callee_id = "app3"
+    handle = "DPKI"
+
+    [[bridges]]
+    caller_id = "app3"
+    callee_id = "app2"
+    handle = "happ-store"
+    "#,
+        );
+        let config = load_configuration::<Configuration>(&toml)
+            .expect("Config should be syntactically correct");
+        assert_eq!(
+            config.check_consistency(),
+            Err("Cyclic dependency in bridge configuration: app2 -> app3 -> app2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_remote_bridge_config() {
+        let toml = bridges_config(
+            r#"
+    [[remote_bridges]]
+    caller_id = "app1"
+    callee_url = "http://127.0.0.1:4000"
+    callee_instance = "other-app"
+    handle = "other"
+    auth_token = "secret"
+    "#,
+        );
+        let config = load_configuration::<Configuration>(&toml)
+            .expect("Config should be syntactically correct");
+        assert_eq!(config.check_consistency(), Ok(()));
+        assert_eq!(
+            config.remote_bridge_dependencies(String::from("app1"))[0].auth_token,
+            Some(String::from("secret"))
+        );
+
+        let toml = bridges_config(
+            r#"
+    [[remote_bridges]]
+    caller_id = "app1"
+    callee_url = "ws://127.0.0.1:4000"
+    callee_instance = "other-app"
+    handle = "other"
+    "#,
+        );
+        let config = load_configuration::<Configuration>(&toml)
+            .expect("Config should be syntactically correct");
+        assert_eq!(
+            config.check_consistency(),
+            Err(
+                "Remote bridge callee URL \"ws://127.0.0.1:4000\" is not an http:// URL"
+                    .to_string()
+            )
         );
     }
 
@@ -888,6 +2469,46 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_config_diff() {
+        let old_config = load_configuration::<Configuration>(&bridges_config(
+            r#"
+    [[bridges]]
+    caller_id = "app1"
+    callee_id = "app2"
+    handle = "happ-store"
+    "#,
+        ))
+        .unwrap();
+        assert_eq!(
+            ConfigDiff::between(&old_config, &old_config),
+            ConfigDiff::default()
+        );
+
+        let mut new_config = old_config.clone();
+        new_config
+            .instances
+            .retain(|instance| instance.id != "app3");
+        new_config.instances[1].storage = StorageConfiguration::Memory;
+        new_config.bridges.push(Bridge {
+            caller_id: "app1".to_string(),
+            callee_id: "app2".to_string(),
+            handle: "dpki".to_string(),
+        });
+
+        let diff = ConfigDiff::between(&old_config, &new_config);
+        assert_eq!(diff.removed_instances, vec![String::from("app3")]);
+        // app1 changed because of the new bridge
+        assert_eq!(
+            diff.changed_instances,
+            vec![String::from("app1"), String::from("app2")]
+        );
+        assert!(diff.added_instances.is_empty());
+        assert_eq!(diff.added_bridges.len(), 1);
+        assert!(diff.removed_bridges.is_empty());
+        assert!(diff.touches_instance(&String::from("app3")));
+    }
+
     #[test]
     fn test_n3h_defaults() {
         assert_eq!(default_n3h_mode(), String::from("HACK"));
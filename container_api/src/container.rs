@@ -1,17 +1,40 @@
 use crate::{
-    config::{Configuration, InterfaceConfiguration, InterfaceDriver, StorageConfiguration},
+    call_queue::CallQueue,
+    calls_in_flight::CallsInFlight,
+    config::{
+        restore_env_var_references, AgentConfiguration, Bridge, ConfigDiff, Configuration,
+        InstanceConfiguration, InstanceNetworkConfiguration, InterfaceConfiguration,
+        InterfaceDriver, PanicPolicy, PassphraseSource, StorageConfiguration,
+    },
     context_builder::ContextBuilder,
-    error::HolochainInstanceError,
+    error::{HolochainInstanceError, InterfaceStartError, LoadConfigError},
+    keystore::{read_passphrase, Keystore},
     logger::DebugLogger,
+    metrics::ZomeCallMetrics,
+    prepared_call::PreparedCall,
+    remote_bridge,
+    signal_broadcaster::{broadcast_through, spawn_signal_relay, SignalBroadcaster},
+    state_diff::StateDiff,
+    state_dump::{dump_source_chain, read_source_chain_dump},
     Holochain,
 };
 use holochain_core::{
+    entry_cache::{EntryCache, DEFAULT_LIVE_ENTRY_MAX_AGE_MS},
+    instance::Observer,
     logger::{ChannelLogger, Logger},
-    signal::Signal,
+    metrics::GetEntryMetrics,
+    nucleus::state::NucleusStatus,
+    signal::{Signal, SignalReceiver},
+    state::State,
 };
 use holochain_core_types::{
     agent::{AgentId, KeyBuffer},
-    dna::Dna,
+    cas::content::{Address, AddressableContent},
+    dna::{
+        capabilities::{CapabilityCall, CapabilityType},
+        Dna,
+    },
+    entry::entry_type::EntryType,
     error::HolochainError,
     json::JsonString,
 };
@@ -19,18 +42,31 @@ use jsonrpc_ws_server::jsonrpc_core::IoHandler;
 
 use std::{
     clone::Clone,
-    collections::HashMap,
+    collections::{BTreeSet, HashMap, HashSet},
     convert::TryFrom,
     fs::File,
-    io::prelude::*,
-    sync::{mpsc::SyncSender, Arc, Mutex, RwLock},
+    io::{self, prelude::*},
+    net::TcpListener,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, Sender, SyncSender},
+        Arc, Mutex, PoisonError, RwLock, TryLockError,
+    },
     thread,
+    time::{Duration, Instant},
 };
 
+use flate2::read::GzDecoder;
 use holochain_net::p2p_config::P2pConfig;
 use holochain_net_connection::net_connection::NetShutdown;
 use holochain_net_ipc::spawn::{ipc_spawn, SpawnResult};
-use interface::{ContainerApiBuilder, InstanceMap, Interface};
+use holochain_sodium::secbuf::SecBuf;
+use interface::{
+    with_replaceable_handler, ContainerApiBuilder, InstanceMap, Interface, InterfaceFailures,
+    InterfaceHandler, InterfaceState, SignalSubscriptions,
+};
+use toml;
 /// Main representation of the container.
 /// Holds a `HashMap` of Holochain instances referenced by ID.
 
@@ -44,44 +80,126 @@ use interface::{ContainerApiBuilder, InstanceMap, Interface};
 pub struct Container {
     instances: InstanceMap,
     config: Configuration,
-    interface_threads: HashMap<String, InterfaceThreadHandle>,
+    interface_threads: HashMap<String, InterfaceThread>,
+    interface_failures: InterfaceFailures,
+    /// Instances that got removed by `recover_poisoned_instances()`
+    failed_instances: HashSet<String>,
     dna_loader: DnaLoader,
-    signal_tx: Option<SignalSender>,
+    signal_broadcaster: Arc<Mutex<SignalBroadcaster>>,
+    /// Queues of the instances with `prioritized_calls`, shared by all interface handlers
+    call_queues: Mutex<HashMap<String, Arc<CallQueue>>>,
+    /// Passphrases entered at the prompt by agent ID, so the container asks only once
+    prompted_passphrases: Mutex<HashMap<String, String>>,
+    /// Zome call metrics of all instances, only recorded into if `zome_call_metrics` is set
+    zome_call_metrics: Arc<ZomeCallMetrics>,
+    /// Zome calls running on the instances, see `shutdown_graceful()`
+    calls_in_flight: Arc<CallsInFlight>,
+    admin_command_tx: Sender<ContainerAdminCommand>,
+    admin_command_rx: Receiver<ContainerAdminCommand>,
     logger: DebugLogger,
     p2p_config: Option<JsonString>,
     network_child_process: NetShutdown,
+    network_ipc_binding: Option<String>,
+    network_p2p_bindings: Option<Vec<String>>,
 }
 
 impl Drop for Container {
     fn drop(&mut self) {
+        self.stop_all_interfaces();
+        // Only set if we spawned n3h ourselves, shared networks are left running
         if let Some(kill) = self.network_child_process.take() {
             kill();
         }
     }
 }
 
-type SignalSender = SyncSender<Signal>;
 type InterfaceThreadHandle = thread::JoinHandle<Result<(), String>>;
-type DnaLoader = Arc<Box<FnMut(&String) -> Result<Dna, HolochainError> + Send>>;
+type InterfaceThreadResult = Arc<Mutex<Option<Result<(), String>>>>;
+
+/// Everything the container holds on to for a spawned interface thread.
+/// `result` gets set by the thread itself right before it finishes.
+/// `handler` and `signal_subscriptions` are shared with the running interface,
+/// see `Container::update_interfaces()`.
+struct InterfaceThread {
+    handle: InterfaceThreadHandle,
+    kill_switch: Sender<()>,
+    result: InterfaceThreadResult,
+    handler: InterfaceHandler,
+    signal_subscriptions: SignalSubscriptions,
+}
 
-// preparing for having container notifiers go to one of the log streams
-pub fn notify(msg: String) {
-    println!("{}", msg);
+/// Requests that admin interfaces pass on to the container since they need to change it.
+/// They get executed by `Container::process_admin_commands()`.
+pub enum ContainerAdminCommand {
+    /// Apply the given configuration, see `Container::reload_config()`.
+    /// The diff gets sent back once all instances are updated, before interfaces get restarted,
+    /// so that the interface the request came in on can answer it.
+    ReloadConfig(Configuration, Sender<Result<ConfigDiff, String>>),
 }
 
+const PORT_RELEASE_RETRIES: u32 = 50;
+const PORT_RELEASE_RETRY_DELAY_MS: u64 = 100;
+const READINESS_POLL_INTERVAL_MS: u64 = 50;
+const DRAIN_POLL_INTERVAL_MS: u64 = 20;
+/// First two bytes of every gzip file
+const GZIP_MAGIC_BYTES: [u8; 2] = [0x1f, 0x8b];
+/// Function that loads the DNA file at the given path, see `Container::with_dna_loader()`
+pub type DnaLoader = Arc<Box<Fn(&String) -> Result<Dna, HolochainError> + Send + Sync>>;
+
 impl Container {
+    /// Logger printing to stdout, or to stderr if stdout is taken by a stdio interface
+    fn console_logger(config: &Configuration) -> DebugLogger {
+        if config.has_stdio_interface() {
+            DebugLogger::with_output(
+                config.logger.rules.clone(),
+                config.logger.format,
+                Box::new(io::stderr()),
+            )
+        } else {
+            DebugLogger::new(config.logger.rules.clone(), config.logger.format)
+        }
+    }
+
     /// Creates a new instance with the default DnaLoader that actually loads files.
     pub fn from_config(config: Configuration) -> Self {
-        let rules = config.logger.rules.clone();
+        let logger = match config.logger.file {
+            Some(ref file) => DebugLogger::with_file(
+                config.logger.rules.clone(),
+                config.logger.format,
+                PathBuf::from(file),
+                config.logger.max_size_bytes,
+            )
+            .unwrap_or_else(|error| {
+                eprintln!(
+                    "err/container: Could not open log file \"{}\", logging to the console: {}",
+                    file, error
+                );
+                Self::console_logger(&config)
+            }),
+            None => Self::console_logger(&config),
+        };
+        let signal_broadcaster = SignalBroadcaster::new(config.signals.history_size)
+            .with_delivery(config.signals.delivery);
+        let (admin_command_tx, admin_command_rx) = channel();
         Container {
             instances: HashMap::new(),
             interface_threads: HashMap::new(),
+            interface_failures: Arc::new(Mutex::new(HashMap::new())),
+            failed_instances: HashSet::new(),
             config,
             dna_loader: Arc::new(Box::new(Self::load_dna)),
-            signal_tx: None,
-            logger: DebugLogger::new(rules),
+            signal_broadcaster: Arc::new(Mutex::new(signal_broadcaster)),
+            call_queues: Mutex::new(HashMap::new()),
+            prompted_passphrases: Mutex::new(HashMap::new()),
+            zome_call_metrics: Arc::new(ZomeCallMetrics::new()),
+            calls_in_flight: Arc::new(CallsInFlight::new()),
+            admin_command_tx,
+            admin_command_rx,
+            logger,
             p2p_config: None,
             network_child_process: None,
+            network_ipc_binding: None,
+            network_p2p_bindings: None,
         }
     }
 
@@ -89,582 +207,3974 @@ impl Container {
         if !self.instances.is_empty() {
             panic!("Cannot set a signal channel after having run load_config()");
         }
-        self.signal_tx = Some(signal_tx);
+        self.signal_broadcaster
+            .lock()
+            .unwrap()
+            .set_external(signal_tx);
+        self
+    }
+
+    /// Like `with_signal_channel()` but only the signals the given predicate accepts get
+    /// sent to the channel, so consumers don't have to drain everything else.
+    pub fn with_filtered_signal_channel<F>(
+        mut self,
+        signal_tx: SyncSender<Signal>,
+        predicate: F,
+    ) -> Self
+    where
+        F: Fn(&Signal) -> bool + Send + 'static,
+    {
+        if !self.instances.is_empty() {
+            panic!("Cannot set a signal channel after having run load_config()");
+        }
+        self.signal_broadcaster
+            .lock()
+            .unwrap()
+            .set_filtered_external(signal_tx, Box::new(predicate));
+        self
+    }
+
+    /// Replaces the default DNA loader, which reads DNA files from disk, with the given one.
+    /// It gets called with the `file` of each DNA configuration.
+    pub fn with_dna_loader(mut self, loader: DnaLoader) -> Self {
+        if !self.instances.is_empty() {
+            panic!("Cannot set a DNA loader after having run load_config()");
+        }
+        self.dna_loader = loader;
         self
     }
 
+    /// Returns a receiver for the signals of all instances.
+    /// It first gets the last `signals.history_size` signals replayed and then all new ones.
+    /// Instances only send signals if something consumes them when they get created,
+    /// so subscribe before `load_config()` if nothing else does.
+    pub fn subscribe_signals(&self) -> SignalReceiver {
+        self.signal_broadcaster.lock().unwrap().subscribe()
+    }
+
+    /// Number of signals the signal channel missed because it was full,
+    /// see `signals.delivery`.
+    pub fn dropped_signal_count(&self) -> usize {
+        self.signal_broadcaster.lock().unwrap().dropped_signals()
+    }
+
+    /// Sends a message of the container itself to the logger, tagged with "container",
+    /// so that it is subject to the configured log rules and format.
+    fn notify(&self, msg: String) {
+        let _ = self
+            .logger
+            .get_sender()
+            .send((String::from("container"), msg));
+    }
+
     pub fn config(&self) -> Configuration {
         self.config.clone()
     }
 
-    pub fn start_all_interfaces(&mut self) {
-        self.interface_threads = self
-            .config
+    /// Serializes the current configuration, including changes made at runtime,
+    /// to TOML that `load_configuration()` can read again.
+    pub fn config_toml(&self) -> Result<String, HolochainError> {
+        let serialize_error = |e: String| {
+            HolochainError::ConfigError(format!("Could not serialize configuration: {}", e))
+        };
+        // Going through a toml::Value puts plain values before tables, as TOML requires
+        let mut value =
+            toml::Value::try_from(&self.config).map_err(|e| serialize_error(e.to_string()))?;
+        restore_env_var_references(&mut value, &self.config.interpolated_values);
+        toml::to_string(&value).map_err(|e| serialize_error(e.to_string()))
+    }
+
+    /// Starts all configured interfaces that are not running yet, see `start_interface()`.
+    /// Stops at the first interface that fails to start, interfaces started before it
+    /// keep running.
+    pub fn start_all_interfaces(&mut self) -> Result<(), InterfaceStartError> {
+        for interface_config in self.config.interfaces.clone() {
+            if !self.interface_threads.contains_key(&interface_config.id) {
+                self.start_interface(&interface_config)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts the given interface in its own thread.
+    /// Waits for the interface to bind its port there, so that a taken port makes this fail
+    /// instead of only the interface thread, see `Interface::start()`. Other errors of the
+    /// interface's server only show in `interface_status()`.
+    pub fn start_interface(
+        &mut self,
+        config: &InterfaceConfiguration,
+    ) -> Result<(), InterfaceStartError> {
+        if self.interface_threads.contains_key(&config.id) {
+            return Err(InterfaceStartError::AlreadyRunning(config.id.clone()));
+        }
+        let (handle, bind_result) = self.spawn_interface_thread(config.clone());
+        let bind_error = match bind_result.recv() {
+            Ok(Ok(())) => None,
+            Ok(Err(error)) => Some(interface_start_error(config, error)),
+            Err(_) => Some(InterfaceStartError::Io {
+                interface: config.id.clone(),
+                message: String::from("Interface thread ended before binding"),
+            }),
+        };
+        if let Some(error) = bind_error {
+            self.interface_failures
+                .lock()
+                .unwrap()
+                .insert(config.id.clone(), error.to_string());
+            return Err(error);
+        }
+        self.interface_threads.insert(config.id.clone(), handle);
+        Ok(())
+    }
+
+    /// Reports the state of every configured interface, i.e. whether its thread is
+    /// still running, got stopped or ended with an error, or whether it failed to start.
+    pub fn interface_status(&self) -> HashMap<String, InterfaceState> {
+        self.config
             .interfaces
             .iter()
-            .map(|ic| (ic.id.clone(), self.spawn_interface_thread(ic.clone())))
+            .map(|interface_config| {
+                let state = match self.interface_threads.get(&interface_config.id) {
+                    Some(interface_thread) => match *interface_thread.result.lock().unwrap() {
+                        None => InterfaceState::Running,
+                        Some(Ok(())) => InterfaceState::Stopped,
+                        Some(Err(ref error)) => InterfaceState::Failed(error.clone()),
+                    },
+                    None => match self
+                        .interface_failures
+                        .lock()
+                        .unwrap()
+                        .get(&interface_config.id)
+                    {
+                        Some(error) => InterfaceState::Failed(error.clone()),
+                        None => InterfaceState::Stopped,
+                    },
+                };
+                (interface_config.id.clone(), state)
+            })
             .collect()
     }
 
-    pub fn start_interface_by_id(&mut self, id: String) -> Result<(), String> {
-        self.config
-            .interface_by_id(&id)
-            .ok_or(format!("Interface does not exist: {}", id))
-            .and_then(|config| self.start_interface(&config))
+    /// Signals all running interface threads to shut down their servers
+    /// and waits for them to finish, so that no listener stays bound.
+    pub fn stop_all_interfaces(&mut self) {
+        let ids: Vec<String> = self.interface_threads.keys().cloned().collect();
+        for id in ids {
+            self.stop_interface_by_id(&id);
+        }
     }
 
-    /// Starts all instances
-    pub fn start_all_instances(&mut self) -> Result<(), HolochainInstanceError> {
-        self.instances
-            .iter_mut()
-            .map(|(id, hc)| {
-                notify(format!("Starting instance \"{}\"...", id));
-                hc.write().unwrap().start()
-            })
-            .collect::<Result<Vec<()>, _>>()
-            .map(|_| ())
+    /// Stops the interface thread with the given ID, if running, and waits for it to finish.
+    /// Returns true if there was a thread to stop.
+    fn stop_interface_by_id(&mut self, id: &String) -> bool {
+        self.interface_failures.lock().unwrap().remove(id);
+        match self.interface_threads.remove(id) {
+            Some(interface_thread) => {
+                self.notify(format!("Stopping interface \"{}\"...", id));
+                // The thread might have ended already if its server failed to start
+                let _ = interface_thread.kill_switch.send(());
+                if let Err(error) = interface_thread.handle.join() {
+                    self.notify(format!(
+                        "err/container: Interface thread \"{}\" panicked: {:?}",
+                        id, error
+                    ));
+                }
+                true
+            }
+            None => false,
+        }
     }
 
-    /// Stops all instances
-    pub fn stop_all_instances(&mut self) -> Result<(), HolochainInstanceError> {
-        self.instances
-            .iter_mut()
-            .map(|(id, hc)| {
-                notify(format!("Stopping instance \"{}\"...", id));
-                hc.write().unwrap().stop()
-            })
-            .collect::<Result<Vec<()>, _>>()
-            .map(|_| ())
+    /// Stops the interface with the given ID and starts it again with a handler built from
+    /// the current interface configuration and instance set.
+    /// Starts the interface if it is not running.
+    pub fn restart_interface_by_id(&mut self, id: String) -> Result<(), String> {
+        let config = self
+            .config
+            .interface_by_id(&id)
+            .ok_or(format!("Interface does not exist: {}", id))?;
+        if self.stop_interface_by_id(&id) {
+            wait_for_port_release(&config.driver)?;
+        }
+        self.start_interface(&config)
+            .map_err(|error| error.to_string())
     }
 
-    pub fn instances(&self) -> &InstanceMap {
-        &self.instances
+    /// Starts all instances, bridge callees before their callers,
+    /// and emits a `Signal::InstanceStarted` for each.
+    /// Up to `instance_start_concurrency` instances whose callees are running already
+    /// get started in parallel threads. If any of them fails, no further instances get
+    /// started. A single failure is returned as is, several get combined into an error
+    /// that names all failed instances.
+    pub fn start_all_instances(&mut self) -> Result<(), HolochainInstanceError> {
+        let concurrency = self.config.instance_start_concurrency.max(1);
+        let mut pending = self.instance_ids_sorted_by_bridge_dependencies()?;
+        while !pending.is_empty() {
+            let pending_ids = pending.clone();
+            let (startable, waiting): (Vec<String>, Vec<String>) =
+                pending.into_iter().partition(|id| {
+                    self.config
+                        .bridge_dependencies(id.clone())
+                        .iter()
+                        .all(|bridge| !pending_ids.contains(&bridge.callee_id))
+                });
+
+            for chunk in startable.chunks(concurrency) {
+                let threads: Vec<_> = chunk
+                    .iter()
+                    .map(|id| {
+                        self.notify(format!("Starting instance \"{}\"...", id));
+                        let instance = self.instances[id].clone();
+                        let instance_id = id.clone();
+                        let handle = thread::spawn(move || {
+                            instance
+                                .write()
+                                .map_err(|_| instance_poisoned_error(&instance_id))?
+                                .start()
+                        });
+                        (id.clone(), handle)
+                    })
+                    .collect();
+
+                let mut failures: Vec<(String, HolochainInstanceError)> = Vec::new();
+                for (id, handle) in threads {
+                    let result = handle.join().unwrap_or_else(|_| {
+                        Err(HolochainInstanceError::InternalFailure(
+                            HolochainError::ErrorGeneric(String::from("Start thread panicked")),
+                        ))
+                    });
+                    match result {
+                        Ok(()) => {
+                            self.emit_instance_signal(&id, Signal::InstanceStarted(id.clone()))
+                        }
+                        Err(error) => {
+                            self.notify(format!(
+                                "err/container: Could not start instance \"{}\": {}",
+                                id, error
+                            ));
+                            failures.push((id, error));
+                        }
+                    }
+                }
+                if failures.len() == 1 {
+                    return Err(failures.remove(0).1);
+                }
+                if !failures.is_empty() {
+                    let failed: Vec<String> = failures
+                        .iter()
+                        .map(|(id, error)| format!("\"{}\": {}", id, error))
+                        .collect();
+                    return Err(HolochainInstanceError::InternalFailure(
+                        HolochainError::ErrorGeneric(format!(
+                            "Could not start instances: {}",
+                            failed.join(", ")
+                        )),
+                    ));
+                }
+            }
+
+            pending = waiting;
+        }
+        Ok(())
     }
 
-    /// Stop and clear all instances
-    pub fn shutdown(&mut self) -> Result<(), HolochainInstanceError> {
-        self.stop_all_instances()?;
-        // @TODO: also stop all interfaces
-        self.instances = HashMap::new();
+    /// Stops all instances, bridge callers before their callees,
+    /// and emits a `Signal::InstanceStopped` for each
+    pub fn stop_all_instances(&mut self) -> Result<(), HolochainInstanceError> {
+        for id in self
+            .instance_ids_sorted_by_bridge_dependencies()?
+            .into_iter()
+            .rev()
+        {
+            self.notify(format!("Stopping instance \"{}\"...", id));
+            self.instances[&id]
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .stop()?;
+            self.emit_instance_signal(&id, Signal::InstanceStopped(id.clone()));
+        }
         Ok(())
     }
 
-    pub fn spawn_network(&mut self) -> Result<String, HolochainError> {
-        let network_config = self
+    /// IDs of all instances of this container, bridge callees before their callers
+    fn instance_ids_sorted_by_bridge_dependencies(
+        &self,
+    ) -> Result<Vec<String>, HolochainInstanceError> {
+        Ok(self
             .config
-            .clone()
-            .network
-            .ok_or(HolochainError::ErrorGeneric(
-                "attempt to spawn network when not configured".to_string(),
-            ))?;
-
-        println!(
-            "Spawning network with working directory: {}",
-            network_config.n3h_persistence_path
-        );
-        let SpawnResult {
-            kill,
-            ipc_binding,
-            p2p_bindings: _,
-        } = ipc_spawn(
-            "node".to_string(),
-            vec![format!(
-                "{}/packages/n3h/bin/n3h",
-                network_config.n3h_path.clone()
-            )],
-            network_config.n3h_persistence_path.clone(),
-            hashmap! {
-                String::from("N3H_MODE") => network_config.n3h_mode.clone(),
-                String::from("N3H_WORK_DIR") => network_config.n3h_persistence_path.clone(),
-                String::from("N3H_IPC_SOCKET") => String::from("tcp://127.0.0.1:*"),
-            },
-            true,
-        )
-        .map_err(|error| {
-            println!("Error spawning network process! {:?}", error);
-            HolochainError::ErrorGeneric(error.to_string())
-        })?;
-        self.network_child_process = kill;
-        println!("Network spawned with binding: {:?}", ipc_binding);
-        Ok(ipc_binding)
+            .instance_ids_sorted_by_bridge_dependencies()?
+            .into_iter()
+            .filter(|id| self.instances.contains_key(id))
+            .collect())
     }
 
-    fn instance_p2p_config(&self) -> Result<JsonString, HolochainError> {
-        let config = self.p2p_config.clone().unwrap_or_else(|| {
-            // This should never happen, but we'll throw out a named mock network rather than crashing,
-            // just to be nice (TODO make proper logging statement)
-            println!("warn: instance_network_config called before p2p_config initialized! Using default mock network name.");
-            JsonString::from(P2pConfig::named_mock_config("container-default-mock"))
-        });
-        Ok(config)
+    /// Hands a signal the container emits about the given instance to all signal receivers
+    fn emit_instance_signal(&self, instance_id: &String, signal: Signal) {
+        broadcast_through(&self.signal_broadcaster, Some(instance_id.clone()), signal);
     }
 
-    fn initialize_p2p_config(&mut self) -> JsonString {
-        match self.config.network.clone() {
-            // if there is a config then either we need to spawn a process and get the
-            // ipc_uri for it and save it for future calls to `load_config`
-            // or we use that uri value that was created from previous calls!
-            Some(ref net_config) => {
-                let uri = self
-                    .config
-                    .clone()
-                    .network
-                    .unwrap() // unwrap safe because of check above
-                    .n3h_ipc_uri
-                    .clone()
-                    .or_else(|| self.spawn_network().ok());
-                JsonString::from(json!(
-                    {
-                        "backend_kind": "IPC",
-                        "backend_config": {
-                            "socketType": "zmq",
-                            "bootstrapNodes": net_config.bootstrap_nodes,
-                            "ipcUri": uri
-                        }
+    /// Whether anything takes the signals of instances created with the given config:
+    /// the signal channel, subscribers, the signal history, websocket interfaces that offer
+    /// "signals/subscribe" or interfaces that wait for publish confirmations.
+    /// Instances only get a signal sender if so, since that means cloning every action.
+    fn consumes_signals(&self, config: &Configuration) -> bool {
+        self.signal_broadcaster.lock().unwrap().has_receivers()
+            || config.interfaces.iter().any(|interface| {
+                interface.publish_confirmation_timeout_ms.is_some()
+                    || match interface.driver {
+                        InterfaceDriver::Websocket { .. }
+                        | InterfaceDriver::WebsocketTls { .. } => true,
+                        _ => false,
                     }
-                ))
-            }
-            // if there's no NetworkConfig we won't spawn a network process
-            // and instead configure instances to use a unique mock network
-            None => JsonString::from(P2pConfig::unique_mock_config()),
-        }
+            })
     }
 
-    /// Tries to create all instances configured in the given Configuration object.
-    /// Calls `Configuration::check_consistency()` first and clears `self.instances`.
-    /// The first time we call this, we also initialize the container-wide config
-    /// for use with all instances
+    /// Stops the instance with the given ID and removes it from the container.
+    /// The instance's configuration and its bridges are dropped from the container's
+    /// config, and it is removed from all interface configurations. Every interface
+    /// that served it, by listing it or through `all_instances`, gets a new handler
+    /// without being restarted.
     ///
-    /// @TODO: clean up the container creation process to prevent loading config before proper setup,
-    ///        especially regarding the signal handler.
-    ///        (see https://github.com/holochain/holochain-rust/issues/739)
-    pub fn load_config(&mut self) -> Result<(), String> {
-        let _ = self.config.check_consistency()?;
+    /// Instances that have a bridge to the removed one keep running. Their bridge
+    /// calls to it fail with an `InstanceRemoved` error since bridges get bound
+    /// during instantiation.
+    ///
+    /// Fails without changing anything if the instance can't be stopped.
+    pub fn remove_instance(&mut self, id: &String) -> Result<(), HolochainInstanceError> {
+        let instance = self.instances.get(id).cloned().ok_or_else(|| {
+            HolochainInstanceError::InternalFailure(HolochainError::ErrorGeneric(format!(
+                "Instance does not exist: {}",
+                id
+            )))
+        })?;
 
-        if self.p2p_config.is_none() {
-            self.p2p_config = Some(self.initialize_p2p_config());
+        self.notify(format!("Removing instance \"{}\"...", id));
+        {
+            let mut hc = instance.write().unwrap_or_else(PoisonError::into_inner);
+            match hc.stop() {
+                Ok(()) => self.emit_instance_signal(id, Signal::InstanceStopped(id.clone())),
+                Err(HolochainInstanceError::InstanceNotActiveYet) => (),
+                Err(error) => return Err(error),
+            }
+            hc.mark_removed();
         }
+        self.instances.remove(id);
 
-        let config = self.config.clone();
-        self.shutdown().map_err(|e| e.to_string())?;
-        self.instances = HashMap::new();
+        let affected_interfaces: Vec<String> = self
+            .config
+            .interfaces
+            .iter()
+            .filter(|interface| {
+                interface.all_instances
+                    || interface
+                        .instances
+                        .iter()
+                        .any(|instance| &instance.id == id)
+            })
+            .map(|interface| interface.id.clone())
+            .collect();
+        self.config.instances.retain(|instance| &instance.id != id);
+        self.config
+            .bridges
+            .retain(|bridge| &bridge.caller_id != id && &bridge.callee_id != id);
+        self.config
+            .remote_bridges
+            .retain(|bridge| &bridge.caller_id != id);
+        for interface in self.config.interfaces.iter_mut() {
+            interface.instances.retain(|instance| &instance.id != id);
+        }
+        self.zome_call_metrics.remove_instance(id);
+        self.calls_in_flight.remove_instance(id);
+        self.update_interfaces(&affected_interfaces);
 
-        for id in config.instance_ids_sorted_by_bridge_dependencies()? {
-            let instance = self
-                .instantiate_from_config(&id, &config)
-                .map_err(|error| {
-                    format!(
-                        "Error while trying to create instance \"{}\": {}",
-                        id, error
-                    )
-                })?;
+        Ok(())
+    }
 
-            self.instances
-                .insert(id.clone(), Arc::new(RwLock::new(instance)));
-        }
+    /// Creates the instance with the given ID from the current configuration,
+    /// adds it to the container and starts it, without touching any other instance.
+    /// Interfaces serving `all_instances` make it available without being restarted.
+    ///
+    /// All bridge callees of the new instance have to be running already since
+    /// bridges get bound during instantiation.
+    pub fn add_instance(&mut self, id: &String) -> Result<(), String> {
+        self.instantiate_and_start(id)?;
+        self.update_all_instances_interfaces();
         Ok(())
     }
 
-    /// Creates one specific Holochain instance from a given Configuration,
-    /// id string and DnaLoader.
-    pub fn instantiate_from_config(
-        &mut self,
-        id: &String,
-        config: &Configuration,
-    ) -> Result<Holochain, String> {
-        let _ = config.check_consistency()?;
+    /// `add_instance()` without updating any interface
+    fn instantiate_and_start(&mut self, id: &String) -> Result<(), String> {
+        if self.instances.contains_key(id) {
+            return Err(format!("Instance already exists: {}", id));
+        }
 
+        let config = self.config.clone();
         config
-            .instance_by_id(&id)
-            .ok_or(String::from("Instance not found in config"))
-            .and_then(|instance_config| {
-                // Build context:
-                let mut context_builder = ContextBuilder::new();
-
-                // Agent:
-                let agent_config = config.agent_by_id(&instance_config.agent).unwrap();
-                let pub_key = KeyBuffer::with_corrected(&agent_config.public_address)?;
-                context_builder =
-                    context_builder.with_agent(AgentId::new(&agent_config.name, &pub_key));
-
-                context_builder = context_builder.with_network_config(self.instance_p2p_config()?);
-
-                // Storage:
-                if let StorageConfiguration::File { path } = instance_config.storage {
-                    context_builder = context_builder.with_file_storage(path).map_err(|hc_err| {
-                        format!("Error creating context: {}", hc_err.to_string())
-                    })?
-                };
+            .instance_by_id(id)
+            .ok_or(format!("Instance not found in config: {}", id))?;
 
-                if config.logger.logger_type == "debug" {
-                    context_builder = context_builder.with_logger(Arc::new(Mutex::new(
-                        ChannelLogger::new(instance_config.id.clone(), self.logger.get_sender()),
-                    )));
-                }
+        for bridge in config.bridge_dependencies(id.clone()) {
+            if !self.instances.contains_key(&bridge.callee_id) {
+                return Err(format!(
+                    "Cannot add instance \"{}\" because its bridge callee \"{}\" is not running",
+                    id, bridge.callee_id
+                ));
+            }
+        }
 
-                // Container API
-                let mut api_builder = ContainerApiBuilder::new();
-                // Bridges:
-                let id = instance_config.id.clone();
-                for bridge in config.bridge_dependencies(id.clone()) {
-                    assert_eq!(bridge.caller_id, id.clone());
-                    let callee_config = config
-                        .instance_by_id(&bridge.callee_id)
-                        .expect("config.check_consistency()? jumps out if config is broken");
-                    let callee_instance = self.instances.get(&bridge.callee_id).expect(
-                        r#"
-                            We have to create instances ordered by bridge dependencies such that we
-                            can expect the callee to be present here because we need it to create
-                            the bridge API"#,
-                    );
-
-                    api_builder = api_builder
-                        .with_named_instance(bridge.handle.clone(), callee_instance.clone());
-                    api_builder = api_builder
-                        .with_named_instance_config(bridge.handle.clone(), callee_config);
-                }
-                context_builder = context_builder.with_container_api(api_builder.spawn());
-                if let Some(signal_tx) = self.signal_tx.clone() {
-                    context_builder = context_builder.with_signals(signal_tx);
-                }
+        if self.p2p_config.is_none() {
+            self.p2p_config = Some(self.initialize_p2p_config());
+        }
 
-                // Spawn context
-                let context = context_builder.spawn();
+        let mut instance = self
+            .instantiate_from_config(id, &config)
+            .map_err(|error| error.to_string())?;
 
-                // Get DNA
-                let dna_config = config.dna_by_id(&instance_config.dna).unwrap();
-                let dna = Arc::get_mut(&mut self.dna_loader).unwrap()(&dna_config.file).map_err(
-                    |_| {
-                        HolochainError::ConfigError(format!(
-                            "Could not load DNA file \"{}\"",
-                            dna_config.file
-                        ))
-                    },
-                )?;
+        self.notify(format!("Starting instance \"{}\"...", id));
+        instance.start().map_err(|error| error.to_string())?;
+        self.instances
+            .insert(id.clone(), Arc::new(RwLock::new(instance)));
+        self.failed_instances.remove(id);
+        self.emit_instance_signal(id, Signal::InstanceStarted(id.clone()));
+        Ok(())
+    }
 
-                Holochain::new(dna, Arc::new(context)).map_err(|hc_err| hc_err.to_string())
-            })
+    /// Gives the running interfaces that serve `all_instances` a new handler,
+    /// see `update_interfaces()`.
+    fn update_all_instances_interfaces(&mut self) {
+        let interface_ids: Vec<String> = self
+            .config
+            .interfaces
+            .iter()
+            .filter(|interface| interface.all_instances)
+            .map(|interface| interface.id.clone())
+            .collect();
+        self.update_interfaces(&interface_ids);
     }
 
-    fn start_interface(&mut self, config: &InterfaceConfiguration) -> Result<(), String> {
-        if self.interface_threads.contains_key(&config.id) {
-            return Err(format!("Interface {} already started!", config.id));
+    /// Gives the running interfaces with the given IDs a new handler and lets their
+    /// clients subscribe to the signals of the instances they now serve, so that they pick
+    /// up added and removed instances without dropping their connections and subscriptions.
+    fn update_interfaces(&mut self, interface_ids: &[String]) {
+        for interface_config in self.config.interfaces.iter() {
+            if !interface_ids.contains(&interface_config.id) {
+                continue;
+            }
+            if let Some(interface_thread) = self.interface_threads.get(&interface_config.id) {
+                interface_thread
+                    .handler
+                    .replace(self.make_interface_handler(interface_config));
+                interface_thread
+                    .signal_subscriptions
+                    .set_instance_ids(self.interface_instance_ids(interface_config));
+            }
         }
-        let handle = self.spawn_interface_thread(config.clone());
-        self.interface_threads.insert(config.id.clone(), handle);
-        Ok(())
     }
 
-    /// Default DnaLoader that actually reads files from the filesystem
-    fn load_dna(file: &String) -> Result<Dna, HolochainError> {
-        let mut f = File::open(file)?;
-        let mut contents = String::new();
-        f.read_to_string(&mut contents)?;
-        Dna::try_from(JsonString::from(contents))
+    /// IDs of the instances the given interface serves
+    fn interface_instance_ids(&self, interface_config: &InterfaceConfiguration) -> Vec<String> {
+        if interface_config.all_instances {
+            self.instances.keys().cloned().collect()
+        } else {
+            interface_config
+                .instances
+                .iter()
+                .map(|i| i.id.clone())
+                .collect()
+        }
     }
 
-    fn make_interface_handler(&self, interface_config: &InterfaceConfiguration) -> IoHandler {
-        let instance_ids: Vec<String> = interface_config
+    /// Looks for instances whose lock got poisoned, i.e. a call panicked while holding it,
+    /// and deals with them according to the configured `panic_policy`: the instance gets
+    /// stopped and removed, and with `PanicPolicy::Restart` created and started again.
+    /// Interfaces serving such an instance get restarted so that their handlers let go of
+    /// the poisoned one. Bridges of other instances to it keep pointing at the old one.
+    /// Returns the IDs of the poisoned instances.
+    ///
+    /// Has to be called regularly by whoever owns the container, like
+    /// `process_admin_commands()`.
+    pub fn recover_poisoned_instances(&mut self) -> Vec<String> {
+        let mut poisoned: Vec<String> = self
             .instances
             .iter()
-            .map(|i| i.id.clone())
+            .filter(|(_, instance)| instance.is_poisoned())
+            .map(|(id, _)| id.clone())
             .collect();
+        poisoned.sort();
+        for id in poisoned.iter() {
+            self.notify(format!(
+                "err/container: A call to instance \"{}\" panicked",
+                id
+            ));
+            let instance = self.instances.remove(id).expect("Found above");
+            // Stopping marks the instance inactive, so that handles to it that are still
+            // around refuse calls. It fails for instances that were not active, which then
+            // did not get started and don't need a stopped signal either.
+            if let Ok(()) = instance
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .stop()
+            {
+                self.emit_instance_signal(id, Signal::InstanceStopped(id.clone()));
+            }
+            self.failed_instances.insert(id.clone());
+            // The interfaces serving the instance get restarted below, once for all of them
+            if self.config.panic_policy == PanicPolicy::Restart {
+                if let Err(error) = self.instantiate_and_start(id) {
+                    self.notify(format!(
+                        "err/container: Could not restart instance \"{}\": {}",
+                        id, error
+                    ));
+                }
+            }
+        }
 
-        let instance_subset: InstanceMap = self
-            .instances
+        let interface_ids: Vec<String> = self
+            .config
+            .interfaces
             .iter()
-            .filter(|(id, _)| instance_ids.contains(&id))
-            .map(|(id, val)| (id.clone(), val.clone()))
+            .filter(|interface| poisoned.iter().any(|id| interface.serves_instance(id)))
+            .map(|interface| interface.id.clone())
+            .filter(|id| self.interface_threads.contains_key(id))
             .collect();
-
-        ContainerApiBuilder::new()
-            .with_instances(instance_subset)
-            .with_instance_configs(self.config.instances.clone())
-            .spawn()
+        for id in interface_ids {
+            if let Err(error) = self.restart_interface_by_id(id) {
+                self.notify(format!("err/container: {}", error));
+            }
+        }
+        poisoned
     }
 
-    fn spawn_interface_thread(
-        &self,
-        interface_config: InterfaceConfiguration,
-    ) -> InterfaceThreadHandle {
-        let dispatcher = self.make_interface_handler(&interface_config);
-        let log_sender = self.logger.get_sender();
-        thread::spawn(move || {
-            let iface = make_interface(&interface_config);
-            iface.run(dispatcher).map_err(|error| {
-                let message = format!(
-                    "err/container: Error running interface '{}': {}",
-                    interface_config.id, error
-                );
-                let _ = log_sender.send((String::from("container"), message));
-                error
-            })
-        })
+    /// IDs of the instances that got removed after a panic and were not restarted,
+    /// see `recover_poisoned_instances()`
+    pub fn failed_instances(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.failed_instances.iter().cloned().collect();
+        ids.sort();
+        ids
     }
-}
 
-impl<'a> TryFrom<&'a Configuration> for Container {
-    type Error = HolochainError;
-    fn try_from(config: &'a Configuration) -> Result<Self, Self::Error> {
-        let mut container = Container::from_config((*config).clone());
-        container
-            .load_config()
-            .map_err(|string| HolochainError::ConfigError(string))?;
-        Ok(container)
-    }
-}
+    /// Atomically swaps the two running instances with the given IDs, including their
+    /// instance configurations (DNA, agent and storage).
+    /// Every interface handler or other reference that pointed at `id_a` will be served
+    /// by the instance that was formerly running as `id_b` and vice versa, without
+    /// restarting any interface.
+    ///
+    /// Since handlers route by zome function name, both instances need to expose the
+    /// exact same set of zome functions. Instances that are part of a bridge can't be
+    /// swapped because bridges get bound during instantiation.
+    pub fn swap_instances(
+        &mut self,
+        id_a: &String,
+        id_b: &String,
+    ) -> Result<(), HolochainInstanceError> {
+        let generic_error = |message: String| {
+            HolochainInstanceError::InternalFailure(HolochainError::ErrorGeneric(message))
+        };
 
-/// This can eventually be dependency injected for third party Interface definitions
-fn make_interface(interface_config: &InterfaceConfiguration) -> Box<Interface> {
-    use interface_impls::{http::HttpInterface, websocket::WebsocketInterface};
-    match interface_config.driver {
-        InterfaceDriver::Websocket { port } => Box::new(WebsocketInterface::new(port)),
-        InterfaceDriver::Http { port } => Box::new(HttpInterface::new(port)),
-        _ => unimplemented!(),
-    }
+        if id_a == id_b {
+            return Err(generic_error(format!(
+                "Cannot swap instance \"{}\" with itself",
+                id_a
+            )));
+        }
+
+        for id in [id_a, id_b].iter() {
+            if self
+                .config
+                .bridges
+                .iter()
+                .any(|bridge| &&bridge.caller_id == id || &&bridge.callee_id == id)
+            {
+                return Err(generic_error(format!(
+                    "Cannot swap instance \"{}\" because it is part of a bridge",
+                    id
+                )));
+            }
+        }
+
+        let instance_a = self
+            .instances
+            .get(id_a)
+            .cloned()
+            .ok_or_else(|| generic_error(format!("Instance does not exist: {}", id_a)))?;
+        let instance_b = self
+            .instances
+            .get(id_b)
+            .cloned()
+            .ok_or_else(|| generic_error(format!("Instance does not exist: {}", id_b)))?;
+        let config_a = self
+            .config
+            .instance_by_id(id_a)
+            .ok_or_else(|| generic_error(format!("Instance configuration not found: {}", id_a)))?;
+        let config_b = self
+            .config
+            .instance_by_id(id_b)
+            .ok_or_else(|| generic_error(format!("Instance configuration not found: {}", id_b)))?;
+
+        let mut hc_a = instance_a
+            .write()
+            .map_err(|_| instance_poisoned_error(id_a))?;
+        let mut hc_b = instance_b
+            .write()
+            .map_err(|_| instance_poisoned_error(id_b))?;
+
+        if !hc_a.active() || !hc_b.active() {
+            return Err(HolochainInstanceError::InstanceNotActiveYet);
+        }
+
+        if zome_function_names(&hc_a)? != zome_function_names(&hc_b)? {
+            return Err(generic_error(format!(
+                "Cannot swap instances \"{}\" and \"{}\": they don't expose the same zome functions",
+                id_a, id_b
+            )));
+        }
+
+        self.notify(format!(
+            "Swapping instances \"{}\" and \"{}\"...",
+            id_a, id_b
+        ));
+        std::mem::swap(&mut *hc_a, &mut *hc_b);
+        for instance_config in self.config.instances.iter_mut() {
+            let swapped_config = if &instance_config.id == id_a {
+                &config_b
+            } else if &instance_config.id == id_b {
+                &config_a
+            } else {
+                continue;
+            };
+            instance_config.dna = swapped_config.dna.clone();
+            instance_config.agent = swapped_config.agent.clone();
+            instance_config.storage = swapped_config.storage.clone();
+        }
+
+        Ok(())
+    }
+
+    /// Loads the DNA file at `new_dna_path` with the DnaLoader and rebinds the instance
+    /// with the given ID to it, keeping the instance's agent and storage, i.e. its source chain.
+    /// Fails without changing anything if the new DNA does not define all app entry types
+    /// that are already on the source chain.
+    /// A running instance gets stopped for the swap and restarted with the new DNA.
+    /// The new DNA entry gets committed on top of the source chain.
+    ///
+    /// The configuration is not changed, so re-creating the instance from the config
+    /// brings back the configured DNA. Interfaces keep routing the zome functions they
+    /// were started with until they get restarted.
+    pub fn reload_dna(
+        &mut self,
+        instance_id: &String,
+        new_dna_path: &String,
+    ) -> Result<(), String> {
+        let instance = self
+            .instances
+            .get(instance_id)
+            .cloned()
+            .ok_or_else(|| format!("Instance does not exist: {}", instance_id))?;
+        let new_dna = (self.dna_loader)(new_dna_path)
+            .map_err(|_| format!("Could not load DNA file \"{}\"", new_dna_path))?;
+
+        let mut hc = instance
+            .write()
+            .map_err(|_| instance_poisoned_error(instance_id).to_string())?;
+        let state = hc.state().map_err(|e| e.to_string())?;
+        let agent = state.agent();
+        let missing_entry_types: BTreeSet<String> = agent
+            .chain()
+            .iter(&agent.top_chain_header())
+            .filter_map(|header| match header.entry_type() {
+                EntryType::App(app_entry_type) => Some(app_entry_type.clone()),
+                _ => None,
+            })
+            .filter(|app_entry_type| {
+                new_dna
+                    .get_zome_name_for_app_entry_type(app_entry_type)
+                    .is_none()
+            })
+            .map(String::from)
+            .collect();
+        if !missing_entry_types.is_empty() {
+            return Err(format!(
+                "DNA file \"{}\" is incompatible with the source chain of instance \"{}\", it does not define the entry types: {}",
+                new_dna_path,
+                instance_id,
+                missing_entry_types.into_iter().collect::<Vec<_>>().join(", ")
+            ));
+        }
+
+        self.notify(format!(
+            "Reloading instance \"{}\" with DNA file \"{}\"...",
+            instance_id, new_dna_path
+        ));
+        let mut new_hc = hc.with_new_dna(new_dna).map_err(|e| e.to_string())?;
+        if hc.active() {
+            hc.stop().map_err(|e| e.to_string())?;
+            new_hc.start().map_err(|e| e.to_string())?;
+        }
+        *hc = new_hc;
+        Ok(())
+    }
+
+    /// Rotates the agent key of the instance with the given ID: the instance gets rebound
+    /// to the agent with the given ID from the config, keeping its DNA and storage.
+    /// A running instance gets stopped for the swap and restarted with the new agent.
+    ///
+    /// Entries already on the source chain stay signed by the former agent, so the chain is
+    /// never rewritten. Instead, the new agent's AgentId entry gets committed on top of it,
+    /// starting a new chain segment that the new agent signs, see `Holochain::with_new_agent()`.
+    ///
+    /// The instance's configuration is changed to the new agent, so re-creating the instance
+    /// from the config continues the new segment. Fails without changing anything if the
+    /// agent does not exist, has an invalid public address or its key can't be loaded.
+    /// The instance is stopped before the new agent's AgentId entry gets committed. If that
+    /// or initializing the new agent fails, the commit gets undone and the instance is
+    /// restarted with its former agent.
+    pub fn replace_agent(
+        &mut self,
+        instance_id: &String,
+        new_agent_id: &String,
+    ) -> Result<(), String> {
+        let instance = self
+            .instances
+            .get(instance_id)
+            .cloned()
+            .ok_or_else(|| format!("Instance does not exist: {}", instance_id))?;
+        let agent_config = self
+            .config
+            .agent_by_id(new_agent_id)
+            .ok_or_else(|| format!("Agent does not exist: {}", new_agent_id))?;
+        let (agent_id, maybe_secret_key) =
+            load_agent(&self.config, &agent_config, &self.prompted_passphrases)?;
+
+        let mut hc = instance
+            .write()
+            .map_err(|_| instance_poisoned_error(instance_id).to_string())?;
+        self.notify(format!(
+            "Replacing the agent of instance \"{}\" with agent \"{}\"...",
+            instance_id, new_agent_id
+        ));
+        let was_active = hc.active();
+        if was_active {
+            hc.stop().map_err(|e| e.to_string())?;
+        }
+        let mut new_hc = match hc.with_new_agent(agent_id, maybe_secret_key) {
+            Ok(new_hc) => new_hc,
+            Err(error) => {
+                if was_active {
+                    let _ = hc.start();
+                }
+                return Err(error.to_string());
+            }
+        };
+        if was_active {
+            new_hc.start().map_err(|e| e.to_string())?;
+        }
+        *hc = new_hc;
+        for instance_config in self.config.instances.iter_mut() {
+            if &instance_config.id == instance_id {
+                instance_config.agent = new_agent_id.clone();
+            }
+        }
+        Ok(())
+    }
+
+    /// Compares the local DHT shards of the two instances with the given IDs and reports
+    /// entries held by only one of them and entries with differing CRUD status.
+    /// Also available through the "admin/dht/diff" method of admin interfaces.
+    pub fn diff_instances(
+        &self,
+        id_a: &String,
+        id_b: &String,
+    ) -> Result<StateDiff, HolochainInstanceError> {
+        let get_instance = |id: &String| {
+            self.instances.get(id).ok_or_else(|| {
+                HolochainInstanceError::InternalFailure(HolochainError::ErrorGeneric(format!(
+                    "Instance does not exist: {}",
+                    id
+                )))
+            })
+        };
+        let instance_a = get_instance(id_a)?;
+        let instance_b = get_instance(id_b)?;
+        StateDiff::between(
+            &*instance_a
+                .read()
+                .map_err(|_| instance_poisoned_error(id_a))?,
+            &*instance_b
+                .read()
+                .map_err(|_| instance_poisoned_error(id_b))?,
+        )
+    }
+
+    /// Applies the given configuration to the running container without touching anything
+    /// that did not change: removed instances and interfaces get stopped, added ones started
+    /// and changed ones re-created, see `ConfigDiff`. Interfaces that serve any re-created
+    /// instance get restarted so that their handlers pick up the new instances.
+    ///
+    /// Relative paths of a configuration without a `base_dir` get resolved against the
+    /// `base_dir` of the current one.
+    ///
+    /// Fails without changing anything if the new configuration is not consistent or
+    /// any of the added or changed instances can not be created.
+    pub fn reload_config(&mut self, config: Configuration) -> Result<ConfigDiff, String> {
+        let diff = self.reload_instances(config)?;
+        self.reload_interfaces(&diff)?;
+        Ok(diff)
+    }
+
+    /// Executes all admin commands that interfaces have queued since the last call.
+    /// Has to be called regularly by whoever owns the container for admin methods
+    /// like "admin/config/reload" to work.
+    pub fn process_admin_commands(&mut self) {
+        while let Ok(command) = self.admin_command_rx.try_recv() {
+            match command {
+                ContainerAdminCommand::ReloadConfig(config, response_tx) => {
+                    let result = self.reload_instances(config);
+                    let diff = result.clone().ok();
+                    let _ = response_tx.send(result);
+                    if let Some(diff) = diff {
+                        if let Err(error) = self.reload_interfaces(&diff) {
+                            self.notify(format!("err/container: {}", error));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// First part of `reload_config()`: stops, removes and (re-)creates instances
+    /// and replaces the current configuration.
+    ///
+    /// All new instances get created before any running instance is touched so that
+    /// a failing instance leaves the container as it was.
+    fn reload_instances(&mut self, mut config: Configuration) -> Result<ConfigDiff, String> {
+        if config.base_dir.is_none() {
+            config.base_dir = self.config.base_dir.clone();
+        }
+        config.check_consistency()?;
+        let diff = ConfigDiff::between(&self.config, &config);
+
+        if self.p2p_config.is_none() {
+            let current_config = std::mem::replace(&mut self.config, config.clone());
+            self.p2p_config = Some(self.initialize_p2p_config());
+            self.config = current_config;
+        }
+
+        // Bridges of new instances have to point to the new callees, so the instances
+        // that keep running and the new ones are collected in a map of their own.
+        let mut instances: InstanceMap = self
+            .instances
+            .iter()
+            .filter(|(id, _)| {
+                !diff.removed_instances.contains(*id) && !diff.changed_instances.contains(*id)
+            })
+            .map(|(id, instance)| (id.clone(), instance.clone()))
+            .collect();
+        let mut created_ids = Vec::new();
+        for id in config.instance_ids_sorted_by_bridge_dependencies()? {
+            if instances.contains_key(&id) {
+                continue;
+            }
+            let instance = self
+                .instantiate_with_callees(&id, &config, &instances)
+                .map_err(|error| error.to_string())?;
+            instances.insert(id.clone(), Arc::new(RwLock::new(instance)));
+            created_ids.push(id);
+        }
+
+        for id in diff
+            .removed_instances
+            .iter()
+            .chain(diff.changed_instances.iter())
+        {
+            if let Some(instance) = self.instances.remove(id) {
+                self.notify(format!("Stopping instance \"{}\"...", id));
+                match instance
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .stop()
+                {
+                    Ok(()) => self.emit_instance_signal(id, Signal::InstanceStopped(id.clone())),
+                    Err(HolochainInstanceError::InstanceNotActiveYet) => (),
+                    Err(error) => self.notify(format!(
+                        "err/container: Could not stop instance \"{}\": {}",
+                        id, error
+                    )),
+                }
+            }
+        }
+
+        self.config = config;
+        self.instances = instances;
+
+        for id in created_ids {
+            self.notify(format!("Starting instance \"{}\"...", id));
+            self.instances[&id]
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .start()
+                .map_err(|error| error.to_string())?;
+            self.emit_instance_signal(&id, Signal::InstanceStarted(id.clone()));
+        }
+
+        Ok(diff)
+    }
+
+    /// Second part of `reload_config()`: stops removed interfaces, starts added ones and
+    /// restarts running interfaces that changed or serve a re-created instance.
+    fn reload_interfaces(&mut self, diff: &ConfigDiff) -> Result<(), String> {
+        for id in diff.removed_interfaces.iter() {
+            self.stop_interface_by_id(id);
+        }
+
+        let restart_ids: Vec<String> = self
+            .config
+            .interfaces
+            .iter()
+            .filter(|interface| self.interface_threads.contains_key(&interface.id))
+            .filter(|interface| {
+                diff.changed_interfaces.contains(&interface.id)
+                    || diff
+                        .added_instances
+                        .iter()
+                        .chain(diff.removed_instances.iter())
+                        .chain(diff.changed_instances.iter())
+                        .any(|id| interface.serves_instance(id))
+            })
+            .map(|interface| interface.id.clone())
+            .collect();
+        for id in restart_ids {
+            self.restart_interface_by_id(id)?;
+        }
+
+        for id in diff.added_interfaces.iter() {
+            let config = self
+                .config
+                .interface_by_id(id)
+                .expect("Added interfaces are part of the current config");
+            self.start_interface(&config)
+                .map_err(|error| error.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    /// Blocks until the instance with the given ID is ready, i.e. running and past its
+    /// readiness grace (see `readiness_grace_ms`), or fails after the given timeout.
+    pub fn wait_until_ready(&self, id: &String, timeout: Duration) -> Result<(), String> {
+        let instance = self
+            .instances
+            .get(id)
+            .ok_or(format!("Instance does not exist: {}", id))?;
+        let deadline = Instant::now() + timeout;
+        while !instance
+            .read()
+            .map_err(|_| instance_poisoned_error(id).to_string())?
+            .ready()
+        {
+            if Instant::now() >= deadline {
+                return Err(format!("Instance \"{}\" did not get ready in time", id));
+            }
+            thread::sleep(Duration::from_millis(READINESS_POLL_INTERVAL_MS));
+        }
+        Ok(())
+    }
+
+    /// Same as `wait_until_ready()`, but also waits until the instance has initialized its
+    /// application and network and can serve calls.
+    /// Fails right away if the initialization of the instance failed.
+    pub fn wait_for_instance_ready(
+        &self,
+        instance_id: &str,
+        timeout: Duration,
+    ) -> Result<(), HolochainInstanceError> {
+        let deadline = Instant::now() + timeout;
+        self.wait_until_ready(&instance_id.to_string(), timeout)
+            .map_err(HolochainError::ErrorGeneric)?;
+        let context = self.instances[instance_id]
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .context()
+            .clone();
+
+        // The observer only sees states after the next action, so it has to be registered
+        // before looking at the current state to not miss the initialization.
+        let (sender, receiver) = channel();
+        let expired = Arc::new(AtomicBool::new(false));
+        if let Some(ref observer_channel) = context.observer_channel {
+            let sender = sender.clone();
+            let expired = expired.clone();
+            let _ = observer_channel.send(Observer {
+                sensor: Box::new(move |state| {
+                    if expired.load(Ordering::SeqCst) {
+                        return true;
+                    }
+                    match initialization_result(state) {
+                        Some(result) => {
+                            let _ = sender.send(result);
+                            true
+                        }
+                        None => false,
+                    }
+                }),
+            });
+        }
+        if let Some(result) = context
+            .state()
+            .and_then(|state| initialization_result(&state))
+        {
+            let _ = sender.send(result);
+        }
+
+        let now = Instant::now();
+        let remaining = if now < deadline {
+            deadline - now
+        } else {
+            Duration::from_millis(0)
+        };
+        let result = receiver.recv_timeout(remaining);
+        // Gets the observer removed with the next action
+        expired.store(true, Ordering::SeqCst);
+        result
+            .map_err(|_| HolochainError::Timeout)?
+            .map_err(HolochainInstanceError::from)
+    }
+
+    /// Calls the given zome function of the instance with the given ID and returns its result.
+    /// Blocks until the call is done, so embedders don't have to lock instances themselves.
+    /// Calls of instances with `prioritized_calls` wait for their turn with priority 0.
+    pub fn call_instance_function(
+        &self,
+        instance_id: &str,
+        zome: &str,
+        cap: Option<CapabilityCall>,
+        function: &str,
+        params: &str,
+    ) -> Result<JsonString, HolochainInstanceError> {
+        let instance = self.instances.get(instance_id).ok_or_else(|| {
+            HolochainInstanceError::InternalFailure(HolochainError::ErrorGeneric(format!(
+                "Instance does not exist: {}",
+                instance_id
+            )))
+        })?;
+        let call_queue = self.call_queue_for(&self.config, instance_id);
+        let _turn = call_queue.as_ref().map(|queue| queue.wait_for_turn(0));
+        instance
+            .write()
+            .map_err(|_| instance_poisoned_error(instance_id))?
+            .call(zome, cap, function, params)
+    }
+
+    /// Looks up the given zome function of the instance with the given ID and checks it
+    /// against the instance's DNA, for calling it repeatedly with `PreparedCall::execute()`
+    /// instead of `call_instance_function()`.
+    /// The returned handle stops working when the instance gets removed, reloaded or swapped.
+    pub fn prepare_call(
+        &self,
+        instance_id: &str,
+        zome: &str,
+        function: &str,
+        cap: Option<CapabilityCall>,
+    ) -> Result<PreparedCall, HolochainInstanceError> {
+        let instance = self.instances.get(instance_id).ok_or_else(|| {
+            HolochainInstanceError::InternalFailure(HolochainError::ErrorGeneric(format!(
+                "Instance does not exist: {}",
+                instance_id
+            )))
+        })?;
+        let call_queue = self.call_queue_for(&self.config, instance_id);
+        PreparedCall::new(instance_id, instance, call_queue, zome, cap, function)
+    }
+
+    /// Describes the DNA of the instance with the given ID, see `Holochain::describe()`.
+    /// Also available through the "info/describe" method of interfaces.
+    pub fn describe_instance(
+        &self,
+        instance_id: &str,
+    ) -> Result<JsonString, HolochainInstanceError> {
+        let instance = self.instances.get(instance_id).ok_or_else(|| {
+            HolochainInstanceError::InternalFailure(HolochainError::ErrorGeneric(format!(
+                "Instance does not exist: {}",
+                instance_id
+            )))
+        })?;
+        instance
+            .read()
+            .map_err(|_| instance_poisoned_error(instance_id))?
+            .describe()
+    }
+
+    /// Has the instance with the given ID grant the given capability and returns the token,
+    /// which callers can then pass to `call_instance_function()` in a CapabilityCall.
+    pub fn grant_capability(
+        &self,
+        instance_id: &str,
+        cap: &str,
+    ) -> Result<Address, HolochainInstanceError> {
+        let instance = self.instances.get(instance_id).ok_or_else(|| {
+            HolochainInstanceError::InternalFailure(HolochainError::ErrorGeneric(format!(
+                "Instance does not exist: {}",
+                instance_id
+            )))
+        })?;
+        instance
+            .write()
+            .map_err(|_| instance_poisoned_error(instance_id))?
+            .grant_capability(cap)
+    }
+
+    /// Returns the full source chain of the instance with the given ID, in the format of
+    /// `dump_source_chain()`, for importing it into another instance with `import_chain()`.
+    pub fn export_chain(&self, instance_id: &str) -> Result<JsonString, HolochainInstanceError> {
+        let instance = self.instances.get(instance_id).ok_or_else(|| {
+            HolochainInstanceError::InternalFailure(HolochainError::ErrorGeneric(format!(
+                "Instance does not exist: {}",
+                instance_id
+            )))
+        })?;
+        let instance = instance
+            .read()
+            .map_err(|_| instance_poisoned_error(instance_id))?;
+        let mut dump = Vec::new();
+        dump_source_chain(&*instance, &mut dump)?;
+        let dump = String::from_utf8(dump)
+            .map_err(|error| HolochainError::ErrorGeneric(error.to_string()))?;
+        Ok(JsonString::from(dump))
+    }
+
+    /// Replaces the source chain of the instance with the given ID with one exported by
+    /// `export_chain()`, e.g. to move an agent to another container.
+    /// The instance has to have the same DNA and agent as the exported one, and the whole
+    /// chain gets validated before anything is committed.
+    /// Instances that already committed more than their DNA and agent are refused, since
+    /// their entries would be dropped from the chain, unless `force` is set.
+    /// Chains need to be signed by the agent unless `allow_unsigned` is set, which chains
+    /// exported from this version always need, since it does not sign headers yet.
+    pub fn import_chain(
+        &mut self,
+        instance_id: &str,
+        data: JsonString,
+        force: bool,
+        allow_unsigned: bool,
+    ) -> Result<(), HolochainInstanceError> {
+        let instance = self.instances.get(instance_id).ok_or_else(|| {
+            HolochainInstanceError::InternalFailure(HolochainError::ErrorGeneric(format!(
+                "Instance does not exist: {}",
+                instance_id
+            )))
+        })?;
+        let chain = read_source_chain_dump(&String::from(data))?;
+        let mut instance = instance
+            .write()
+            .map_err(|_| instance_poisoned_error(instance_id))?;
+        if !force {
+            let state = instance.state()?;
+            let agent = state.agent();
+            let has_committed_entries =
+                agent.chain().iter(&agent.top_chain_header()).any(|header| {
+                    match header.entry_type() {
+                        EntryType::Dna | EntryType::AgentId => false,
+                        _ => true,
+                    }
+                });
+            if has_committed_entries {
+                return Err(HolochainInstanceError::InternalFailure(
+                    HolochainError::ErrorGeneric(format!(
+                        "Instance {} already has entries on its chain, import with force to replace them",
+                        instance_id
+                    )),
+                ));
+            }
+        }
+        instance.import_chain(chain, allow_unsigned)?;
+        Ok(())
+    }
+
+    pub fn instances(&self) -> &InstanceMap {
+        &self.instances
+    }
+
+    pub fn instance_count(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn has_instance(&self, id: &str) -> bool {
+        self.instances.contains_key(id)
+    }
+
+    /// Number of interfaces that got started and not stopped since
+    pub fn interface_count(&self) -> usize {
+        self.interface_threads.len()
+    }
+
+    /// IDs of all instances of this container, sorted alphabetically
+    pub fn instance_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.instances.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// IDs of the configured instances that run the DNA with the given ID, sorted
+    /// alphabetically
+    pub fn instances_sharing_dna(&self, dna_id: &str) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .config
+            .instances
+            .iter()
+            .filter(|instance_config| instance_config.dna == dna_id)
+            .map(|instance_config| instance_config.id.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// IDs of the DNAs that configured instances run, each listed once and sorted
+    /// alphabetically
+    pub fn dnas_in_use(&self) -> Vec<String> {
+        self.config
+            .instances
+            .iter()
+            .map(|instance_config| instance_config.dna.clone())
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// All configured bridges between instances of this container
+    pub fn list_bridges(&self) -> Vec<Bridge> {
+        self.config.bridges.clone()
+    }
+
+    /// The bridges the instance with the given ID calls or gets called through
+    pub fn bridges_for(&self, instance_id: &str) -> Vec<Bridge> {
+        self.config
+            .bridges
+            .iter()
+            .filter(|bridge| bridge.involves(instance_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Configuration of the instance with the given ID, if there is one
+    pub fn instance_config(&self, id: &str) -> Option<InstanceConfiguration> {
+        self.config.instance_by_id(id)
+    }
+
+    /// Address of the agent the instance with the given ID runs as, the same address the
+    /// instance itself uses. None if there is no such instance or its agent's public
+    /// address can't be parsed.
+    pub fn agent_address(&self, instance_id: &str) -> Option<Address> {
+        let instance_config = self.config.instance_by_id(instance_id)?;
+        let agent_config = self.config.agent_by_id(&instance_config.agent)?;
+        let pub_key = KeyBuffer::with_corrected(&agent_config.public_address).ok()?;
+        Some(AgentId::new(&agent_config.name, &pub_key).address())
+    }
+
+    /// Stop all interfaces and stop and clear all instances
+    pub fn shutdown(&mut self) -> Result<(), HolochainInstanceError> {
+        self.stop_all_interfaces();
+        self.stop_all_instances()?;
+        self.instances = HashMap::new();
+        Ok(())
+    }
+
+    /// Like `shutdown()`, but lets zome calls that are in flight finish first.
+    /// Interfaces get stopped right away so that no new calls come in. Then, bridge callers
+    /// before their callees, every instance refuses new calls and gets stopped once the
+    /// calls running on it have returned. That way the bridge calls of a caller can still
+    /// reach its callees until the caller is done.
+    ///
+    /// All of this takes at most `timeout`. Instances that are still busy then only
+    /// refuse calls, since their running call can't be interrupted, and get dropped
+    /// without waiting for that call any longer.
+    pub fn shutdown_graceful(&mut self, timeout: Duration) -> Result<(), HolochainInstanceError> {
+        self.stop_all_interfaces();
+
+        let deadline = Instant::now() + timeout;
+        let mut busy = Vec::new();
+        for id in self
+            .instance_ids_sorted_by_bridge_dependencies()?
+            .into_iter()
+            .rev()
+        {
+            self.calls_in_flight.refuse(&id);
+            while self.calls_in_flight.running(&id) > 0 && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(DRAIN_POLL_INTERVAL_MS));
+            }
+            // Methods that only inspect the instance might still hold its lock
+            let instance = self.instances[&id].clone();
+            loop {
+                let mut hc = match instance.try_write() {
+                    Ok(hc) => hc,
+                    Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+                    Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                        thread::sleep(Duration::from_millis(DRAIN_POLL_INTERVAL_MS));
+                        continue;
+                    }
+                    Err(TryLockError::WouldBlock) => {
+                        busy.push(id.clone());
+                        break;
+                    }
+                };
+                if hc.active() {
+                    self.notify(format!("Stopping instance \"{}\"...", id));
+                    hc.stop()?;
+                    self.emit_instance_signal(&id, Signal::InstanceStopped(id.clone()));
+                }
+                break;
+            }
+        }
+        if !busy.is_empty() {
+            self.notify(format!(
+                "warn/container: Instances still busy after {:?}, dropped without waiting for their calls: {}",
+                timeout,
+                busy.join(", ")
+            ));
+        }
+        self.instances = HashMap::new();
+        Ok(())
+    }
+
+    /// Spawns a n3h child process and returns its IPC URI.
+    /// With `reuse_network` set, the configured `n3h_ipc_uri` gets returned instead,
+    /// without spawning anything.
+    pub fn spawn_network(&mut self) -> Result<String, HolochainError> {
+        let mut network_config =
+            self.config
+                .clone()
+                .network
+                .ok_or(HolochainError::ErrorGeneric(
+                    "attempt to spawn network when not configured".to_string(),
+                ))?;
+        network_config.n3h_path = self.config.resolve_path(&network_config.n3h_path);
+        network_config.n3h_persistence_path = self
+            .config
+            .resolve_path(&network_config.n3h_persistence_path);
+
+        if network_config.reuse_network {
+            if let Some(ipc_uri) = network_config.n3h_ipc_uri {
+                self.notify(format!("Reusing network with binding: {}", ipc_uri));
+                return Ok(ipc_uri);
+            }
+        }
+
+        self.notify(format!(
+            "Spawning network with working directory: {}",
+            network_config.n3h_persistence_path
+        ));
+        network_config
+            .check_n3h_ipc_socket()
+            .map_err(HolochainError::ConfigError)?;
+        let n3h_bin = format!("{}/packages/n3h/bin/n3h", network_config.n3h_path);
+        if !Path::new(&n3h_bin).exists() {
+            return Err(HolochainError::ConfigError(format!(
+                "Could not find n3h at {}, please check n3h_path of the network configuration",
+                n3h_bin
+            )));
+        }
+
+        let attempts = network_config.spawn_retries + 1;
+        let mut retry_delay = Duration::from_millis(network_config.spawn_retry_delay_ms);
+        let mut attempt = 1;
+        let SpawnResult {
+            kill,
+            ipc_binding,
+            p2p_bindings,
+        } = loop {
+            let result = ipc_spawn(
+                "node".to_string(),
+                vec![n3h_bin.clone()],
+                network_config.n3h_persistence_path.clone(),
+                hashmap! {
+                    String::from("N3H_MODE") => network_config.n3h_mode.clone(),
+                    String::from("N3H_WORK_DIR") => network_config.n3h_persistence_path.clone(),
+                    String::from("N3H_IPC_SOCKET") => network_config.n3h_ipc_socket.clone(),
+                },
+                true,
+            );
+            match result {
+                Ok(spawn_result) => break spawn_result,
+                Err(error) => {
+                    if is_executable_not_found(&error, &network_config.n3h_persistence_path) {
+                        return Err(HolochainError::ErrorGeneric(String::from(
+                            "Could not spawn network process: executable \"node\" not found. \
+                             Please install Node.js and make sure it is on the PATH, \
+                             or set n3h_ipc_uri to use an already running n3h",
+                        )));
+                    }
+                    self.notify(format!(
+                        "err/container: Error spawning network process (attempt {} of {})! {:?}",
+                        attempt, attempts, error
+                    ));
+                    if attempt >= attempts {
+                        return Err(HolochainError::ErrorGeneric(format!(
+                            "Could not spawn network process after {} attempts: {}",
+                            attempts, error
+                        )));
+                    }
+                    thread::sleep(retry_delay);
+                    retry_delay *= 2;
+                    attempt += 1;
+                }
+            }
+        };
+        self.network_child_process = kill;
+        self.notify(format!(
+            "Network spawned with binding: {:?}, listening for peers on: {:?}",
+            ipc_binding, p2p_bindings
+        ));
+        self.network_p2p_bindings = Some(p2p_bindings);
+        Ok(ipc_binding)
+    }
+
+    /// URI of the IPC socket of the n3h process instances connect to,
+    /// None if there is no network configured or it could not be spawned.
+    pub fn network_ipc_binding(&self) -> Option<String> {
+        self.network_ipc_binding.clone()
+    }
+
+    /// Addresses the n3h process listens on for peer connections.
+    /// Only known if this container spawned n3h itself.
+    pub fn network_p2p_bindings(&self) -> Option<Vec<String>> {
+        self.network_p2p_bindings.clone()
+    }
+
+    fn instance_p2p_config(&self) -> Result<JsonString, HolochainError> {
+        let config = self.p2p_config.clone().unwrap_or_else(|| {
+            // This should never happen, but we'll throw out a named mock network rather than crashing,
+            // just to be nice (TODO make proper logging statement)
+            self.notify(String::from("warn/container: instance_network_config called before p2p_config initialized! Using default mock network name."));
+            JsonString::from(P2pConfig::named_mock_config("container-default-mock"))
+        });
+        Ok(config)
+    }
+
+    fn initialize_p2p_config(&mut self) -> JsonString {
+        match self.config.network.clone() {
+            // if there is a config then either we need to spawn a process and get the
+            // ipc_uri for it and save it for future calls to `load_config`
+            // or we use that uri value that was created from previous calls!
+            Some(ref net_config) => {
+                let uri = self
+                    .config
+                    .clone()
+                    .network
+                    .unwrap() // unwrap safe because of check above
+                    .n3h_ipc_uri
+                    .clone()
+                    .or_else(|| self.spawn_network().ok());
+                self.network_ipc_binding = uri.clone();
+                JsonString::from(json!(
+                    {
+                        "backend_kind": "IPC",
+                        "backend_config": {
+                            "socketType": "zmq",
+                            "bootstrapNodes": net_config.bootstrap_nodes,
+                            "ipcUri": uri
+                        }
+                    }
+                ))
+            }
+            // if there's no NetworkConfig we won't spawn a network process
+            // and instead configure instances to use a unique mock network
+            None => JsonString::from(P2pConfig::unique_mock_config()),
+        }
+    }
+
+    /// Tries to create all instances configured in the given Configuration object.
+    /// Calls `Configuration::check_consistency()` first and clears `self.instances`.
+    /// The first time we call this, we also initialize the container-wide config
+    /// for use with all instances
+    ///
+    /// @TODO: clean up the container creation process to prevent loading config before proper setup,
+    ///        especially regarding the signal handler.
+    ///        (see https://github.com/holochain/holochain-rust/issues/739)
+    pub fn load_config(&mut self) -> Result<(), LoadConfigError> {
+        let _ = self
+            .config
+            .check_consistency()
+            .map_err(LoadConfigError::Consistency)?;
+
+        if self.p2p_config.is_none() {
+            self.p2p_config = Some(self.initialize_p2p_config());
+        }
+
+        let config = self.config.clone();
+        self.shutdown().map_err(LoadConfigError::Shutdown)?;
+        self.instances = HashMap::new();
+
+        for id in config
+            .instance_ids_sorted_by_bridge_dependencies()
+            .map_err(|error| LoadConfigError::BridgeOrder(error.to_string()))?
+        {
+            let instance = self.instantiate_from_config(&id, &config)?;
+
+            self.instances
+                .insert(id.clone(), Arc::new(RwLock::new(instance)));
+        }
+        Ok(())
+    }
+
+    /// Creates one specific Holochain instance from a given Configuration,
+    /// id string and DnaLoader.
+    pub fn instantiate_from_config(
+        &mut self,
+        id: &String,
+        config: &Configuration,
+    ) -> Result<Holochain, LoadConfigError> {
+        self.instantiate_with_callees(id, config, &self.instances)
+    }
+
+    /// Like `instantiate_from_config()` but looks up the callees of the instance's
+    /// bridges in the given map instead of the running instances.
+    fn instantiate_with_callees(
+        &self,
+        id: &String,
+        config: &Configuration,
+        callees: &InstanceMap,
+    ) -> Result<Holochain, LoadConfigError> {
+        let _ = config
+            .check_consistency()
+            .map_err(LoadConfigError::Consistency)?;
+
+        let instantiation_error = |message: String| LoadConfigError::Instantiation {
+            instance: id.clone(),
+            message,
+        };
+
+        let instance_config = config
+            .instance_by_id(&id)
+            .ok_or_else(|| instantiation_error(String::from("Instance not found in config")))?;
+
+        // Build context:
+        let mut context_builder = ContextBuilder::new();
+
+        // Agent:
+        let agent_config = config.agent_by_id(&instance_config.agent).unwrap();
+        let (agent_id, maybe_secret_key) =
+            load_agent(config, &agent_config, &self.prompted_passphrases)
+                .map_err(&instantiation_error)?;
+        context_builder = context_builder.with_agent(agent_id);
+        if let Some(secret_key) = maybe_secret_key {
+            context_builder = context_builder.with_agent_secret_key(secret_key);
+        }
+
+        let network_config = match instance_config.network {
+            Some(InstanceNetworkConfiguration::UniqueMock) => {
+                JsonString::from(P2pConfig::unique_mock_config())
+            }
+            Some(InstanceNetworkConfiguration::NamedMock { ref name }) => {
+                JsonString::from(P2pConfig::named_mock_config(name))
+            }
+            None => self
+                .instance_p2p_config()
+                .map_err(|error| instantiation_error(error.to_string()))?,
+        };
+        context_builder = context_builder.with_network_config(network_config);
+
+        // Storage:
+        context_builder = match instance_config.storage {
+            StorageConfiguration::Memory => context_builder.with_memory_storage(),
+            StorageConfiguration::File { ref path, shared } => {
+                let mut path =
+                    PathBuf::from(config.resolve_path(&instance_config.resolve_storage_path(path)));
+                if shared {
+                    path.push(&instance_config.id);
+                }
+                context_builder
+                    .with_file_storage(path.to_string_lossy().to_string())
+                    .map_err(|hc_err| {
+                        instantiation_error(format!(
+                            "Error creating context: {}",
+                            hc_err.to_string()
+                        ))
+                    })?
+            }
+        };
+
+        // Instances log to stdout unless they use the container's logger
+        if config.logger.logger_type == "debug" || config.has_stdio_interface() {
+            context_builder = context_builder.with_logger(Arc::new(Mutex::new(
+                ChannelLogger::new(instance_config.id.clone(), self.logger.get_sender()),
+            )));
+        }
+
+        if let Some(validation_timeout_ms) = instance_config.validation_timeout_ms {
+            context_builder = context_builder
+                .with_validation_timeout(Duration::from_millis(validation_timeout_ms));
+        }
+
+        if config.metrics {
+            context_builder =
+                context_builder.with_get_entry_metrics(Arc::new(GetEntryMetrics::new()));
+        }
+
+        if let Some(entry_cache_size) = config.entry_cache_size {
+            context_builder = context_builder.with_entry_cache(Arc::new(EntryCache::new(
+                entry_cache_size,
+                Duration::from_millis(DEFAULT_LIVE_ENTRY_MAX_AGE_MS),
+            )));
+        }
+
+        // Container API
+        let mut api_builder = ContainerApiBuilder::new();
+        // Bridges:
+        let id = instance_config.id.clone();
+        for bridge in config.bridge_dependencies(id.clone()) {
+            assert_eq!(bridge.caller_id, id.clone());
+            let callee_config = config
+                .instance_by_id(&bridge.callee_id)
+                .expect("config.check_consistency()? jumps out if config is broken");
+            let callee_instance = callees.get(&bridge.callee_id).expect(
+                r#"
+                    We have to create instances ordered by bridge dependencies such that we
+                    can expect the callee to be present here because we need it to create
+                    the bridge API"#,
+            );
+
+            let caller_address = Address::from(agent_config.public_address.clone());
+            let capability_calls =
+                bridge_capability_calls(&bridge.callee_id, callee_instance, caller_address)
+                    .map_err(|error| instantiation_error(error.to_string()))?;
+            if let Some(call_queue) = self.call_queue_for(config, &bridge.callee_id) {
+                api_builder =
+                    api_builder.with_named_instance_call_queue(bridge.handle.clone(), call_queue);
+            }
+            api_builder =
+                api_builder.with_named_instance(bridge.handle.clone(), callee_instance.clone());
+            api_builder = api_builder
+                .with_named_instance_capabilities(bridge.handle.clone(), capability_calls);
+            api_builder =
+                api_builder.with_named_instance_config(bridge.handle.clone(), callee_config);
+        }
+        for bridge in config.remote_bridge_dependencies(id.clone()) {
+            let functions =
+                remote_bridge::remote_functions(&bridge).map_err(&instantiation_error)?;
+            api_builder = api_builder.with_remote_bridge(bridge, functions);
+        }
+        context_builder = context_builder.with_container_api(api_builder.spawn());
+        if self.consumes_signals(config) {
+            context_builder = context_builder.with_signals(spawn_signal_relay(
+                self.signal_broadcaster.clone(),
+                instance_config.id.clone(),
+            ));
+        }
+
+        // Spawn context
+        let context = context_builder.spawn();
+
+        let dna = self.load_instance_dna(&instance_config, config)?;
+
+        let mut holochain = Holochain::new(dna, Arc::new(context))
+            .map_err(|hc_err| instantiation_error(hc_err.to_string()))?;
+        holochain.set_readiness_grace(Duration::from_millis(config.readiness_grace_ms));
+        if config.zome_call_metrics {
+            holochain.set_zome_call_metrics(id.clone(), self.zome_call_metrics.clone());
+        }
+        holochain.set_calls_in_flight(id.clone(), self.calls_in_flight.clone());
+        Ok(holochain)
+    }
+
+    /// Checks whether `load_config()` would succeed with the given configuration,
+    /// i.e. whether it is consistent, its bridges can be ordered and all DNAs of its
+    /// instances can be loaded and match their configured hashes.
+    /// Neither touches the running instances nor the network.
+    pub fn validate_config(&self, config: &Configuration) -> Result<(), LoadConfigError> {
+        config
+            .check_consistency()
+            .map_err(LoadConfigError::Consistency)?;
+        config
+            .instance_ids_sorted_by_bridge_dependencies()
+            .map_err(|error| LoadConfigError::BridgeOrder(error.to_string()))?;
+        for instance_config in config.instances.iter() {
+            self.load_instance_dna(instance_config, config)?;
+        }
+        Ok(())
+    }
+
+    /// Loads the DNA of the given instance with the DnaLoader and verifies its hash.
+    fn load_instance_dna(
+        &self,
+        instance_config: &InstanceConfiguration,
+        config: &Configuration,
+    ) -> Result<Dna, LoadConfigError> {
+        let dna_config = config
+            .dna_by_id(&instance_config.dna)
+            .expect("config.check_consistency()? jumps out if config is broken");
+        let dna_load_error = |message: String| LoadConfigError::DnaLoad {
+            instance: instance_config.id.clone(),
+            path: dna_config.file.clone(),
+            message,
+        };
+        let dna = (self.dna_loader)(&config.resolve_path(&dna_config.file))
+            .map_err(|error| dna_load_error(error.to_string()))?;
+
+        if config.validate_wasm_on_load {
+            for (zome_name, zome) in dna.zomes.iter() {
+                wasmi::Module::from_buffer(&zome.code.code).map_err(|error| {
+                    dna_load_error(format!(
+                        "WASM of zome \"{}\" does not compile: {}",
+                        zome_name, error
+                    ))
+                })?;
+            }
+        }
+
+        // Verify DNA hash
+        let dna_hash = dna.address().to_string();
+        if dna_hash != dna_config.hash {
+            let message = format!(
+                "DNA hash mismatch: configured hash is \"{}\" but loaded DNA has hash \"{}\"",
+                dna_config.hash, dna_hash
+            );
+            if config.warn_on_dna_hash_mismatch {
+                self.notify(format!(
+                    "warn/container: Instance \"{}\" from DNA file \"{}\": {}",
+                    instance_config.id, dna_config.file, message
+                ));
+            } else {
+                return Err(dna_load_error(message));
+            }
+        }
+        Ok(dna)
+    }
+
+    /// Default DnaLoader that actually reads files from the filesystem.
+    /// Gzip compressed files get decompressed transparently.
+    pub fn load_dna(file: &String) -> Result<Dna, HolochainError> {
+        let mut bytes = Vec::new();
+        File::open(file)?.read_to_end(&mut bytes)?;
+        if bytes.starts_with(&GZIP_MAGIC_BYTES) {
+            let mut decompressed = Vec::new();
+            GzDecoder::new(&bytes[..]).read_to_end(&mut decompressed)?;
+            bytes = decompressed;
+        }
+        let contents = String::from_utf8(bytes).map_err(|error| {
+            HolochainError::ErrorGeneric(format!("DNA file is not UTF-8: {}", error))
+        })?;
+        Dna::try_from(JsonString::from(contents))
+    }
+
+    fn make_interface_handler(&self, interface_config: &InterfaceConfiguration) -> IoHandler {
+        let instance_subset: InstanceMap = self
+            .instances
+            .iter()
+            .filter(|(id, _)| interface_config.serves_instance(id))
+            .map(|(id, val)| (id.clone(), val.clone()))
+            .collect();
+
+        ContainerApiBuilder::new()
+            .with_structured_zome_errors(self.config.structured_zome_errors)
+            .with_zome_function_input_validation(interface_config.validate_zome_function_inputs)
+            .with_admin_api(interface_config.admin)
+            .with_admin_commands(self.admin_command_tx.clone())
+            .with_network_bindings(
+                self.network_ipc_binding.clone(),
+                self.network_p2p_bindings.clone(),
+            )
+            .with_network_configured(self.config.network.is_some())
+            .with_interface_failures(self.interface_failures.clone())
+            .with_publish_confirmation(
+                self.signal_broadcaster.clone(),
+                interface_config
+                    .publish_confirmation_timeout_ms
+                    .map(Duration::from_millis),
+                self.config
+                    .instances
+                    .iter()
+                    .map(|instance| (instance.id.clone(), instance.agent.clone()))
+                    .collect(),
+            )
+            .with_read_only_functions(if interface_config.read_only {
+                Some(interface_config.read_only_functions.clone())
+            } else {
+                None
+            })
+            .with_call_queues(self.call_queues_for(&instance_subset))
+            .with_zome_call_metrics(self.enabled_zome_call_metrics())
+            .with_instances(instance_subset)
+            .with_instance_configs(self.config.instances.clone())
+            .with_dna_configs(self.config.dnas.clone())
+            .with_agent_configs(self.config.agents.clone())
+            .with_bridges(self.config.bridges.clone())
+            .with_interface_configs(self.config.interfaces.clone())
+            .spawn()
+    }
+
+    /// The zome call metrics, if the config enables them
+    fn enabled_zome_call_metrics(&self) -> Option<Arc<ZomeCallMetrics>> {
+        if self.config.zome_call_metrics {
+            Some(self.zome_call_metrics.clone())
+        } else {
+            None
+        }
+    }
+
+    /// The call queues of those of the given instances that have `prioritized_calls`
+    /// configured, see `call_queue_for()`.
+    fn call_queues_for(&self, instances: &InstanceMap) -> HashMap<String, Arc<CallQueue>> {
+        instances
+            .keys()
+            .filter_map(|id| {
+                self.call_queue_for(&self.config, id)
+                    .map(|call_queue| (id.clone(), call_queue))
+            })
+            .collect()
+    }
+
+    /// The call queue of the instance with the given ID if the given config enables
+    /// `prioritized_calls` for it. Every instance has one queue that the handlers of all
+    /// interfaces, bridges, prepared calls and `call_instance_function()` share.
+    fn call_queue_for(&self, config: &Configuration, id: &str) -> Option<Arc<CallQueue>> {
+        if !config
+            .instance_by_id(id)
+            .map(|instance_config| instance_config.prioritized_calls)
+            .unwrap_or(false)
+        {
+            return None;
+        }
+        let mut call_queues = self.call_queues.lock().unwrap();
+        Some(
+            call_queues
+                .entry(id.to_string())
+                .or_insert_with(|| Arc::new(CallQueue::new()))
+                .clone(),
+        )
+    }
+
+    /// Also returns where the thread reports whether the interface could bind its port.
+    fn spawn_interface_thread(
+        &self,
+        mut interface_config: InterfaceConfiguration,
+    ) -> (InterfaceThread, Receiver<io::Result<()>>) {
+        interface_config.driver = self.config.resolve_driver_paths(&interface_config.driver);
+        let handler = InterfaceHandler::new(self.make_interface_handler(&interface_config));
+        let mut dispatcher = with_replaceable_handler(
+            handler.clone(),
+            interface_config.public_info,
+            interface_config.max_requests_per_second,
+            interface_config.worker_threads,
+        );
+        let signal_subscriptions = SignalSubscriptions::new(
+            self.signal_broadcaster.clone(),
+            self.interface_instance_ids(&interface_config),
+        );
+        signal_subscriptions.add_methods(&mut dispatcher);
+        let thread_signal_subscriptions = signal_subscriptions.clone();
+        let log_sender = self.logger.get_sender();
+        let (kill_switch_tx, kill_switch_rx) = channel();
+        let result: InterfaceThreadResult = Arc::new(Mutex::new(None));
+        let thread_result = result.clone();
+        let interface_failures = self.interface_failures.clone();
+        interface_failures
+            .lock()
+            .unwrap()
+            .remove(&interface_config.id);
+        let zome_call_metrics = self.zome_call_metrics.clone();
+        let (bind_result_tx, bind_result_rx) = channel();
+        let handle = thread::spawn(move || {
+            let started = make_interface(
+                &interface_config,
+                thread_signal_subscriptions,
+                zome_call_metrics,
+            )
+            .and_then(|mut iface| {
+                iface.start(dispatcher)?;
+                Ok(iface)
+            });
+            let mut iface = match started {
+                Ok(iface) => iface,
+                Err(error) => {
+                    let message = error.to_string();
+                    let _ = bind_result_tx.send(Err(error));
+                    *thread_result.lock().unwrap() = Some(Err(message.clone()));
+                    return Err(message);
+                }
+            };
+            let _ = bind_result_tx.send(Ok(()));
+            let result = iface.run(kill_switch_rx).map_err(|error| {
+                let message = format!(
+                    "err/container: Error running interface '{}': {}",
+                    interface_config.id, error
+                );
+                let _ = log_sender.send((String::from("container"), message));
+                interface_failures
+                    .lock()
+                    .unwrap()
+                    .insert(interface_config.id.clone(), error.clone());
+                error
+            });
+            *thread_result.lock().unwrap() = Some(result.clone());
+            result
+        });
+        (
+            InterfaceThread {
+                handle,
+                kill_switch: kill_switch_tx,
+                result,
+                handler,
+                signal_subscriptions,
+            },
+            bind_result_rx,
+        )
+    }
+}
+
+impl<'a> TryFrom<&'a Configuration> for Container {
+    type Error = HolochainError;
+    fn try_from(config: &'a Configuration) -> Result<Self, Self::Error> {
+        let mut container = Container::from_config((*config).clone());
+        container.load_config()?;
+        Ok(container)
+    }
+}
+
+/// Error for instances whose lock got poisoned, see `Container::recover_poisoned_instances()`
+pub(crate) fn instance_poisoned_error(instance_id: &str) -> HolochainInstanceError {
+    HolochainInstanceError::InternalFailure(HolochainError::ErrorGeneric(format!(
+        "Instance {} failed since a call panicked",
+        instance_id
+    )))
+}
+
+/// Whether the instance with the given state is done initializing its application and
+/// network, with the reason if that failed. None while it is still initializing.
+fn initialization_result(state: &State) -> Option<Result<(), HolochainError>> {
+    let nucleus = state.nucleus();
+    match nucleus.status() {
+        NucleusStatus::InitializationFailed(reason) => Some(Err(HolochainError::ErrorGeneric(
+            format!("Initialization failed: {}", reason),
+        ))),
+        NucleusStatus::Initialized if state.network().initialized().is_ok() => Some(Ok(())),
+        _ => None,
+    }
+}
+
+/// Tells whether spawning a process failed because its executable could not be found.
+/// Spawning reports a missing working directory the same way, so that has to exist.
+fn is_executable_not_found(error: &failure::Error, work_dir: &str) -> bool {
+    error
+        .downcast_ref::<io::Error>()
+        .map(|error| error.kind() == io::ErrorKind::NotFound && Path::new(work_dir).is_dir())
+        .unwrap_or(false)
+}
+
+/// Error for an interface that could not bind its port, see `Interface::start()`
+fn interface_start_error(
+    interface_config: &InterfaceConfiguration,
+    error: io::Error,
+) -> InterfaceStartError {
+    match (error.kind(), interface_config.driver.port()) {
+        (io::ErrorKind::AddrInUse, Some(port)) => InterfaceStartError::PortInUse {
+            interface: interface_config.id.clone(),
+            port,
+        },
+        _ => InterfaceStartError::Io {
+            interface: interface_config.id.clone(),
+            message: error.to_string(),
+        },
+    }
+}
+
+/// Interface servers might release their listening socket only after their thread
+/// has finished, so this polls until the port of the given driver can be bound again.
+fn wait_for_port_release(driver: &InterfaceDriver) -> Result<(), String> {
+    let port = match driver.port() {
+        Some(port) => port,
+        None => return Ok(()),
+    };
+    for _ in 0..PORT_RELEASE_RETRIES {
+        if TcpListener::bind(("0.0.0.0", port)).is_ok() {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(PORT_RELEASE_RETRY_DELAY_MS));
+    }
+    Err(format!("Port {} was not released by the stopped interface", port))
+}
+
+/// This can eventually be dependency injected for third party Interface definitions
+/// Fails for custom drivers since there is no way to provide their implementation yet.
+fn make_interface(
+    interface_config: &InterfaceConfiguration,
+    signal_subscriptions: SignalSubscriptions,
+    zome_call_metrics: Arc<ZomeCallMetrics>,
+) -> io::Result<Box<Interface>> {
+    use interface_impls::{
+        domain_socket::DomainSocketInterface, http::HttpInterface, metrics::MetricsInterface,
+        stdio::StdioInterface, websocket::WebsocketInterface, websocket_tls::WebsocketTlsInterface,
+    };
+    let auth_token = interface_config.auth_token.clone();
+    let max_request_bytes = interface_config.max_request_bytes;
+    let interface: Box<Interface> = match interface_config.driver {
+        InterfaceDriver::Websocket { port } => {
+            let interface = WebsocketInterface::new(port, auth_token, signal_subscriptions)
+                .with_max_request_bytes(max_request_bytes);
+            match interface_config.ping_interval_ms {
+                Some(ping_interval) => {
+                    Box::new(interface.with_keep_alive(Duration::from_millis(ping_interval)))
+                }
+                None => Box::new(interface),
+            }
+        }
+        InterfaceDriver::WebsocketTls {
+            port,
+            ref cert_path,
+            ref key_path,
+        } => {
+            let interface = WebsocketTlsInterface::new(
+                port,
+                cert_path.clone(),
+                key_path.clone(),
+                auth_token,
+                signal_subscriptions,
+            )
+            .with_max_request_bytes(max_request_bytes);
+            match interface_config.ping_interval_ms {
+                Some(ping_interval) => {
+                    Box::new(interface.with_keep_alive(Duration::from_millis(ping_interval)))
+                }
+                None => Box::new(interface),
+            }
+        }
+        InterfaceDriver::Http { port } => {
+            let interface =
+                HttpInterface::new(port, auth_token).with_max_request_bytes(max_request_bytes);
+            match interface_config.cors {
+                Some(ref cors) => Box::new(interface.with_cors(cors.clone())),
+                None => Box::new(interface),
+            }
+        }
+        InterfaceDriver::DomainSocket { ref file } => {
+            Box::new(DomainSocketInterface::new(file.clone(), auth_token))
+        }
+        InterfaceDriver::Stdio => Box::new(StdioInterface::new(auth_token)),
+        InterfaceDriver::Metrics {
+            port,
+            ref bind_address,
+        } => {
+            let interface = MetricsInterface::new(port, auth_token, zome_call_metrics);
+            match bind_address {
+                Some(bind_address) => Box::new(interface.with_bind_address(bind_address.clone())),
+                None => Box::new(interface),
+            }
+        }
+        InterfaceDriver::Custom(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Custom interface drivers are not supported",
+            ))
+        }
+    };
+    Ok(interface)
+}
+
+/// Builds the AgentId of the given agent from its public address and, if the agent has
+/// a keystore passphrase configured, loads its secret key from the key file.
+/// Passphrases from the prompt get remembered in `prompted_passphrases` once they
+/// decrypted the key file.
+fn load_agent(
+    config: &Configuration,
+    agent_config: &AgentConfiguration,
+    prompted_passphrases: &Mutex<HashMap<String, String>>,
+) -> Result<(AgentId, Option<SecBuf>), String> {
+    let pub_key =
+        KeyBuffer::with_corrected(&agent_config.public_address).map_err(|e| e.to_string())?;
+    let passphrase_source = agent_config
+        .keystore_passphrase
+        .as_ref()
+        .map(|source| config.resolve_passphrase_source(source));
+    let maybe_secret_key = match passphrase_source {
+        Some(ref passphrase_source) => Some(
+            prompted_passphrase(passphrase_source, &agent_config.id, prompted_passphrases)
+                .map(Ok)
+                .unwrap_or_else(|| read_passphrase(passphrase_source, &agent_config.id))
+                .and_then(|passphrase| {
+                    let secret_key =
+                        Keystore::load(Path::new(&config.resolve_path(&agent_config.key_file)))?
+                            .secret_key(&passphrase, &pub_key)?;
+                    if *passphrase_source == PassphraseSource::Prompt {
+                        prompted_passphrases
+                            .lock()
+                            .unwrap()
+                            .insert(agent_config.id.clone(), passphrase);
+                    }
+                    Ok(secret_key)
+                })
+                .map_err(|error| {
+                    format!(
+                        "Could not load key file {} of agent {}: {}",
+                        agent_config.key_file, agent_config.id, error
+                    )
+                })?,
+        ),
+        None => None,
+    };
+    Ok((AgentId::new(&agent_config.name, &pub_key), maybe_secret_key))
+}
+
+/// The passphrase the given agent got prompted for before, if it still gets it that way
+fn prompted_passphrase(
+    source: &PassphraseSource,
+    agent_id: &str,
+    prompted_passphrases: &Mutex<HashMap<String, String>>,
+) -> Option<String> {
+    match *source {
+        PassphraseSource::Prompt => prompted_passphrases.lock().unwrap().get(agent_id).cloned(),
+        _ => None,
+    }
+}
+
+/// Returns the names of all zome functions the given instance exposes,
+/// formatted as "{zome}/{capability}/{function}".
+fn zome_function_names(hc: &Holochain) -> Result<BTreeSet<String>, HolochainInstanceError> {
+    let dna = hc
+        .state()?
+        .nucleus()
+        .dna()
+        .ok_or(HolochainInstanceError::InternalFailure(
+            HolochainError::DnaMissing,
+        ))?;
+    Ok(dna
+        .zomes
+        .iter()
+        .flat_map(|(zome_name, zome)| {
+            zome.capabilities.iter().flat_map(move |(cap_name, cap)| {
+                cap.functions
+                    .iter()
+                    .map(move |func| format!("{}/{}/{}", zome_name, cap_name, func.name))
+            })
+        })
+        .collect())
+}
+
+/// Has the callee of a bridge grant its transferable capabilities and returns the calls
+/// the caller's bridge API has to present, keyed by capability name.
+/// Public capabilities need no token. Assigned capabilities are left out as well since
+/// grants get created without assignees, so calling them still fails the capability check.
+fn bridge_capability_calls(
+    callee_id: &str,
+    callee: &Arc<RwLock<Holochain>>,
+    caller: Address,
+) -> Result<HashMap<String, CapabilityCall>, HolochainInstanceError> {
+    let mut callee = callee
+        .write()
+        .map_err(|_| instance_poisoned_error(callee_id))?;
+    let dna = callee
+        .state()?
+        .nucleus()
+        .dna()
+        .ok_or(HolochainInstanceError::InternalFailure(
+            HolochainError::DnaMissing,
+        ))?;
+    let mut capability_calls = HashMap::new();
+    for zome in dna.zomes.values() {
+        for (cap_name, cap) in zome.capabilities.iter() {
+            if cap.cap_type != CapabilityType::Transferable
+                || capability_calls.contains_key(cap_name)
+            {
+                continue;
+            }
+            let token = callee.reuse_or_grant_capability(cap_name)?;
+            capability_calls.insert(
+                cap_name.clone(),
+                CapabilityCall::new(cap_name.clone(), token, Some(caller.clone())),
+            );
+        }
+    }
+    Ok(capability_calls)
+}
+
+#[derive(Clone, Debug)]
+struct NullLogger {}
+
+impl Logger for NullLogger {
+    fn log(&mut self, _msg: String) {}
 }
 
-#[derive(Clone, Debug)]
-struct NullLogger {}
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        config::{
+            load_configuration, load_container_configuration, InstanceConfiguration, NetworkConfig,
+            PassphraseSource,
+        },
+        keystore,
+        logger::{LogFormat, LogRules},
+    };
+    use flate2::{write::GzEncoder, Compression};
+    use futures::executor::block_on;
+    use holochain_core::{
+        action::Action,
+        agent::actions::commit::commit_entry,
+        signal::{signal_channel, SignalSender},
+    };
+    use holochain_core_types::{
+        cas::content::Address,
+        chain_header::ChainHeader,
+        crud_status::{create_crud_status_eav, CrudStatus},
+        dna,
+        entry::test_entry,
+        json::RawString,
+    };
+    use holochain_wasm_utils::wasm_target_dir;
+    use std::{env, fs::File, io::Write, sync::mpsc::sync_channel};
+    use tempfile::tempdir;
+    use test_utils::*;
+
+    pub fn test_dna_loader() -> DnaLoader {
+        let loader = Box::new(|path: &String| {
+            Ok(match path.as_ref() {
+                "bridge/callee.dna" => callee_dna(),
+                "bridge/caller.dna" => caller_dna(),
+                _ => Dna::try_from(JsonString::from(example_dna_string())).unwrap(),
+            })
+        }) as Box<Fn(&String) -> Result<Dna, HolochainError> + Send + Sync>;
+        Arc::new(loader)
+    }
+
+    pub fn test_toml() -> String {
+        r#"
+    warn_on_dna_hash_mismatch = true
+
+    [[agents]]
+    id = "test-agent-1"
+    name = "Holo Tester 1"
+    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
+    key_file = "holo_tester.key"
+
+    [[agents]]
+    id = "test-agent-2"
+    name = "Holo Tester 2"
+    public_address = "HoloTester2-----------------------------------------------------------------------AAAGy4WW9e"
+    key_file = "holo_tester.key"
+
+    [[agents]]
+    id = "test-agent-3"
+    name = "Holo Tester 3"
+    public_address = "HoloTester2-----------------------------------------------------------------------AAAGy4WW9e"
+    key_file = "holo_tester.key"
+
+    [[dnas]]
+    id = "test-dna"
+    file = "app_spec.hcpkg"
+    hash = "Qm328wyq38924y"
+
+    [[dnas]]
+    id = "bridge-callee"
+    file = "bridge/callee.dna"
+    hash = "Qm328wyq38924y"
+
+    [[dnas]]
+    id = "bridge-caller"
+    file = "bridge/caller.dna"
+    hash = "Qm328wyq38924y"
+
+    [[instances]]
+    id = "test-instance-1"
+    dna = "bridge-callee"
+    agent = "test-agent-1"
+    [instances.storage]
+    type = "memory"
+
+    [[instances]]
+    id = "test-instance-2"
+    dna = "test-dna"
+    agent = "test-agent-2"
+    [instances.storage]
+    type = "memory"
+
+    [[instances]]
+    id = "bridge-caller"
+    dna = "bridge-caller"
+    agent = "test-agent-3"
+    [instances.storage]
+    type = "memory"
+
+    [[interfaces]]
+    id = "test-interface"
+    [interfaces.driver]
+    type = "websocket"
+    port = 8888
+    [[interfaces.instances]]
+    id = "test-instance-1"
+    [[interfaces.instances]]
+    id = "test-instance-2"
+
+    [[interfaces]]
+    id = "test-interface-http"
+    [interfaces.driver]
+    type = "http"
+    port = 4000
+    [[interfaces.instances]]
+    id = "test-instance-1"
+    [[interfaces.instances]]
+    id = "test-instance-2"
+
+    [[bridges]]
+    caller_id = "test-instance-2"
+    callee_id = "test-instance-1"
+    handle = "DPKI"
+
+    [[bridges]]
+    caller_id = "bridge-caller"
+    callee_id = "test-instance-2"
+    handle = "happ-store"
+
+    [[bridges]]
+    caller_id = "bridge-caller"
+    callee_id = "test-instance-1"
+    handle = "test-callee"
+    "#
+        .to_string()
+    }
+
+    pub fn test_container() -> Container {
+        let config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        let mut container =
+            Container::from_config(config.clone()).with_dna_loader(test_dna_loader());
+        container.load_config().unwrap();
+        container
+    }
+
+    fn test_container_with_signals(signal_tx: SignalSender) -> Container {
+        let config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        let mut container = Container::from_config(config.clone())
+            .with_signal_channel(signal_tx)
+            .with_dna_loader(test_dna_loader());
+        container.load_config().unwrap();
+        container
+    }
+
+    pub fn example_dna_string() -> String {
+        r#"{
+                "name": "my dna",
+                "description": "",
+                "version": "",
+                "uuid": "00000000-0000-0000-0000-000000000001",
+                "dna_spec_version": "2.0",
+                "properties": {},
+                "zomes": {
+                    "": {
+                        "description": "",
+                        "config": {
+                            "error_handling": "throw-errors"
+                        },
+                        "entry_types": {
+                            "": {
+                                "description": "",
+                                "sharing": "public"
+                            }
+                        },
+                        "capabilities": {
+                            "test": {
+                                "type": "public",
+                                "functions": [
+                                    {
+                                        "name": "test",
+                       "inputs" : [
+                            {
+                                "name": "post",
+                                "type": "string"
+                            }
+                        ],
+                        "outputs" : [
+                            {
+                                "name": "hash",
+                                "type": "string"
+                            }
+                        ]
+                                    }
+                                ]
+                            }
+                        },
+                        "code": {
+                            "code": "AAECAw=="
+                        }
+                    }
+                }
+            }"#
+        .to_string()
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot set a DNA loader after having run load_config()")]
+    fn test_container_with_dna_loader_after_load_config() {
+        test_container().with_dna_loader(test_dna_loader());
+    }
+
+    #[test]
+    fn test_container_with_shared_dna_loader() {
+        let loader = test_dna_loader();
+        let _held_elsewhere = loader.clone();
+        let config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        let mut container = Container::from_config(config).with_dna_loader(loader);
+        assert_eq!(container.load_config(), Ok(()));
+        assert!(container.instances().contains_key("test-instance-1"));
+    }
+
+    #[test]
+    fn test_default_dna_loader() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.path().join("test.dna.json");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+        writeln!(tmp_file, "{}", example_dna_string()).unwrap();
+        match Container::load_dna(&file_path.into_os_string().into_string().unwrap()) {
+            Ok(dna) => {
+                assert_eq!(dna.name, "my dna");
+            }
+            Err(_) => assert!(false),
+        }
+    }
+
+    #[test]
+    fn test_default_dna_loader_gzip() {
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.path().join("test.dna.json.gz");
+        let mut encoder = GzEncoder::new(File::create(&file_path).unwrap(), Compression::default());
+        writeln!(encoder, "{}", example_dna_string()).unwrap();
+        encoder.finish().unwrap();
+        let dna = Container::load_dna(&file_path.to_str().unwrap().to_string()).unwrap();
+        assert_eq!(dna.name, "my dna");
+
+        // Truncated archives are reported as errors
+        let bytes = ::std::fs::read(&file_path).unwrap();
+        File::create(&file_path)
+            .unwrap()
+            .write_all(&bytes[..bytes.len() / 2])
+            .unwrap();
+        assert!(Container::load_dna(&file_path.to_str().unwrap().to_string()).is_err());
+    }
+
+    #[test]
+    fn test_container_load_config() {
+        let mut container = test_container();
+        assert_eq!(container.instance_count(), 3);
+        assert!(container.has_instance("test-instance-1"));
+        assert!(!container.has_instance("unknown-instance"));
+
+        container.start_all_instances().unwrap();
+        container.start_all_interfaces().unwrap();
+        container.stop_all_instances().unwrap();
+    }
+
+    #[test]
+    fn test_container_list_bridges() {
+        let container = test_container();
+        assert_eq!(container.list_bridges().len(), 3);
+        let handles = |instance_id: &str| -> Vec<String> {
+            container
+                .bridges_for(instance_id)
+                .into_iter()
+                .map(|bridge| bridge.handle)
+                .collect()
+        };
+        assert_eq!(handles("test-instance-2"), vec!["DPKI", "happ-store"]);
+        assert_eq!(handles("bridge-caller"), vec!["happ-store", "test-callee"]);
+        assert!(handles("unknown-instance").is_empty());
+    }
+
+    /// Log output that tests can read back
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_container_notify_uses_log_rules() {
+        let mut rules = LogRules::new();
+        rules
+            .add_rule("^(Starting|Stopping) instance", true, None)
+            .unwrap();
+        rules.add_rule(".*", false, None).unwrap();
+        let output = SharedOutput::default();
+
+        let mut container = test_container();
+        container.logger =
+            DebugLogger::with_output(rules, LogFormat::Text, Box::new(output.clone()));
+        container.start_all_instances().unwrap();
+        container.stop_all_instances().unwrap();
+        container.notify(String::from("done"));
+
+        // Messages get rendered by the logger thread in the order they were sent
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let rendered = loop {
+            let rendered = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+            if rendered.contains("done") || Instant::now() >= deadline {
+                break rendered;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+        assert!(rendered.contains("container"));
+        assert!(rendered.contains("done"));
+        assert!(!rendered.contains("Starting instance"));
+        assert!(!rendered.contains("Stopping instance"));
+    }
+
+    #[test]
+    fn test_container_stop_all_interfaces() {
+        let mut container = test_container();
+        container.start_all_interfaces().unwrap();
+        assert!(!container.interface_threads.is_empty());
+
+        // returns only after all interface threads have been joined
+        container.stop_all_interfaces();
+        assert!(container.interface_threads.is_empty());
+
+        container.start_all_interfaces().unwrap();
+        container.shutdown().unwrap();
+        assert!(container.interface_threads.is_empty());
+    }
+
+    #[test]
+    fn test_container_restart_interface_by_id() {
+        let mut container = test_container();
+        let id = String::from("restart-interface");
+        container.config.interfaces[0].id = id.clone();
+        container.config.interfaces[0].driver = InterfaceDriver::Websocket { port: 8890 };
+
+        // not running yet, so this just starts it
+        container.restart_interface_by_id(id.clone()).unwrap();
+        assert!(container.interface_threads.contains_key(&id));
+
+        container.restart_interface_by_id(id.clone()).unwrap();
+        assert!(container.interface_threads.contains_key(&id));
+        assert_eq!(container.interface_count(), 1);
+
+        assert!(container
+            .restart_interface_by_id(String::from("non-existent"))
+            .is_err());
+        container.stop_all_interfaces();
+    }
+
+    #[test]
+    fn test_container_interface_status() {
+        let mut container = test_container();
+        let id = String::from("status-interface");
+        container.config.interfaces.truncate(1);
+        container.config.interfaces[0].id = id.clone();
+        container.config.interfaces[0].driver = InterfaceDriver::Websocket { port: 8891 };
+        assert_eq!(container.interface_status()[&id], InterfaceState::Stopped);
+
+        container.restart_interface_by_id(id.clone()).unwrap();
+        assert_eq!(container.interface_status()[&id], InterfaceState::Running);
+
+        container.stop_all_interfaces();
+        assert_eq!(container.interface_status()[&id], InterfaceState::Stopped);
+
+        // with the port taken the interface fails to start
+        let _listener = TcpListener::bind(("0.0.0.0", 8891)).unwrap();
+        let interface_config = container.config.interfaces[0].clone();
+        assert_eq!(
+            container.start_interface(&interface_config),
+            Err(InterfaceStartError::PortInUse {
+                interface: id.clone(),
+                port: 8891,
+            })
+        );
+        assert!(!container.interface_threads.contains_key(&id));
+        match container.interface_status()[&id] {
+            InterfaceState::Failed(ref error) => assert!(error.contains("already in use")),
+            ref state => panic!("Expected interface to have failed, got {:?}", state),
+        }
+        assert!(container.start_all_interfaces().is_err());
+    }
+
+    #[test]
+    fn test_container_custom_interface_fails_to_start() {
+        let mut container = test_container();
+        let mut interface_config = container.config.interfaces[0].clone();
+        interface_config.driver = InterfaceDriver::Custom(toml::Value::String("driver".into()));
+        assert_eq!(
+            container.start_interface(&interface_config),
+            Err(InterfaceStartError::Io {
+                interface: interface_config.id.clone(),
+                message: String::from("Custom interface drivers are not supported"),
+            })
+        );
+        assert!(!container
+            .interface_threads
+            .contains_key(&interface_config.id));
+    }
+
+    #[test]
+    fn test_container_wait_until_ready() {
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        config.readiness_grace_ms = 200;
+        let mut container = Container::from_config(config);
+        container.dna_loader = test_dna_loader();
+        container.load_config().unwrap();
+        let id = String::from("test-instance-1");
+
+        // not started yet
+        assert!(container
+            .wait_until_ready(&id, Duration::from_millis(10))
+            .is_err());
+
+        container.start_all_instances().unwrap();
+        assert!(!container.instances()[&id].read().unwrap().ready());
+        container
+            .wait_until_ready(&id, Duration::from_secs(5))
+            .unwrap();
+        assert!(container.instances()[&id].read().unwrap().ready());
+    }
+
+    #[test]
+    fn test_container_wait_for_instance_ready() {
+        let mut container = test_container();
+        container.start_all_instances().unwrap();
+        assert_eq!(
+            container.wait_for_instance_ready("test-instance-1", Duration::from_secs(5)),
+            Ok(())
+        );
+        assert_eq!(
+            container.wait_for_instance_ready("unknown-instance", Duration::from_secs(5)),
+            Err(HolochainInstanceError::InternalFailure(
+                HolochainError::ErrorGeneric(String::from(
+                    "Instance does not exist: unknown-instance"
+                ))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_container_domain_socket_interface() {
+        let tempdir = tempdir().unwrap();
+        let path = tempdir.path().join("holochain.sock");
+        let path_string = path.to_str().unwrap().to_string();
+        // stale socket file of a crashed process
+        File::create(&path).unwrap();
+
+        let mut container = test_container();
+        let id = String::from("domain-socket-interface");
+        container.config.interfaces.truncate(1);
+        container.config.interfaces[0].id = id.clone();
+        container.config.interfaces[0].driver = InterfaceDriver::DomainSocket { file: path_string };
+
+        container.restart_interface_by_id(id.clone()).unwrap();
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(container.interface_status()[&id], InterfaceState::Running);
+        assert!(path.exists());
+
+        container.stop_all_interfaces();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_container_try_from_configuration() {
+        let config = load_configuration::<Configuration>(&test_toml()).unwrap();
+
+        let maybe_container = Container::try_from(&config);
+
+        match maybe_container {
+            Err(HolochainError::ConfigError(message)) => assert!(message.starts_with(
+                "Error while trying to create instance \"test-instance-1\" from DNA file \"bridge/callee.dna\": "
+            )),
+            _ => panic!("Expected a ConfigError"),
+        }
+    }
+
+    #[test]
+    fn test_container_remove_instance() {
+        let mut container = test_container();
+        container.start_all_instances().unwrap();
+
+        // test-instance-1 is the callee of two bridges
+        let callee = container.instances()["test-instance-1"].clone();
+        container
+            .remove_instance(&String::from("test-instance-1"))
+            .unwrap();
+        assert_eq!(container.instances().len(), 2);
+        assert!(!callee.read().unwrap().active());
+        assert!(container
+            .config()
+            .interfaces
+            .iter()
+            .all(|interface| interface
+                .instances
+                .iter()
+                .all(|instance| instance.id != "test-instance-1")));
+        assert!(container
+            .config()
+            .bridges
+            .iter()
+            .all(|bridge| bridge.callee_id != "test-instance-1"));
+        assert_eq!(
+            callee.write().unwrap().start(),
+            Err(HolochainInstanceError::InstanceRemoved)
+        );
+
+        // The caller keeps running but its bridge calls to the removed callee fail
+        let caller = container.instances()["bridge-caller"].clone();
+        assert!(caller.read().unwrap().active());
+        let bridge_api = caller
+            .read()
+            .unwrap()
+            .context()
+            .container_api
+            .clone()
+            .unwrap();
+        let request = r#"{"jsonrpc": "2.0", "method": "test-callee/greeter/public/hello", "params": {}, "id": 1}"#;
+        let response = bridge_api
+            .read()
+            .unwrap()
+            .handle_request_sync(request)
+            .unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("removed from its container"));
+
+        container
+            .remove_instance(&String::from("test-instance-2"))
+            .unwrap();
+        assert_eq!(container.instances().len(), 1);
+        assert!(container
+            .config()
+            .instance_by_id("test-instance-2")
+            .is_none());
+        assert!(container.config().bridges.is_empty());
+
+        assert!(container
+            .remove_instance(&String::from("test-instance-2"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_container_config_toml() {
+        let mut container = test_container();
+        container
+            .remove_instance(&String::from("bridge-caller"))
+            .unwrap();
+
+        let toml = container.config_toml().unwrap();
+        let config = load_configuration::<Configuration>(&toml).unwrap();
+        assert_eq!(
+            config
+                .instances
+                .iter()
+                .map(|i| i.id.clone())
+                .collect::<Vec<_>>(),
+            vec!["test-instance-1", "test-instance-2"]
+        );
+        assert!(config.check_consistency().is_ok());
+        assert_eq!(Container::from_config(config).config_toml().unwrap(), toml);
+    }
+
+    #[test]
+    fn test_container_config_toml_keeps_env_var_references() {
+        env::set_var("HC_TEST_CONFIG_TOML_NAME", "Holo Tester 1");
+        let toml = test_toml().replace(
+            "name = \"Holo Tester 1\"",
+            "name = \"${HC_TEST_CONFIG_TOML_NAME}\"",
+        );
+        let config = load_container_configuration(&toml).unwrap();
+        assert_eq!(config.agents[0].name, "Holo Tester 1");
+
+        let toml = Container::from_config(config).config_toml().unwrap();
+        assert!(toml.contains("${HC_TEST_CONFIG_TOML_NAME}"));
+        assert!(!toml.contains("Holo Tester 1"));
+    }
+
+    #[test]
+    fn test_container_add_instance() {
+        let mut container = test_container();
+        let bridge_caller = String::from("bridge-caller");
+        let instance_2 = String::from("test-instance-2");
+        container.instances.remove(&bridge_caller);
+        container.start_all_instances().unwrap();
+        assert_eq!(container.instances().len(), 2);
+
+        container.add_instance(&bridge_caller).unwrap();
+        assert_eq!(container.instances().len(), 3);
+        assert!(container.instances().contains_key("bridge-caller"));
+        assert!(container.instances()[&bridge_caller]
+            .read()
+            .unwrap()
+            .active());
+
+        assert!(container.add_instance(&bridge_caller).is_err());
+        assert!(container
+            .add_instance(&String::from("non-existent"))
+            .is_err());
+
+        // bridge-caller can't be added while one of its callees is missing
+        container.instances.remove(&bridge_caller);
+        container.instances.remove(&instance_2);
+        let result = container.add_instance(&bridge_caller);
+        assert!(result.is_err());
+        assert!(result.err().unwrap().contains("\"test-instance-2\""));
+    }
+
+    #[test]
+    fn test_container_all_instances_interface() {
+        let mut container = test_container();
+        let bridge_caller = String::from("bridge-caller");
+        container.instances.remove(&bridge_caller);
+        container.start_all_instances().unwrap();
+        let id = String::from("all-instances-interface");
+        container.config.interfaces.truncate(1);
+        container.config.interfaces[0].id = id.clone();
+        container.config.interfaces[0].driver = InterfaceDriver::Websocket { port: 8894 };
+        container.config.interfaces[0].instances = Vec::new();
+        container.config.interfaces[0].all_instances = true;
+        assert_eq!(container.config.check_consistency(), Ok(()));
+        container.start_all_interfaces().unwrap();
+
+        let interface_thread_id =
+            |container: &Container| container.interface_threads[&id].handle.thread().id();
+        let thread_id = interface_thread_id(&container);
+        // Asks the handler of the running interface
+        let served_ids = |container: &Container| {
+            let io = container.interface_threads[&id].handler.current();
+            let request =
+                r#"{"jsonrpc": "2.0", "method": "info/instances", "params": null, "id": 1}"#;
+            let response = io.handle_request_sync(request).unwrap();
+            let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+            let infos: serde_json::Value =
+                serde_json::from_str(response["result"].as_str().unwrap()).unwrap();
+            let mut ids: Vec<String> = infos
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|info| info["id"].as_str().unwrap().to_string())
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(
+            served_ids(&container),
+            vec!["test-instance-1", "test-instance-2"]
+        );
+
+        container.add_instance(&bridge_caller).unwrap();
+        assert_eq!(
+            served_ids(&container),
+            vec!["bridge-caller", "test-instance-1", "test-instance-2"]
+        );
+        assert_eq!(container.interface_status()[&id], InterfaceState::Running);
+        assert_eq!(interface_thread_id(&container), thread_id);
+
+        container.remove_instance(&bridge_caller).unwrap();
+        assert_eq!(
+            served_ids(&container),
+            vec!["test-instance-1", "test-instance-2"]
+        );
+        assert_eq!(container.interface_status()[&id], InterfaceState::Running);
+        assert_eq!(interface_thread_id(&container), thread_id);
+        container.stop_all_interfaces();
+    }
+
+    #[test]
+    fn test_container_spawn_network_reuses_existing() {
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        config.network = Some(NetworkConfig {
+            bootstrap_nodes: Vec::new(),
+            n3h_path: String::from("/nonexistent/n3h"),
+            n3h_mode: String::from("HACK"),
+            n3h_persistence_path: String::from("/nonexistent/n3h_persistence"),
+            n3h_ipc_uri: Some(String::from("tcp://127.0.0.1:12345")),
+            reuse_network: true,
+            spawn_retries: 0,
+            spawn_retry_delay_ms: 0,
+            n3h_ipc_socket: String::from("tcp://127.0.0.1:*"),
+        });
+        let mut container = Container::from_config(config);
+        assert_eq!(
+            container.spawn_network(),
+            Ok(String::from("tcp://127.0.0.1:12345"))
+        );
+        assert!(container.network_child_process.is_none());
+    }
+
+    #[test]
+    fn test_container_spawn_network_reports_missing_n3h() {
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        config.network = Some(NetworkConfig {
+            bootstrap_nodes: Vec::new(),
+            n3h_path: String::from("/nonexistent/n3h"),
+            n3h_mode: String::from("HACK"),
+            n3h_persistence_path: String::from("/nonexistent/n3h_persistence"),
+            n3h_ipc_uri: None,
+            reuse_network: false,
+            spawn_retries: 0,
+            spawn_retry_delay_ms: 0,
+            n3h_ipc_socket: String::from("tcp://127.0.0.1:*"),
+        });
+        let mut container = Container::from_config(config);
+        assert_eq!(
+            container.spawn_network(),
+            Err(HolochainError::ConfigError(String::from(
+                "Could not find n3h at /nonexistent/n3h/packages/n3h/bin/n3h, \
+                 please check n3h_path of the network configuration"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_container_reload_config() {
+        let mut container = test_container();
+        container.start_all_instances().unwrap();
+        let instance_1 = container.instances()["test-instance-1"].clone();
+        let instance_2 = container.instances()["test-instance-2"].clone();
+
+        // Inconsistent configs get rejected without touching any instance
+        let broken_toml = test_toml().replace(r#"agent = "test-agent-2""#, r#"agent = "nope""#);
+        let broken_config = load_configuration::<Configuration>(&broken_toml).unwrap();
+        assert!(container.reload_config(broken_config).is_err());
+        assert!(Arc::ptr_eq(
+            &container.instances()["test-instance-2"],
+            &instance_2
+        ));
+        assert_eq!(
+            container
+                .config()
+                .instance_by_id("test-instance-2")
+                .unwrap()
+                .agent,
+            "test-agent-2"
+        );
+
+        let new_toml =
+            test_toml().replace(r#"agent = "test-agent-2""#, r#"agent = "test-agent-3""#);
+
+        // Instances that can't be created reject the reload before anything gets stopped
+        let failing_toml = new_toml.replace(
+            r#"dna = "bridge-caller"
+    agent = "test-agent-3"
+    [instances.storage]
+    type = "memory"
+"#,
+            r#"dna = "bridge-caller"
+    agent = "test-agent-3"
+    [instances.storage]
+    type = "file"
+    path = "/dev/null/storage"
+"#,
+        );
+        let failing_config = load_configuration::<Configuration>(&failing_toml).unwrap();
+        assert!(container.reload_config(failing_config).is_err());
+        assert!(Arc::ptr_eq(
+            &container.instances()["test-instance-2"],
+            &instance_2
+        ));
+        assert!(instance_2.read().unwrap().active());
+
+        let new_config = load_configuration::<Configuration>(&new_toml).unwrap();
+        let diff = container.reload_config(new_config).unwrap();
+        assert_eq!(
+            diff.changed_instances,
+            vec![
+                String::from("test-instance-2"),
+                String::from("bridge-caller")
+            ]
+        );
+        assert!(diff.added_instances.is_empty());
+        assert!(diff.removed_instances.is_empty());
+
+        assert_eq!(container.instances().len(), 3);
+        assert!(Arc::ptr_eq(
+            &container.instances()["test-instance-1"],
+            &instance_1
+        ));
+        assert!(!Arc::ptr_eq(
+            &container.instances()["test-instance-2"],
+            &instance_2
+        ));
+        assert!(container.instances()["test-instance-2"]
+            .read()
+            .unwrap()
+            .active());
+        assert!(!instance_2.read().unwrap().active());
+    }
+
+    #[test]
+    fn test_rpc_config_reload() {
+        let mut container = test_container();
+        container.start_all_instances().unwrap();
+        let mut interface_config = container.config.interfaces[0].clone();
+        interface_config.admin = true;
+        let io = container.make_interface_handler(&interface_config);
+
+        let new_toml =
+            test_toml().replace(r#"agent = "test-agent-2""#, r#"agent = "test-agent-3""#);
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": "admin/config/reload",
+            "params": {"config": new_toml},
+            "id": 1
+        })
+        .to_string();
+        let (response_tx, response_rx) = channel();
+        thread::spawn(move || {
+            response_tx
+                .send(io.handle_request_sync(&request).unwrap())
+                .unwrap()
+        });
+
+        // The container only applies the reload once we process admin commands
+        let response = loop {
+            container.process_admin_commands();
+            if let Ok(response) = response_rx.recv_timeout(Duration::from_millis(10)) {
+                break response;
+            }
+        };
+        assert!(response.contains(r#"\"changed_instances\":[\"test-instance-2\""#));
+        assert_eq!(
+            container
+                .config()
+                .instance_by_id("test-instance-2")
+                .unwrap()
+                .agent,
+            "test-agent-3"
+        );
+    }
+
+    #[test]
+    fn test_container_swap_instances() {
+        let mut container = test_container();
+        let instance_1 = String::from("test-instance-1");
+        let instance_2 = String::from("test-instance-2");
+        let instance_4 = String::from("test-instance-4");
+
+        // all instances in the test config are bridged
+        let result = container.swap_instances(&instance_1, &instance_2);
+        assert!(result
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("because it is part of a bridge"));
+
+        container.config.bridges = Vec::new();
+        container.config.instances.push(InstanceConfiguration {
+            id: instance_4.clone(),
+            dna: String::from("test-dna"),
+            agent: String::from("test-agent-1"),
+            storage: StorageConfiguration::Memory,
+            network: None,
+            priority: 0,
+            validation_timeout_ms: None,
+            prioritized_calls: false,
+        });
+        let config = container.config.clone();
+        let holochain = container
+            .instantiate_from_config(&instance_4, &config)
+            .unwrap();
+        container
+            .instances
+            .insert(instance_4.clone(), Arc::new(RwLock::new(holochain)));
+        container.start_all_instances().unwrap();
+
+        // test-instance-1 and test-instance-2 run different DNAs
+        let result = container.swap_instances(&instance_1, &instance_2);
+        assert!(result
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("they don't expose the same zome functions"));
+
+        assert!(container.swap_instances(&instance_2, &instance_2).is_err());
+
+        container.swap_instances(&instance_2, &instance_4).unwrap();
+        assert_eq!(
+            container.config.instance_by_id(&instance_2).unwrap().agent,
+            "test-agent-1"
+        );
+        assert_eq!(
+            container.config.instance_by_id(&instance_4).unwrap().agent,
+            "test-agent-2"
+        );
+        assert_eq!(
+            container.instances[&instance_2]
+                .read()
+                .unwrap()
+                .context()
+                .agent_id
+                .nick,
+            "Holo Tester 1"
+        );
+    }
+
+    #[test]
+    fn test_container_diff_instances() {
+        let container = test_container();
+        let instance_1 = String::from("test-instance-1");
+        let instance_2 = String::from("test-instance-2");
+        let add_status = |id: &String, address: &Address, status: CrudStatus| {
+            let eav_storage = container.instances[id]
+                .read()
+                .unwrap()
+                .context()
+                .eav_storage
+                .clone();
+            let eav = create_crud_status_eav(address, status).unwrap();
+            eav_storage.write().unwrap().add_eav(&eav).unwrap();
+        };
+
+        assert!(container
+            .diff_instances(&instance_1, &instance_1)
+            .unwrap()
+            .is_empty());
+        assert!(container
+            .diff_instances(&instance_1, &String::from("non-existent"))
+            .is_err());
+
+        let only_in_1 = Address::from("QmOnlyInInstance1");
+        let deleted_in_2 = Address::from("QmDeletedInInstance2");
+        add_status(&instance_1, &only_in_1, CrudStatus::Live);
+        add_status(&instance_1, &deleted_in_2, CrudStatus::Live);
+        add_status(&instance_2, &deleted_in_2, CrudStatus::Live);
+        add_status(&instance_2, &deleted_in_2, CrudStatus::Deleted);
+
+        let diff = container.diff_instances(&instance_1, &instance_2).unwrap();
+        assert!(diff.only_in_a.contains(&only_in_1));
+        assert!(!diff.only_in_b.contains(&only_in_1));
+        assert!(diff.crud_status_mismatches.contains(&(
+            deleted_in_2.clone(),
+            CrudStatus::Live,
+            CrudStatus::Deleted
+        )));
+
+        let diff = container.diff_instances(&instance_2, &instance_1).unwrap();
+        assert!(diff.only_in_b.contains(&only_in_1));
+    }
+
+    #[test]
+    fn test_container_dna_hash_mismatch() {
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        config.warn_on_dna_hash_mismatch = false;
+        let mut container = Container::from_config(config);
+        container.dna_loader = test_dna_loader();
+
+        let result = container.load_config();
+        assert!(result.is_err());
+        let error = result.err().unwrap();
+        match error {
+            LoadConfigError::DnaLoad {
+                ref instance,
+                ref path,
+                ..
+            } => {
+                assert_eq!(instance, "test-instance-1");
+                assert_eq!(path, "bridge/callee.dna");
+            }
+            _ => panic!("Expected a DnaLoad error, got {:?}", error),
+        }
+        let message = error.to_string();
+        assert!(message.contains(
+            "instance \"test-instance-1\" from DNA file \"bridge/callee.dna\": DNA hash mismatch"
+        ));
+        assert!(message.contains("Qm328wyq38924y"));
+    }
+
+    #[test]
+    fn test_container_shared_file_storage() {
+        let tempdir = tempdir().unwrap();
+        let path = tempdir.path().to_str().unwrap().to_string();
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        for instance in config.instances.iter_mut().take(2) {
+            instance.storage = StorageConfiguration::File {
+                path: path.clone(),
+                shared: false,
+            };
+        }
+
+        let mut container = Container::from_config(config.clone());
+        container.dna_loader = test_dna_loader();
+        let message = container.load_config().unwrap_err().to_string();
+        assert!(message.contains("both store their data in"));
+
+        for instance in config.instances.iter_mut().take(2) {
+            instance.storage = StorageConfiguration::File {
+                path: path.clone(),
+                shared: true,
+            };
+        }
+        let mut container = Container::from_config(config.clone());
+        container.dna_loader = test_dna_loader();
+        container.load_config().unwrap();
+        for instance in config.instances.iter().take(2) {
+            assert!(tempdir.path().join(&instance.id).join("cas").is_dir());
+            assert!(tempdir.path().join(&instance.id).join("eav").is_dir());
+        }
+    }
+
+    #[test]
+    fn test_container_templated_file_storage() {
+        let tempdir = tempdir().unwrap();
+        let path = tempdir.path().to_str().unwrap().to_string();
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        for instance in config.instances.iter_mut().take(2) {
+            instance.storage = StorageConfiguration::File {
+                path: format!("{}/{{agent_id}}/{{instance_id}}", path),
+                shared: false,
+            };
+        }
+        let mut container = Container::from_config(config.clone());
+        container.dna_loader = test_dna_loader();
+        container.load_config().unwrap();
+        for instance in config.instances.iter().take(2) {
+            let instance_path = tempdir.path().join(&instance.agent).join(&instance.id);
+            assert!(instance_path.join("cas").is_dir());
+            assert!(instance_path.join("eav").is_dir());
+        }
+
+        // Both instances would end up in the same directory
+        config.instances[1].agent = config.instances[0].agent.clone();
+        for instance in config.instances.iter_mut().take(2) {
+            instance.storage = StorageConfiguration::File {
+                path: format!("{}/{{agent_id}}", path),
+                shared: false,
+            };
+        }
+        let mut container = Container::from_config(config);
+        container.dna_loader = test_dna_loader();
+        let message = container.load_config().unwrap_err().to_string();
+        assert!(message.contains("both store their data in"));
+    }
+
+    #[test]
+    fn test_container_base_dir() {
+        let base_dir = tempdir().unwrap();
+        let other_dir = tempdir().unwrap();
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        config.base_dir = Some(base_dir.path().to_string_lossy().to_string());
+        config.instances[0].storage = StorageConfiguration::File {
+            path: String::from("storage/{instance_id}"),
+            shared: false,
+        };
+        config.instances[1].storage = StorageConfiguration::File {
+            path: other_dir.path().to_string_lossy().to_string(),
+            shared: false,
+        };
+
+        let loaded_paths = Arc::new(Mutex::new(Vec::new()));
+        let recorded_paths = loaded_paths.clone();
+        let loader = Box::new(move |path: &String| {
+            recorded_paths.lock().unwrap().push(path.clone());
+            let file = Path::new(path).file_name().unwrap().to_string_lossy();
+            test_dna_loader()(&format!("bridge/{}", file))
+        }) as Box<Fn(&String) -> Result<Dna, HolochainError> + Send + Sync>;
+        let mut container = Container::from_config(config);
+        container.dna_loader = Arc::new(loader);
+        container.load_config().unwrap();
+
+        let callee_path = base_dir.path().join("bridge/callee.dna");
+        assert!(loaded_paths
+            .lock()
+            .unwrap()
+            .contains(&callee_path.to_string_lossy().to_string()));
+        let instance_path = base_dir.path().join("storage").join("test-instance-1");
+        assert!(instance_path.join("cas").is_dir());
+        assert!(other_dir.path().join("cas").is_dir());
+
+        let mut reloaded_config = container.config.clone();
+        reloaded_config.base_dir = None;
+        container.reload_config(reloaded_config).unwrap();
+        assert_eq!(
+            container.config.base_dir,
+            Some(base_dir.path().to_string_lossy().to_string())
+        );
+    }
+
+    #[test]
+    fn test_container_instance_network_override() {
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        for instance in config.instances.iter_mut() {
+            instance.network = Some(match instance.id.as_ref() {
+                "bridge-caller" => InstanceNetworkConfiguration::UniqueMock,
+                _ => InstanceNetworkConfiguration::NamedMock {
+                    name: String::from("shared-mock"),
+                },
+            });
+        }
+        let mut container = Container::from_config(config);
+        container.dna_loader = test_dna_loader();
+        container.load_config().unwrap();
+
+        let network_config = |id: &str| {
+            container.instances[id]
+                .read()
+                .unwrap()
+                .context()
+                .network_config
+                .clone()
+        };
+        // Mock networks of the same name are one network, so these two see each other's data
+        let shared = JsonString::from(P2pConfig::named_mock_config("shared-mock"));
+        assert_eq!(network_config("test-instance-1"), shared);
+        assert_eq!(network_config("test-instance-2"), shared);
+        assert_ne!(network_config("bridge-caller"), shared);
+    }
+
+    #[test]
+    fn test_container_agent_keystore() {
+        let (mut seed, public_address) = keystore::tests::test_seed_and_public_address();
+        let dir = tempdir().unwrap();
+        let key_file = dir.path().join("holo_tester.key");
+        Keystore::new(&mut seed, "secret")
+            .unwrap()
+            .save(&key_file)
+            .unwrap();
+        env::set_var("HC_TEST_CONTAINER_KEYSTORE_PASSPHRASE", "secret");
+
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        let agent = config
+            .agents
+            .iter_mut()
+            .find(|agent| agent.id == "test-agent-1")
+            .unwrap();
+        agent.public_address = public_address;
+        agent.key_file = key_file.to_string_lossy().to_string();
+        agent.keystore_passphrase = Some(PassphraseSource::Env {
+            variable: String::from("HC_TEST_CONTAINER_KEYSTORE_PASSPHRASE"),
+        });
+        let mut container =
+            Container::from_config(config.clone()).with_dna_loader(test_dna_loader());
+        let instance = container
+            .instantiate_from_config(&String::from("test-instance-1"), &config)
+            .unwrap();
+        assert!(instance.context().sign(String::from("message")).is_ok());
+
+        config.agents[0].key_file = dir.path().join("missing.key").to_string_lossy().to_string();
+        match container.instantiate_from_config(&String::from("test-instance-1"), &config) {
+            Err(LoadConfigError::Instantiation { message, .. }) => {
+                assert!(message.starts_with("Could not load key file"))
+            }
+            _ => panic!("Instantiation should fail without the key file"),
+        }
+    }
+
+    #[test]
+    fn test_container_validation_timeout() {
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        config.instances[0].validation_timeout_ms = Some(250);
+        let mut container =
+            Container::from_config(config.clone()).with_dna_loader(test_dna_loader());
+        let timeout = |container: &mut Container, id: &str| {
+            container
+                .instantiate_from_config(&String::from(id), &config)
+                .unwrap()
+                .context()
+                .validation_timeout
+        };
+        assert_eq!(
+            timeout(&mut container, "test-instance-1"),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(timeout(&mut container, "test-instance-2"), None);
+    }
+
+    #[test]
+    fn test_container_instance_ids_and_configs() {
+        let container = test_container();
+        assert_eq!(
+            container.instance_ids(),
+            vec![
+                String::from("bridge-caller"),
+                String::from("test-instance-1"),
+                String::from("test-instance-2"),
+            ]
+        );
+        assert_eq!(
+            container.instance_config("test-instance-2").unwrap().agent,
+            "test-agent-2"
+        );
+        assert!(container.instance_config("non-existent").is_none());
+    }
+
+    #[test]
+    fn test_container_instances_sharing_dna() {
+        let mut container = test_container();
+        container.config.instances[1].dna = String::from("bridge-callee");
+        assert_eq!(
+            container.instances_sharing_dna("bridge-callee"),
+            vec![
+                String::from("test-instance-1"),
+                String::from("test-instance-2"),
+            ]
+        );
+        assert!(container.instances_sharing_dna("test-dna").is_empty());
+        assert_eq!(
+            container.dnas_in_use(),
+            vec![String::from("bridge-callee"), String::from("bridge-caller")]
+        );
+    }
+
+    #[test]
+    fn test_container_agent_address() {
+        let container = test_container();
+        assert_eq!(
+            container.agent_address("test-instance-1"),
+            Some(
+                container.instances["test-instance-1"]
+                    .read()
+                    .unwrap()
+                    .context()
+                    .agent_id
+                    .address()
+            )
+        );
+        assert_eq!(
+            container.agent_address("test-instance-2"),
+            Some(Address::from(
+                "HoloTester2-----------------------------------------------------------------------AAAGy4WW9e"
+            ))
+        );
+        assert_eq!(container.agent_address("non-existent"), None);
+    }
+
+    #[test]
+    fn test_container_validate_wasm_on_load() {
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        config.validate_wasm_on_load = true;
+
+        // The callee DNA is built from proper WAT
+        let mut container = Container::from_config(config.clone());
+        container.dna_loader = Arc::new(Box::new(|_: &String| Ok(callee_dna())));
+        assert_eq!(container.load_config(), Ok(()));
+
+        // The example DNA's zome has four bytes of corrupt WASM
+        let mut container = Container::from_config(config.clone());
+        container.dna_loader = Arc::new(Box::new(|_: &String| {
+            Ok(Dna::try_from(JsonString::from(example_dna_string())).unwrap())
+        }));
+        let message = container.load_config().unwrap_err().to_string();
+        assert!(message.contains("from DNA file \"bridge/callee.dna\""));
+        assert!(message.contains("WASM of zome \"\" does not compile"));
+    }
+
+    #[test]
+    fn test_container_validate_config() {
+        let container = test_container();
+        let instances_before: Vec<_> = container.instances().values().cloned().collect();
 
-impl Logger for NullLogger {
-    fn log(&mut self, _msg: String) {}
-}
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        assert_eq!(container.validate_config(&config), Ok(()));
 
-#[cfg(test)]
-pub mod tests {
-    use super::*;
-    use crate::config::load_configuration;
-    use holochain_core::{action::Action, signal::signal_channel};
-    use holochain_core_types::{cas::content::Address, dna, json::RawString};
-    use holochain_wasm_utils::wasm_target_dir;
-    use std::{fs::File, io::Write};
-    use tempfile::tempdir;
-    use test_utils::*;
+        config.warn_on_dna_hash_mismatch = false;
+        let message = container.validate_config(&config).unwrap_err().to_string();
+        assert!(message.contains(
+            "instance \"test-instance-1\" from DNA file \"bridge/callee.dna\": DNA hash mismatch"
+        ));
 
-    pub fn test_dna_loader() -> DnaLoader {
-        let loader = Box::new(|path: &String| {
-            Ok(match path.as_ref() {
-                "bridge/callee.dna" => callee_dna(),
-                "bridge/caller.dna" => caller_dna(),
-                _ => Dna::try_from(JsonString::from(example_dna_string())).unwrap(),
+        config.instances[0].dna = String::from("unknown-dna");
+        assert!(container.validate_config(&config).is_err());
+
+        // Running instances are left alone
+        let instances_after: Vec<_> = container.instances().values().cloned().collect();
+        assert_eq!(instances_before.len(), instances_after.len());
+        for before in instances_before.iter() {
+            assert!(instances_after
+                .iter()
+                .any(|after| Arc::ptr_eq(before, after)));
+        }
+    }
+
+    #[test]
+    fn test_rpc_admin_network_info() {
+        let mut container = test_container();
+        container.network_ipc_binding = Some(String::from("tcp://127.0.0.1:12345"));
+        container.network_p2p_bindings = Some(vec![String::from("/ip4/127.0.0.1/tcp/45737")]);
+        assert_eq!(
+            container.network_p2p_bindings(),
+            Some(vec![String::from("/ip4/127.0.0.1/tcp/45737")])
+        );
+        let mut interface_config = container.config.interfaces[0].clone();
+        interface_config.admin = true;
+        let io = container.make_interface_handler(&interface_config);
+
+        let request =
+            r#"{"jsonrpc": "2.0", "method": "admin/network/info", "params": null, "id": 1}"#;
+        let response = io.handle_request_sync(request).unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        let info: serde_json::Value =
+            serde_json::from_str(response["result"].as_str().unwrap()).unwrap();
+        assert_eq!(
+            info,
+            json!({
+                "ipc_binding": "tcp://127.0.0.1:12345",
+                "p2p_bindings": ["/ip4/127.0.0.1/tcp/45737"],
             })
-        }) as Box<FnMut(&String) -> Result<Dna, HolochainError> + Send>;
-        Arc::new(loader)
+        );
     }
 
-    pub fn test_toml() -> String {
-        r#"
-    [[agents]]
-    id = "test-agent-1"
-    name = "Holo Tester 1"
-    public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
-    key_file = "holo_tester.key"
+    #[test]
+    fn test_container_describe_instance() {
+        let container = test_container();
+        let description: serde_json::Value = serde_json::from_str(&String::from(
+            container.describe_instance("test-instance-2").unwrap(),
+        ))
+        .unwrap();
+        assert_eq!(description["name"], json!("my dna"));
+        assert_eq!(
+            description["zomes"][""]["capabilities"],
+            json!({
+                "test": {
+                    "type": "public",
+                    "functions": [{
+                        "name": "test",
+                        "inputs": [{"name": "post", "type": "string"}],
+                        "outputs": [{"name": "hash", "type": "string"}],
+                    }],
+                },
+            })
+        );
+        assert_eq!(description["zomes"][""]["code"], serde_json::Value::Null);
+        assert!(container.describe_instance("unknown-instance").is_err());
 
-    [[agents]]
-    id = "test-agent-2"
-    name = "Holo Tester 2"
-    public_address = "HoloTester2-----------------------------------------------------------------------AAAGy4WW9e"
-    key_file = "holo_tester.key"
+        let interface_config = &container.config.interfaces[0];
+        let io = container.make_interface_handler(&interface_config);
+        let request = r#"{"jsonrpc": "2.0", "method": "info/describe", "params": {"instance_id": "test-instance-2"}, "id": 1}"#;
+        let response = io.handle_request_sync(request).unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        let rpc_description: serde_json::Value =
+            serde_json::from_str(response["result"].as_str().unwrap()).unwrap();
+        assert_eq!(rpc_description, description);
+    }
 
-    [[agents]]
-    id = "test-agent-3"
-    name = "Holo Tester 3"
-    public_address = "HoloTester2-----------------------------------------------------------------------AAAGy4WW9e"
-    key_file = "holo_tester.key"
+    #[test]
+    fn test_rpc_info_instances() {
+        let container = test_container();
+        let interface_config = &container.config.interfaces[0];
+        let io = container.make_interface_handler(&interface_config);
 
-    [[dnas]]
-    id = "test-dna"
-    file = "app_spec.hcpkg"
-    hash = "Qm328wyq38924y"
+        let request = r#"{"jsonrpc": "2.0", "method": "info/instances", "params": null, "id": 1}"#;
+        let response = io
+            .handle_request_sync(request)
+            .expect("No response returned for info/instances");
+        assert!(response.contains("test-instance-1"));
+        assert!(response.contains("test-instance-2"));
 
-    [[dnas]]
-    id = "bridge-callee"
-    file = "bridge/callee.dna"
-    hash = "Qm328wyq38924y"
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        let infos: serde_json::Value =
+            serde_json::from_str(response["result"].as_str().unwrap()).unwrap();
+        let info = infos
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|info| info["id"] == "test-instance-2")
+            .unwrap();
+        assert_eq!(
+            info,
+            &json!({
+                "id": "test-instance-2",
+                "dna": "test-dna",
+                "dna_hash": "Qm328wyq38924y",
+                "agent": "test-agent-2",
+                "agent_public_address": "HoloTester2-----------------------------------------------------------------------AAAGy4WW9e",
+                "storage": "memory",
+                "active": false,
+            })
+        );
+    }
 
-    [[dnas]]
-    id = "bridge-caller"
-    file = "bridge/caller.dna"
-    hash = "Qm328wyq38924y"
+    #[test]
+    fn test_rpc_info_instances_active() {
+        let mut container = test_container();
+        container.start_all_instances().unwrap();
+        let interface_config = container.config.interfaces[0].clone();
+        let io = container.make_interface_handler(&interface_config);
+        let request = r#"{"jsonrpc": "2.0", "method": "info/instances", "params": null, "id": 1}"#;
+        let active = |id: &str| {
+            let response = io.handle_request_sync(request).unwrap();
+            let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+            let infos: serde_json::Value =
+                serde_json::from_str(response["result"].as_str().unwrap()).unwrap();
+            infos
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|info| info["id"] == id)
+                .unwrap()["active"]
+                .clone()
+        };
+        assert_eq!(active("test-instance-1"), json!(true));
 
-    [[instances]]
-    id = "test-instance-1"
-    dna = "bridge-callee"
-    agent = "test-agent-1"
-    [instances.storage]
-    type = "memory"
+        container.instances["test-instance-1"]
+            .write()
+            .unwrap()
+            .stop()
+            .unwrap();
+        assert_eq!(active("test-instance-1"), json!(false));
+        assert_eq!(active("test-instance-2"), json!(true));
+    }
 
-    [[instances]]
-    id = "test-instance-2"
-    dna = "test-dna"
-    agent = "test-agent-2"
-    [instances.storage]
-    type = "memory"
+    #[test]
+    fn test_rpc_info_health() {
+        let mut container = test_container();
+        container.start_all_instances().unwrap();
+        let interface_config = &container.config.interfaces[0];
+        let io = container.make_interface_handler(&interface_config);
 
-    [[instances]]
-    id = "bridge-caller"
-    dna = "bridge-caller"
-    agent = "test-agent-3"
-    [instances.storage]
-    type = "memory"
+        let request = r#"{"jsonrpc": "2.0", "method": "info/health", "params": null, "id": 1}"#;
+        let response = io
+            .handle_request_sync(request)
+            .expect("No response returned for info/health");
+        assert!(response.contains("test-instance-1"));
+        assert!(response.contains(r#"\"active\":true,\"ready\":true"#));
+        assert!(response.contains(r#"\"healthy\":true"#));
 
-    [[interfaces]]
-    id = "test-interface"
-    [interfaces.driver]
-    type = "websocket"
-    port = 8888
-    [[interfaces.instances]]
-    id = "test-instance-1"
-    [[interfaces.instances]]
-    id = "test-instance-2"
+        // A stopped instance and a failed interface degrade the container
+        // but still get a successful response
+        container.instances["test-instance-2"]
+            .write()
+            .unwrap()
+            .stop()
+            .unwrap();
+        container
+            .interface_failures
+            .lock()
+            .unwrap()
+            .insert(String::from("test-interface"), String::from("port in use"));
+        let response = io
+            .handle_request_sync(request)
+            .expect("No response returned for info/health");
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        let health: serde_json::Value =
+            serde_json::from_str(response["result"].as_str().unwrap()).unwrap();
+        assert_eq!(health["running_instances"], json!(1));
+        assert_eq!(health["stopped_instances"], json!(1));
+        assert_eq!(health["network"], serde_json::Value::Null);
+        assert_eq!(health["failed_interfaces"], json!(["test-interface"]));
+        assert_eq!(health["healthy"], json!(false));
+    }
 
-    [[interfaces]]
-    id = "test-interface"
-    [interfaces.driver]
-    type = "http"
-    port = 4000
-    [[interfaces.instances]]
-    id = "test-instance-1"
-    [[interfaces.instances]]
-    id = "test-instance-2"
+    #[test]
+    fn test_container_signal_replay() {
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        config.signals.history_size = 5;
+        let mut container = Container::from_config(config);
+        container.dna_loader = test_dna_loader();
+        container.load_config().unwrap();
 
-    [[bridges]]
-    caller_id = "test-instance-2"
-    callee_id = "test-instance-1"
-    handle = "DPKI"
+        // instantiation emits more than 5 signals, only the most recent ones are replayed
+        let signal_rx = container.subscribe_signals();
+        thread::sleep(Duration::from_millis(500));
+        assert_eq!(signal_rx.try_iter().count(), 5);
+    }
 
-    [[bridges]]
-    caller_id = "bridge-caller"
-    callee_id = "test-instance-2"
-    handle = "happ-store"
+    #[test]
+    fn test_container_signal_sender_only_with_consumers() {
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        config.interfaces.clear();
+        let has_signal_sender = |container: &Container| {
+            container.instances()["test-instance-1"]
+                .read()
+                .unwrap()
+                .context()
+                .signal_tx
+                .is_some()
+        };
 
-    [[bridges]]
-    caller_id = "bridge-caller"
-    callee_id = "test-instance-1"
-    handle = "test-callee"
-    "#
-        .to_string()
+        let mut container =
+            Container::from_config(config.clone()).with_dna_loader(test_dna_loader());
+        container.load_config().unwrap();
+        assert!(!has_signal_sender(&container));
+
+        let mut container = Container::from_config(config).with_dna_loader(test_dna_loader());
+        let _signal_rx = container.subscribe_signals();
+        container.load_config().unwrap();
+        assert!(has_signal_sender(&container));
     }
 
-    pub fn test_container() -> Container {
-        let config = load_configuration::<Configuration>(&test_toml()).unwrap();
-        let mut container = Container::from_config(config.clone());
+    #[test]
+    fn test_container_lifecycle_signals() {
+        let mut container = test_container();
+        let signal_rx = container.subscribe_signals();
+        let lifecycle_signals = |signal_rx: &SignalReceiver| -> Vec<String> {
+            signal_rx
+                .try_iter()
+                .filter_map(|signal| match signal {
+                    Signal::InstanceStarted(id) => Some(format!("started {}", id)),
+                    Signal::InstanceStopped(id) => Some(format!("stopped {}", id)),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        container.start_all_instances().unwrap();
+        assert_eq!(
+            lifecycle_signals(&signal_rx),
+            vec![
+                "started test-instance-1",
+                "started test-instance-2",
+                "started bridge-caller"
+            ]
+        );
+
+        container.stop_all_instances().unwrap();
+        assert_eq!(
+            lifecycle_signals(&signal_rx),
+            vec![
+                "stopped bridge-caller",
+                "stopped test-instance-2",
+                "stopped test-instance-1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_container_start_instances_concurrently() {
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        config.instance_start_concurrency = 3;
+        let mut container = Container::from_config(config);
         container.dna_loader = test_dna_loader();
         container.load_config().unwrap();
-        container
+
+        container.start_all_instances().unwrap();
+        assert!(container
+            .instances()
+            .values()
+            .all(|instance| instance.read().unwrap().active()));
+
+        // Only the instance without callees gets tried and fails with its own error,
+        // its callers are left alone
+        assert_eq!(
+            container.start_all_instances(),
+            Err(HolochainInstanceError::InstanceAlreadyActive)
+        );
     }
 
-    fn test_container_with_signals(signal_tx: SignalSender) -> Container {
+    #[test]
+    fn test_container_get_entry_metrics() {
+        let metrics = |config: Configuration| {
+            let mut container = Container::from_config(config);
+            container.dna_loader = test_dna_loader();
+            container.load_config().unwrap();
+            let handler = ContainerApiBuilder::new()
+                .with_admin_api(true)
+                .with_instances(container.instances().clone())
+                .spawn();
+            let request =
+                r#"{"jsonrpc": "2.0", "method": "metrics/get_entry", "params": null, "id": 1}"#;
+            let response = handler.handle_request_sync(request).unwrap();
+            let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+            serde_json::from_str::<serde_json::Value>(response["result"].as_str().unwrap()).unwrap()
+        };
+
         let config = load_configuration::<Configuration>(&test_toml()).unwrap();
-        let mut container = Container::from_config(config.clone()).with_signal_channel(signal_tx);
-        container.dna_loader = test_dna_loader();
-        container.load_config().unwrap();
-        container
+        let result = metrics(config.clone());
+        assert_eq!(result["test-instance-1"], serde_json::Value::Null);
+
+        let mut config = config;
+        config.metrics = true;
+        let result = metrics(config);
+        assert_eq!(result["test-instance-1"]["local_hits"], json!(0));
+        assert_eq!(result["test-instance-1"]["network_fallbacks"], json!(0));
+        assert_eq!(
+            result["test-instance-1"]["network_latency_ms"]
+                .as_array()
+                .unwrap()
+                .len(),
+            9
+        );
     }
 
-    pub fn example_dna_string() -> String {
-        r#"{
-                "name": "my dna",
-                "description": "",
-                "version": "",
-                "uuid": "00000000-0000-0000-0000-000000000001",
-                "dna_spec_version": "2.0",
-                "properties": {},
-                "zomes": {
-                    "": {
-                        "description": "",
-                        "config": {
-                            "error_handling": "throw-errors"
-                        },
-                        "entry_types": {
-                            "": {
-                                "description": "",
-                                "sharing": "public"
-                            }
-                        },
-                        "capabilities": {
-                            "test": {
-                                "type": "public",
-                                "functions": [
-                                    {
-                                        "name": "test",
-                       "inputs" : [
-                            {
-                                "name": "post",
-                                "type": "string"
-                            }
-                        ],
-                        "outputs" : [
-                            {
-                                "name": "hash",
-                                "type": "string"
-                            }
-                        ]
-                                    }
-                                ]
-                            }
-                        },
-                        "code": {
-                            "code": "AAECAw=="
-                        }
-                    }
-                }
-            }"#
-        .to_string()
+    #[test]
+    fn test_container_slow_signal_consumer_does_not_block_instances() {
+        // a consumer that never catches up
+        let (signal_tx, signal_rx) = sync_channel(1);
+        let config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        let mut container = Container::from_config(config)
+            .with_signal_channel(signal_tx)
+            .with_dna_loader(test_dna_loader());
+        container.load_config().unwrap();
+        container.start_all_instances().unwrap();
+
+        let instance = container.instances["test-instance-1"].clone();
+        let (done_tx, done_rx) = channel();
+        thread::spawn(move || {
+            let cap = Some(dna::capabilities::CapabilityCall::new(
+                String::from("public"),
+                Address::from("fake_token"),
+                None,
+            ));
+            for _ in 0..20 {
+                instance
+                    .write()
+                    .unwrap()
+                    .call("greeter", cap.clone(), "hello", "{}")
+                    .unwrap();
+            }
+            done_tx.send(()).unwrap();
+        });
+        done_rx.recv_timeout(Duration::from_secs(30)).unwrap();
+        assert!(container.dropped_signal_count() > 0);
+        assert_eq!(signal_rx.try_iter().count(), 1);
     }
 
     #[test]
-    fn test_default_dna_loader() {
-        let tempdir = tempdir().unwrap();
-        let file_path = tempdir.path().join("test.dna.json");
-        let mut tmp_file = File::create(file_path.clone()).unwrap();
-        writeln!(tmp_file, "{}", example_dna_string()).unwrap();
-        match Container::load_dna(&file_path.into_os_string().into_string().unwrap()) {
-            Ok(dna) => {
-                assert_eq!(dna.name, "my dna");
-            }
-            Err(_) => assert!(false),
+    fn test_container_zome_call_metrics() {
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        config.zome_call_metrics = true;
+        let mut container = Container::from_config(config);
+        container.dna_loader = test_dna_loader();
+        container.load_config().unwrap();
+        container.start_all_instances().unwrap();
+        let cap = Some(dna::capabilities::CapabilityCall::new(
+            String::from("public"),
+            Address::from("fake_token"),
+            None,
+        ));
+        container
+            .call_instance_function("test-instance-1", "greeter", cap, "hello", "{}")
+            .unwrap();
+
+        let handler = ContainerApiBuilder::new()
+            .with_admin_api(true)
+            .with_zome_call_metrics(container.enabled_zome_call_metrics())
+            .with_instances(container.instances().clone())
+            .spawn();
+        // Calls hold the write lock of their instance, which reading metrics must not wait for
+        let _lock = container.instances()["test-instance-1"].write().unwrap();
+        let request =
+            r#"{"jsonrpc": "2.0", "method": "metrics/instances", "params": null, "id": 1}"#;
+        let response = handler.handle_request_sync(request).unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        let result =
+            serde_json::from_str::<serde_json::Value>(response["result"].as_str().unwrap())
+                .unwrap();
+        let hello_metrics = &result["test-instance-1"]["greeter/hello"];
+        assert_eq!(hello_metrics["calls"], json!(1));
+        assert_eq!(hello_metrics["errors"], json!(0));
+        assert_eq!(result["test-instance-2"], json!({}));
+    }
+
+    /// Calls the callee's "slow" zome function in a thread and returns once it is running
+    fn start_slow_call(
+        container: &Container,
+        id: &str,
+    ) -> thread::JoinHandle<Result<JsonString, HolochainInstanceError>> {
+        let busy_instance = container.instances()[id].clone();
+        let call = thread::spawn(move || {
+            let mut hc = busy_instance.write().unwrap();
+            hc.call(
+                "greeter",
+                Some(dna::capabilities::CapabilityCall::new(
+                    String::from("public"),
+                    Address::from("fake_token"),
+                    None,
+                )),
+                "slow",
+                "{}",
+            )
+        });
+        while container.calls_in_flight.running(id) == 0 {
+            thread::sleep(Duration::from_millis(1));
         }
+        call
     }
 
     #[test]
-    fn test_container_load_config() {
+    fn test_container_shutdown_graceful() {
         let mut container = test_container();
-        assert_eq!(container.instances.len(), 3);
-
         container.start_all_instances().unwrap();
-        container.start_all_interfaces();
-        container.stop_all_instances().unwrap();
+        let instance = container.instances()["test-instance-1"].clone();
+
+        let call = start_slow_call(&container, "test-instance-1");
+        container
+            .shutdown_graceful(Duration::from_secs(60))
+            .unwrap();
+        // The call got to finish before the instance was stopped
+        assert!(call.join().unwrap().is_ok());
+        assert!(!instance.read().unwrap().active());
+        assert!(container.instances().is_empty());
+    }
+
+    /// Panics in a thread holding the instance's write lock, like a panicking zome call
+    fn poison_instance(container: &Container, id: &str) {
+        let instance = container.instances()[id].clone();
+        let _ = thread::spawn(move || {
+            let _call = instance.write().unwrap();
+            panic!("Zome call panicked");
+        })
+        .join();
     }
 
     #[test]
-    fn test_container_try_from_configuration() {
-        let config = load_configuration::<Configuration>(&test_toml()).unwrap();
+    fn test_container_mark_poisoned_instance_failed() {
+        let mut container = test_container();
+        container.start_all_instances().unwrap();
+        poison_instance(&container, "test-instance-1");
 
-        let maybe_container = Container::try_from(&config);
+        let error = container
+            .call_instance_function("test-instance-1", "greeter", None, "hello", "{}")
+            .unwrap_err();
+        assert!(error.to_string().contains("failed since a call panicked"));
 
-        assert!(maybe_container.is_err());
         assert_eq!(
-            maybe_container.err().unwrap(),
-            HolochainError::ConfigError(
-                "Error while trying to create instance \"test-instance-1\": Could not load DNA file \"bridge/callee.dna\"".to_string()
-            )
+            container.recover_poisoned_instances(),
+            vec![String::from("test-instance-1")]
+        );
+        assert!(!container.has_instance("test-instance-1"));
+        assert_eq!(
+            container.failed_instances(),
+            vec![String::from("test-instance-1")]
         );
+        // The other instances are not affected
+        assert!(container.instances()["test-instance-2"]
+            .read()
+            .unwrap()
+            .active());
+        assert!(container.recover_poisoned_instances().is_empty());
     }
 
     #[test]
-    fn test_rpc_info_instances() {
-        let container = test_container();
-        let interface_config = &container.config.interfaces[0];
-        let io = container.make_interface_handler(&interface_config);
+    fn test_container_restart_poisoned_instance() {
+        let mut config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        config.panic_policy = PanicPolicy::Restart;
+        let mut container = Container::from_config(config);
+        container.dna_loader = test_dna_loader();
+        container.load_config().unwrap();
+        container.start_all_instances().unwrap();
+        let poisoned = container.instances()["test-instance-1"].clone();
+        poison_instance(&container, "test-instance-1");
 
-        let request = r#"{"jsonrpc": "2.0", "method": "info/instances", "params": null, "id": 1}"#;
-        let response = io
-            .handle_request_sync(request)
-            .expect("No response returned for info/instances");
-        assert!(response.contains("test-instance-1"));
-        assert!(response.contains("test-instance-2"));
+        assert_eq!(
+            container.recover_poisoned_instances(),
+            vec![String::from("test-instance-1")]
+        );
+        assert!(container.failed_instances().is_empty());
+        let instance = container.instances()["test-instance-1"].clone();
+        assert!(!Arc::ptr_eq(&instance, &poisoned));
+        assert!(!instance.is_poisoned());
+        assert!(instance.read().unwrap().active());
+    }
+
+    #[test]
+    fn test_container_shutdown_graceful_timeout() {
+        let mut container = test_container();
+        container.start_all_instances().unwrap();
+        let instance = container.instances()["test-instance-1"].clone();
+
+        let call = start_slow_call(&container, "test-instance-1");
+        container
+            .shutdown_graceful(Duration::from_millis(1))
+            .unwrap();
+        assert!(container.instances().is_empty());
+        assert!(call.join().unwrap().is_ok());
+        // The busy instance refuses calls as if it was stopped
+        assert_eq!(
+            instance.write().unwrap().call(
+                "greeter",
+                Some(dna::capabilities::CapabilityCall::new(
+                    String::from("public"),
+                    Address::from("fake_token"),
+                    None,
+                )),
+                "hello",
+                "{}",
+            ),
+            Err(HolochainInstanceError::InstanceNotActiveYet)
+        );
     }
 
     #[test]
@@ -693,6 +4203,33 @@ pub mod tests {
         .unwrap();
     }
 
+    #[test]
+    fn test_container_filtered_signal_channel() {
+        let (signal_tx, signal_rx) = signal_channel();
+        let config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        let mut container = Container::from_config(config)
+            .with_filtered_signal_channel(signal_tx, |signal| match signal {
+                Signal::Internal(Action::InitNetwork(_)) => true,
+                _ => false,
+            })
+            .with_dna_loader(test_dna_loader());
+        container.load_config().unwrap();
+
+        match signal_rx.recv_timeout(Duration::from_secs(5)) {
+            Ok(Signal::Internal(Action::InitNetwork(_))) => (),
+            other => panic!("Unexpected signal: {:?}", other),
+        }
+        // InstanceStarted signals and all other actions get dropped
+        container.start_all_instances().unwrap();
+        thread::sleep(Duration::from_millis(200));
+        for signal in signal_rx.try_iter() {
+            match signal {
+                Signal::Internal(Action::InitNetwork(_)) => (),
+                other => panic!("Unexpected signal: {:?}", other),
+            }
+        }
+    }
+
     pub fn callee_wat() -> String {
         r#"
 (module
@@ -759,6 +4296,24 @@ pub mod tests {
         (i32.const 0)
     )
 
+    (func
+        (export "slow")
+        (param $allocation i32)
+        (result i32)
+        (local $i i32)
+
+        ;; Counts to a million to keep the instance busy for a while
+        (block
+            (loop
+                (br_if 1 (i32.eq (get_local $i) (i32.const 1000000)))
+                (set_local $i (i32.add (get_local $i) (i32.const 1)))
+                (br 0)
+            )
+        )
+
+        (i32.const 0)
+    )
+
     (func
         (export "hello")
         (param $allocation i32)
@@ -802,6 +4357,18 @@ pub mod tests {
                     parameter_type: String::from("String"),
                 }],
             });
+        dna.zomes
+            .get_mut("greeter")
+            .unwrap()
+            .capabilities
+            .get_mut("public")
+            .unwrap()
+            .functions
+            .push(dna::capabilities::FnDeclaration {
+                name: String::from("slow"),
+                inputs: vec![],
+                outputs: vec![],
+            });
         dna
     }
 
@@ -845,4 +4412,404 @@ pub mod tests {
         assert_eq!(result, JsonString::from(RawString::from("Holo World")));
     }
 
+    #[test]
+    fn bridge_call_with_provisioned_token() {
+        let loader = Box::new(|path: &String| {
+            Ok(match path.as_ref() {
+                "bridge/callee.dna" => {
+                    let mut dna = callee_dna();
+                    dna.zomes
+                        .get_mut("greeter")
+                        .unwrap()
+                        .capabilities
+                        .get_mut("public")
+                        .unwrap()
+                        .cap_type = CapabilityType::Transferable;
+                    dna
+                }
+                "bridge/caller.dna" => caller_dna(),
+                _ => Dna::try_from(JsonString::from(example_dna_string())).unwrap(),
+            })
+        }) as Box<Fn(&String) -> Result<Dna, HolochainError> + Send + Sync>;
+        let config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        let mut container = Container::from_config(config).with_dna_loader(Arc::new(loader));
+        container.load_config().expect("Test config must be sane");
+        container
+            .start_all_instances()
+            .expect("Instances must be spawnable");
+
+        // Without a granted token the callee's function can't be called anymore
+        let cap = Some(dna::capabilities::CapabilityCall::new(
+            String::from("public"),
+            Address::from("fake_token"),
+            Some(Address::from("someone")),
+        ));
+        assert!(container
+            .call_instance_function("test-instance-1", "greeter", cap, "hello", "{}")
+            .is_err());
+
+        let result = container
+            .call_instance_function(
+                "bridge-caller",
+                "main",
+                Some(dna::capabilities::CapabilityCall::new(
+                    String::from("main"),
+                    Address::from("fake_token"),
+                    None,
+                )),
+                "call_bridge",
+                "{}",
+            )
+            .unwrap();
+        assert_eq!(result, JsonString::from(RawString::from("Holo World")));
+
+        // Instantiating a caller again, like reloading it does, reuses the grant
+        let config = container.config();
+        container
+            .instantiate_with_callees(
+                &String::from("bridge-caller"),
+                &config,
+                &container.instances,
+            )
+            .unwrap();
+        let state = container.instances["test-instance-1"]
+            .read()
+            .unwrap()
+            .state()
+            .unwrap();
+        let agent = state.agent();
+        let grants = agent
+            .chain()
+            .iter(&agent.top_chain_header())
+            .filter(|header| header.entry_type() == &EntryType::CapTokenGrant)
+            .count();
+        assert_eq!(grants, 1);
+    }
+
+    #[test]
+    fn test_call_instance_function() {
+        let config = load_configuration::<Configuration>(&test_toml()).unwrap();
+        let mut container = Container::from_config(config.clone());
+        container.dna_loader = test_dna_loader();
+        container.load_config().expect("Test config must be sane");
+        container
+            .start_all_instances()
+            .expect("Instances must be spawnable");
+
+        let cap = Some(dna::capabilities::CapabilityCall::new(
+            String::from("public"),
+            Address::from("fake_token"),
+            None,
+        ));
+        let result = container
+            .call_instance_function("test-instance-1", "greeter", cap.clone(), "hello", "{}")
+            .unwrap();
+        assert_eq!(result, JsonString::from(RawString::from("Holo World")));
+
+        assert_eq!(
+            container.call_instance_function("unknown-instance", "greeter", cap, "hello", "{}"),
+            Err(HolochainInstanceError::InternalFailure(
+                HolochainError::ErrorGeneric("Instance does not exist: unknown-instance".into())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_call_public_function_without_capability_call() {
+        let mut container = test_container();
+        container.start_all_instances().unwrap();
+        let result = container
+            .call_instance_function("test-instance-1", "greeter", None, "hello", "{}")
+            .unwrap();
+        assert_eq!(result, JsonString::from(RawString::from("Holo World")));
+    }
+
+    #[test]
+    fn test_container_grant_capability() {
+        let mut container = test_container();
+        assert_eq!(
+            container.grant_capability("test-instance-1", "public"),
+            Err(HolochainInstanceError::InstanceNotActiveYet)
+        );
+        container.start_all_instances().unwrap();
+
+        let token = container
+            .grant_capability("test-instance-1", "public")
+            .unwrap();
+        let chain_storage = container.instances["test-instance-1"]
+            .read()
+            .unwrap()
+            .context()
+            .chain_storage
+            .clone();
+        assert!(chain_storage
+            .read()
+            .unwrap()
+            .fetch(&token)
+            .unwrap()
+            .is_some());
+
+        let cap = Some(dna::capabilities::CapabilityCall::new(
+            String::from("public"),
+            token,
+            None,
+        ));
+        let result = container
+            .call_instance_function("test-instance-1", "greeter", cap, "hello", "{}")
+            .unwrap();
+        assert_eq!(result, JsonString::from(RawString::from("Holo World")));
+
+        assert_eq!(
+            container.grant_capability("test-instance-1", "unknown"),
+            Err(HolochainInstanceError::InternalFailure(
+                HolochainError::ErrorGeneric("Capability not found in DNA: unknown".into())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_container_export_and_import_chain() {
+        let mut container = test_container();
+        container.start_all_instances().unwrap();
+        let top_chain_header = |container: &Container| {
+            container.instances["test-instance-1"]
+                .read()
+                .unwrap()
+                .state()
+                .unwrap()
+                .agent()
+                .top_chain_header()
+        };
+
+        let genesis_chain = container.export_chain("test-instance-1").unwrap();
+        let genesis_top = top_chain_header(&container);
+        container
+            .grant_capability("test-instance-1", "public")
+            .unwrap();
+        let full_chain = container.export_chain("test-instance-1").unwrap();
+        let full_top = top_chain_header(&container);
+        assert_eq!(
+            read_source_chain_dump(&String::from(full_chain.clone()))
+                .unwrap()
+                .len(),
+            3
+        );
+
+        assert_eq!(
+            container.import_chain("test-instance-1", genesis_chain.clone(), false, true),
+            Err(HolochainInstanceError::InternalFailure(
+                HolochainError::ErrorGeneric(String::from(
+                    "Instance test-instance-1 already has entries on its chain, import with force to replace them"
+                ))
+            ))
+        );
+        assert_eq!(top_chain_header(&container), full_top);
+
+        // headers don't get signed yet
+        assert!(container
+            .import_chain("test-instance-1", genesis_chain.clone(), true, false)
+            .is_err());
+        assert_eq!(top_chain_header(&container), full_top);
+
+        let has_header = |container: &Container, header: &ChainHeader| {
+            container.instances["test-instance-1"]
+                .read()
+                .unwrap()
+                .state()
+                .unwrap()
+                .agent()
+                .chain()
+                .content_storage()
+                .read()
+                .unwrap()
+                .contains(&header.address())
+                .unwrap()
+        };
+        container
+            .import_chain("test-instance-1", genesis_chain.clone(), true, true)
+            .unwrap();
+        assert_eq!(top_chain_header(&container), genesis_top);
+        // the replaced header does not stay behind
+        assert!(!has_header(&container, full_top.as_ref().unwrap()));
+        container
+            .import_chain("test-instance-1", full_chain, false, true)
+            .unwrap();
+        assert_eq!(top_chain_header(&container), full_top);
+        assert!(has_header(&container, full_top.as_ref().unwrap()));
+
+        // test-instance-2 runs another DNA
+        assert!(container
+            .import_chain("test-instance-2", genesis_chain, false, true)
+            .is_err());
+        assert!(container
+            .import_chain(
+                "test-instance-1",
+                JsonString::from("{\"chain\":[]}"),
+                true,
+                true
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_container_reload_dna() {
+        let mut container = test_container();
+        container.start_all_instances().unwrap();
+        let instance_id = String::from("test-instance-1");
+        let hc_lock = container.instances[&instance_id].clone();
+        let top_chain_header = || {
+            hc_lock
+                .read()
+                .unwrap()
+                .state()
+                .unwrap()
+                .agent()
+                .top_chain_header()
+        };
+        let cap = Some(dna::capabilities::CapabilityCall::new(
+            String::from("public"),
+            Address::from("fake_token"),
+            None,
+        ));
+        let hello = |container: &Container| {
+            container
+                .call_instance_function(&instance_id, "greeter", cap.clone(), "hello", "{}")
+                .unwrap()
+        };
+
+        // the callee DNA defines "testEntryType", the example DNA doesn't
+        block_on(commit_entry(
+            test_entry(),
+            None,
+            hc_lock.read().unwrap().context(),
+        ))
+        .unwrap();
+        let chain_top = top_chain_header();
+        let greeting = hello(&container);
+
+        let result = container.reload_dna(&instance_id, &String::from("example.dna"));
+        assert!(result
+            .unwrap_err()
+            .contains("it does not define the entry types: testEntryType"));
+
+        container
+            .reload_dna(&instance_id, &String::from("bridge/callee.dna"))
+            .unwrap();
+        assert!(hc_lock.read().unwrap().active());
+        assert_eq!(top_chain_header(), chain_top);
+        assert_eq!(hello(&container), greeting);
+
+        assert!(container
+            .reload_dna(
+                &String::from("unknown-instance"),
+                &String::from("bridge/callee.dna")
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_container_replace_agent() {
+        let mut container = test_container();
+        container.start_all_instances().unwrap();
+        let instance_id = String::from("test-instance-1");
+        let new_agent_id = String::from("test-agent-2");
+        let hc_lock = container.instances[&instance_id].clone();
+        let top_chain_header = || {
+            hc_lock
+                .read()
+                .unwrap()
+                .state()
+                .unwrap()
+                .agent()
+                .top_chain_header()
+                .unwrap()
+        };
+        let chain_top = top_chain_header();
+
+        assert!(container
+            .replace_agent(&instance_id, &String::from("unknown-agent"))
+            .unwrap_err()
+            .contains("Agent does not exist"));
+        container.config.agents[1].public_address = String::from("invalid");
+        assert!(container
+            .replace_agent(&instance_id, &new_agent_id)
+            .is_err());
+        assert_eq!(top_chain_header(), chain_top);
+        container.config.agents[1].public_address = String::from(
+            "HoloTester2-----------------------------------------------------------------------AAAGy4WW9e",
+        );
+
+        container
+            .replace_agent(&instance_id, &new_agent_id)
+            .unwrap();
+        assert!(hc_lock.read().unwrap().active());
+        assert_eq!(
+            container.instance_config(&instance_id).unwrap().agent,
+            new_agent_id
+        );
+        assert_eq!(
+            hc_lock.read().unwrap().context().agent_id.nick,
+            "Holo Tester 2"
+        );
+
+        // the former chain is kept, the new agent's entry starts a new segment on top of it
+        let new_top = top_chain_header();
+        assert_eq!(new_top.entry_type(), &EntryType::AgentId);
+        assert_eq!(new_top.link(), Some(chain_top.address()));
+        assert_eq!(
+            Some(new_top.entry_address().clone()),
+            container.agent_address(&instance_id)
+        );
+
+        assert!(container
+            .replace_agent(&instance_id, &new_agent_id)
+            .unwrap_err()
+            .contains("already acts as agent"));
+    }
+
+    #[test]
+    fn test_container_prepare_call() {
+        let mut container = test_container();
+        container.start_all_instances().unwrap();
+        let instance_id = String::from("test-instance-1");
+        let holo_world = JsonString::from(RawString::from("Holo World"));
+
+        let prepared = container
+            .prepare_call(&instance_id, "greeter", "hello", None)
+            .unwrap();
+        for _ in 0..3 {
+            assert_eq!(prepared.execute("{}").unwrap(), holo_world);
+        }
+        assert!(prepared.is_valid());
+
+        assert!(container
+            .prepare_call(&instance_id, "greeter", "unknown_function", None)
+            .is_err());
+        assert!(container
+            .prepare_call(&instance_id, "unknown_zome", "hello", None)
+            .is_err());
+        assert!(container
+            .prepare_call("unknown-instance", "greeter", "hello", None)
+            .is_err());
+
+        container
+            .reload_dna(&instance_id, &String::from("bridge/callee.dna"))
+            .unwrap();
+        assert!(!prepared.is_valid());
+        assert!(prepared
+            .execute("{}")
+            .unwrap_err()
+            .to_string()
+            .contains("no longer valid"));
+
+        let prepared = container
+            .prepare_call(&instance_id, "greeter", "hello", None)
+            .unwrap();
+        assert_eq!(prepared.execute("{}").unwrap(), holo_world);
+        for id in ["bridge-caller", "test-instance-2", "test-instance-1"].iter() {
+            container.remove_instance(&id.to_string()).unwrap();
+        }
+        assert!(!prepared.is_valid());
+        assert!(prepared.execute("{}").is_err());
+    }
 }
@@ -6,7 +6,9 @@ use holochain_cas_implementations::{
 
 use holochain_core::{
     context::Context,
+    entry_cache::EntryCache,
     logger::{Logger, SimpleLogger},
+    metrics::GetEntryMetrics,
     persister::SimplePersister,
     signal::SignalSender,
 };
@@ -15,8 +17,12 @@ use holochain_core_types::{
     error::HolochainError, json::JsonString,
 };
 use holochain_net::p2p_config::P2pConfig;
+use holochain_sodium::secbuf::SecBuf;
 use jsonrpc_ws_server::jsonrpc_core::IoHandler;
-use std::sync::{Arc, Mutex, RwLock};
+use std::{
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
 
 /// This type helps building [context objects](struct.Context.html) that need to be
 /// passed in to Holochain intances.
@@ -38,6 +44,10 @@ pub struct ContextBuilder {
     network_config: Option<JsonString>,
     container_api: Option<Arc<RwLock<IoHandler>>>,
     signal_tx: Option<SignalSender>,
+    get_entry_metrics: Option<Arc<GetEntryMetrics>>,
+    entry_cache: Option<Arc<EntryCache>>,
+    validation_timeout: Option<Duration>,
+    agent_secret_key: Option<SecBuf>,
 }
 
 impl ContextBuilder {
@@ -51,6 +61,10 @@ impl ContextBuilder {
             network_config: None,
             container_api: None,
             signal_tx: None,
+            get_entry_metrics: None,
+            entry_cache: None,
+            validation_timeout: None,
+            agent_secret_key: None,
         }
     }
 
@@ -110,6 +124,33 @@ impl ContextBuilder {
         self
     }
 
+    /// Makes the get entry workflows of the context record into the given metrics.
+    /// Without this, no metrics get collected.
+    pub fn with_get_entry_metrics(mut self, get_entry_metrics: Arc<GetEntryMetrics>) -> Self {
+        self.get_entry_metrics = Some(get_entry_metrics);
+        self
+    }
+
+    /// Makes the get entry workflows of the context read through the given cache.
+    /// Without this, every lookup goes to the DHT.
+    pub fn with_entry_cache(mut self, entry_cache: Arc<EntryCache>) -> Self {
+        self.entry_cache = Some(entry_cache);
+        self
+    }
+
+    /// Makes validations of the context fail once their validation callback runs longer
+    /// than the given timeout. Without this, validations may run forever.
+    pub fn with_validation_timeout(mut self, validation_timeout: Duration) -> Self {
+        self.validation_timeout = Some(validation_timeout);
+        self
+    }
+
+    /// Sets the secret signing key of the agent, which the context needs in order to sign.
+    pub fn with_agent_secret_key(mut self, secret_key: SecBuf) -> Self {
+        self.agent_secret_key = Some(secret_key);
+        self
+    }
+
     /// Actually creates the context.
     /// Defaults to memory storages, a mock network config and a fake agent called "alice".
     /// The logger gets set to SimpleLogger.
@@ -124,7 +165,7 @@ impl ContextBuilder {
         let eav_storage = self
             .eav_storage
             .unwrap_or(Arc::new(RwLock::new(EavMemoryStorage::new())));
-        Context::new(
+        let mut context = Context::new(
             self.agent_id.unwrap_or(AgentId::generate_fake("alice")),
             self.logger.unwrap_or(Arc::new(Mutex::new(SimpleLogger {}))),
             Arc::new(Mutex::new(SimplePersister::new(chain_storage.clone()))),
@@ -136,7 +177,20 @@ impl ContextBuilder {
             ))),
             self.container_api,
             self.signal_tx,
-        )
+        );
+        if let Some(get_entry_metrics) = self.get_entry_metrics {
+            context.set_get_entry_metrics(get_entry_metrics);
+        }
+        if let Some(entry_cache) = self.entry_cache {
+            context.set_entry_cache(entry_cache);
+        }
+        if let Some(validation_timeout) = self.validation_timeout {
+            context.set_validation_timeout(validation_timeout);
+        }
+        if let Some(secret_key) = self.agent_secret_key {
+            context.set_agent_secret_key(secret_key);
+        }
+        context
     }
 }
 
@@ -9,6 +9,8 @@ pub enum HolochainInstanceError {
     InternalFailure(HolochainError),
     InstanceNotActiveYet,
     InstanceAlreadyActive,
+    /// The instance got removed from its container and can't be started or called anymore
+    InstanceRemoved,
 }
 
 impl Error for HolochainInstanceError {
@@ -19,6 +21,9 @@ impl Error for HolochainInstanceError {
             HolochainInstanceError::InstanceAlreadyActive => {
                 "Holochain instance is already active."
             }
+            HolochainInstanceError::InstanceRemoved => {
+                "Holochain instance has been removed from its container."
+            }
         }
     }
 
@@ -29,6 +34,7 @@ impl Error for HolochainInstanceError {
             HolochainInstanceError::InternalFailure(ref err)  => Some(err),
             HolochainInstanceError::InstanceNotActiveYet => None,
             HolochainInstanceError::InstanceAlreadyActive => None,
+            HolochainInstanceError::InstanceRemoved => None,
         }
     }
 }
@@ -45,10 +51,113 @@ impl From<HolochainError> for HolochainInstanceError {
     }
 }
 
+/// Reasons why `Container::load_config()` and `Container::instantiate_from_config()` fail
+#[derive(Debug, PartialEq, Clone)]
+pub enum LoadConfigError {
+    /// The configuration is not consistent, see `Configuration::check_consistency()`
+    Consistency(String),
+    /// The DNA file of an instance could not be loaded or did not pass verification
+    DnaLoad {
+        instance: String,
+        path: String,
+        message: String,
+    },
+    /// The instances could not be ordered by their bridge dependencies
+    BridgeOrder(String),
+    /// An instance could not be created for any other reason
+    Instantiation { instance: String, message: String },
+    /// The previously loaded instances could not be shut down
+    Shutdown(HolochainInstanceError),
+}
+
+impl Error for LoadConfigError {
+    fn description(&self) -> &str {
+        match self {
+            LoadConfigError::Consistency(_) => "Inconsistent configuration",
+            LoadConfigError::DnaLoad { .. } => "Could not load DNA",
+            LoadConfigError::BridgeOrder(_) => "Could not order instances by bridges",
+            LoadConfigError::Instantiation { .. } => "Could not create instance",
+            LoadConfigError::Shutdown(_) => "Could not shut down instances",
+        }
+    }
+}
+
+impl fmt::Display for LoadConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadConfigError::Consistency(message) | LoadConfigError::BridgeOrder(message) => {
+                write!(f, "{}", message)
+            }
+            LoadConfigError::DnaLoad {
+                instance,
+                path,
+                message,
+            } => write!(
+                f,
+                "Error while trying to create instance \"{}\" from DNA file \"{}\": {}",
+                instance, path, message
+            ),
+            LoadConfigError::Instantiation { instance, message } => write!(
+                f,
+                "Error while trying to create instance \"{}\": {}",
+                instance, message
+            ),
+            LoadConfigError::Shutdown(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<LoadConfigError> for HolochainError {
+    fn from(error: LoadConfigError) -> Self {
+        HolochainError::ConfigError(error.to_string())
+    }
+}
+
+/// Reasons why `Container::start_interface()` fails
+#[derive(Debug, PartialEq, Clone)]
+pub enum InterfaceStartError {
+    /// An interface with the given ID is running already
+    AlreadyRunning(String),
+    /// The port the interface should listen on is taken
+    PortInUse { interface: String, port: u16 },
+    /// Binding the port failed for another reason
+    Io { interface: String, message: String },
+}
+
+impl Error for InterfaceStartError {
+    fn description(&self) -> &str {
+        match self {
+            InterfaceStartError::AlreadyRunning(_) => "Interface already started",
+            InterfaceStartError::PortInUse { .. } => "Interface port already in use",
+            InterfaceStartError::Io { .. } => "Could not bind interface",
+        }
+    }
+}
+
+impl fmt::Display for InterfaceStartError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InterfaceStartError::AlreadyRunning(interface) => {
+                write!(f, "Interface {} already started!", interface)
+            }
+            InterfaceStartError::PortInUse { interface, port } => write!(
+                f,
+                "Could not start interface \"{}\": port {} is already in use",
+                interface, port
+            ),
+            InterfaceStartError::Io { interface, message } => write!(
+                f,
+                "Could not start interface \"{}\": {}",
+                interface, message
+            ),
+        }
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
 
-    use crate::error::HolochainInstanceError;
+    use crate::error::{HolochainInstanceError, LoadConfigError};
     use holochain_core_types::error::HolochainError;
     use std::error::Error;
 
@@ -97,6 +206,34 @@ pub mod tests {
         }
     }
 
+    #[test]
+    /// show ToString for LoadConfigError
+    fn load_config_error_to_string_test() {
+        for (i, o) in vec![
+            (
+                LoadConfigError::Consistency(String::from("Duplicate instance configuration ID")),
+                "Duplicate instance configuration ID",
+            ),
+            (
+                LoadConfigError::DnaLoad {
+                    instance: String::from("app"),
+                    path: String::from("app.dna.json"),
+                    message: String::from("No such file or directory"),
+                },
+                "Error while trying to create instance \"app\" from DNA file \"app.dna.json\": No such file or directory",
+            ),
+            (
+                LoadConfigError::Instantiation {
+                    instance: String::from("app"),
+                    message: String::from("Instance not found in config"),
+                },
+                "Error while trying to create instance \"app\": Instance not found in config",
+            ),
+        ] {
+            assert_eq!(i.to_string(), o);
+        }
+    }
+
     #[test]
     /// show From<HolochainError> for HolochainInstanceError
     fn holochain_instance_error_from_holochain_error_test() {
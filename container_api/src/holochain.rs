@@ -57,22 +57,42 @@
 //!
 //!```
 
-use crate::error::{HolochainInstanceError, HolochainResult};
+use crate::{
+    calls_in_flight::CallsInFlight,
+    error::{HolochainInstanceError, HolochainResult},
+    metrics::ZomeCallMetrics,
+};
 use futures::executor::block_on;
 use holochain_core::{
+    agent::actions::commit::commit_entry,
     context::Context,
     instance::Instance,
+    network::actions::initialize_network::initialize_network,
     nucleus::{call_and_wait_for_result, ZomeFnCall},
     persister::{Persister, SimplePersister},
     state::State,
-    workflows::application,
+    workflows::{
+        application,
+        author_entry::author_entry,
+        graph_query::{graph_query, GraphNode, GraphQuery},
+        import_chain::import_chain_workflow,
+    },
 };
 use holochain_core_types::{
+    agent::AgentId,
+    cas::content::{Address, AddressableContent},
+    chain_header::ChainHeader,
     dna::{capabilities::CapabilityCall, Dna},
+    entry::{cap_entries::CapTokenGrant, Entry},
     error::HolochainError,
     json::JsonString,
 };
-use std::sync::Arc;
+use holochain_sodium::secbuf::SecBuf;
+use serde_json;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 /// contains a Holochain application instance
 pub struct Holochain {
@@ -80,6 +100,14 @@ pub struct Holochain {
     #[allow(dead_code)]
     context: Arc<Context>,
     active: bool,
+    removed: bool,
+    started_at: Option<Instant>,
+    readiness_grace: Duration,
+    /// The container's metrics and the ID of this instance in them,
+    /// only set if zome call metrics are enabled
+    zome_call_metrics: Option<(String, Arc<ZomeCallMetrics>)>,
+    /// The container's running calls and the ID of this instance in them
+    calls_in_flight: Option<(String, Arc<CallsInFlight>)>,
 }
 
 impl Holochain {
@@ -108,6 +136,11 @@ impl Holochain {
                     instance,
                     context: new_context.clone(),
                     active: false,
+                    removed: false,
+                    started_at: None,
+                    readiness_grace: Duration::from_millis(0),
+                    zome_call_metrics: None,
+                    calls_in_flight: None,
                 };
                 Ok(hc)
             }
@@ -127,15 +160,177 @@ impl Holochain {
             instance,
             context: new_context.clone(),
             active: false,
+            removed: false,
+            started_at: None,
+            readiness_grace: Duration::from_millis(0),
+            zome_call_metrics: None,
+            calls_in_flight: None,
+        })
+    }
+
+    /// Creates a new instance with the same context, agent state (i.e. source chain)
+    /// and storage as this one, but running the given DNA.
+    /// The new DNA entry gets committed on top of the chain, so that the chain's head
+    /// names the DNA it runs from then on.
+    /// This instance's network gets stopped before the new instance commits anything and
+    /// initializes its own network. If that fails, the chain is left as it was and this
+    /// instance's network is restored, see `with_new_agent()`.
+    /// The new instance is not active. This instance should be stopped and dropped
+    /// in favour of the new one.
+    pub fn with_new_dna(&self, dna: Dna) -> HolochainResult<Self> {
+        let name = dna.name.clone();
+        let entry = Entry::Dna(dna.clone());
+        let entry_was_stored = self
+            .context
+            .chain_storage
+            .read()
+            .unwrap()
+            .contains(&entry.address())?;
+        let mut instance = Instance::from_state(self.state()?.with_dna(dna));
+        instance.start_action_loop(self.context.clone());
+        self.stop_network()?;
+        let new_context = block_on(commit_entry(
+            entry.clone(),
+            None,
+            &instance.initialize_context(self.context.clone()),
+        ))
+        .and_then(|_| {
+            block_on(application::initialize(
+                &instance,
+                None,
+                self.context.clone(),
+            ))
+        })
+        .map_err(|error| {
+            self.undo_commit(&instance, &entry, entry_was_stored);
+            HolochainInstanceError::InternalFailure(error)
+        })?;
+        new_context.log(format!("debug/container: {} reloaded", name));
+        Ok(Holochain {
+            instance,
+            context: new_context.clone(),
+            active: false,
+            removed: false,
+            started_at: None,
+            readiness_grace: self.readiness_grace,
+            zome_call_metrics: self.zome_call_metrics.clone(),
+            calls_in_flight: self.calls_in_flight.clone(),
+        })
+    }
+
+    /// Creates a new instance with the same DNA, source chain and storage as this one,
+    /// but acting as the given agent and signing with the given secret key.
+    ///
+    /// The existing chain stays untouched since its entries are signed by the former agent.
+    /// Instead, the new agent's AgentId entry gets committed on top of it, which starts a
+    /// new chain segment: headers below it belong to the former agent, headers above it to
+    /// the new one. This instance's network gets stopped before the new instance commits
+    /// anything and initializes its own network with the new agent.
+    /// If that fails, the AgentId entry and its header get removed from the storage again,
+    /// the state of this instance gets persisted instead of the new one's and this
+    /// instance's network is restored, so the chain is left as it was.
+    /// The new instance is not active. This instance should be stopped and dropped
+    /// in favour of the new one.
+    pub fn with_new_agent(
+        &self,
+        agent_id: AgentId,
+        secret_key: Option<SecBuf>,
+    ) -> HolochainResult<Self> {
+        if agent_id == self.context.agent_id {
+            return Err(HolochainInstanceError::InternalFailure(
+                HolochainError::ErrorGeneric(format!(
+                    "Instance already acts as agent {}",
+                    agent_id.nick
+                )),
+            ));
+        }
+        let mut context = (*self.context).clone();
+        context.set_agent(agent_id.clone(), secret_key);
+        let context = Arc::new(context);
+        let state = self.state()?;
+        let entry = Entry::AgentId(agent_id.clone());
+        let entry_was_stored = self
+            .context
+            .chain_storage
+            .read()
+            .unwrap()
+            .contains(&entry.address())?;
+        let mut instance = Instance::from_state(state);
+        instance.start_action_loop(context.clone());
+        self.stop_network()?;
+        let new_context = block_on(commit_entry(
+            entry.clone(),
+            None,
+            &instance.initialize_context(context.clone()),
+        ))
+        .and_then(|_| block_on(application::initialize(&instance, None, context)))
+        .map_err(|error| {
+            self.undo_commit(&instance, &entry, entry_was_stored);
+            HolochainInstanceError::InternalFailure(error)
+        })?;
+        new_context.log(format!(
+            "debug/container: agent replaced by {}",
+            agent_id.nick
+        ));
+        Ok(Holochain {
+            instance,
+            context: new_context.clone(),
+            active: false,
+            removed: false,
+            started_at: None,
+            readiness_grace: self.readiness_grace,
+            zome_call_metrics: self.zome_call_metrics.clone(),
+            calls_in_flight: self.calls_in_flight.clone(),
         })
     }
 
+    /// Reverts what a failed `with_new_agent()` or `with_new_dna()` left behind: removes
+    /// the header the given new instance committed on top of this instance's chain, and the
+    /// committed entry if it wasn't stored before, persists this instance's state again and
+    /// restores its network.
+    fn undo_commit(&self, new_instance: &Instance, entry: &Entry, entry_was_stored: bool) {
+        let old_top = self.instance.state().agent().top_chain_header();
+        let new_top = new_instance.state().agent().top_chain_header();
+        let mut storage = self.context.chain_storage.write().unwrap();
+        if let Some(header) = new_top.filter(|header| Some(header) != old_top.as_ref()) {
+            let _ = storage.remove(&header.address());
+        }
+        if !entry_was_stored {
+            let _ = storage.remove(&entry.address());
+        }
+        drop(storage);
+        if let Ok(state) = self.state() {
+            let _ = self.context.persister.lock().unwrap().save(state);
+        }
+        let _ = block_on(initialize_network(&self.context));
+    }
+
     /// activate the Holochain instance
     pub fn start(&mut self) -> Result<(), HolochainInstanceError> {
+        if self.removed {
+            return Err(HolochainInstanceError::InstanceRemoved);
+        }
         if self.active {
             return Err(HolochainInstanceError::InstanceAlreadyActive);
         }
         self.active = true;
+        self.started_at = Some(Instant::now());
+        if let Some((ref instance_id, ref calls_in_flight)) = self.calls_in_flight {
+            calls_in_flight.accept(instance_id);
+        }
+        Ok(())
+    }
+
+    /// Disconnects this instance from the network, leaving it unable to publish or get
+    /// anything until the network gets initialized again
+    fn stop_network(&self) -> HolochainResult<()> {
+        if let Some(network) = self.state()?.network().network.clone() {
+            network.lock().unwrap().stop().map_err(|error| {
+                HolochainInstanceError::InternalFailure(HolochainError::ErrorGeneric(
+                    error.to_string(),
+                ))
+            })?;
+        }
         Ok(())
     }
 
@@ -145,6 +340,7 @@ impl Holochain {
             return Err(HolochainInstanceError::InstanceNotActiveYet);
         }
         self.active = false;
+        self.started_at = None;
         Ok(())
     }
 
@@ -156,11 +352,130 @@ impl Holochain {
         fn_name: &str,
         params: &str,
     ) -> HolochainResult<JsonString> {
+        if self.removed {
+            return Err(HolochainInstanceError::InstanceRemoved);
+        }
         if !self.active {
             return Err(HolochainInstanceError::InstanceNotActiveYet);
         }
+        let _call_in_flight = match self.calls_in_flight {
+            Some((ref instance_id, ref calls_in_flight)) => Some(
+                CallsInFlight::begin(calls_in_flight, instance_id)
+                    .ok_or(HolochainInstanceError::InstanceNotActiveYet)?,
+            ),
+            None => None,
+        };
         let zome_call = ZomeFnCall::new(&zome, cap, &fn_name, String::from(params));
-        Ok(call_and_wait_for_result(zome_call, &mut self.instance)?)
+        let start = Instant::now();
+        let result = call_and_wait_for_result(zome_call, &mut self.instance);
+        if let Some((ref instance_id, ref metrics)) = self.zome_call_metrics {
+            metrics.record_call(instance_id, zome, fn_name, start.elapsed(), result.is_ok());
+        }
+        Ok(result?)
+    }
+
+    /// Commits a grant for the capability with the given name, as declared by any zome of
+    /// the DNA, and returns its token for use in a CapabilityCall.
+    /// Assigned capabilities can't be granted this way since they need assignees.
+    pub fn grant_capability(&mut self, cap: &str) -> HolochainResult<Address> {
+        if !self.active {
+            return Err(HolochainInstanceError::InstanceNotActiveYet);
+        }
+        let grant = self.capability_grant(cap)?;
+        Ok(block_on(author_entry(&grant, None, &self.context))?)
+    }
+
+    /// Like `grant_capability()` but reuses a grant that is already on the chain instead
+    /// of committing the same grant again.
+    /// Works on instances that are not started yet, so that the container can hand out
+    /// tokens to bridge callers while instantiating.
+    pub(crate) fn reuse_or_grant_capability(&mut self, cap: &str) -> HolochainResult<Address> {
+        let grant = self.capability_grant(cap)?;
+        let token = grant.address();
+        let state = self.state()?;
+        let agent = state.agent();
+        let already_granted = agent
+            .chain()
+            .iter(&agent.top_chain_header())
+            .any(|header| header.entry_address() == &token);
+        if already_granted {
+            return Ok(token);
+        }
+        Ok(block_on(author_entry(&grant, None, &self.context))?)
+    }
+
+    /// The grant entry for the capability with the given name
+    fn capability_grant(&self, cap: &str) -> HolochainResult<Entry> {
+        let dna = self
+            .context
+            .get_dna()
+            .ok_or_else(|| HolochainError::ErrorGeneric(String::from("Instance has no DNA")))?;
+        let cap_type = dna
+            .zomes
+            .values()
+            .filter_map(|zome| zome.capabilities.get(cap))
+            .map(|capability| capability.cap_type.clone())
+            .next()
+            .ok_or_else(|| {
+                HolochainError::ErrorGeneric(format!("Capability not found in DNA: {}", cap))
+            })?;
+        Ok(Entry::CapTokenGrant(CapTokenGrant::create(cap_type, None)?))
+    }
+
+    /// Replaces the source chain of this instance with the given headers and entries,
+    /// oldest first, after validating them (see `import_chain_workflow()`).
+    /// Chains with unsigned headers only get imported if `allow_unsigned` is set.
+    /// Returns the address of the new top chain header.
+    pub fn import_chain(
+        &mut self,
+        chain: Vec<(ChainHeader, Entry)>,
+        allow_unsigned: bool,
+    ) -> HolochainResult<Address> {
+        Ok(block_on(import_chain_workflow(
+            chain,
+            allow_unsigned,
+            &self.context,
+        ))?)
+    }
+
+    /// resolves the given graph query against this instance's view of the DHT
+    pub fn graph_query(&self, query: &GraphQuery) -> HolochainResult<GraphNode> {
+        if !self.active {
+            return Err(HolochainInstanceError::InstanceNotActiveYet);
+        }
+        Ok(graph_query(&self.context, query)?)
+    }
+
+    /// describes the DNA of this instance without its WASM code: name, version and for each
+    /// zome its capabilities with their type and the declared inputs and outputs of their
+    /// functions, e.g. for generating typed bindings
+    pub fn describe(&self) -> HolochainResult<JsonString> {
+        let dna = self.context.get_dna().ok_or(HolochainError::DnaMissing)?;
+        let zomes: serde_json::Map<String, serde_json::Value> = dna
+            .zomes
+            .iter()
+            .map(|(zome_name, zome)| {
+                let description = json!({
+                    "description": zome.description,
+                    "capabilities": zome.capabilities,
+                });
+                (zome_name.clone(), description)
+            })
+            .collect();
+        Ok(JsonString::from(json!({
+            "name": dna.name,
+            "description": dna.description,
+            "version": dna.version,
+            "uuid": dna.uuid,
+            "zomes": zomes,
+        })))
+    }
+
+    /// Marks this instance as removed from its container, so that calls still coming
+    /// in through bridges or old handlers get an `InstanceRemoved` error.
+    /// Can't be undone.
+    pub(crate) fn mark_removed(&mut self) {
+        self.removed = true;
     }
 
     /// checks to see if an instance is active
@@ -168,6 +483,41 @@ impl Holochain {
         self.active
     }
 
+    /// sets how long the instance has to be active before it is considered ready,
+    /// to give it time to sync DHT data after joining the network
+    pub fn set_readiness_grace(&mut self, readiness_grace: Duration) {
+        self.readiness_grace = readiness_grace;
+    }
+
+    /// makes `call()` record into the given metrics of the container,
+    /// under the given ID of this instance
+    pub fn set_zome_call_metrics(
+        &mut self,
+        instance_id: String,
+        zome_call_metrics: Arc<ZomeCallMetrics>,
+    ) {
+        self.zome_call_metrics = Some((instance_id, zome_call_metrics));
+    }
+
+    /// makes `call()` count its calls as running in the given calls of the container,
+    /// under the given ID of this instance, and fail if the container refuses them
+    pub fn set_calls_in_flight(
+        &mut self,
+        instance_id: String,
+        calls_in_flight: Arc<CallsInFlight>,
+    ) {
+        self.calls_in_flight = Some((instance_id, calls_in_flight));
+    }
+
+    /// checks to see if an instance is active and its readiness grace has elapsed
+    pub fn ready(&self) -> bool {
+        self.active
+            && self
+                .started_at
+                .map(|started_at| started_at.elapsed() >= self.readiness_grace)
+                .unwrap_or(false)
+    }
+
     /// return
     pub fn state(&self) -> Result<State, HolochainInstanceError> {
         Ok(self.instance.state().clone())
@@ -179,7 +529,7 @@ impl Holochain {
 }
 
 #[cfg(test)]
-mod tests {
+pub mod tests {
     extern crate holochain_cas_implementations;
 
     use super::*;
@@ -192,6 +542,7 @@ mod tests {
         signal::{signal_channel, SignalReceiver},
     };
     use holochain_core_types::{agent::AgentId, cas::content::Address, dna::Dna};
+    use holochain_net_connection::{net_connection::NetConnection, protocol::Protocol};
     use holochain_wasm_utils::wasm_target_dir;
     use std::sync::{Arc, Mutex};
     use tempfile::tempdir;
@@ -200,7 +551,9 @@ mod tests {
         create_wasm_from_file, expect_action, hc_setup_and_call_zome_fn,
     };
 
-    fn test_context(agent_name: &str) -> (Arc<Context>, Arc<Mutex<TestLogger>>, SignalReceiver) {
+    pub fn test_context(
+        agent_name: &str,
+    ) -> (Arc<Context>, Arc<Mutex<TestLogger>>, SignalReceiver) {
         let agent = AgentId::generate_fake(agent_name);
         let (signal_tx, signal_rx) = signal_channel();
         let logger = test_logger();
@@ -228,7 +581,7 @@ mod tests {
         )
     }
 
-    fn example_api_wasm() -> Vec<u8> {
+    pub fn example_api_wasm() -> Vec<u8> {
         create_wasm_from_file(&example_api_wasm_path())
     }
 
@@ -259,6 +612,35 @@ mod tests {
         assert!(format!("{:?}", *test_logger).contains("\"debug/container: TestApp instantiated\""));
     }
 
+    #[test]
+    fn with_new_dna_stops_the_old_network() {
+        let (context, _, _) = test_context("bob");
+        let hc = Holochain::new(Dna::new(), context).unwrap();
+        let old_network = hc.state().unwrap().network().network.clone().unwrap();
+
+        let new_hc = hc.with_new_dna(Dna::new()).unwrap();
+        let new_network = new_hc.state().unwrap().network().network.clone().unwrap();
+        assert!(!Arc::ptr_eq(&old_network, &new_network));
+        assert!(old_network
+            .lock()
+            .unwrap()
+            .send(Protocol::P2pReady)
+            .is_err());
+        assert!(new_network.lock().unwrap().send(Protocol::P2pReady).is_ok());
+    }
+
+    #[test]
+    fn with_new_dna_commits_the_new_dna() {
+        let (context, _, _) = test_context("bob");
+        let hc = Holochain::new(Dna::new(), context).unwrap();
+        let mut new_dna = Dna::new();
+        new_dna.uuid = String::from("new version");
+
+        let new_hc = hc.with_new_dna(new_dna.clone()).unwrap();
+        let top = new_hc.state().unwrap().agent().top_chain_header().unwrap();
+        assert_eq!(top.entry_address(), &Entry::Dna(new_dna).address());
+    }
+
     fn write_agent_state_to_file() -> String {
         let tempdir = tempdir().unwrap();
         let path = tempdir.path().to_str().unwrap();
@@ -373,6 +755,27 @@ mod tests {
         assert!(!hc.active());
     }
 
+    #[test]
+    fn ready_after_readiness_grace() {
+        let dna = Dna::new();
+        let (context, _, _) = test_context("bob");
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        assert!(!hc.ready());
+
+        hc.start().unwrap();
+        assert!(hc.ready());
+        hc.stop().unwrap();
+
+        hc.set_readiness_grace(Duration::from_millis(200));
+        hc.start().unwrap();
+        assert!(!hc.ready());
+        ::std::thread::sleep(Duration::from_millis(250));
+        assert!(hc.ready());
+
+        hc.stop().unwrap();
+        assert!(!hc.ready());
+    }
+
     #[test]
     fn can_call() {
         let wat = r#"
@@ -1,18 +1,63 @@
-use holochain_core::state::State;
-use holochain_core_types::{cas::content::Address, dna::capabilities::CapabilityCall};
+use call_queue::CallQueue;
+use config::{
+    load_container_configuration, AgentConfiguration, Bridge, Configuration, DnaConfiguration,
+    InstanceConfiguration, InterfaceConfiguration, InterfaceDriver, RemoteBridge,
+    StorageConfiguration,
+};
+use container::ContainerAdminCommand;
+use holochain_core::{
+    action::Action,
+    signal::{Signal, SignalReceiver},
+    state::State,
+    workflows::graph_query::GraphQuery,
+};
+use holochain_core_types::{
+    cas::content::{Address, AddressableContent},
+    chain_header::ChainHeader,
+    dna::capabilities::{CapabilityCall, FnDeclaration},
+    entry::entry_type::EntryType,
+    error::ZomeFunctionError,
+};
+use metrics::ZomeCallMetrics;
+use remote_bridge;
+use signal_broadcaster::{SignalBroadcaster, SubscriptionId};
+use state_diff::StateDiff;
+use state_dump::dump_source_chain_page;
 use Holochain;
 
-use jsonrpc_ws_server::jsonrpc_core::{self, IoHandler, Value};
+use jsonrpc_ws_server::jsonrpc_core::{
+    self,
+    futures::{
+        future,
+        sync::{mpsc, oneshot},
+        Future, Sink,
+    },
+    Call, Failure, FutureOutput, IoHandler, MetaIoHandler, Metadata, Middleware, Output, Params,
+    Value,
+};
 use serde_json;
 use std::{
-    collections::HashMap,
-    sync::{Arc, RwLock},
+    collections::{BTreeMap, HashMap, HashSet},
+    io,
+    net::{TcpStream, ToSocketAddrs},
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex, PoisonError, RwLock, RwLockReadGuard, TryLockError,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
-use config::InstanceConfiguration;
+/// How long "admin/config/reload" waits for the container to process the reload.
+const ADMIN_COMMAND_TIMEOUT_MS: u64 = 60_000;
+
+/// How long "info/health" waits for n3h to accept a connection at its IPC binding.
+const NETWORK_PROBE_TIMEOUT_MS: u64 = 500;
 
 pub type InterfaceError = String;
 pub type InstanceMap = HashMap<String, Arc<RwLock<Holochain>>>;
+/// Error messages of interfaces whose thread failed, keyed by interface ID
+pub type InterfaceFailures = Arc<Mutex<HashMap<String, String>>>;
 
 pub trait DispatchRpc {
     fn handler(self) -> IoHandler;
@@ -25,7 +70,25 @@ pub trait DispatchRpc {
 /// Examples for method names are:
 /// {instance_id}/{zome}/{cap}/{func} -> a zome call
 /// info/list_instances               -> Map of InstanceConfigs, keyed by ID
+/// info/health                       -> Overall container health, see setup_health_api()
+/// info/version                      -> Version, git commit and build time of the container
+/// info/functions                    -> List of an instance's zome functions
+/// query/graph                       -> Nested entries and links, see GraphQuery
+/// instances/call_many               -> Several zome calls in one request
+/// admin/dht/diff                    -> StateDiff between two instances' local shards
+/// admin/instance/dump               -> JSON dump of an instance's source chain
+/// admin/config/reload               -> Apply a new config, returns the ConfigDiff
+/// admin/network/info                -> IPC and P2P bindings of the network process
+/// admin/interfaces/list             -> Driver, port and instances of every interface
 /// admin/...                         -> TODO
+/// metrics/get_entry                 -> Cache, local and network look-ups of get entry workflows
+/// metrics/instances                 -> Zome call counts, errors and latencies per function
+///
+/// Methods under admin/ and metrics/ are only registered for admin interfaces,
+/// see with_admin_api().
+/// Every other method is available on all interfaces.
+/// The signals/subscribe and signals/unsubscribe methods are not part of this handler,
+/// see SignalSubscriptions.
 ///
 /// Each interface has their own handler, and each may be configured differently.
 /// This builder makes it convenient to create handlers with different configurations.
@@ -35,7 +98,24 @@ pub trait DispatchRpc {
 pub struct ContainerApiBuilder {
     instances: InstanceMap,
     instance_configs: HashMap<String, InstanceConfiguration>,
+    dna_configs: HashMap<String, DnaConfiguration>,
+    agent_configs: HashMap<String, AgentConfiguration>,
+    bridges: Vec<Bridge>,
+    interface_configs: Vec<InterfaceConfiguration>,
     io: Box<IoHandler>,
+    structured_zome_errors: bool,
+    validate_zome_function_inputs: bool,
+    admin: bool,
+    admin_commands: Option<Arc<Mutex<Sender<ContainerAdminCommand>>>>,
+    network_ipc_binding: Option<String>,
+    network_p2p_bindings: Option<Vec<String>>,
+    network_configured: bool,
+    interface_failures: InterfaceFailures,
+    capability_calls: Arc<RwLock<HashMap<String, HashMap<String, CapabilityCall>>>>,
+    publish_confirmation: Option<PublishConfirmation>,
+    read_only_functions: Option<HashSet<String>>,
+    call_queues: HashMap<String, Arc<CallQueue>>,
+    zome_call_metrics: Option<Arc<ZomeCallMetrics>>,
 }
 
 impl ContainerApiBuilder {
@@ -43,33 +123,595 @@ impl ContainerApiBuilder {
         ContainerApiBuilder {
             instances: HashMap::new(),
             instance_configs: HashMap::new(),
+            dna_configs: HashMap::new(),
+            agent_configs: HashMap::new(),
+            bridges: Vec::new(),
+            interface_configs: Vec::new(),
             io: Box::new(IoHandler::new()),
+            structured_zome_errors: false,
+            validate_zome_function_inputs: false,
+            admin: false,
+            admin_commands: None,
+            network_ipc_binding: None,
+            network_p2p_bindings: None,
+            network_configured: false,
+            interface_failures: Arc::new(Mutex::new(HashMap::new())),
+            capability_calls: Arc::new(RwLock::new(HashMap::new())),
+            publish_confirmation: None,
+            read_only_functions: None,
+            call_queues: HashMap::new(),
+            zome_call_metrics: None,
         }
     }
 
     /// Finish the building and retrieve the populated handler
     pub fn spawn(mut self) -> IoHandler {
         self.setup_info_api();
+        self.setup_health_api();
+        self.setup_version_api();
+        self.setup_functions_api();
+        self.setup_query_api();
+        self.setup_call_many_api();
+        if self.admin {
+            self.setup_admin_api();
+        }
         *self.io
     }
 
-    /// Adds a "info/instances" method that returns a JSON object describing all registered
-    /// instances we have a config for.
+    /// Adds a "info/health" method that returns a JSON object describing the health of
+    /// the container, meant for load balancers and readiness probes:
+    /// * "instances": the "active" and "ready" flags of every registered instance,
+    ///   keyed by instance name. Instances that are locked by a call get reported as
+    ///   `{"busy": true}` instead of waiting for the call, instances whose lock got
+    ///   poisoned by a panicking call as `{"failed": true}`.
+    /// * "running_instances" and "stopped_instances": how many instances are (not) active,
+    ///   busy instances count as running, failed ones as stopped
+    /// * "network": null without a network config, otherwise the n3h "ipc_binding"
+    ///   and whether it is "live", i.e. n3h accepts connections at that binding
+    /// * "failed_interfaces": IDs of the interfaces whose thread ended with an error
+    /// * "healthy": true if all instances are running, the network is live (if configured)
+    ///   and no interface failed.
+    /// A degraded container still gets a successful response, callers have to inspect
+    /// the fields.
+    fn setup_health_api(&mut self) {
+        let instances = self.instances.clone();
+        let network_configured = self.network_configured;
+        let network_ipc_binding = self.network_ipc_binding.clone();
+        let interface_failures = self.interface_failures.clone();
+
+        self.io.add_method("info/health", move |_| {
+            let instance_health: HashMap<_, _> = instances
+                .iter()
+                .map(|(name, instance)| {
+                    let health = match instance.try_read() {
+                        Ok(hc) => json!({"active": hc.active(), "ready": hc.ready()}),
+                        Err(TryLockError::WouldBlock) => json!({"busy": true}),
+                        Err(TryLockError::Poisoned(_)) => json!({"failed": true}),
+                    };
+                    (name.clone(), health)
+                })
+                .collect();
+            let running_instances = instance_health
+                .values()
+                .filter(|health| {
+                    health["active"] == Value::Bool(true) || health["busy"] == Value::Bool(true)
+                })
+                .count();
+            let stopped_instances = instance_health.len() - running_instances;
+            let network_live = network_configured
+                && network_ipc_binding
+                    .as_ref()
+                    .map(|uri| ipc_endpoint_reachable(uri))
+                    .unwrap_or(false);
+            let network = if network_configured {
+                json!({
+                    "ipc_binding": network_ipc_binding,
+                    "live": network_live,
+                })
+            } else {
+                Value::Null
+            };
+            let mut failed_interfaces: Vec<String> =
+                interface_failures.lock().unwrap().keys().cloned().collect();
+            failed_interfaces.sort();
+            let healthy = stopped_instances == 0
+                && (!network_configured || network_live)
+                && failed_interfaces.is_empty();
+            let health = json!({
+                "instances": instance_health,
+                "running_instances": running_instances,
+                "stopped_instances": stopped_instances,
+                "network": network,
+                "failed_interfaces": failed_interfaces,
+                "healthy": healthy,
+            });
+            Ok(Value::String(health.to_string()))
+        });
+    }
+
+    /// Adds a "info/version" method that returns a JSON object with the "version" of the
+    /// container API crate and the "git_commit" and "build_timestamp" it was built from.
+    fn setup_version_api(&mut self) {
+        self.io.add_method("info/version", |_| {
+            let version = json!({
+                "version": env!("CARGO_PKG_VERSION"),
+                "git_commit": env!("HC_BUILD_GIT_COMMIT"),
+                "build_timestamp": env!("HC_BUILD_TIMESTAMP"),
+            });
+            Ok(Value::String(version.to_string()))
+        });
+    }
+
+    /// Adds a "info/instances" method that returns a JSON array describing all registered
+    /// instances we have a config for, as objects with the fields
+    /// "id", "dna", "dna_hash", "agent", "agent_public_address", "storage" (the type)
+    /// and "active", i.e. whether the instance is currently running.
+    /// Like in "info/health", instances locked by a call get `"busy": true` and poisoned
+    /// ones `"failed": true` instead of "active".
+    /// DNA hash and agent address are null if their configs were not added to the builder.
     fn setup_info_api(&mut self) {
         let instance_configs = self.instance_configs.clone();
 
-        let configs: Vec<_> = self
+        let infos: Vec<_> = self
             .instances
             .iter()
-            .filter(|&(name, _)| instance_configs.contains_key(name))
-            .map(|(name, _)| instance_configs.get(name).unwrap())
+            .filter_map(|(name, instance)| {
+                instance_configs
+                    .get(name)
+                    .map(|config| (config, instance.clone()))
+            })
+            .map(|(config, instance)| {
+                let storage = match config.storage {
+                    StorageConfiguration::Memory => "memory",
+                    StorageConfiguration::File { .. } => "file",
+                };
+                let info = json!({
+                    "id": config.id,
+                    "dna": config.dna,
+                    "dna_hash": self.dna_configs.get(&config.dna).map(|dna| &dna.hash),
+                    "agent": config.agent,
+                    "agent_public_address": self
+                        .agent_configs
+                        .get(&config.agent)
+                        .map(|agent| &agent.public_address),
+                    "storage": storage,
+                });
+                (info, instance)
+            })
             .collect();
 
-        let config_string = serde_json::to_string(&configs)
-            .expect("Vector of InstanceConfigurations must be serializable");
-
         self.io.add_method("info/instances", move |_| {
-            Ok(Value::String(config_string.clone()))
+            let infos: Vec<_> = infos
+                .iter()
+                .map(|(info, instance)| {
+                    let mut info = info.clone();
+                    match instance.try_read() {
+                        Ok(hc) => info["active"] = Value::Bool(hc.active()),
+                        Err(TryLockError::WouldBlock) => info["busy"] = Value::Bool(true),
+                        Err(TryLockError::Poisoned(_)) => info["failed"] = Value::Bool(true),
+                    }
+                    info
+                })
+                .collect();
+            Ok(Value::String(Value::from(infos).to_string()))
+        });
+    }
+
+    /// Adds a "info/functions" method that expects the params `{"instance_id": <id>}` and
+    /// returns a JSON array of the zome functions the instance exposes, as objects with the
+    /// fields "zome", "capability" and "function". Remote bridges use it to find out which
+    /// methods to proxy.
+    /// Also adds a "info/describe" method that expects the same params and returns the
+    /// description of the instance's DNA, see `Holochain::describe()`, or
+    /// `instance_busy_error()` while a call holds the instance's lock.
+    fn setup_functions_api(&mut self) {
+        let instances = self.instances.clone();
+
+        self.io.add_method("info/functions", move |params| {
+            let params: InstanceIdParams = params.parse()?;
+            let instance = instances.get(&params.instance_id).ok_or_else(|| {
+                jsonrpc_core::Error::invalid_params(format!(
+                    "Instance does not exist: {}",
+                    params.instance_id
+                ))
+            })?;
+            let state = instance
+                .read()
+                .map_err(|_| instance_failed_error())?
+                .state()
+                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+            let functions: Vec<ZomeFunctionInfo> = state
+                .nucleus()
+                .dna()
+                .map(|dna| {
+                    dna.zomes
+                        .iter()
+                        .flat_map(|(zome_name, zome)| {
+                            zome.capabilities.iter().flat_map(move |(cap_name, cap)| {
+                                cap.functions.iter().map(move |func| ZomeFunctionInfo {
+                                    zome: zome_name.clone(),
+                                    capability: cap_name.clone(),
+                                    function: func.name.clone(),
+                                })
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let functions_string = serde_json::to_string(&functions)
+                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+            Ok(Value::String(functions_string))
+        });
+
+        let instances = self.instances.clone();
+        self.io.add_method("info/describe", move |params| {
+            let params: InstanceIdParams = params.parse()?;
+            let instance = instances.get(&params.instance_id).ok_or_else(|| {
+                jsonrpc_core::Error::invalid_params(format!(
+                    "Instance does not exist: {}",
+                    params.instance_id
+                ))
+            })?;
+            let description = try_read_instance(instance)?
+                .describe()
+                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+            Ok(Value::String(description.to_string()))
+        });
+    }
+
+    /// Answer zome calls that return a `ZomeFunctionError` with a JSON-RPC error object
+    /// built from its code, message and data.
+    /// Only affects instances added after this call.
+    pub fn with_structured_zome_errors(mut self, structured_zome_errors: bool) -> Self {
+        self.structured_zome_errors = structured_zome_errors;
+        self
+    }
+
+    /// Reject zome calls whose params don't match the inputs the DNA declares for the
+    /// function, see `check_zome_function_inputs()`, instead of passing them on to the zome.
+    /// Only affects instances added after this call.
+    pub fn with_zome_function_input_validation(mut self, validate: bool) -> Self {
+        self.validate_zome_function_inputs = validate;
+        self
+    }
+
+    /// Makes zome calls only return once every entry they published got held by an instance
+    /// of another agent, as seen by the Hold signals the broadcaster passes on, or once
+    /// the timeout passed. Calls return their result either way.
+    /// `instance_agents` maps the IDs of the container's instances to their agent's ID,
+    /// holds of instances of the calling instance's agent don't count. Calls of instances
+    /// whose agent has no other instance in the container don't wait since nothing could
+    /// confirm them.
+    /// No waiting without a timeout. Only affects instances added after this call.
+    pub fn with_publish_confirmation(
+        mut self,
+        broadcaster: Arc<Mutex<SignalBroadcaster>>,
+        timeout: Option<Duration>,
+        instance_agents: HashMap<String, String>,
+    ) -> Self {
+        self.publish_confirmation = timeout.map(|timeout| PublishConfirmation {
+            broadcaster,
+            timeout,
+            instance_agents,
+        });
+        self
+    }
+
+    /// Only lets clients call the given zome functions, named "{zome}/{function}".
+    /// Calls of other zome functions get rejected with an error, directly as well as
+    /// through "instances/call_many". All zome functions are callable if None.
+    /// Only affects instances added after this call.
+    pub fn with_read_only_functions(mut self, functions: Option<Vec<String>>) -> Self {
+        self.read_only_functions = functions.map(|functions| functions.into_iter().collect());
+        self
+    }
+
+    /// Makes zome calls of the instances with the given names wait for their turn in the
+    /// given queues before calling into the instance, ordered by the `_call_priority` param
+    /// of the call, see `CALL_PRIORITY_PARAM`.
+    /// Only affects instances added after this call.
+    pub fn with_call_queues(mut self, call_queues: HashMap<String, Arc<CallQueue>>) -> Self {
+        self.call_queues = call_queues;
+        self
+    }
+
+    /// Like `with_call_queues()` for a single instance added under the given name,
+    /// as bridges do with the callee's queue.
+    pub fn with_named_instance_call_queue(
+        mut self,
+        instance_name: String,
+        call_queue: Arc<CallQueue>,
+    ) -> Self {
+        self.call_queues.insert(instance_name, call_queue);
+        self
+    }
+
+    /// The container's zome call metrics, reported by "metrics/instances"
+    pub fn with_zome_call_metrics(mut self, metrics: Option<Arc<ZomeCallMetrics>>) -> Self {
+        self.zome_call_metrics = metrics;
+        self
+    }
+
+    /// Adds admin functions to the handler, for interfaces flagged as admin.
+    pub fn with_admin_api(mut self, admin: bool) -> Self {
+        self.admin = admin;
+        self
+    }
+
+    /// Channel to the container that admin methods changing the container get passed on to.
+    /// Without it, those methods are not available even on admin handlers.
+    pub fn with_admin_commands(mut self, admin_commands: Sender<ContainerAdminCommand>) -> Self {
+        self.admin_commands = Some(Arc::new(Mutex::new(admin_commands)));
+        self
+    }
+
+    /// Bindings of the container's n3h process, reported by "admin/network/info".
+    pub fn with_network_bindings(
+        mut self,
+        ipc_binding: Option<String>,
+        p2p_bindings: Option<Vec<String>>,
+    ) -> Self {
+        self.network_ipc_binding = ipc_binding;
+        self.network_p2p_bindings = p2p_bindings;
+        self
+    }
+
+    /// Whether the container has a network config, so "info/health" expects
+    /// the network bindings to be set.
+    pub fn with_network_configured(mut self, network_configured: bool) -> Self {
+        self.network_configured = network_configured;
+        self
+    }
+
+    /// Errors of the container's failed interfaces, reported by "info/health".
+    pub fn with_interface_failures(mut self, interface_failures: InterfaceFailures) -> Self {
+        self.interface_failures = interface_failures;
+        self
+    }
+
+    /// Adds a "query/graph" method that expects the params
+    /// `{"instance_id": <id>, "query": <GraphQuery>}` and resolves the whole query
+    /// in one round trip, returning the serialized GraphNode for the query's base.
+    fn setup_query_api(&mut self) {
+        let instances = self.instances.clone();
+
+        self.io.add_method("query/graph", move |params| {
+            let params: GraphQueryParams = params.parse()?;
+            let instance = instances.get(&params.instance_id).ok_or_else(|| {
+                jsonrpc_core::Error::invalid_params(format!(
+                    "Instance does not exist: {}",
+                    params.instance_id
+                ))
+            })?;
+            let node = instance
+                .read()
+                .map_err(|_| instance_failed_error())?
+                .graph_query(&params.query)
+                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+            let node_string = serde_json::to_string(&node)
+                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+            Ok(Value::String(node_string))
+        });
+    }
+
+    /// Adds a "instances/call_many" method that expects the params
+    /// `{"calls": [{"instance_id": <id>, "zome": <zome>, "capability": <optional cap>,
+    /// "function": <fn>, "params": <params>}], "stop_on_error": <bool>}` and calls the given
+    /// zome functions in the given order, each like a call of its own method would be.
+    /// Returns an array with `{"result": <result>}` or `{"error": <JSON-RPC error>}` per call.
+    /// If `stop_on_error` is set, the calls after the first failing one are skipped.
+    fn setup_call_many_api(&mut self) {
+        let instances = self.instances.clone();
+        let settings = self.zome_call_settings();
+        let call_queues = self.call_queues.clone();
+
+        self.io.add_method("instances/call_many", move |params| {
+            let params: CallManyParams = params.parse()?;
+            let mut results = Vec::new();
+            for call in params.calls.iter() {
+                let result = instances
+                    .get(&call.instance_id)
+                    .ok_or_else(|| {
+                        jsonrpc_core::Error::invalid_params(format!(
+                            "Instance does not exist: {}",
+                            call.instance_id
+                        ))
+                    })
+                    .and_then(|instance| {
+                        let call_queue = call_queues.get(&call.instance_id);
+                        call_zome_function(
+                            &settings,
+                            instance,
+                            call_queue.map(|queue| &**queue),
+                            None,
+                            call,
+                        )
+                    });
+                match result {
+                    Ok(response) => results.push(json!({ "result": response })),
+                    Err(error) => {
+                        results.push(json!({ "error": error }));
+                        if params.stop_on_error {
+                            break;
+                        }
+                    }
+                }
+            }
+            Ok(Value::Array(results))
+        });
+    }
+
+    /// Adds admin methods:
+    /// "admin/dht/diff" expects the params `{"instance_a": <id>, "instance_b": <id>}` and
+    /// returns the serialized StateDiff between those two instances.
+    /// "admin/instance/dump" expects the params `{"instance_id": <id>}` and returns the
+    /// instance's source chain as JSON, see `dump_source_chain()`. jsonrpc_core hands
+    /// whole responses to the transports, so the dump can't be streamed. Long chains can be
+    /// fetched in pages instead with the optional params `"offset"`, the number of most
+    /// recent headers to skip, and `"limit"`, the number of headers to return at most.
+    /// The instance's lock is only held to take a snapshot of its state.
+    /// "admin/bridges/list" returns the configured bridges as JSON array of
+    /// `{"caller_id": <id>, "callee_id": <id>, "handle": <handle>}`, only the ones of the
+    /// given instance if called with `{"instance_id": <id>}`.
+    /// "admin/interfaces/list" returns the configured interfaces as JSON array of
+    /// `{"id": <id>, "driver": <driver type>, "port": <port or null>, "instances": [<id>]}`,
+    /// listing all instances for interfaces that serve all instances.
+    /// "admin/config/reload" expects the params `{"config": <TOML string>}`, has the container
+    /// apply that configuration and returns the serialized ConfigDiff.
+    /// "admin/network/info" returns the n3h bindings as
+    /// `{"ipc_binding": <uri>, "p2p_bindings": [<address>]}`, see `with_network_bindings()`.
+    /// "metrics/get_entry" returns the GetEntryMetricsSnapshot of every instance, keyed by
+    /// instance ID, or null for instances without metrics (see the `metrics` config flag).
+    /// "metrics/instances" likewise returns the InstanceCallMetricsSnapshot of every instance
+    /// (see the `zome_call_metrics` config flag), read without locking any instance.
+    fn setup_admin_api(&mut self) {
+        let instances = self.instances.clone();
+        let dump_instances = self.instances.clone();
+        let metrics_instances = self.instances.clone();
+        let call_metrics_instance_ids: Vec<String> = self.instances.keys().cloned().collect();
+        let zome_call_metrics = self.zome_call_metrics.clone();
+
+        let network_info = json!({
+            "ipc_binding": self.network_ipc_binding,
+            "p2p_bindings": self.network_p2p_bindings,
+        })
+        .to_string();
+        self.io.add_method("admin/network/info", move |_| {
+            Ok(Value::String(network_info.clone()))
+        });
+
+        if let Some(admin_commands) = self.admin_commands.clone() {
+            self.io.add_method("admin/config/reload", move |params| {
+                let params: ReloadConfigParams = params.parse()?;
+                let config = load_container_configuration(&params.config)
+                    .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+                let (response_tx, response_rx) = channel();
+                admin_commands
+                    .lock()
+                    .unwrap()
+                    .send(ContainerAdminCommand::ReloadConfig(config, response_tx))
+                    .map_err(|_| jsonrpc_core::Error::internal_error())?;
+                let diff = response_rx
+                    .recv_timeout(Duration::from_millis(ADMIN_COMMAND_TIMEOUT_MS))
+                    .map_err(|_| jsonrpc_core::Error::internal_error())?
+                    .map_err(jsonrpc_core::Error::invalid_params)?;
+                let diff_string = serde_json::to_string(&diff)
+                    .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+                Ok(Value::String(diff_string))
+            });
+        }
+
+        let mut instance_ids: Vec<String> = self.instance_configs.keys().cloned().collect();
+        instance_ids.sort();
+        let interfaces: Vec<Value> = self
+            .interface_configs
+            .iter()
+            .map(|interface_config| {
+                let served_ids: Vec<&String> = instance_ids
+                    .iter()
+                    .filter(|id| interface_config.serves_instance(id))
+                    .collect();
+                json!({
+                    "id": interface_config.id,
+                    "driver": driver_type(&interface_config.driver),
+                    "port": interface_config.driver.port(),
+                    "instances": served_ids,
+                })
+            })
+            .collect();
+        let interfaces_string = Value::Array(interfaces).to_string();
+        self.io.add_method("admin/interfaces/list", move |_| {
+            Ok(Value::String(interfaces_string.clone()))
+        });
+
+        let bridges = self.bridges.clone();
+        self.io.add_method("admin/bridges/list", move |params| {
+            let params: Option<InstanceIdParams> = params.parse()?;
+            let bridges: Vec<&Bridge> = bridges
+                .iter()
+                .filter(|bridge| match params {
+                    Some(ref params) => bridge.involves(&params.instance_id),
+                    None => true,
+                })
+                .collect();
+            let bridges_string = serde_json::to_string(&bridges)
+                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+            Ok(Value::String(bridges_string))
+        });
+
+        self.io.add_method("admin/instance/dump", move |params| {
+            let params: DumpParams = params.parse()?;
+            let instance = dump_instances.get(&params.instance_id).ok_or_else(|| {
+                jsonrpc_core::Error::invalid_params(format!(
+                    "Instance does not exist: {}",
+                    params.instance_id
+                ))
+            })?;
+            let state = instance
+                .read()
+                .map_err(|_| instance_failed_error())?
+                .state()
+                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+            let mut dump = Vec::new();
+            dump_source_chain_page(&state, params.offset, params.limit, &mut dump)
+                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+            let dump_string = String::from_utf8(dump)
+                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+            Ok(Value::String(dump_string))
+        });
+
+        self.io.add_method("metrics/get_entry", move |_| {
+            let metrics = metrics_instances
+                .iter()
+                .map(|(id, instance)| {
+                    let snapshot = instance
+                        .read()
+                        .map_err(|_| instance_failed_error())?
+                        .context()
+                        .get_entry_metrics
+                        .as_ref()
+                        .map(|metrics| metrics.snapshot());
+                    Ok((id.clone(), snapshot))
+                })
+                .collect::<Result<HashMap<_, _>, jsonrpc_core::Error>>()?;
+            let metrics_string = serde_json::to_string(&metrics)
+                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+            Ok(Value::String(metrics_string))
+        });
+
+        self.io.add_method("metrics/instances", move |_| {
+            let mut snapshot = zome_call_metrics.as_ref().map(|metrics| metrics.snapshot());
+            let metrics: BTreeMap<_, _> = call_metrics_instance_ids
+                .iter()
+                .map(|id| {
+                    let instance_snapshot = snapshot
+                        .as_mut()
+                        .map(|snapshot| snapshot.remove(id).unwrap_or_default());
+                    (id.clone(), instance_snapshot)
+                })
+                .collect();
+            let metrics_string = serde_json::to_string(&metrics)
+                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+            Ok(Value::String(metrics_string))
+        });
+
+        self.io.add_method("admin/dht/diff", move |params| {
+            let params: DiffInstancesParams = params.parse()?;
+            let get_instance = |id: &String| {
+                instances.get(id).cloned().ok_or_else(|| {
+                    jsonrpc_core::Error::invalid_params(format!("Instance does not exist: {}", id))
+                })
+            };
+            let instance_a = get_instance(&params.instance_a)?;
+            let instance_b = get_instance(&params.instance_b)?;
+            let diff = StateDiff::between(
+                &*instance_a.read().map_err(|_| instance_failed_error())?,
+                &*instance_b.read().map_err(|_| instance_failed_error())?,
+            )
+            .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+            let diff_string = serde_json::to_string(&diff)
+                .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+            Ok(Value::String(diff_string))
         });
     }
 
@@ -92,6 +734,39 @@ impl ContainerApiBuilder {
         self
     }
 
+    /// Add the DNA configurations instances refer to, so "info/instances" can report DNA hashes
+    pub fn with_dna_configs(mut self, dna_configs: Vec<DnaConfiguration>) -> Self {
+        for config in dna_configs {
+            self.dna_configs.insert(config.id.clone(), config);
+        }
+        self
+    }
+
+    /// Add the agent configurations instances refer to, so "info/instances" can report
+    /// agent addresses
+    pub fn with_agent_configs(mut self, agent_configs: Vec<AgentConfiguration>) -> Self {
+        for config in agent_configs {
+            self.agent_configs.insert(config.id.clone(), config);
+        }
+        self
+    }
+
+    /// Add the container's bridges, which "admin/bridges/list" reports
+    pub fn with_bridges(mut self, bridges: Vec<Bridge>) -> Self {
+        self.bridges = bridges;
+        self
+    }
+
+    /// Add the container's interfaces, which "admin/interfaces/list" reports together with
+    /// the instances of the instance configs they serve
+    pub fn with_interface_configs(
+        mut self,
+        interface_configs: Vec<InterfaceConfiguration>,
+    ) -> Self {
+        self.interface_configs = interface_configs;
+        self
+    }
+
     /// Add several instances with the names given in the InstanceMap
     pub fn with_instances(mut self, instances: InstanceMap) -> Self {
         for (instance_id, hc_lock) in instances {
@@ -100,6 +775,31 @@ impl ContainerApiBuilder {
         self
     }
 
+    /// Sets the CapabilityCalls (i.e. tokens) that calls to the functions of the instance with
+    /// the given name present, keyed by capability name.
+    /// Capabilities without a call get called with a fake token.
+    pub fn with_named_instance_capabilities(
+        self,
+        instance_name: String,
+        capability_calls: HashMap<String, CapabilityCall>,
+    ) -> Self {
+        self.capability_calls
+            .write()
+            .unwrap()
+            .insert(instance_name, capability_calls);
+        self
+    }
+
+    fn zome_call_settings(&self) -> ZomeCallSettings {
+        ZomeCallSettings {
+            structured_zome_errors: self.structured_zome_errors,
+            validate_inputs: self.validate_zome_function_inputs,
+            read_only_functions: self.read_only_functions.clone(),
+            capability_calls: self.capability_calls.clone(),
+            publish_confirmation: self.publish_confirmation.clone(),
+        }
+    }
+
     /// Add a single instance and register it under the given name
     pub fn with_named_instance(
         mut self,
@@ -107,7 +807,9 @@ impl ContainerApiBuilder {
         instance: Arc<RwLock<Holochain>>,
     ) -> Self {
         let hc_lock = instance.clone();
-        let hc = hc_lock.read().unwrap();
+        // The DNA can still be read from an instance whose lock got poisoned,
+        // calls to its functions fail with instance_failed_error() then
+        let hc = hc_lock.read().unwrap_or_else(PoisonError::into_inner);
         let state: State = hc.state().unwrap();
         let nucleus = state.nucleus();
         let dna = nucleus.dna();
@@ -116,35 +818,44 @@ impl ContainerApiBuilder {
                 for (zome_name, zome) in dna.zomes {
                     for (cap_name, cap) in zome.capabilities {
                         for func in cap.functions {
-                            let func_name = func.name;
-                            let zome_name = zome_name.clone();
-                            let cap_name = cap_name.clone();
+                            let maybe_declaration = if self.validate_zome_function_inputs {
+                                Some(func.clone())
+                            } else {
+                                None
+                            };
                             let method_name = format!(
                                 "{}/{}/{}/{}",
-                                instance_name, zome_name, cap_name, func_name
+                                instance_name, zome_name, cap_name, func.name
                             );
-                            let hc_lock_inner = hc_lock.clone();
-                            self.io.add_method(&method_name, move |params| {
-                                let mut hc = hc_lock_inner.write().unwrap();
-                                let params_string =
-                                    serde_json::to_string(&params).map_err(|e| {
-                                        jsonrpc_core::Error::invalid_params(e.to_string())
-                                    })?;
-                                let response = hc
-                                    .call(
-                                        &zome_name,
-                                        Some(CapabilityCall::new(
-                                            cap_name.clone(),
-                                            Address::from("fake_token"),
-                                            None,
-                                        )),
-                                        &func_name,
-                                        &params_string,
-                                    )
-                                    .map_err(|e| {
+                            let settings = self.zome_call_settings();
+                            let instance = hc_lock.clone();
+                            let call_queue = self.call_queues.get(&instance_name).cloned();
+                            let instance_name = instance_name.clone();
+                            let zome_name = zome_name.clone();
+                            let cap_name = cap_name.clone();
+                            let func_name = func.name;
+                            self.io.add_method(&method_name, move |mut params| {
+                                let priority = match call_queue {
+                                    Some(_) => take_call_priority(&mut params)?,
+                                    None => 0,
+                                };
+                                let call = ZomeCallParams {
+                                    instance_id: instance_name.clone(),
+                                    zome: zome_name.clone(),
+                                    capability: Some(cap_name.clone()),
+                                    function: func_name.clone(),
+                                    params: serde_json::to_value(&params).map_err(|e| {
                                         jsonrpc_core::Error::invalid_params(e.to_string())
-                                    })?;
-                                Ok(Value::String(response.to_string()))
+                                    })?,
+                                    priority,
+                                };
+                                call_zome_function(
+                                    &settings,
+                                    &instance,
+                                    call_queue.as_ref().map(|queue| &**queue),
+                                    maybe_declaration.as_ref(),
+                                    &call,
+                                )
                             })
                         }
                     }
@@ -156,60 +867,1844 @@ impl ContainerApiBuilder {
             .insert(instance_name.clone(), instance.clone());
         self
     }
+
+    /// Add the given zome functions of a remote bridge's callee under the bridge's handle.
+    /// Calls of these methods get proxied to the callee's container, see `remote_bridge`.
+    pub fn with_remote_bridge(
+        mut self,
+        bridge: RemoteBridge,
+        functions: Vec<ZomeFunctionInfo>,
+    ) -> Self {
+        let bridge = Arc::new(bridge);
+        for function in functions {
+            let method_name = format!(
+                "{}/{}/{}/{}",
+                bridge.handle, function.zome, function.capability, function.function
+            );
+            let remote_method = format!(
+                "{}/{}/{}/{}",
+                bridge.callee_instance, function.zome, function.capability, function.function
+            );
+            let bridge = bridge.clone();
+            self.io.add_method(&method_name, move |params| {
+                remote_bridge::call(&bridge, &remote_method, Value::from(params))
+            });
+        }
+        self
+    }
 }
 
-pub trait Interface {
-    fn run(&self, handler: IoHandler) -> Result<(), String>;
+/// Waits for the Hold signals of the entries zome calls publish,
+/// see `ContainerApiBuilder::with_publish_confirmation()`.
+#[derive(Clone)]
+struct PublishConfirmation {
+    broadcaster: Arc<Mutex<SignalBroadcaster>>,
+    timeout: Duration,
+    instance_agents: HashMap<String, String>,
 }
 
-#[cfg(test)]
-pub mod tests {
-    use super::*;
-    use crate::{config::Configuration, container::tests::test_container};
+impl PublishConfirmation {
+    /// Subscribes before the call of the given instance so that no Hold signal gets missed.
+    /// Only Hold signals of the instances of other agents get through, so that the
+    /// subscription's channel doesn't fill up with signals that don't confirm anything.
+    /// None if there is no instance of another agent.
+    fn start(&self, hc: &Holochain, instance_id: &str) -> Option<PendingConfirmation> {
+        let author = self.instance_agents.get(instance_id);
+        let holders: HashSet<String> = self
+            .instance_agents
+            .iter()
+            .filter(|(id, agent)| id.as_str() != instance_id && Some(*agent) != author)
+            .map(|(id, _)| id.clone())
+            .collect();
+        if holders.is_empty() {
+            return None;
+        }
+        let is_hold = Box::new(|signal: &Signal| match signal {
+            Signal::Internal(Action::Hold(_)) => true,
+            _ => false,
+        });
+        Some(PendingConfirmation {
+            signal_rx: self
+                .broadcaster
+                .lock()
+                .unwrap()
+                .subscribe_filtered(holders, is_hold),
+            chain_top: top_chain_header(hc),
+            timeout: self.timeout,
+        })
+    }
+}
 
-    fn example_config_and_instances() -> (Configuration, InstanceMap) {
-        let container = test_container();
-        let holochain = container
-            .instances()
-            .get("test-instance-1")
-            .unwrap()
-            .clone();
-        let mut instances = InstanceMap::new();
-        instances.insert("test-instance-1".into(), holochain);
-        (container.config(), instances)
+struct PendingConfirmation {
+    signal_rx: SignalReceiver,
+    chain_top: Option<ChainHeader>,
+    timeout: Duration,
+}
+
+impl PendingConfirmation {
+    /// Addresses of the entries the call added to the source chain and published
+    fn published_entries(&self, hc: &Holochain) -> HashSet<Address> {
+        let state = match hc.state() {
+            Ok(state) => state,
+            Err(_) => return HashSet::new(),
+        };
+        let old_top = self.chain_top.as_ref().map(|header| header.address());
+        state
+            .agent()
+            .chain()
+            .iter(&state.agent().top_chain_header())
+            .take_while(|header| Some(header.address()) != old_top)
+            .filter(|header| gets_published(header.entry_type()))
+            .map(|header| header.entry_address().clone())
+            .collect()
     }
 
-    #[test]
-    fn test_new_dispatcher() {
-        let (config, instances) = example_config_and_instances();
-        let handler = ContainerApiBuilder::new()
-            .with_instances(instances.clone())
-            .with_instance_configs(config.instances)
-            .spawn();
-        let result = format!("{:?}", handler).to_string();
-        println!("{}", result);
-        assert!(result.contains("info/instances"));
-        assert!(result.contains(r#""test-instance-1/greeter/public/hello""#));
-        assert!(!result.contains(r#""test-instance-2//test/test""#));
+    /// Returns true once all given entries got held, false if the timeout passed first.
+    /// Holds that happened before the call was made also count since the subscription
+    /// replays the broadcaster's history.
+    fn wait(self, mut pending: HashSet<Address>) -> bool {
+        let deadline = Instant::now() + self.timeout;
+        while !pending.is_empty() {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            match self.signal_rx.recv_timeout(deadline - now) {
+                Ok(Signal::Internal(Action::Hold(entry))) => {
+                    pending.remove(&entry.address());
+                }
+                Ok(_) => (),
+                Err(_) => return false,
+            }
+        }
+        true
     }
+}
 
-    #[test]
-    fn test_named_instances() {
-        let (config, instances) = example_config_and_instances();
-        let handler = ContainerApiBuilder::new()
-            .with_named_instance(
-                String::from("happ-store"),
-                instances.iter().nth(0).unwrap().1.clone(),
-            )
-            .with_named_instance_config(
-                String::from("happ-store"),
-                config.instances.iter().nth(0).unwrap().clone(),
+fn top_chain_header(hc: &Holochain) -> Option<ChainHeader> {
+    hc.state()
+        .ok()
+        .and_then(|state| state.agent().top_chain_header())
+}
+
+/// Entry types the publish workflow sends to the DHT, which makes their holders
+/// dispatch a Hold action
+fn gets_published(entry_type: &EntryType) -> bool {
+    match entry_type {
+        EntryType::AgentId | EntryType::App(_) | EntryType::LinkAdd | EntryType::Deletion => true,
+        _ => false,
+    }
+}
+
+/// Whether n3h accepts connections at the given IPC binding,
+/// `tcp://<host>:<port>` or `ipc://<socket path>`
+fn ipc_endpoint_reachable(uri: &str) -> bool {
+    if uri.starts_with("tcp://") {
+        let timeout = Duration::from_millis(NETWORK_PROBE_TIMEOUT_MS);
+        uri["tcp://".len()..]
+            .to_socket_addrs()
+            .map(|mut addresses| {
+                addresses.any(|address| TcpStream::connect_timeout(&address, timeout).is_ok())
+            })
+            .unwrap_or(false)
+    } else if uri.starts_with("ipc://") {
+        ipc_socket_reachable(&uri["ipc://".len()..])
+    } else {
+        false
+    }
+}
+
+#[cfg(unix)]
+fn ipc_socket_reachable(path: &str) -> bool {
+    ::std::os::unix::net::UnixStream::connect(path).is_ok()
+}
+
+#[cfg(not(unix))]
+fn ipc_socket_reachable(_path: &str) -> bool {
+    false
+}
+
+/// Type of the given interface driver as written in the config
+fn driver_type(driver: &InterfaceDriver) -> &'static str {
+    match driver {
+        InterfaceDriver::Websocket { .. } => "websocket",
+        InterfaceDriver::WebsocketTls { .. } => "websockettls",
+        InterfaceDriver::Http { .. } => "http",
+        InterfaceDriver::DomainSocket { .. } => "domainsocket",
+        InterfaceDriver::Stdio => "stdio",
+        InterfaceDriver::Metrics { .. } => "metrics",
+        InterfaceDriver::Custom(_) => "custom",
+    }
+}
+
+/// A zome function as listed by "info/functions"
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ZomeFunctionInfo {
+    pub zome: String,
+    pub capability: String,
+    pub function: String,
+}
+
+#[derive(Deserialize)]
+struct GraphQueryParams {
+    instance_id: String,
+    query: GraphQuery,
+}
+
+#[derive(Deserialize)]
+struct ReloadConfigParams {
+    config: String,
+}
+
+/// What zome calls of an interface depend on besides the call itself, shared by the methods
+/// of the zome functions and "instances/call_many", see `call_zome_function()`
+#[derive(Clone)]
+struct ZomeCallSettings {
+    structured_zome_errors: bool,
+    validate_inputs: bool,
+    read_only_functions: Option<HashSet<String>>,
+    /// Looked up per call, so capabilities can be set after the instance
+    capability_calls: Arc<RwLock<HashMap<String, HashMap<String, CapabilityCall>>>>,
+    publish_confirmation: Option<PublishConfirmation>,
+}
+
+/// Calls a zome function of the given instance, the way every zome call of an interface
+/// gets made: checks that it may be called and, if enabled, its params, waits for the turn
+/// of the call in the instance's call queue, presents the capability's token and, with
+/// publish confirmation, waits for the entries the call published to get held.
+/// Calls without a capability go through the first capability, by name, exposing the
+/// function. The declaration of the function gets looked up in the DNA if not given.
+fn call_zome_function(
+    settings: &ZomeCallSettings,
+    instance: &RwLock<Holochain>,
+    call_queue: Option<&CallQueue>,
+    declaration: Option<&FnDeclaration>,
+    call: &ZomeCallParams,
+) -> Result<Value, jsonrpc_core::Error> {
+    if !is_callable(&settings.read_only_functions, &call.zome, &call.function) {
+        return Err(jsonrpc_core::Error::invalid_params(read_only_error(
+            &call.zome,
+            &call.function,
+        )));
+    }
+    let needs_lookup =
+        call.capability.is_none() || (settings.validate_inputs && declaration.is_none());
+    let (cap_name, declaration) = match call.capability {
+        Some(ref cap_name) if !needs_lookup => (cap_name.clone(), declaration.cloned()),
+        _ => {
+            let hc = instance.read().map_err(|_| instance_failed_error())?;
+            let (cap_name, declaration) = find_zome_function(&hc, call)?;
+            (cap_name, Some(declaration))
+        }
+    };
+    if settings.validate_inputs {
+        if let Some(ref declaration) = declaration {
+            check_zome_function_inputs(declaration, &call.params)
+                .map_err(jsonrpc_core::Error::invalid_params)?;
+        }
+    }
+    let params_string = serde_json::to_string(&call.params)
+        .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+
+    let turn = call_queue.map(|queue| queue.wait_for_turn(call.priority));
+    let mut hc = instance.write().map_err(|_| instance_failed_error())?;
+    let capability_call = settings
+        .capability_calls
+        .read()
+        .unwrap()
+        .get(&call.instance_id)
+        .and_then(|calls| calls.get(&cap_name))
+        .cloned()
+        .unwrap_or_else(|| {
+            CapabilityCall::new(cap_name.clone(), Address::from("fake_token"), None)
+        });
+    let pending = settings
+        .publish_confirmation
+        .as_ref()
+        .and_then(|confirmation| confirmation.start(&hc, &call.instance_id));
+    let response = hc
+        .call(
+            &call.zome,
+            Some(capability_call),
+            &call.function,
+            &params_string,
+        )
+        .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+    if let Some(pending) = pending {
+        let published = pending.published_entries(&hc);
+        // Holding is done by the instances' own threads,
+        // other calls don't have to wait for it.
+        drop(hc);
+        drop(turn);
+        pending.wait(published);
+    }
+    if settings.structured_zome_errors {
+        if let Some(error) = ZomeFunctionError::from_zome_function_result(&response) {
+            return Err(jsonrpc_core::Error {
+                code: jsonrpc_core::ErrorCode::ServerError(error.code),
+                message: error.message,
+                data: error.data,
+            });
+        }
+    }
+    Ok(Value::String(response.to_string()))
+}
+
+/// The capability of the given call, or the first by name exposing the called function,
+/// and the declaration of the function
+fn find_zome_function(
+    hc: &Holochain,
+    call: &ZomeCallParams,
+) -> Result<(String, FnDeclaration), jsonrpc_core::Error> {
+    let dna = hc
+        .state()
+        .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?
+        .nucleus()
+        .dna()
+        .ok_or_else(|| {
+            jsonrpc_core::Error::invalid_params(format!(
+                "Instance has no DNA: {}",
+                call.instance_id
+            ))
+        })?;
+    dna.zomes
+        .get(&call.zome)
+        .and_then(|zome| {
+            zome.capabilities
+                .iter()
+                .filter(|(cap_name, _)| {
+                    call.capability
+                        .as_ref()
+                        .map(|capability| capability == *cap_name)
+                        .unwrap_or(true)
+                })
+                .filter_map(|(cap_name, cap)| {
+                    cap.functions
+                        .iter()
+                        .find(|f| f.name == call.function)
+                        .map(|f| (cap_name.clone(), f.clone()))
+                })
+                .next()
+        })
+        .ok_or_else(|| {
+            jsonrpc_core::Error::invalid_params(format!(
+                "Zome function does not exist: {}/{}",
+                call.zome, call.function
+            ))
+        })
+}
+
+/// Param of zome calls that sets the priority of the call in the call queue of instances
+/// with `prioritized_calls`, 0 if not given. Calls with a higher priority get their turn
+/// first. The param does not get passed on to the zome function. Instances without
+/// `prioritized_calls` get it passed on like any other param.
+/// Calls in "instances/call_many" have a "priority" field instead.
+pub const CALL_PRIORITY_PARAM: &str = "_call_priority";
+
+/// Removes the call priority from the given zome call params, see `CALL_PRIORITY_PARAM`
+fn take_call_priority(params: &mut Params) -> Result<i32, jsonrpc_core::Error> {
+    let value = match params {
+        Params::Map(ref mut map) => map.remove(CALL_PRIORITY_PARAM),
+        _ => None,
+    };
+    match value {
+        None => Ok(0),
+        Some(value) => value
+            .as_i64()
+            .filter(|priority| {
+                *priority >= i64::from(i32::min_value()) && *priority <= i64::from(i32::max_value())
+            })
+            .map(|priority| priority as i32)
+            .ok_or_else(|| {
+                jsonrpc_core::Error::invalid_params(format!(
+                    "{} has to be a 32 bit integer, got {}",
+                    CALL_PRIORITY_PARAM, value
+                ))
+            }),
+    }
+}
+
+/// Whether clients may call the given zome function, see
+/// `ContainerApiBuilder::with_read_only_functions()`
+fn is_callable(read_only_functions: &Option<HashSet<String>>, zome: &str, function: &str) -> bool {
+    read_only_functions
+        .as_ref()
+        .map(|functions| functions.contains(&format!("{}/{}", zome, function)))
+        .unwrap_or(true)
+}
+
+fn read_only_error(zome: &str, function: &str) -> String {
+    format!(
+        "Interface is read-only and {}/{} is not one of its read-only functions",
+        zome, function
+    )
+}
+
+/// Checks zome call params against the inputs declared for the function: every declared
+/// input has to be a field of the params object, with a JSON type matching its declared type.
+/// Declared types without a JSON counterpart (e.g. "json" or struct names) accept any value,
+/// and params fields without a declaration are ignored.
+pub fn check_zome_function_inputs(
+    declaration: &FnDeclaration,
+    params: &Value,
+) -> Result<(), String> {
+    if declaration.inputs.is_empty() {
+        return Ok(());
+    }
+    let object = params.as_object().ok_or_else(|| {
+        format!(
+            "Invalid params for {}: expected an object with the fields {}",
+            declaration.name,
+            declaration
+                .inputs
+                .iter()
+                .map(|input| input.name.clone())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    })?;
+    for input in declaration.inputs.iter() {
+        let value = object.get(&input.name).ok_or_else(|| {
+            format!(
+                "Invalid params for {}: missing input \"{}\"",
+                declaration.name, input.name
             )
-            .spawn();
-        let result = format!("{:?}", handler).to_string();
-        println!("{}", result);
-        assert!(result.contains("info/instances"));
-        assert!(result.contains(r#""happ-store/greeter/public/hello""#));
-        assert!(!result.contains(r#""test-instance-1//test/test""#));
+        })?;
+        let matches = match input.parameter_type.as_ref() {
+            "String" | "string" | "Address" | "HashString" => value.is_string(),
+            "bool" | "boolean" => value.is_boolean(),
+            "u8" | "u16" | "u32" | "u64" | "usize" => value.is_u64(),
+            "i8" | "i16" | "i32" | "i64" | "isize" | "integer" => value.is_i64(),
+            "f32" | "f64" | "number" => value.is_number(),
+            _ => true,
+        };
+        if !matches {
+            return Err(format!(
+                "Invalid params for {}: input \"{}\" has to be of type {}, got {}",
+                declaration.name, input.name, input.parameter_type, value
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct ZomeCallParams {
+    instance_id: String,
+    zome: String,
+    /// See `call_zome_function()` for calls without a capability
+    #[serde(default)]
+    capability: Option<String>,
+    function: String,
+    params: Value,
+    /// See `CALL_PRIORITY_PARAM`
+    #[serde(default)]
+    priority: i32,
+}
+
+#[derive(Deserialize)]
+struct CallManyParams {
+    calls: Vec<ZomeCallParams>,
+    #[serde(default)]
+    stop_on_error: bool,
+}
+
+#[derive(Deserialize)]
+struct InstanceIdParams {
+    instance_id: String,
+}
+
+#[derive(Deserialize)]
+struct DumpParams {
+    instance_id: String,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct DiffInstancesParams {
+    instance_a: String,
+    instance_b: String,
+}
+
+/// Liveness of an interface as reported by `Container::interface_status()`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum InterfaceState {
+    Running,
+    Stopped,
+    /// The interface thread ended with the contained error
+    Failed(String),
+}
+
+/// Request metadata interfaces have to provide for the AuthMiddleware,
+/// i.e. whether the client presented the interface's auth token.
+#[derive(Clone, Debug, Default)]
+pub struct AuthMeta {
+    pub authorized: bool,
+    /// Connection the request came in on, if the interface can push notifications to it
+    pub session: Option<NotificationSession>,
+}
+
+impl AuthMeta {
+    /// Tells websocket clients apart by their connection. The other interfaces don't
+    /// get to know anything about the client from their server, see `RateLimiter`.
+    pub fn rate_limit_key(&self) -> RateLimitKey {
+        match self.session {
+            Some(ref session) => RateLimitKey::Session(session.id),
+            None => RateLimitKey::Interface,
+        }
+    }
+}
+
+pub type SessionId = u64;
+
+/// Open client connection that JSON-RPC notifications can be sent to
+#[derive(Clone, Debug)]
+pub struct NotificationSession {
+    pub id: SessionId,
+    pub sender: mpsc::Sender<String>,
+}
+
+impl NotificationSession {
+    pub fn new(id: SessionId, sender: mpsc::Sender<String>) -> Self {
+        NotificationSession { id, sender }
+    }
+}
+
+impl Metadata for AuthMeta {}
+
+/// Rejects calls of unauthorized clients with a JSON-RPC error and hands all other calls
+/// to the wrapped handler, except for the "signals/*" methods which need the request's
+/// AuthMeta and are registered on the AuthIoHandler itself, see `SignalSubscriptions`.
+/// "info/version" can always be called without authorization, "info/instances" too if
+/// `public_info` is set.
+/// With a RateLimiter, calls above its limit get rejected with `too_many_requests_error()`.
+/// With a WorkerPool, the wrapped handler runs on its threads instead of the server's.
+pub struct AuthMiddleware {
+    handler: InterfaceHandler,
+    public_info: bool,
+    rate_limiter: Option<RateLimiter>,
+    workers: Option<WorkerPool>,
+}
+
+impl Middleware<AuthMeta> for AuthMiddleware {
+    type Future = jsonrpc_core::FutureResponse;
+    type CallFuture = FutureOutput;
+
+    fn on_call<F, X>(&self, call: Call, meta: AuthMeta, next: F) -> future::Either<FutureOutput, X>
+    where
+        F: FnOnce(Call, AuthMeta) -> X + Send,
+        X: future::Future<Item = Option<Output>, Error = ()> + Send + 'static,
+    {
+        let is_public = match call {
+            Call::MethodCall(ref method_call) => {
+                method_call.method == "info/version"
+                    || (self.public_info && method_call.method == "info/instances")
+            }
+            _ => false,
+        };
+        if !meta.authorized && !is_public {
+            let output = match call {
+                Call::MethodCall(method_call) => Some(Output::Failure(Failure {
+                    jsonrpc: method_call.jsonrpc,
+                    error: unauthorized_error(),
+                    id: method_call.id,
+                })),
+                // notifications don't get a response
+                _ => None,
+            };
+            return future::Either::A(Box::new(future::ok(output)));
+        }
+        let limited = self
+            .rate_limiter
+            .as_ref()
+            .map(|rate_limiter| !rate_limiter.allow(meta.rate_limit_key()))
+            .unwrap_or(false);
+        if limited {
+            let output = match call {
+                Call::MethodCall(method_call) => Some(Output::Failure(Failure {
+                    jsonrpc: method_call.jsonrpc,
+                    error: too_many_requests_error(),
+                    id: method_call.id,
+                })),
+                _ => None,
+            };
+            return future::Either::A(Box::new(future::ok(output)));
+        }
+        let is_signal_method = match call {
+            Call::MethodCall(ref method_call) => method_call.method.starts_with("signals/"),
+            _ => false,
+        };
+        if is_signal_method {
+            return future::Either::B(next(call, meta));
+        }
+        match self.workers {
+            Some(ref workers) => future::Either::A(workers.dispatch(call)),
+            None => future::Either::A(Box::new(self.handler.current().handle_call(call, ()))),
+        }
+    }
+}
+
+/// The handler a running interface hands its calls to, see `AuthMiddleware`.
+/// Clones share the handler, so that the container can replace it without restarting
+/// the interface, keeping its connections and signal subscriptions.
+#[derive(Clone)]
+pub struct InterfaceHandler(Arc<RwLock<Arc<IoHandler>>>);
+
+impl InterfaceHandler {
+    pub fn new(handler: IoHandler) -> Self {
+        InterfaceHandler(Arc::new(RwLock::new(Arc::new(handler))))
+    }
+
+    /// Calls from now on go to the given handler, calls in progress finish with the old one.
+    pub fn replace(&self, handler: IoHandler) {
+        *self.0.write().unwrap() = Arc::new(handler);
+    }
+
+    pub(crate) fn current(&self) -> Arc<IoHandler> {
+        self.0.read().unwrap().clone()
+    }
+}
+
+/// Handler type interfaces serve, see `with_auth()`
+pub type AuthIoHandler = MetaIoHandler<AuthMeta, AuthMiddleware>;
+
+/// Wraps the given handler so that only calls with authorized AuthMeta get through.
+pub fn with_auth(handler: IoHandler, public_info: bool) -> AuthIoHandler {
+    with_auth_and_rate_limit(handler, public_info, None)
+}
+
+/// Like `with_auth()`, additionally allowing each client at most the given number of
+/// calls per second, see `RateLimiter`.
+pub fn with_auth_and_rate_limit(
+    handler: IoHandler,
+    public_info: bool,
+    max_requests_per_second: Option<u32>,
+) -> AuthIoHandler {
+    with_auth_rate_limit_and_workers(handler, public_info, max_requests_per_second, None)
+}
+
+/// Like `with_auth_and_rate_limit()`, additionally handling calls on the given number of
+/// worker threads, see `WorkerPool`.
+pub fn with_auth_rate_limit_and_workers(
+    handler: IoHandler,
+    public_info: bool,
+    max_requests_per_second: Option<u32>,
+    worker_threads: Option<usize>,
+) -> AuthIoHandler {
+    with_replaceable_handler(
+        InterfaceHandler::new(handler),
+        public_info,
+        max_requests_per_second,
+        worker_threads,
+    )
+}
+
+/// Like `with_auth_rate_limit_and_workers()`, with a handler that can be replaced
+/// while the interface is running, see `InterfaceHandler::replace()`.
+pub fn with_replaceable_handler(
+    handler: InterfaceHandler,
+    public_info: bool,
+    max_requests_per_second: Option<u32>,
+    worker_threads: Option<usize>,
+) -> AuthIoHandler {
+    MetaIoHandler::with_middleware(AuthMiddleware {
+        handler: handler.clone(),
+        public_info,
+        rate_limiter: max_requests_per_second.map(RateLimiter::new),
+        workers: worker_threads.map(|threads| WorkerPool::new(threads, handler)),
+    })
+}
+
+type WorkerJob = (Call, oneshot::Sender<Option<Output>>);
+
+/// Threads that handle the calls of an interface, all with the same handler.
+/// Interface servers handle calls on their event loop thread, one after the other, unless
+/// the handler hands them off. With a WorkerPool, a slow zome call only holds up other calls
+/// once all workers are busy. Calls to the same instance still get serialized by the lock of
+/// the instance, calls to different instances run concurrently.
+/// The threads end when the pool gets dropped together with its interface's handler.
+pub struct WorkerPool {
+    jobs: Mutex<Sender<WorkerJob>>,
+}
+
+impl WorkerPool {
+    /// Spawns `threads` workers, at least one
+    pub fn new(threads: usize, handler: InterfaceHandler) -> Self {
+        let (jobs, job_receiver) = channel::<WorkerJob>();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        for _ in 0..threads.max(1) {
+            let job_receiver = job_receiver.clone();
+            let handler = handler.clone();
+            thread::spawn(move || loop {
+                let job = job_receiver.lock().unwrap().recv();
+                match job {
+                    Ok((call, response)) => {
+                        let output = handler
+                            .current()
+                            .handle_call(call, ())
+                            .wait()
+                            .unwrap_or(None);
+                        let _ = response.send(output);
+                    }
+                    Err(_) => break,
+                }
+            });
+        }
+        WorkerPool {
+            jobs: Mutex::new(jobs),
+        }
+    }
+
+    /// Queues the call for the next free worker
+    fn dispatch(&self, call: Call) -> FutureOutput {
+        let (response, output) = oneshot::channel();
+        if self.jobs.lock().unwrap().send((call, response)).is_err() {
+            return Box::new(future::ok(None));
+        }
+        Box::new(output.map_err(|_| ()))
+    }
+}
+
+/// What the RateLimiter tells clients apart by, see `AuthMeta::rate_limit_key()`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RateLimitKey {
+    Session(SessionId),
+    /// All clients of an interface without sessions share one limit
+    Interface,
+}
+
+/// Counts calls per client in one second windows.
+/// Each websocket connection has its own limit. The HTTP, Unix socket and stdio servers
+/// don't tell the handler where a request came from, so all clients of such an interface
+/// share one limit.
+pub struct RateLimiter {
+    max_requests_per_second: u32,
+    windows: Mutex<HashMap<RateLimitKey, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    pub fn new(max_requests_per_second: u32) -> Self {
+        RateLimiter {
+            max_requests_per_second,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a call of the given client and returns whether it is within the limit
+    pub fn allow(&self, client: RateLimitKey) -> bool {
+        let second = Duration::from_secs(1);
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        if !windows.contains_key(&client) {
+            // Forget clients that stayed quiet, e.g. closed websocket connections
+            windows.retain(|_, (start, _)| now.duration_since(*start) < second);
+        }
+        let window = windows.entry(client).or_insert((now, 0));
+        if now.duration_since(window.0) >= second {
+            *window = (now, 0);
+        }
+        window.1 += 1;
+        window.1 <= self.max_requests_per_second
+    }
+}
+
+pub fn unauthorized_error() -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: jsonrpc_core::ErrorCode::ServerError(UNAUTHORIZED_ERROR_CODE),
+        message: String::from("Unauthorized"),
+        data: None,
+    }
+}
+
+/// JSON-RPC error code for calls without a valid auth token
+pub const UNAUTHORIZED_ERROR_CODE: i64 = -32001;
+
+pub fn too_many_requests_error() -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: jsonrpc_core::ErrorCode::ServerError(TOO_MANY_REQUESTS_ERROR_CODE),
+        message: String::from("Too many requests"),
+        data: None,
+    }
+}
+
+/// JSON-RPC error code for calls above the interface's `max_requests_per_second`
+pub const TOO_MANY_REQUESTS_ERROR_CODE: i64 = -32029;
+
+/// Calls to an instance get this until the container recovers it from a panic,
+/// see `Container::recover_poisoned_instances()`
+pub fn instance_failed_error() -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: jsonrpc_core::ErrorCode::ServerError(INSTANCE_FAILED_ERROR_CODE),
+        message: String::from("Instance failed since a call panicked"),
+        data: None,
+    }
+}
+
+/// JSON-RPC error code for calls to instances whose lock got poisoned by a panic
+pub const INSTANCE_FAILED_ERROR_CODE: i64 = -32010;
+
+/// Methods that only inspect an instance get this instead of waiting for the call
+/// that currently holds the instance's lock
+pub fn instance_busy_error() -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: jsonrpc_core::ErrorCode::ServerError(INSTANCE_BUSY_ERROR_CODE),
+        message: String::from("Instance is busy with a call, try again later"),
+        data: None,
+    }
+}
+
+/// JSON-RPC error code for inspecting instances that are locked by a call
+pub const INSTANCE_BUSY_ERROR_CODE: i64 = -32011;
+
+/// Read access to an instance without blocking the interface thread,
+/// see `instance_busy_error()` and `instance_failed_error()`
+fn try_read_instance(
+    instance: &RwLock<Holochain>,
+) -> Result<RwLockReadGuard<Holochain>, jsonrpc_core::Error> {
+    instance.try_read().map_err(|error| match error {
+        TryLockError::WouldBlock => instance_busy_error(),
+        TryLockError::Poisoned(_) => instance_failed_error(),
+    })
+}
+
+/// Checks an HTTP `Authorization` header value against the configured token.
+/// Everything is authorized if no token is configured.
+pub fn is_authorized(auth_token: &Option<String>, authorization: Option<&str>) -> bool {
+    match auth_token {
+        None => true,
+        Some(token) => authorization == Some(format!("Bearer {}", token).as_str()),
+    }
+}
+
+/// Extracts the value of the `token` query parameter from a request resource like
+/// "/?token=abc", used by websocket clients that can't set headers.
+pub fn query_token(resource: &str) -> Option<&str> {
+    let query = resource.splitn(2, '?').nth(1)?;
+    query
+        .split('&')
+        .filter_map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some("token"), Some(value)) => Some(value),
+                _ => None,
+            }
+        })
+        .next()
+}
+
+/// Signal subscriptions of the clients of one interface.
+/// Clients can subscribe to the signals of the interface's instances with "signals/subscribe"
+/// if the interface keeps connections open and sets `AuthMeta::session`, i.e. websockets.
+/// Signals get pushed as "signal" notifications with the params
+/// `{"subscription": <id>, "instance_id": <id>, "signal": <signal>}`.
+#[derive(Clone)]
+pub struct SignalSubscriptions {
+    broadcaster: Arc<Mutex<SignalBroadcaster>>,
+    instance_ids: Arc<RwLock<Vec<String>>>,
+    sessions: Arc<Mutex<HashMap<SessionId, Vec<SubscriptionId>>>>,
+}
+
+impl SignalSubscriptions {
+    pub fn new(broadcaster: Arc<Mutex<SignalBroadcaster>>, instance_ids: Vec<String>) -> Self {
+        SignalSubscriptions {
+            broadcaster,
+            instance_ids: Arc::new(RwLock::new(instance_ids)),
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Sets the instances that can be subscribed to from now on, for all clones.
+    /// Existing subscriptions stay.
+    pub fn set_instance_ids(&self, instance_ids: Vec<String>) {
+        *self.instance_ids.write().unwrap() = instance_ids;
+    }
+
+    /// Adds "signals/subscribe", which expects the params `{"instance_id": <id>}` and returns
+    /// the subscription id, and "signals/unsubscribe", which expects the params
+    /// `{"subscription": <id>}` and returns whether there was such a subscription.
+    pub fn add_methods(&self, handler: &mut AuthIoHandler) {
+        let subscriptions = self.clone();
+        handler.add_method_with_meta("signals/subscribe", move |params: Params, meta| {
+            future::result(subscriptions.handle_subscribe(params, meta))
+        });
+        let subscriptions = self.clone();
+        handler.add_method_with_meta("signals/unsubscribe", move |params: Params, meta| {
+            future::result(subscriptions.handle_unsubscribe(params, meta))
+        });
+    }
+
+    fn handle_subscribe(
+        &self,
+        params: Params,
+        meta: AuthMeta,
+    ) -> Result<Value, jsonrpc_core::Error> {
+        let params: SubscribeSignalsParams = params.parse()?;
+        let session = notification_session(meta)?;
+        self.subscribe(session, params.instance_id).map(Value::from)
+    }
+
+    fn handle_unsubscribe(
+        &self,
+        params: Params,
+        meta: AuthMeta,
+    ) -> Result<Value, jsonrpc_core::Error> {
+        let params: UnsubscribeSignalsParams = params.parse()?;
+        let session = notification_session(meta)?;
+        Ok(Value::Bool(
+            self.unsubscribe(session.id, params.subscription),
+        ))
+    }
+
+    fn subscribe(
+        &self,
+        session: NotificationSession,
+        instance_id: String,
+    ) -> Result<SubscriptionId, jsonrpc_core::Error> {
+        if !self.instance_ids.read().unwrap().contains(&instance_id) {
+            return Err(jsonrpc_core::Error::invalid_params(format!(
+                "Instance does not exist: {}",
+                instance_id
+            )));
+        }
+        let (subscription_id, signal_rx) = self
+            .broadcaster
+            .lock()
+            .unwrap()
+            .subscribe_instance(instance_id.clone());
+        self.sessions
+            .lock()
+            .unwrap()
+            .entry(session.id)
+            .or_insert_with(Vec::new)
+            .push(subscription_id);
+
+        // Ends once the subscription gets removed from the broadcaster
+        // or the client can't be reached anymore.
+        let broadcaster = self.broadcaster.clone();
+        let mut sender = session.sender;
+        thread::spawn(move || {
+            for signal in signal_rx.iter() {
+                let notification = json!({
+                    "jsonrpc": "2.0",
+                    "method": "signal",
+                    "params": {
+                        "subscription": subscription_id,
+                        "instance_id": instance_id,
+                        "signal": signal_to_json(&signal),
+                    }
+                });
+                sender = match sender.send(notification.to_string()).wait() {
+                    Ok(sender) => sender,
+                    Err(_) => {
+                        broadcaster.lock().unwrap().unsubscribe(subscription_id);
+                        break;
+                    }
+                };
+            }
+        });
+        Ok(subscription_id)
+    }
+
+    fn unsubscribe(&self, session_id: SessionId, subscription_id: SubscriptionId) -> bool {
+        let owned = match self.sessions.lock().unwrap().get_mut(&session_id) {
+            Some(subscription_ids) => {
+                let count = subscription_ids.len();
+                subscription_ids.retain(|id| *id != subscription_id);
+                subscription_ids.len() < count
+            }
+            None => false,
+        };
+        owned
+            && self
+                .broadcaster
+                .lock()
+                .unwrap()
+                .unsubscribe(subscription_id)
+    }
+
+    /// Ends all subscriptions of the given session.
+    /// Interfaces have to call this when a connection closes.
+    pub fn close_session(&self, session_id: SessionId) {
+        let subscription_ids = self.sessions.lock().unwrap().remove(&session_id);
+        let mut broadcaster = self.broadcaster.lock().unwrap();
+        for subscription_id in subscription_ids.unwrap_or_default() {
+            broadcaster.unsubscribe(subscription_id);
+        }
+    }
+}
+
+fn notification_session(meta: AuthMeta) -> Result<NotificationSession, jsonrpc_core::Error> {
+    meta.session.ok_or_else(|| {
+        jsonrpc_core::Error::invalid_params("Interface does not support notifications")
+    })
+}
+
+fn signal_to_json(signal: &Signal) -> Value {
+    match signal {
+        Signal::Internal(action) => json!({"type": "internal", "action": format!("{:?}", action)}),
+        Signal::User => json!({"type": "user"}),
+        Signal::InstanceStarted(id) => json!({"type": "instance_started", "instance_id": id}),
+        Signal::InstanceStopped(id) => json!({"type": "instance_stopped", "instance_id": id}),
+    }
+}
+
+#[derive(Deserialize)]
+struct SubscribeSignalsParams {
+    instance_id: String,
+}
+
+#[derive(Deserialize)]
+struct UnsubscribeSignalsParams {
+    subscription: SubscriptionId,
+}
+
+pub trait Interface {
+    /// Starts serving the given handler. Binds what the interface listens on right away,
+    /// so that a taken port fails starting the interface instead of only its thread.
+    /// Has to set `AuthMeta::authorized` for each request depending on the auth token.
+    fn start(&mut self, handler: AuthIoHandler) -> io::Result<()>;
+
+    /// Keeps serving until a message is received on the kill switch or its sender gets
+    /// dropped, then stops serving. Fails if the interface was not started.
+    fn run(&mut self, kill_switch: Receiver<()>) -> Result<(), String>;
+}
+
+/// Error of `Interface::run()` for interfaces that were not started
+pub fn interface_not_started_error() -> String {
+    String::from("Interface was not started")
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        config::Configuration,
+        container::tests::test_container,
+        holochain::tests::{example_api_wasm, test_context},
+    };
+    use holochain_core_types::{
+        dna::capabilities::FnParameter,
+        entry::{test_entry, Entry},
+    };
+    use jsonrpc_ws_server::jsonrpc_core::futures::Stream;
+    use test_utils::{create_test_cap_with_fn_name, create_test_dna_with_cap};
+
+    fn example_config_and_instances() -> (Configuration, InstanceMap) {
+        let container = test_container();
+        let holochain = container
+            .instances()
+            .get("test-instance-1")
+            .unwrap()
+            .clone();
+        let mut instances = InstanceMap::new();
+        instances.insert("test-instance-1".into(), holochain);
+        (container.config(), instances)
+    }
+
+    #[test]
+    fn test_new_dispatcher() {
+        let (config, instances) = example_config_and_instances();
+        let handler = ContainerApiBuilder::new()
+            .with_instances(instances.clone())
+            .with_instance_configs(config.instances)
+            .spawn();
+        let result = format!("{:?}", handler).to_string();
+        println!("{}", result);
+        assert!(result.contains("info/instances"));
+        assert!(result.contains("query/graph"));
+        assert!(result.contains(r#""test-instance-1/greeter/public/hello""#));
+        assert!(!result.contains(r#""test-instance-2//test/test""#));
+    }
+
+    #[test]
+    fn test_admin_methods_only_on_admin_handler() {
+        let (config, instances) = example_config_and_instances();
+        let public_methods = vec![
+            "info/instances",
+            "info/health",
+            "info/describe",
+            "query/graph",
+            "test-instance-1/greeter/public/hello",
+        ];
+        let admin_methods = vec![
+            "admin/dht/diff",
+            "admin/instance/dump",
+            "admin/bridges/list",
+            "admin/interfaces/list",
+            "metrics/get_entry",
+            "metrics/instances",
+        ];
+
+        let handler = ContainerApiBuilder::new()
+            .with_instances(instances.clone())
+            .with_instance_configs(config.instances.clone())
+            .spawn();
+        let result = format!("{:?}", handler);
+        for method in public_methods.iter() {
+            assert!(result.contains(&format!(r#""{}""#, method)));
+        }
+        for method in admin_methods.iter() {
+            assert!(!result.contains(&format!(r#""{}""#, method)));
+        }
+
+        let handler = ContainerApiBuilder::new()
+            .with_admin_api(true)
+            .with_instances(instances)
+            .with_instance_configs(config.instances)
+            .spawn();
+        let result = format!("{:?}", handler);
+        for method in public_methods.iter().chain(admin_methods.iter()) {
+            assert!(result.contains(&format!(r#""{}""#, method)));
+        }
+    }
+
+    #[test]
+    fn test_info_methods_report_locked_instances() {
+        let (config, instances) = example_config_and_instances();
+        let instance = instances["test-instance-1"].clone();
+        let handler = ContainerApiBuilder::new()
+            .with_instances(instances)
+            .with_instance_configs(config.instances)
+            .spawn();
+        let result = |method: &str, params: &str| {
+            let request = format!(
+                r#"{{"jsonrpc": "2.0", "method": "{}", "params": {}, "id": 1}}"#,
+                method, params
+            );
+            let response = handler.handle_request_sync(&request).unwrap();
+            serde_json::from_str::<Value>(&response).unwrap()
+        };
+        let describe_params = r#"{"instance_id": "test-instance-1"}"#;
+
+        {
+            let _call = instance.write().unwrap();
+            let health = result("info/health", "null");
+            let health: Value = serde_json::from_str(health["result"].as_str().unwrap()).unwrap();
+            assert_eq!(
+                health["instances"]["test-instance-1"],
+                json!({"busy": true})
+            );
+            let infos = result("info/instances", "null");
+            assert!(infos["result"].as_str().unwrap().contains(r#""busy":true"#));
+            assert_eq!(
+                result("info/describe", describe_params)["error"]["code"],
+                json!(INSTANCE_BUSY_ERROR_CODE)
+            );
+        }
+
+        let poisoning_instance = instance.clone();
+        let _ = thread::spawn(move || {
+            let _call = poisoning_instance.write().unwrap();
+            panic!("zome call panicked");
+        })
+        .join();
+        let health = result("info/health", "null");
+        let health: Value = serde_json::from_str(health["result"].as_str().unwrap()).unwrap();
+        assert_eq!(
+            health["instances"]["test-instance-1"],
+            json!({"failed": true})
+        );
+        assert_eq!(health["healthy"], json!(false));
+        let infos = result("info/instances", "null");
+        assert!(infos["result"]
+            .as_str()
+            .unwrap()
+            .contains(r#""failed":true"#));
+        assert_eq!(
+            result("info/describe", describe_params)["error"]["code"],
+            json!(INSTANCE_FAILED_ERROR_CODE)
+        );
+    }
+
+    #[test]
+    fn test_health_network() {
+        let health = |builder: ContainerApiBuilder| {
+            let request = r#"{"jsonrpc": "2.0", "method": "info/health", "params": null, "id": 1}"#;
+            let response = builder.spawn().handle_request_sync(request).unwrap();
+            let response: Value = serde_json::from_str(&response).unwrap();
+            serde_json::from_str::<Value>(response["result"].as_str().unwrap()).unwrap()
+        };
+
+        let result = health(ContainerApiBuilder::new());
+        assert_eq!(result["network"], Value::Null);
+        assert_eq!(result["healthy"], json!(true));
+
+        let result = health(ContainerApiBuilder::new().with_network_configured(true));
+        assert_eq!(
+            result["network"],
+            json!({"ipc_binding": null, "live": false})
+        );
+        assert_eq!(result["healthy"], json!(false));
+
+        // Nothing listens at the binding
+        let result = health(
+            ContainerApiBuilder::new()
+                .with_network_configured(true)
+                .with_network_bindings(Some(String::from("ipc:///nonexistent/n3h")), None),
+        );
+        assert_eq!(
+            result["network"],
+            json!({"ipc_binding": "ipc:///nonexistent/n3h", "live": false})
+        );
+        assert_eq!(result["healthy"], json!(false));
+
+        let listener = ::std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let binding = format!("tcp://{}", listener.local_addr().unwrap());
+        let result = health(
+            ContainerApiBuilder::new()
+                .with_network_configured(true)
+                .with_network_bindings(Some(binding.clone()), None),
+        );
+        assert_eq!(
+            result["network"],
+            json!({"ipc_binding": binding, "live": true})
+        );
+        assert_eq!(result["healthy"], json!(true));
+    }
+
+    #[test]
+    fn test_named_instances() {
+        let (config, instances) = example_config_and_instances();
+        let handler = ContainerApiBuilder::new()
+            .with_named_instance(
+                String::from("happ-store"),
+                instances.iter().nth(0).unwrap().1.clone(),
+            )
+            .with_named_instance_config(
+                String::from("happ-store"),
+                config.instances.iter().nth(0).unwrap().clone(),
+            )
+            .spawn();
+        let result = format!("{:?}", handler).to_string();
+        println!("{}", result);
+        assert!(result.contains("info/instances"));
+        assert!(result.contains(r#""happ-store/greeter/public/hello""#));
+        assert!(!result.contains(r#""test-instance-1//test/test""#));
+    }
+
+    #[test]
+    fn test_graph_query_unknown_instance() {
+        let (config, instances) = example_config_and_instances();
+        let handler = ContainerApiBuilder::new()
+            .with_instances(instances)
+            .with_instance_configs(config.instances)
+            .spawn();
+        let request = r#"{"jsonrpc": "2.0", "method": "query/graph", "params": {"instance_id": "non-existent", "query": {"base": "QmBase"}}, "id": 1}"#;
+        let response = handler
+            .handle_request_sync(request)
+            .expect("No response returned for query/graph");
+        assert!(response.contains("Instance does not exist: non-existent"));
+    }
+
+    fn auth_handler(public_info: bool) -> AuthIoHandler {
+        let (config, instances) = example_config_and_instances();
+        let handler = ContainerApiBuilder::new()
+            .with_instances(instances)
+            .with_instance_configs(config.instances)
+            .spawn();
+        with_auth(handler, public_info)
+    }
+
+    #[test]
+    fn test_auth_accepted() {
+        let handler = auth_handler(false);
+        let request = r#"{"jsonrpc": "2.0", "method": "info/instances", "params": null, "id": 1}"#;
+        let response = handler
+            .handle_request_sync(
+                request,
+                AuthMeta {
+                    authorized: true,
+                    session: None,
+                },
+            )
+            .unwrap();
+        assert!(response.contains("test-instance-1"));
+    }
+
+    #[test]
+    fn test_auth_rejected() {
+        let handler = auth_handler(false);
+        let request = r#"{"jsonrpc": "2.0", "method": "info/instances", "params": null, "id": 1}"#;
+        let response = handler
+            .handle_request_sync(
+                request,
+                AuthMeta {
+                    authorized: false,
+                    session: None,
+                },
+            )
+            .unwrap();
+        assert!(response.contains("Unauthorized"));
+        assert!(!response.contains("test-instance-1"));
+
+        // public_info only opens up info/instances
+        let handler = auth_handler(true);
+        let response = handler
+            .handle_request_sync(
+                request,
+                AuthMeta {
+                    authorized: false,
+                    session: None,
+                },
+            )
+            .unwrap();
+        assert!(response.contains("test-instance-1"));
+        let request = r#"{"jsonrpc": "2.0", "method": "info/health", "params": null, "id": 1}"#;
+        let response = handler
+            .handle_request_sync(
+                request,
+                AuthMeta {
+                    authorized: false,
+                    session: None,
+                },
+            )
+            .unwrap();
+        assert!(response.contains("Unauthorized"));
+    }
+
+    #[test]
+    fn test_version_is_public() {
+        let handler = auth_handler(false);
+        let request = r#"{"jsonrpc": "2.0", "method": "info/version", "params": null, "id": 1}"#;
+        let response: Value = serde_json::from_str(
+            &handler
+                .handle_request_sync(
+                    request,
+                    AuthMeta {
+                        authorized: false,
+                        session: None,
+                    },
+                )
+                .unwrap(),
+        )
+        .unwrap();
+        let version: Value = serde_json::from_str(response["result"].as_str().unwrap()).unwrap();
+        assert_eq!(version["version"], json!(env!("CARGO_PKG_VERSION")));
+        assert!(version["git_commit"].is_string());
+        assert!(version["build_timestamp"].is_string());
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let (config, instances) = example_config_and_instances();
+        let handler = ContainerApiBuilder::new()
+            .with_instances(instances)
+            .with_instance_configs(config.instances)
+            .spawn();
+        let handler = with_auth_and_rate_limit(handler, false, Some(2));
+        let request = r#"{"jsonrpc": "2.0", "method": "info/instances", "params": null, "id": 1}"#;
+        let (sender, _receiver) = mpsc::channel(0);
+        let call = |session_id: Option<SessionId>| {
+            handler
+                .handle_request_sync(
+                    request,
+                    AuthMeta {
+                        authorized: true,
+                        session: session_id.map(|id| NotificationSession::new(id, sender.clone())),
+                    },
+                )
+                .unwrap()
+        };
+        assert!(call(Some(1)).contains("test-instance-1"));
+        assert!(call(Some(1)).contains("test-instance-1"));
+        assert!(call(Some(1)).contains("Too many requests"));
+        // Other connections have their own limit
+        assert!(call(Some(2)).contains("test-instance-1"));
+
+        // Clients without a session share the interface's limit
+        assert!(call(None).contains("test-instance-1"));
+        assert!(call(None).contains("test-instance-1"));
+        assert!(call(None).contains("Too many requests"));
+
+        thread::sleep(Duration::from_millis(1100));
+        assert!(call(Some(1)).contains("test-instance-1"));
+    }
+
+    #[test]
+    fn test_worker_threads_handle_calls_concurrently() {
+        let mut io = IoHandler::new();
+        io.add_method("slow", |_| {
+            thread::sleep(Duration::from_millis(500));
+            Ok(Value::String(String::from("done")))
+        });
+        let handler = with_auth_rate_limit_and_workers(io, false, None, Some(2));
+        let meta = AuthMeta {
+            authorized: true,
+            session: None,
+        };
+        let start = Instant::now();
+        let first = handler.handle_request(
+            r#"{"jsonrpc": "2.0", "method": "slow", "params": null, "id": 1}"#,
+            meta.clone(),
+        );
+        let second = handler.handle_request(
+            r#"{"jsonrpc": "2.0", "method": "slow", "params": null, "id": 2}"#,
+            meta,
+        );
+        let (first, second) = first.join(second).wait().unwrap();
+        assert!(first.unwrap().contains("done"));
+        assert!(second.unwrap().contains("done"));
+        assert!(start.elapsed() < Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_publish_confirmation_waits_for_hold() {
+        let broadcaster = Arc::new(Mutex::new(SignalBroadcaster::new(0)));
+        let subscribe = |timeout_ms| PendingConfirmation {
+            signal_rx: broadcaster.lock().unwrap().subscribe(),
+            chain_top: None,
+            timeout: Duration::from_millis(timeout_ms),
+        };
+        let entry = test_entry();
+        let published: HashSet<Address> = vec![entry.address()].into_iter().collect();
+
+        assert!(subscribe(0).wait(HashSet::new()));
+
+        let pending = subscribe(200);
+        broadcaster
+            .lock()
+            .unwrap()
+            .broadcast_from(Some("test-instance-1".to_string()), Signal::User);
+        assert!(!pending.wait(published.clone()));
+
+        let pending = subscribe(5000);
+        let hold_broadcaster = broadcaster.clone();
+        let holder = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            hold_broadcaster.lock().unwrap().broadcast_from(
+                Some("test-instance-2".to_string()),
+                Signal::Internal(Action::Hold(entry)),
+            );
+        });
+        let start = Instant::now();
+        assert!(pending.wait(published));
+        assert!(start.elapsed() < Duration::from_millis(5000));
+        holder.join().unwrap();
+    }
+
+    #[test]
+    fn test_publish_confirmation_only_counts_holds_of_other_agents() {
+        let capability = create_test_cap_with_fn_name("commit_test");
+        let dna =
+            create_test_dna_with_cap("test_zome", "test_cap", &capability, &example_api_wasm());
+        let (context, _, _) = test_context("alex");
+        let mut hc = Holochain::new(dna, context).unwrap();
+        hc.start().unwrap();
+        let mut instances = InstanceMap::new();
+        instances.insert("author".to_string(), Arc::new(RwLock::new(hc)));
+        let instance_agents = |ids_and_agents: Vec<(&str, &str)>| -> HashMap<String, String> {
+            ids_and_agents
+                .into_iter()
+                .map(|(id, agent)| (id.to_string(), agent.to_string()))
+                .collect()
+        };
+        let broadcaster = Arc::new(Mutex::new(SignalBroadcaster::new(10)));
+        let handler = |agents| {
+            ContainerApiBuilder::new()
+                .with_publish_confirmation(
+                    broadcaster.clone(),
+                    Some(Duration::from_millis(2000)),
+                    agents,
+                )
+                .with_instances(instances.clone())
+                .spawn()
+        };
+        // Entry the commit_test function of the test wasm commits
+        let entry = Entry::App("testEntryType".to_owned().into(), "hello".to_owned().into());
+        let hold_later = |instance_id: &str| {
+            let broadcaster = broadcaster.clone();
+            let instance_id = instance_id.to_string();
+            let entry = entry.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(100));
+                broadcaster
+                    .lock()
+                    .unwrap()
+                    .broadcast_from(Some(instance_id), Signal::Internal(Action::Hold(entry)));
+            })
+        };
+        let call = |handler: &IoHandler| {
+            let start = Instant::now();
+            let response = handler
+                .handle_request_sync(
+                    r#"{"jsonrpc": "2.0", "method": "author/test_zome/test_cap/commit_test", "params": {}, "id": 1}"#,
+                )
+                .unwrap();
+            assert!(response.contains("result"), "response = {}", response);
+            start.elapsed()
+        };
+
+        // Holds by instances of the author's agent don't confirm anything
+        let holder = hold_later("author-copy");
+        let elapsed = call(&handler(instance_agents(vec![
+            ("author", "agent-1"),
+            ("author-copy", "agent-1"),
+            ("other", "agent-2"),
+        ])));
+        assert!(elapsed >= Duration::from_millis(2000));
+        holder.join().unwrap();
+
+        // A hold by an instance of another agent does
+        let holder = hold_later("other");
+        let elapsed = call(&handler(instance_agents(vec![
+            ("author", "agent-1"),
+            ("other", "agent-2"),
+        ])));
+        assert!(elapsed < Duration::from_millis(2000));
+        holder.join().unwrap();
+
+        // Without instances of other agents, calls don't wait
+        let elapsed = call(&handler(instance_agents(vec![("author", "agent-1")])));
+        assert!(elapsed < Duration::from_millis(2000));
+    }
+
+    #[test]
+    fn test_take_call_priority() {
+        let mut params = Params::Map(
+            json!({"content": "hi", "_call_priority": 5})
+                .as_object()
+                .unwrap()
+                .clone(),
+        );
+        assert_eq!(take_call_priority(&mut params), Ok(5));
+        assert_eq!(
+            params,
+            Params::Map(json!({"content": "hi"}).as_object().unwrap().clone())
+        );
+        assert_eq!(take_call_priority(&mut params), Ok(0));
+        assert_eq!(take_call_priority(&mut Params::None), Ok(0));
+
+        let mut params = Params::Map(
+            json!({"_call_priority": "high"})
+                .as_object()
+                .unwrap()
+                .clone(),
+        );
+        assert!(take_call_priority(&mut params).is_err());
+    }
+
+    #[test]
+    fn test_rate_limiter_windows() {
+        let rate_limiter = RateLimiter::new(1);
+        assert!(rate_limiter.allow(RateLimitKey::Interface));
+        assert!(!rate_limiter.allow(RateLimitKey::Interface));
+        assert!(rate_limiter.allow(RateLimitKey::Session(7)));
+    }
+
+    #[test]
+    fn test_is_authorized() {
+        let token = Some(String::from("secret"));
+        assert!(is_authorized(&None, None));
+        assert!(is_authorized(&token, Some("Bearer secret")));
+        assert!(!is_authorized(&token, Some("Bearer wrong")));
+        assert!(!is_authorized(&token, Some("secret")));
+        assert!(!is_authorized(&token, None));
+
+        assert_eq!(query_token("/?token=secret"), Some("secret"));
+        assert_eq!(query_token("/?foo=bar&token=secret"), Some("secret"));
+        assert_eq!(query_token("/?foo=bar"), None);
+        assert_eq!(query_token("/"), None);
+    }
+
+    #[test]
+    fn test_admin_instance_dump() {
+        let (config, instances) = example_config_and_instances();
+        let request = r#"{"jsonrpc": "2.0", "method": "admin/instance/dump", "params": {"instance_id": "test-instance-1"}, "id": 1}"#;
+
+        let handler = ContainerApiBuilder::new()
+            .with_instances(instances.clone())
+            .with_instance_configs(config.instances.clone())
+            .spawn();
+        let response = handler.handle_request_sync(request).unwrap();
+        assert!(response.contains("Method not found"));
+
+        let handler = ContainerApiBuilder::new()
+            .with_admin_api(true)
+            .with_instances(instances)
+            .with_instance_configs(config.instances)
+            .spawn();
+        let response = handler.handle_request_sync(request).unwrap();
+        assert!(response.contains(r#"{\"chain\":[{\"header\":"#));
+        assert!(response.contains("AgentId"));
+
+        let page = |params: &str| {
+            let request = format!(
+                r#"{{"jsonrpc": "2.0", "method": "admin/instance/dump", "params": {{"instance_id": "test-instance-1"{}}}, "id": 1}}"#,
+                params
+            );
+            let response = handler.handle_request_sync(&request).unwrap();
+            let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+            let dump: serde_json::Value =
+                serde_json::from_str(response["result"].as_str().unwrap()).unwrap();
+            dump["chain"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|item| item["header"]["entry_type"].clone())
+                .collect::<Vec<_>>()
+        };
+        let chain = page("");
+        assert_eq!(chain.first(), Some(&json!("AgentId")));
+        assert_eq!(chain.last(), Some(&json!("Dna")));
+        for (offset, entry_type) in chain.iter().enumerate() {
+            let params = format!(r#", "offset": {}, "limit": 1"#, offset);
+            assert_eq!(page(&params), vec![entry_type.clone()]);
+        }
+        assert!(page(&format!(r#", "offset": {}"#, chain.len())).is_empty());
+    }
+
+    #[test]
+    fn test_admin_bridges_list() {
+        let bridge = |caller_id: &str, callee_id: &str| Bridge {
+            caller_id: caller_id.to_string(),
+            callee_id: callee_id.to_string(),
+            handle: format!("{}-to-{}", caller_id, callee_id),
+        };
+        let handler = ContainerApiBuilder::new()
+            .with_admin_api(true)
+            .with_bridges(vec![bridge("app1", "app2"), bridge("app3", "app4")])
+            .spawn();
+        let list = |params: &str| {
+            let request = format!(
+                r#"{{"jsonrpc": "2.0", "method": "admin/bridges/list", "params": {}, "id": 1}}"#,
+                params
+            );
+            let response: serde_json::Value =
+                serde_json::from_str(&handler.handle_request_sync(&request).unwrap()).unwrap();
+            let bridges: Vec<Bridge> =
+                serde_json::from_str(response["result"].as_str().unwrap()).unwrap();
+            bridges
+        };
+
+        assert_eq!(list("null").len(), 2);
+        assert_eq!(
+            list(r#"{"instance_id": "app2"}"#),
+            vec![bridge("app1", "app2")]
+        );
+        assert!(list(r#"{"instance_id": "app5"}"#).is_empty());
+    }
+
+    #[test]
+    fn test_admin_interfaces_list() {
+        let mut config = test_container().config();
+        config.interfaces.truncate(1);
+        let mut all_instances_interface = config.interfaces[0].clone();
+        all_instances_interface.id = String::from("all-instances-interface");
+        all_instances_interface.driver = InterfaceDriver::Stdio;
+        all_instances_interface.all_instances = true;
+        config.interfaces.push(all_instances_interface);
+        let handler = ContainerApiBuilder::new()
+            .with_admin_api(true)
+            .with_instance_configs(config.instances)
+            .with_interface_configs(config.interfaces)
+            .spawn();
+        let request =
+            r#"{"jsonrpc": "2.0", "method": "admin/interfaces/list", "params": null, "id": 1}"#;
+        let response: Value =
+            serde_json::from_str(&handler.handle_request_sync(request).unwrap()).unwrap();
+        let interfaces: Value = serde_json::from_str(response["result"].as_str().unwrap()).unwrap();
+        assert_eq!(
+            interfaces,
+            json!([
+                {
+                    "id": "test-interface",
+                    "driver": "websocket",
+                    "port": 8888,
+                    "instances": ["test-instance-1", "test-instance-2"],
+                },
+                {
+                    "id": "all-instances-interface",
+                    "driver": "stdio",
+                    "port": null,
+                    "instances": ["bridge-caller", "test-instance-1", "test-instance-2"],
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_signal_subscriptions() {
+        let broadcaster = Arc::new(Mutex::new(SignalBroadcaster::new(0)));
+        let subscriptions =
+            SignalSubscriptions::new(broadcaster.clone(), vec!["test-instance-1".to_string()]);
+        let mut handler = auth_handler(false);
+        subscriptions.add_methods(&mut handler);
+        let (sender, notifications) = mpsc::channel(10);
+        let session = NotificationSession::new(1, sender);
+        let call = |request: &str, session: Option<NotificationSession>| {
+            handler
+                .handle_request_sync(
+                    request,
+                    AuthMeta {
+                        authorized: true,
+                        session,
+                    },
+                )
+                .unwrap()
+        };
+
+        let subscribe = r#"{"jsonrpc": "2.0", "method": "signals/subscribe", "params": {"instance_id": "test-instance-1"}, "id": 1}"#;
+        assert!(call(subscribe, None).contains("Interface does not support notifications"));
+        let unknown = subscribe.replace("test-instance-1", "test-instance-2");
+        assert!(call(&unknown, Some(session.clone())).contains("Instance does not exist"));
+        assert_eq!(
+            call(subscribe, Some(session.clone())),
+            r#"{"jsonrpc":"2.0","result":0,"id":1}"#
+        );
+
+        broadcaster
+            .lock()
+            .unwrap()
+            .broadcast_from(Some("test-instance-1".to_string()), Signal::User);
+        let notification = notifications.wait().next().unwrap().unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&notification).unwrap(),
+            json!({
+                "jsonrpc": "2.0",
+                "method": "signal",
+                "params": {
+                    "subscription": 0,
+                    "instance_id": "test-instance-1",
+                    "signal": {"type": "user"},
+                }
+            })
+        );
+
+        // Other sessions can't end the subscription, closing the session does
+        let unsubscribe = r#"{"jsonrpc": "2.0", "method": "signals/unsubscribe", "params": {"subscription": 0}, "id": 1}"#;
+        let other_session = NotificationSession::new(2, session.sender.clone());
+        assert!(call(unsubscribe, Some(other_session)).contains(r#""result":false"#));
+        subscriptions.close_session(1);
+        assert!(!broadcaster.lock().unwrap().unsubscribe(0));
+    }
+
+    #[test]
+    fn test_call_many() {
+        let (config, instances) = example_config_and_instances();
+        instances["test-instance-1"]
+            .write()
+            .unwrap()
+            .start()
+            .unwrap();
+        let handler = ContainerApiBuilder::new()
+            .with_instances(instances)
+            .with_instance_configs(config.instances)
+            .spawn();
+        let request = |stop_on_error: bool| {
+            json!({
+                "jsonrpc": "2.0",
+                "method": "instances/call_many",
+                "params": {
+                    "calls": [
+                        {"instance_id": "test-instance-1", "zome": "greeter", "function": "hello", "params": {}},
+                        {"instance_id": "unknown-instance", "zome": "greeter", "function": "hello", "params": {}},
+                        {"instance_id": "test-instance-1", "zome": "greeter", "capability": "public", "function": "hello", "params": {}},
+                        {"instance_id": "test-instance-1", "zome": "greeter", "capability": "unknown", "function": "hello", "params": {}},
+                    ],
+                    "stop_on_error": stop_on_error,
+                },
+                "id": 1
+            })
+            .to_string()
+        };
+        let results = |stop_on_error: bool| {
+            let response = handler
+                .handle_request_sync(&request(stop_on_error))
+                .unwrap();
+            let response: Value = serde_json::from_str(&response).unwrap();
+            response["result"].as_array().unwrap().clone()
+        };
+
+        let all = results(false);
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0], json!({"result": "Holo World"}));
+        assert_eq!(all[1]["error"]["code"], json!(-32602));
+        assert_eq!(
+            all[1]["error"]["message"],
+            json!("Instance does not exist: unknown-instance")
+        );
+        assert_eq!(all[2], json!({"result": "Holo World"}));
+        assert_eq!(
+            all[3]["error"]["message"],
+            json!("Zome function does not exist: greeter/hello")
+        );
+
+        assert_eq!(results(true).len(), 2);
+    }
+
+    #[test]
+    fn test_read_only_functions() {
+        let (config, instances) = example_config_and_instances();
+        instances["test-instance-1"]
+            .write()
+            .unwrap()
+            .start()
+            .unwrap();
+        let handler = |functions: Vec<&str>| {
+            ContainerApiBuilder::new()
+                .with_read_only_functions(Some(functions.into_iter().map(String::from).collect()))
+                .with_instances(instances.clone())
+                .with_instance_configs(config.instances.clone())
+                .spawn()
+        };
+        let call = r#"{"jsonrpc": "2.0", "method": "test-instance-1/greeter/public/hello", "params": {}, "id": 1}"#;
+        let call_many = r#"{"jsonrpc": "2.0", "method": "instances/call_many", "params": {"calls": [{"instance_id": "test-instance-1", "zome": "greeter", "function": "hello", "params": {}}]}, "id": 1}"#;
+
+        let read_only = handler(Vec::new());
+        let response = read_only.handle_request_sync(call).unwrap();
+        assert!(response.contains("greeter/hello is not one of its read-only functions"));
+        let response = read_only.handle_request_sync(call_many).unwrap();
+        assert!(response.contains("greeter/hello is not one of its read-only functions"));
+
+        let allowed = handler(vec!["greeter/hello"]);
+        let response = allowed.handle_request_sync(call).unwrap();
+        assert!(response.contains("Holo World"));
+        let response = allowed.handle_request_sync(call_many).unwrap();
+        assert!(response.contains("Holo World"));
+    }
+
+    #[test]
+    fn test_check_zome_function_inputs() {
+        let declaration = FnDeclaration {
+            name: String::from("create_post"),
+            inputs: vec![
+                FnParameter::new("content", "String"),
+                FnParameter::new("timestamp", "u64"),
+                FnParameter::new("meta", "json"),
+            ],
+            outputs: Vec::new(),
+        };
+        let check = |params: Value| check_zome_function_inputs(&declaration, &params);
+
+        assert_eq!(
+            check(json!({"content": "hi", "timestamp": 1, "meta": {"a": 1}})),
+            Ok(())
+        );
+        assert_eq!(
+            check(json!({"content": "hi", "timestamp": 1, "meta": null, "extra": true})),
+            Ok(())
+        );
+        assert_eq!(
+            check(json!({"content": "hi", "meta": null})),
+            Err(String::from(
+                "Invalid params for create_post: missing input \"timestamp\""
+            ))
+        );
+        assert_eq!(
+            check(json!({"content": 42, "timestamp": 1, "meta": null})),
+            Err(String::from(
+                "Invalid params for create_post: input \"content\" has to be of type String, got 42"
+            ))
+        );
+        assert_eq!(
+            check(json!({"content": "hi", "timestamp": -1, "meta": null})),
+            Err(String::from(
+                "Invalid params for create_post: input \"timestamp\" has to be of type u64, got -1"
+            ))
+        );
+        assert!(check(json!("hi")).is_err());
     }
 }
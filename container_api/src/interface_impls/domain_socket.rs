@@ -0,0 +1,55 @@
+use interface::{interface_not_started_error, AuthIoHandler, AuthMeta, Interface};
+use jsonrpc_ipc_server::{RequestContext, Server, ServerBuilder};
+use std::{fs, io, path::Path, sync::mpsc::Receiver};
+
+/// Interface listening on a Unix domain socket at the given filesystem path.
+///
+/// There are no headers to present a token with, so access is governed by the
+/// permissions of the socket file: if an auth token is configured for this interface
+/// only public methods (see `public_info`) can be called.
+pub struct DomainSocketInterface {
+    path: String,
+    server: Option<Server>,
+    auth_token: Option<String>,
+}
+
+impl DomainSocketInterface {
+    pub fn new(path: String, auth_token: Option<String>) -> Self {
+        DomainSocketInterface {
+            path,
+            server: None,
+            auth_token,
+        }
+    }
+}
+
+impl Interface for DomainSocketInterface {
+    fn start(&mut self, handler: AuthIoHandler) -> io::Result<()> {
+        // A socket file left behind by a crashed process would make binding fail
+        if Path::new(&self.path).exists() {
+            fs::remove_file(&self.path).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("Could not remove stale socket \"{}\": {}", self.path, e),
+                )
+            })?;
+        }
+        let authorized = self.auth_token.is_none();
+        let server =
+            ServerBuilder::with_meta_extractor(handler, move |_: &RequestContext| AuthMeta {
+                authorized,
+                session: None,
+            })
+            .start(&self.path)?;
+        self.server = Some(server);
+        Ok(())
+    }
+
+    fn run(&mut self, kill_switch: Receiver<()>) -> Result<(), String> {
+        let server = self.server.take().ok_or_else(interface_not_started_error)?;
+        let _ = kill_switch.recv();
+        server.close();
+        fs::remove_file(&self.path).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
@@ -1,23 +1,182 @@
-use interface::Interface;
-use jsonrpc_http_server::{jsonrpc_core::IoHandler, ServerBuilder};
+use config::{default_max_request_bytes, CorsConfiguration};
+use interface::{interface_not_started_error, is_authorized, AuthIoHandler, AuthMeta, Interface};
+use jsonrpc_http_server::{
+    cors::AccessControlAllowHeaders, hyper, AccessControlAllowOrigin, DomainsValidation,
+    RequestMiddlewareAction, Server, ServerBuilder,
+};
+use std::{io, sync::mpsc::Receiver};
 
+/// Interface serving JSON-RPC over HTTP.
+///
+/// The HTTP server doesn't tell the handler where a request came from, so all clients
+/// of the interface share its rate limit, see `RateLimiter`.
 pub struct HttpInterface {
     port: u16,
+    server: Option<Server>,
+    auth_token: Option<String>,
+    max_request_bytes: usize,
+    cors: Option<CorsConfiguration>,
 }
 
 impl HttpInterface {
-    pub fn new(port: u16) -> Self {
-        HttpInterface { port }
+    pub fn new(port: u16, auth_token: Option<String>) -> Self {
+        HttpInterface {
+            port,
+            server: None,
+            auth_token,
+            max_request_bytes: default_max_request_bytes(),
+            cors: None,
+        }
+    }
+
+    /// Requests with a bigger body get answered with 413 without being read any further
+    pub fn with_max_request_bytes(mut self, max_request_bytes: usize) -> Self {
+        self.max_request_bytes = max_request_bytes;
+        self
+    }
+
+    /// Lets browsers call the interface from pages of the configured origins only,
+    /// requests from pages of other origins get rejected with 403.
+    /// Without, requests from any origin get through, but responses carry no CORS headers.
+    pub fn with_cors(mut self, cors: CorsConfiguration) -> Self {
+        self.cors = Some(cors);
+        self
+    }
+}
+
+/// Answers a CORS preflight (OPTIONS) request from the given origin with the configured
+/// methods and headers, or with 403 if the origin is not allowed.
+fn preflight_response(
+    cors: &CorsConfiguration,
+    origin: Option<&str>,
+) -> hyper::Response<hyper::Body> {
+    match origin.and_then(|origin| cors.allow_origin(origin)) {
+        Some(allow_origin) => hyper::Response::builder()
+            .status(hyper::StatusCode::OK)
+            .header("Access-Control-Allow-Origin", allow_origin.as_str())
+            .header(
+                "Access-Control-Allow-Methods",
+                cors.allowed_methods.join(", ").as_str(),
+            )
+            .header(
+                "Access-Control-Allow-Headers",
+                cors.allowed_headers.join(", ").as_str(),
+            )
+            .header("Vary", "Origin")
+            .body(hyper::Body::empty())
+            .expect("Valid response"),
+        None => hyper::Response::builder()
+            .status(hyper::StatusCode::FORBIDDEN)
+            .body(hyper::Body::from("Origin not allowed"))
+            .expect("Valid response"),
     }
 }
 
 impl Interface for HttpInterface {
-    fn run(&self, handler: IoHandler) -> Result<(), String> {
+    fn start(&mut self, handler: AuthIoHandler) -> io::Result<()> {
         let url = format!("0.0.0.0:{}", self.port);
-        let server = ServerBuilder::new(handler)
-            .start_http(&url.parse().expect("Invalid URL!"))
-            .map_err(|e| e.to_string())?;
-        server.wait();
+        let auth_token = self.auth_token.clone();
+        // With CORS configured, responses only carry CORS headers for the allowed origins and
+        // requests from any other origin, except for the interface's own host, get rejected.
+        // Without, origins don't get checked, like before CORS could be configured, so that
+        // e.g. UIs served from another port keep working.
+        let (allowed_origins, allowed_headers) = match self.cors {
+            Some(ref cors) => (
+                DomainsValidation::AllowOnly(
+                    cors.allowed_origins
+                        .iter()
+                        .map(|origin| AccessControlAllowOrigin::from(origin.as_str()))
+                        .collect(),
+                ),
+                AccessControlAllowHeaders::Only(cors.allowed_headers.clone()),
+            ),
+            None => (DomainsValidation::Disabled, AccessControlAllowHeaders::Any),
+        };
+        let preflight_cors = self.cors.clone();
+        let server = ServerBuilder::with_meta_extractor(
+            handler,
+            move |request: &hyper::Request<hyper::Body>| {
+                let authorization = request
+                    .headers()
+                    .get("authorization")
+                    .and_then(|value| value.to_str().ok());
+                AuthMeta {
+                    authorized: is_authorized(&auth_token, authorization),
+                    session: None,
+                }
+            },
+        )
+        .cors(allowed_origins)
+        .cors_allow_headers(allowed_headers)
+        .request_middleware(
+            move |request: hyper::Request<hyper::Body>| -> RequestMiddlewareAction {
+                match preflight_cors {
+                    Some(ref cors) if request.method() == &hyper::Method::OPTIONS => {
+                        let origin = request
+                            .headers()
+                            .get("origin")
+                            .and_then(|value| value.to_str().ok());
+                        preflight_response(cors, origin).into()
+                    }
+                    _ => request.into(),
+                }
+            },
+        )
+        .max_request_body_size(self.max_request_bytes)
+        .start_http(&url.parse().expect("Invalid URL!"))?;
+        self.server = Some(server);
+        Ok(())
+    }
+
+    fn run(&mut self, kill_switch: Receiver<()>) -> Result<(), String> {
+        let server = self.server.take().ok_or_else(interface_not_started_error)?;
+        let _ = kill_switch.recv();
+        server.close();
         Ok(())
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use config::{default_cors_allowed_headers, default_cors_allowed_methods};
+
+    fn cors(allowed_origins: Vec<&str>) -> CorsConfiguration {
+        CorsConfiguration {
+            allowed_origins: allowed_origins.into_iter().map(String::from).collect(),
+            allowed_methods: default_cors_allowed_methods(),
+            allowed_headers: default_cors_allowed_headers(),
+        }
+    }
+
+    #[test]
+    fn test_preflight_response() {
+        let cors = cors(vec!["https://app.example.org"]);
+        let response = preflight_response(&cors, Some("https://app.example.org"));
+        assert_eq!(response.status(), hyper::StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(
+            headers["Access-Control-Allow-Origin"],
+            "https://app.example.org"
+        );
+        assert_eq!(headers["Access-Control-Allow-Methods"], "POST, OPTIONS");
+        assert_eq!(
+            headers["Access-Control-Allow-Headers"],
+            "Content-Type, Authorization"
+        );
+
+        let response = preflight_response(&cors, Some("https://evil.example.org"));
+        assert_eq!(response.status(), hyper::StatusCode::FORBIDDEN);
+        let response = preflight_response(&cors, None);
+        assert_eq!(response.status(), hyper::StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_wildcard_origin_has_to_be_configured() {
+        assert_eq!(
+            cors(vec!["*"]).allow_origin("https://app.example.org"),
+            Some(String::from("*"))
+        );
+        assert_eq!(cors(vec![]).allow_origin("https://app.example.org"), None);
+    }
+}
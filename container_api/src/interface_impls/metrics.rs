@@ -0,0 +1,146 @@
+use interface::{interface_not_started_error, is_authorized, AuthIoHandler, Interface};
+use metrics::{render_prometheus, ZomeCallMetrics};
+use std::{
+    io,
+    sync::{
+        mpsc::{Receiver, TryRecvError},
+        Arc,
+    },
+    time::Duration,
+};
+use tiny_http::{Header, Response, Server};
+
+/// How long the interface waits for a request before checking its kill switch
+const REQUEST_POLL_INTERVAL_MS: u64 = 50;
+
+/// Interface serving the container's zome call metrics at `GET /metrics` in the
+/// Prometheus text format. It serves nothing else, so the handler goes unused.
+pub struct MetricsInterface {
+    port: u16,
+    bind_address: String,
+    server: Option<Server>,
+    auth_token: Option<String>,
+    metrics: Arc<ZomeCallMetrics>,
+}
+
+impl MetricsInterface {
+    /// Listens on localhost only, see `with_bind_address()`
+    pub fn new(port: u16, auth_token: Option<String>, metrics: Arc<ZomeCallMetrics>) -> Self {
+        MetricsInterface {
+            port,
+            bind_address: String::from("127.0.0.1"),
+            server: None,
+            auth_token,
+            metrics,
+        }
+    }
+
+    pub fn with_bind_address(mut self, bind_address: String) -> Self {
+        self.bind_address = bind_address;
+        self
+    }
+}
+
+impl Interface for MetricsInterface {
+    fn start(&mut self, _handler: AuthIoHandler) -> io::Result<()> {
+        let server = Server::http((self.bind_address.as_str(), self.port)).map_err(|error| {
+            match error.downcast::<io::Error>() {
+                Ok(error) => *error,
+                Err(error) => io::Error::new(io::ErrorKind::Other, error.to_string()),
+            }
+        })?;
+        self.server = Some(server);
+        Ok(())
+    }
+
+    fn run(&mut self, kill_switch: Receiver<()>) -> Result<(), String> {
+        let server = self.server.take().ok_or_else(interface_not_started_error)?;
+        let poll_interval = Duration::from_millis(REQUEST_POLL_INTERVAL_MS);
+        loop {
+            match kill_switch.try_recv() {
+                Err(TryRecvError::Empty) => {}
+                _ => return Ok(()),
+            }
+            let request = match server.recv_timeout(poll_interval) {
+                Ok(Some(request)) => request,
+                Ok(None) => continue,
+                Err(error) => return Err(error.to_string()),
+            };
+            let authorization = request
+                .headers()
+                .iter()
+                .find(|header| header.field.equiv("Authorization"))
+                .map(|header| header.value.as_str().to_string());
+            let authorized =
+                is_authorized(&self.auth_token, authorization.as_ref().map(String::as_str));
+            let response = if !authorized {
+                Response::from_string("Unauthorized").with_status_code(401)
+            } else if request.url() != "/metrics" {
+                Response::from_string("Not found").with_status_code(404)
+            } else {
+                Response::from_string(render_prometheus(&self.metrics.snapshot())).with_header(
+                    "Content-Type: text/plain; version=0.0.4"
+                        .parse::<Header>()
+                        .expect("Valid header"),
+                )
+            };
+            let _ = request.respond(response);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use interface::with_auth;
+    use jsonrpc_ws_server::jsonrpc_core::IoHandler;
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        sync::mpsc::channel,
+        thread,
+    };
+
+    #[test]
+    fn test_metrics_interface() {
+        let metrics = Arc::new(ZomeCallMetrics::new());
+        metrics.record_call(
+            "instance-1",
+            "greeter",
+            "hello",
+            Duration::from_millis(3),
+            true,
+        );
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut interface = MetricsInterface::new(port, None, metrics);
+        interface.start(with_auth(IoHandler::new(), false)).unwrap();
+        let (kill_switch_tx, kill_switch_rx) = channel();
+        let server = thread::spawn(move || interface.run(kill_switch_rx));
+
+        let response = (0..50)
+            .find_map(|_| {
+                thread::sleep(Duration::from_millis(20));
+                let mut stream = TcpStream::connect(("127.0.0.1", port)).ok()?;
+                stream
+                    .write_all(
+                        b"GET /metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+                    )
+                    .ok()?;
+                let mut response = String::new();
+                stream.read_to_string(&mut response).ok()?;
+                Some(response)
+            })
+            .expect("Metrics interface should answer");
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains(
+            "holochain_zome_calls_total{instance=\"instance-1\",function=\"greeter/hello\"} 1\n"
+        ));
+
+        kill_switch_tx.send(()).unwrap();
+        assert_eq!(server.join().unwrap(), Ok(()));
+    }
+}
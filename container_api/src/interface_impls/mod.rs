@@ -1,4 +1,8 @@
+pub mod domain_socket;
 pub mod http;
+pub mod metrics;
+pub mod stdio;
 pub mod websocket;
+pub mod websocket_tls;
 
-pub use self::{http::*, websocket::*};
+pub use self::{domain_socket::*, http::*, metrics::*, stdio::*, websocket::*, websocket_tls::*};
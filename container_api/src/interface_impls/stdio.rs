@@ -0,0 +1,102 @@
+use interface::{interface_not_started_error, AuthIoHandler, AuthMeta, Interface};
+use std::{
+    io::{self, Write},
+    sync::{
+        mpsc::{channel, Receiver, RecvTimeoutError, TryRecvError},
+        Mutex, PoisonError,
+    },
+    thread,
+    time::Duration,
+};
+
+/// How often the interface checks its kill switch while waiting for input
+const KILL_SWITCH_POLL_INTERVAL_MS: u64 = 50;
+
+/// Interface reading newline-delimited JSON-RPC requests from stdin and writing each
+/// response as a single line to stdout, for driving the container as a subprocess.
+///
+/// There is only one stdin, so only one such interface can be configured. Like with Unix
+/// sockets there are no headers to present a token with: if an auth token is configured
+/// for this interface only public methods (see `public_info`) can be called.
+/// Anything else the container prints goes to stderr to keep the protocol stream clean.
+pub struct StdioInterface {
+    auth_token: Option<String>,
+    handler: Option<AuthIoHandler>,
+}
+
+impl StdioInterface {
+    pub fn new(auth_token: Option<String>) -> Self {
+        StdioInterface {
+            auth_token,
+            handler: None,
+        }
+    }
+}
+
+lazy_static! {
+    /// Lines read from stdin, shared by all stdio interfaces the process runs.
+    /// Reading stdin can't be interrupted, so a stopped interface can't take its reader
+    /// along. A restarted interface picks up the lines where the stopped one left off.
+    static ref STDIN_LINES: Mutex<Receiver<String>> = Mutex::new(spawn_stdin_reader());
+}
+
+/// Forwards the lines read from stdin until stdin gets closed
+fn spawn_stdin_reader() -> Receiver<String> {
+    let (tx, rx) = channel();
+    thread::spawn(move || loop {
+        let mut line = String::new();
+        match io::stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    rx
+}
+
+impl Interface for StdioInterface {
+    /// There is nothing to bind, stdin gets read once the interface runs
+    fn start(&mut self, handler: AuthIoHandler) -> io::Result<()> {
+        self.handler = Some(handler);
+        Ok(())
+    }
+
+    fn run(&mut self, kill_switch: Receiver<()>) -> Result<(), String> {
+        let handler = self
+            .handler
+            .take()
+            .ok_or_else(interface_not_started_error)?;
+        let meta = AuthMeta {
+            authorized: self.auth_token.is_none(),
+            session: None,
+        };
+        let poll_interval = Duration::from_millis(KILL_SWITCH_POLL_INTERVAL_MS);
+        // Held until the interface stops, which a restarted interface waits for
+        let lines = STDIN_LINES.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            match kill_switch.try_recv() {
+                Err(TryRecvError::Empty) => {}
+                _ => return Ok(()),
+            }
+            let line = match lines.recv_timeout(poll_interval) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => continue,
+                // stdin got closed, nobody is left to send requests
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = handler.handle_request_sync(line.trim(), meta.clone()) {
+                let stdout = io::stdout();
+                let mut stdout = stdout.lock();
+                writeln!(stdout, "{}", response)
+                    .and_then(|_| stdout.flush())
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+}
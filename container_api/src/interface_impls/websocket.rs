@@ -1,23 +1,211 @@
-use interface::Interface;
-use jsonrpc_ws_server::{jsonrpc_core::IoHandler, ServerBuilder};
+use config::default_max_request_bytes;
+use interface::{
+    interface_not_started_error, is_authorized, query_token, AuthIoHandler, AuthMeta, Interface,
+    NotificationSession, SessionId, SignalSubscriptions,
+};
+use jsonrpc_ws_server::{self, ws, RequestContext, Server, ServerBuilder, SessionStats};
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Receiver,
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
 
 pub struct WebsocketInterface {
     port: u16,
+    server: Option<Server>,
+    auth_token: Option<String>,
+    signal_subscriptions: SignalSubscriptions,
+    keep_alive: Option<KeepAlive>,
+    max_request_bytes: usize,
 }
 
 impl WebsocketInterface {
-    pub fn new(port: u16) -> Self {
-        WebsocketInterface { port }
+    pub fn new(
+        port: u16,
+        auth_token: Option<String>,
+        signal_subscriptions: SignalSubscriptions,
+    ) -> Self {
+        WebsocketInterface {
+            port,
+            server: None,
+            auth_token,
+            signal_subscriptions,
+            keep_alive: None,
+            max_request_bytes: default_max_request_bytes(),
+        }
+    }
+
+    /// Connections sending a bigger message get closed with status 1009 before it is parsed
+    pub fn with_max_request_bytes(mut self, max_request_bytes: usize) -> Self {
+        self.max_request_bytes = max_request_bytes;
+        self
+    }
+
+    /// Pings the connections every `ping_interval`, see `KeepAlive`
+    pub fn with_keep_alive(mut self, ping_interval: Duration) -> Self {
+        self.keep_alive = Some(KeepAlive::new(ping_interval));
+        self
     }
 }
 
 impl Interface for WebsocketInterface {
-    fn run(&self, handler: IoHandler) -> Result<(), String> {
+    fn start(&mut self, handler: AuthIoHandler) -> io::Result<()> {
         let url = format!("0.0.0.0:{}", self.port);
-        let server = ServerBuilder::new(handler)
-            .start(&url.parse().expect("Invalid URL!"))
-            .map_err(|e| e.to_string())?;
-        server.wait().map_err(|e| e.to_string())?;
+        self.server = Some(start_server(
+            handler,
+            self.auth_token.clone(),
+            &url.parse().expect("Invalid URL!"),
+            self.signal_subscriptions.clone(),
+            self.keep_alive.clone(),
+            self.max_request_bytes,
+        )?);
         Ok(())
     }
+
+    fn run(&mut self, kill_switch: Receiver<()>) -> Result<(), String> {
+        let server = self.server.take().ok_or_else(interface_not_started_error)?;
+        let _ = kill_switch.recv();
+        if let Some(ref keep_alive) = self.keep_alive {
+            keep_alive.stop();
+        }
+        server.close();
+        Ok(())
+    }
+}
+
+/// Keeps track of the connections of a websocket server and sends them a ping frame every
+/// `ping_interval`. Pings to peers that are gone eventually fail and make the server drop
+/// the connection, which also ends its signal subscriptions.
+///
+/// The websocket server answers and consumes pongs internally, so connections to clients
+/// that stay connected but stopped answering don't get noticed.
+#[derive(Clone)]
+pub struct KeepAlive {
+    ping_interval: Duration,
+    connections: Arc<Mutex<HashMap<SessionId, ws::Sender>>>,
+    running: Arc<AtomicBool>,
+}
+
+impl KeepAlive {
+    pub fn new(ping_interval: Duration) -> Self {
+        KeepAlive {
+            ping_interval,
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Spawns the thread that pings the connections until `stop()` gets called.
+    pub fn start(&self) {
+        self.running.store(true, Ordering::SeqCst);
+        let keep_alive = self.clone();
+        thread::spawn(move || loop {
+            thread::sleep(keep_alive.ping_interval);
+            if !keep_alive.running.load(Ordering::SeqCst) {
+                break;
+            }
+            keep_alive.ping_all();
+        });
+    }
+
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+
+    fn add_connection(&self, session: &NotificationSession, out: ws::Sender) {
+        self.connections.lock().unwrap().insert(session.id, out);
+    }
+
+    fn remove_connection(&self, id: SessionId) {
+        self.connections.lock().unwrap().remove(&id);
+    }
+
+    fn ping_all(&self) {
+        for out in self.connections.lock().unwrap().values() {
+            let _ = out.ping(Vec::new());
+        }
+    }
+}
+
+/// What the server does when sessions open and close
+struct Sessions {
+    signal_subscriptions: SignalSubscriptions,
+    keep_alive: Option<KeepAlive>,
+}
+
+impl SessionStats for Sessions {
+    fn open_session(&self, _id: SessionId) {}
+
+    fn close_session(&self, id: SessionId) {
+        if let Some(ref keep_alive) = self.keep_alive {
+            keep_alive.remove_connection(id);
+        }
+        self.signal_subscriptions.close_session(id)
+    }
+}
+
+/// Starts a websocket server that checks the auth token once during the handshake,
+/// either from the `Authorization: Bearer <token>` header or the `token` query parameter.
+/// Handshakes without the configured token are rejected, so every request of an
+/// established session is authorized.
+/// Signal subscriptions of a session end when its connection closes.
+/// With a KeepAlive, the server's connections get pinged until the KeepAlive gets stopped.
+/// Connections sending messages bigger than `max_request_bytes` get closed.
+pub(crate) fn start_server(
+    handler: AuthIoHandler,
+    auth_token: Option<String>,
+    address: &SocketAddr,
+    signal_subscriptions: SignalSubscriptions,
+    keep_alive: Option<KeepAlive>,
+    max_request_bytes: usize,
+) -> io::Result<Server> {
+    let sessions = Sessions {
+        signal_subscriptions,
+        keep_alive: keep_alive.clone(),
+    };
+    let pinged_connections = keep_alive.clone();
+    let server = ServerBuilder::new(handler)
+        .session_meta_extractor(move |context: &RequestContext| {
+            let session = NotificationSession::new(context.session_id, context.sender());
+            if let Some(ref keep_alive) = keep_alive {
+                keep_alive.add_connection(&session, context.out.clone());
+            }
+            AuthMeta {
+                authorized: true,
+                session: Some(session),
+            }
+        })
+        .session_stats(sessions)
+        .max_payload(max_request_bytes)
+        .request_middleware(move |request: &ws::Request| {
+            let header = request
+                .header("authorization")
+                .and_then(|value| ::std::str::from_utf8(value).ok());
+            let authorized = is_authorized(&auth_token, header)
+                || match (&auth_token, query_token(request.resource())) {
+                    (Some(expected), Some(token)) => expected == token,
+                    _ => false,
+                };
+            if authorized {
+                None
+            } else {
+                Some(ws::Response::new(401, "Unauthorized", b"Unauthorized".to_vec()))
+            }
+        })
+        .start(address)
+        .map_err(|error| match error {
+            jsonrpc_ws_server::Error::Io(error) => error,
+            error => io::Error::new(io::ErrorKind::Other, error.to_string()),
+        })?;
+    if let Some(keep_alive) = pinged_connections {
+        keep_alive.start();
+    }
+    Ok(server)
 }
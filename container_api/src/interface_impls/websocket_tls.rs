@@ -0,0 +1,184 @@
+use super::websocket::{self, KeepAlive};
+use config::default_max_request_bytes;
+use interface::{interface_not_started_error, AuthIoHandler, Interface, SignalSubscriptions};
+use jsonrpc_ws_server::Server;
+use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod, SslStream};
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::mpsc::{Receiver, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+/// How long the accept loop sleeps between checking for new connections and the kill switch
+const ACCEPT_POLL_INTERVAL_MS: u64 = 50;
+/// Read timeout used for proxied connections so both directions can be served by one thread
+const PROXY_POLL_INTERVAL_MS: u64 = 10;
+
+/// Websocket interface that only accepts TLS (WSS) connections.
+///
+/// The JSON-RPC websocket server itself can't do TLS, so this runs it on an ephemeral
+/// localhost port and terminates TLS on the configured port, proxying each decrypted
+/// connection to the local server.
+pub struct WebsocketTlsInterface {
+    port: u16,
+    front: Option<TlsFront>,
+    cert_path: String,
+    key_path: String,
+    auth_token: Option<String>,
+    signal_subscriptions: SignalSubscriptions,
+    keep_alive: Option<KeepAlive>,
+    max_request_bytes: usize,
+}
+
+/// What a started WebsocketTlsInterface serves its clients with
+struct TlsFront {
+    acceptor: SslAcceptor,
+    listener: TcpListener,
+    server: Server,
+}
+
+impl WebsocketTlsInterface {
+    pub fn new(
+        port: u16,
+        cert_path: String,
+        key_path: String,
+        auth_token: Option<String>,
+        signal_subscriptions: SignalSubscriptions,
+    ) -> Self {
+        WebsocketTlsInterface {
+            port,
+            front: None,
+            cert_path,
+            key_path,
+            auth_token,
+            signal_subscriptions,
+            keep_alive: None,
+            max_request_bytes: default_max_request_bytes(),
+        }
+    }
+
+    /// Same as `WebsocketInterface::with_keep_alive()`
+    pub fn with_keep_alive(mut self, ping_interval: Duration) -> Self {
+        self.keep_alive = Some(KeepAlive::new(ping_interval));
+        self
+    }
+
+    /// Same as `WebsocketInterface::with_max_request_bytes()`
+    pub fn with_max_request_bytes(mut self, max_request_bytes: usize) -> Self {
+        self.max_request_bytes = max_request_bytes;
+        self
+    }
+
+    fn acceptor(&self) -> Result<SslAcceptor, String> {
+        let mut builder =
+            SslAcceptor::mozilla_intermediate(SslMethod::tls()).map_err(|e| e.to_string())?;
+        builder
+            .set_private_key_file(&self.key_path, SslFiletype::PEM)
+            .map_err(|e| format!("Could not load TLS key \"{}\": {}", self.key_path, e))?;
+        builder
+            .set_certificate_chain_file(&self.cert_path)
+            .map_err(|e| {
+                format!(
+                    "Could not load TLS certificate \"{}\": {}",
+                    self.cert_path, e
+                )
+            })?;
+        builder.check_private_key().map_err(|e| e.to_string())?;
+        Ok(builder.build())
+    }
+}
+
+impl Interface for WebsocketTlsInterface {
+    fn start(&mut self, handler: AuthIoHandler) -> io::Result<()> {
+        let acceptor = self
+            .acceptor()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+        let listener = TcpListener::bind(("0.0.0.0", self.port))?;
+        listener.set_nonblocking(true)?;
+
+        let local_url = "127.0.0.1:0".parse().expect("Invalid URL!");
+        let server = websocket::start_server(
+            handler,
+            self.auth_token.clone(),
+            &local_url,
+            self.signal_subscriptions.clone(),
+            self.keep_alive.clone(),
+            self.max_request_bytes,
+        )?;
+        self.front = Some(TlsFront {
+            acceptor,
+            listener,
+            server,
+        });
+        Ok(())
+    }
+
+    fn run(&mut self, kill_switch: Receiver<()>) -> Result<(), String> {
+        let TlsFront {
+            acceptor,
+            listener,
+            server,
+        } = self.front.take().ok_or_else(interface_not_started_error)?;
+        let local_address = *server.addr();
+
+        let result = loop {
+            match kill_switch.try_recv() {
+                Err(TryRecvError::Empty) => (),
+                _ => break Ok(()),
+            }
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let acceptor = acceptor.clone();
+                    thread::spawn(move || {
+                        let _ = stream.set_nonblocking(false);
+                        let tls_stream = acceptor.accept(stream).map_err(|e| e.to_string())?;
+                        let local_stream =
+                            TcpStream::connect(local_address).map_err(|e| e.to_string())?;
+                        proxy(tls_stream, local_stream).map_err(|e| e.to_string())
+                    });
+                }
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(ACCEPT_POLL_INTERVAL_MS))
+                }
+                Err(error) => break Err(error.to_string()),
+            }
+        };
+
+        if let Some(ref keep_alive) = self.keep_alive {
+            keep_alive.stop();
+        }
+        server.close();
+        result
+    }
+}
+
+/// Copies bytes in both directions until either side closes the connection.
+fn proxy(mut tls_stream: SslStream<TcpStream>, mut local_stream: TcpStream) -> io::Result<()> {
+    let poll_interval = Some(Duration::from_millis(PROXY_POLL_INTERVAL_MS));
+    tls_stream.get_ref().set_read_timeout(poll_interval)?;
+    local_stream.set_read_timeout(poll_interval)?;
+    let mut buffer = [0; 4096];
+    loop {
+        match tls_stream.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => local_stream.write_all(&buffer[..n])?,
+            Err(ref error) if is_timeout(error) => (),
+            Err(error) => return Err(error),
+        }
+        match local_stream.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => tls_stream.write_all(&buffer[..n])?,
+            Err(ref error) if is_timeout(error) => (),
+            Err(error) => return Err(error),
+        }
+    }
+    let _ = local_stream.shutdown(Shutdown::Both);
+    let _ = tls_stream.shutdown();
+    Ok(())
+}
+
+fn is_timeout(error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::WouldBlock || error.kind() == io::ErrorKind::TimedOut
+}
@@ -0,0 +1,216 @@
+//! Agents' secret keys are stored encrypted in the agent's `key_file`.
+//! The file holds the seed of the agent's signing keypair, encrypted with a key that gets
+//! derived from a passphrase, see [Keystore](struct.Keystore.html).
+use config::PassphraseSource;
+use holochain_core_types::{agent::KeyBuffer, error::HolochainError};
+use holochain_sodium::{aead, pwhash, random::random_secbuf, secbuf::SecBuf, sign};
+use rpassword;
+use serde_json;
+use std::{env, fs::File, io::Read, path::Path};
+
+/// Contents of an encrypted key file, stored as JSON.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Keystore {
+    salt: Vec<u8>,
+    nonce: Vec<u8>,
+    encrypted_seed: Vec<u8>,
+}
+
+impl Keystore {
+    /// Encrypts the given seed of a signing keypair with the passphrase.
+    pub fn new(seed: &mut SecBuf, passphrase: &str) -> Result<Self, HolochainError> {
+        let mut salt = SecBuf::with_insecure(pwhash::SALTBYTES);
+        random_secbuf(&mut salt);
+        let mut nonce = SecBuf::with_insecure(aead::NONCEBYTES);
+        random_secbuf(&mut nonce);
+        let mut key = passphrase_key(passphrase, &mut salt)?;
+        let mut encrypted_seed = SecBuf::with_insecure(seed.len() + aead::ABYTES);
+        aead::enc(seed, &mut key, None, &mut nonce, &mut encrypted_seed)
+            .map_err(|error| HolochainError::ErrorGeneric(format!("{:?}", error)))?;
+        Ok(Keystore {
+            salt: to_vec(&mut salt),
+            nonce: to_vec(&mut nonce),
+            encrypted_seed: to_vec(&mut encrypted_seed),
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, HolochainError> {
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), HolochainError> {
+        let file = File::create(path)?;
+        Ok(serde_json::to_writer(file, self)?)
+    }
+
+    /// Decrypts the seed and returns the secret signing key derived from it.
+    /// Decrypting with a wrong passphrase does not fail by itself, so this checks that the
+    /// derived keypair is the one of the given public key.
+    pub fn secret_key(
+        &self,
+        passphrase: &str,
+        public_key: &KeyBuffer,
+    ) -> Result<SecBuf, HolochainError> {
+        if self.salt.len() != pwhash::SALTBYTES
+            || self.nonce.len() != aead::NONCEBYTES
+            || self.encrypted_seed.len() != sign::SEEDBYTES + aead::ABYTES
+        {
+            return Err(HolochainError::ConfigError(String::from(
+                "Key file is malformed",
+            )));
+        }
+        let mut salt = from_slice(&self.salt);
+        let mut key = passphrase_key(passphrase, &mut salt)?;
+        let mut seed = SecBuf::with_secure(sign::SEEDBYTES);
+        aead::dec(
+            &mut seed,
+            &mut key,
+            None,
+            &mut from_slice(&self.nonce),
+            &mut from_slice(&self.encrypted_seed),
+        )
+        .map_err(|error| HolochainError::ErrorGeneric(format!("{:?}", error)))?;
+
+        let mut derived_public_key = SecBuf::with_insecure(sign::PUBLICKEYBYTES);
+        let mut secret_key = SecBuf::with_secure(sign::SECRETKEYBYTES);
+        sign::seed_keypair(&mut derived_public_key, &mut secret_key, &mut seed)
+            .map_err(|error| HolochainError::ErrorGeneric(format!("{:?}", error)))?;
+        if to_vec(&mut derived_public_key)[..] != public_key.get_sig()[..] {
+            return Err(HolochainError::ConfigError(String::from(
+                "Key file does not hold the key of the agent's public address, wrong passphrase?",
+            )));
+        }
+        Ok(secret_key)
+    }
+}
+
+/// Gets the passphrase from the given source.
+/// Only the first line of passphrase files and prompt input counts.
+/// The prompt reads from the terminal without echoing the input, not from stdin.
+pub fn read_passphrase(
+    source: &PassphraseSource,
+    agent_id: &str,
+) -> Result<String, HolochainError> {
+    let input = match source {
+        PassphraseSource::Env { variable } => env::var(variable).map_err(|_| {
+            HolochainError::ConfigError(format!(
+                "Environment variable {} holding the passphrase is not set",
+                variable
+            ))
+        })?,
+        PassphraseSource::File { path } => {
+            let mut contents = String::new();
+            File::open(path)?.read_to_string(&mut contents)?;
+            contents
+        }
+        PassphraseSource::Prompt => rpassword::read_password_from_tty(Some(&format!(
+            "Passphrase for agent {}: ",
+            agent_id
+        )))?,
+    };
+    Ok(input.lines().next().unwrap_or("").to_string())
+}
+
+fn passphrase_key(passphrase: &str, salt: &mut SecBuf) -> Result<SecBuf, HolochainError> {
+    let mut password = from_slice(passphrase.as_bytes());
+    let mut key = SecBuf::with_secure(pwhash::HASHBYTES);
+    pwhash::hash(
+        &mut password,
+        pwhash::OPSLIMIT_INTERACTIVE,
+        pwhash::MEMLIMIT_INTERACTIVE,
+        pwhash::ALG_ARGON2ID13,
+        salt,
+        &mut key,
+    )
+    .map_err(|error| HolochainError::ErrorGeneric(format!("{:?}", error)))?;
+    Ok(key)
+}
+
+fn from_slice(bytes: &[u8]) -> SecBuf {
+    let mut buf = SecBuf::with_insecure(bytes.len());
+    buf.write_lock().copy_from_slice(bytes);
+    buf
+}
+
+fn to_vec(buf: &mut SecBuf) -> Vec<u8> {
+    buf.read_lock().to_vec()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// Creates a random signing keypair and returns the seed together with the public address
+    /// of an agent with that keypair.
+    pub fn test_seed_and_public_address() -> (SecBuf, String) {
+        let mut seed = SecBuf::with_secure(sign::SEEDBYTES);
+        random_secbuf(&mut seed);
+        let mut public_key = SecBuf::with_insecure(sign::PUBLICKEYBYTES);
+        let mut secret_key = SecBuf::with_secure(sign::SECRETKEYBYTES);
+        sign::seed_keypair(&mut public_key, &mut secret_key, &mut seed).unwrap();
+        // Signing key followed by an (unused) encryption key
+        let mut raw_key = [0; 64];
+        raw_key[..sign::PUBLICKEYBYTES].copy_from_slice(&to_vec(&mut public_key));
+        (seed, KeyBuffer::with_raw(&raw_key).render())
+    }
+
+    #[test]
+    fn test_keystore_roundtrip() {
+        let (mut seed, public_address) = test_seed_and_public_address();
+        let public_key = KeyBuffer::with_corrected(&public_address).unwrap();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("agent.key");
+        Keystore::new(&mut seed, "secret")
+            .unwrap()
+            .save(&path)
+            .unwrap();
+
+        let keystore = Keystore::load(&path).unwrap();
+        assert!(keystore.secret_key("secret", &public_key).is_ok());
+        assert_eq!(
+            keystore.secret_key("wrong", &public_key).err(),
+            Some(HolochainError::ConfigError(String::from(
+                "Key file does not hold the key of the agent's public address, wrong passphrase?"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_read_passphrase() {
+        env::set_var("HC_TEST_KEYSTORE_PASSPHRASE", "secret");
+        assert_eq!(
+            read_passphrase(
+                &PassphraseSource::Env {
+                    variable: String::from("HC_TEST_KEYSTORE_PASSPHRASE")
+                },
+                "agent"
+            ),
+            Ok(String::from("secret"))
+        );
+        assert!(read_passphrase(
+            &PassphraseSource::Env {
+                variable: String::from("HC_TEST_KEYSTORE_PASSPHRASE_UNSET")
+            },
+            "agent"
+        )
+        .is_err());
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("passphrase");
+        File::create(&path)
+            .unwrap()
+            .write_all(b"from file\nignored")
+            .unwrap();
+        assert_eq!(
+            read_passphrase(
+                &PassphraseSource::File {
+                    path: path.to_string_lossy().to_string()
+                },
+                "agent"
+            ),
+            Ok(String::from("from file"))
+        );
+    }
+}
@@ -57,10 +57,10 @@
 ///     println!("Using config path: {}", config_path_str);
 ///     match bootstrap_from_config(config_path_str) {
 ///         Ok(mut container) => {
-///             if container.instances().len() > 0 {
+///             if container.instance_count() > 0 {
 ///                 println!(
 ///                     "Successfully loaded {} instance configurations",
-///                     container.instances().len()
+///                     container.instance_count()
 ///                 );
 ///                 println!("Starting all of them...");
 ///                 container.start_all_instances();
@@ -96,6 +96,7 @@ extern crate holochain_core_types;
 extern crate holochain_net;
 extern crate holochain_net_connection;
 extern crate holochain_net_ipc;
+extern crate holochain_sodium;
 
 extern crate chrono;
 extern crate serde;
@@ -104,10 +105,13 @@ extern crate tempfile;
 extern crate serde_derive;
 extern crate boolinator;
 extern crate colored;
+extern crate failure;
 #[cfg(test)]
 extern crate holochain_wasm_utils;
 extern crate jsonrpc_http_server;
+extern crate jsonrpc_ipc_server;
 extern crate jsonrpc_ws_server;
+extern crate openssl;
 extern crate petgraph;
 extern crate regex;
 #[macro_use]
@@ -120,7 +124,14 @@ extern crate toml;
 #[macro_use]
 extern crate maplit;
 extern crate directories;
+extern crate wasmi;
+extern crate flate2;
+extern crate rpassword;
+#[macro_use]
+extern crate lazy_static;
 
+pub mod call_queue;
+pub mod calls_in_flight;
 pub mod config;
 pub mod container;
 pub mod context_builder;
@@ -128,6 +139,13 @@ pub mod error;
 pub mod holochain;
 pub mod interface;
 pub mod interface_impls;
+pub mod keystore;
 pub mod logger;
+pub mod metrics;
+pub mod prepared_call;
+pub mod remote_bridge;
+pub mod signal_broadcaster;
+pub mod state_diff;
+pub mod state_dump;
 
 pub use crate::holochain::Holochain;
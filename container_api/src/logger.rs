@@ -3,7 +3,28 @@ use colored::*;
 use holochain_core::logger::{ChannelLogger, Sender};
 use holochain_core_types::error::HolochainError;
 use regex::Regex;
-use std::thread;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    thread,
+};
+
+/// Output format of rendered log messages.
+/// "text" prints colorized lines, "json" prints one JSON object per line
+/// with the fields timestamp, instance_id, level and message.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl Default for LogFormat {
+    fn default() -> LogFormat {
+        LogFormat::Text
+    }
+}
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct LogRule {
@@ -13,6 +34,10 @@ pub struct LogRule {
     pub exclude: bool,
     #[serde(default)]
     pub color: Option<String>,
+    /// If set, the rule only applies to messages of this instance and takes precedence
+    /// over all rules without an instance
+    #[serde(default)]
+    pub instance_id: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -45,28 +70,41 @@ impl LogRules {
         pattern: &str,
         exclude: bool,
         color: Option<String>,
+    ) -> Result<(), HolochainError> {
+        self.add_scoped_rule(pattern, exclude, color, None)
+    }
+
+    // add a new rule to the rules list that only applies to the given instance, if any
+    pub fn add_scoped_rule(
+        &mut self,
+        pattern: &str,
+        exclude: bool,
+        color: Option<String>,
+        instance_id: Option<String>,
     ) -> Result<(), HolochainError> {
         let regex = Regex::new(pattern).map_err(|e| HolochainError::new(&e.to_string()))?;
         self.rules.push(LogRule {
             pattern: regex,
             exclude,
             color,
+            instance_id,
         });
         Ok(())
     }
 
     // run the rules on a message, returning None if the message is rejected, or Some(LogMessage)
+    // rules scoped to the message's instance get checked before the unscoped ones
     pub fn run(&self, id: String, msg: String) -> Option<LogMessage> {
-        let mut message = LogMessage {
-            date: Local::now(),
-            id: id,
-            msg: msg.clone(),
-            color: None,
-        };
+        let scoped_rules = self
+            .rules
+            .iter()
+            .filter(|r| r.instance_id.as_ref() == Some(&id));
+        let unscoped_rules = self.rules.iter().filter(|r| r.instance_id.is_none());
+        let mut message = LogMessage::new(id.clone(), msg.clone());
         if self.rules.len() == 0 {
             None
         } else {
-            for r in &self.rules {
+            for r in scoped_rules.chain(unscoped_rules) {
                 if r.pattern.is_match(&msg) {
                     if r.exclude {
                         return None;
@@ -82,18 +120,45 @@ impl LogRules {
 
 // The DebugLogger implements a receiver for the instance ChannelLogger
 // which allows for configurable colorization and filtering of log messages.
+// The container sends its own messages here too, with the id "container".
 pub struct DebugLogger {
     sender: Sender,
 }
 
 impl DebugLogger {
-    pub fn new(rules: LogRules) -> Self {
+    pub fn new(rules: LogRules, format: LogFormat) -> Self {
+        Self::with_output(rules, format, Box::new(io::stdout()))
+    }
+
+    // same as new() but writes the rendered messages to the given output instead of stdout
+    pub fn with_output(rules: LogRules, format: LogFormat, output: Box<Write + Send>) -> Self {
+        Self::spawn(rules, format, output, true)
+    }
+
+    // same as new() but appends uncolored messages to the given file, see RotatingFile.
+    // Messages of all senders get written by the one logger thread, so lines never interleave.
+    pub fn with_file(
+        rules: LogRules,
+        format: LogFormat,
+        path: PathBuf,
+        max_size_bytes: Option<u64>,
+    ) -> Result<Self, HolochainError> {
+        let file = RotatingFile::open(path, max_size_bytes)?;
+        Ok(Self::spawn(rules, format, Box::new(file), false))
+    }
+
+    fn spawn(
+        rules: LogRules,
+        format: LogFormat,
+        mut output: Box<Write + Send>,
+        colorize: bool,
+    ) -> Self {
         let (tx, rx) = ChannelLogger::setup();
         let logger = DebugLogger { sender: tx.clone() };
 
         thread::spawn(move || loop {
             match rx.recv() {
-                Ok((id, msg)) => run(&rules, id, msg),
+                Ok((id, msg)) => run(&rules, id, msg, format, colorize, &mut output),
                 Err(_) => break,
             }
         });
@@ -105,13 +170,71 @@ impl DebugLogger {
 }
 
 // run checks a message against the rules and renders it if it matches
-pub fn run(rules: &LogRules, id: String, msg: String) {
+pub fn run(
+    rules: &LogRules,
+    id: String,
+    msg: String,
+    format: LogFormat,
+    colorize: bool,
+    output: &mut Write,
+) {
     match rules.run(id, msg) {
-        Some(message) => render(message),
+        Some(message) => render(message, format, colorize, output),
         None => (),
     }
 }
 
+// A log file that gets moved to "<path>.1", replacing an earlier one, as soon as
+// a write would make it grow beyond max_size_bytes.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_size_bytes: Option<u64>,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn open(path: PathBuf, max_size_bytes: Option<u64>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            max_size_bytes,
+            file,
+            size,
+        })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let mut rotated_path = self.path.clone().into_os_string();
+        rotated_path.push(".1");
+        fs::rename(&self.path, &rotated_path)?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(max_size_bytes) = self.max_size_bytes {
+            if self.size > 0 && self.size + buf.len() as u64 > max_size_bytes {
+                self.rotate()?;
+            }
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
 static ID_COLORS: &'static [&str] = &["green", "yellow", "blue", "magenta", "cyan"];
 
 // TODO this is actually silly and we should allocate colors to IDs so they aren't likely to collide
@@ -124,19 +247,31 @@ fn pick_color(text: &str) -> &str {
 }
 
 // renders a log message, using the id color if no color specified for the message.
-pub fn render(msg: LogMessage) {
-    let id_color = pick_color(&msg.id);
-    let msg_color = match msg.color {
-        None => id_color.to_string(),
-        Some(color) => color,
+// Lines get written with a single write so that a RotatingFile never splits them.
+pub fn render(msg: LogMessage, format: LogFormat, colorize: bool, output: &mut Write) {
+    let line = match format {
+        LogFormat::Json => msg.to_json(),
+        LogFormat::Text if colorize => {
+            let id_color = pick_color(&msg.id);
+            let msg_color = match msg.color {
+                None => id_color.to_string(),
+                Some(color) => color,
+            };
+            format!(
+                "{}:{}: {}",
+                msg.date.format("%Y-%m-%d %H:%M:%S"),
+                msg.id.color(id_color),
+                msg.msg.color(msg_color)
+            )
+        }
+        LogFormat::Text => format!(
+            "{}:{}: {}",
+            msg.date.format("%Y-%m-%d %H:%M:%S"),
+            msg.id,
+            msg.msg
+        ),
     };
-    let x = format!(
-        "{}:{}: {}",
-        msg.date.format("%Y-%m-%d %H:%M:%S"),
-        msg.id.color(id_color),
-        msg.msg.color(msg_color)
-    );
-    println!("{}", x);
+    let _ = output.write_all(format!("{}\n", line).as_bytes());
 }
 
 #[derive(Debug, PartialEq)]
@@ -147,9 +282,44 @@ pub struct LogMessage {
     color: Option<String>,
 }
 
+impl LogMessage {
+    fn new(id: String, msg: String) -> Self {
+        LogMessage {
+            date: Local::now(),
+            id,
+            msg,
+            color: None,
+        }
+    }
+
+    // the level is taken from the message prefix, as in "err/container: ...",
+    // and defaults to "info"
+    fn level(&self) -> &str {
+        match self.msg.split('/').next() {
+            Some("err") => "error",
+            Some("warn") => "warn",
+            Some("debug") => "debug",
+            Some("trace") => "trace",
+            _ => "info",
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        json!({
+            "timestamp": self.date.to_rfc3339(),
+            "instance_id": self.id,
+            "level": self.level(),
+            "message": self.msg,
+        })
+        .to_string()
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use serde_json;
+    use tempfile::tempdir;
     use toml;
 
     #[test]
@@ -167,11 +337,64 @@ pub mod tests {
         assert_eq!(m.msg, "xboy");
     }
 
+    #[test]
+    fn test_log_rules_scoped_to_instance() {
+        let mut rules = LogRules::new();
+        rules.add_rule("^debug/", true, None).unwrap();
+        rules.add_rule(".*", false, None).unwrap();
+        rules
+            .add_scoped_rule("^debug/", false, None, Some("noisy".to_string()))
+            .unwrap();
+
+        let m = rules
+            .run("noisy".to_string(), "debug/dna: hello".to_string())
+            .unwrap();
+        assert_eq!(m.id, "noisy");
+        assert_eq!(
+            rules.run("quiet".to_string(), "debug/dna: hello".to_string()),
+            None
+        );
+        assert!(rules
+            .run("quiet".to_string(), "warn/dna: hello".to_string())
+            .is_some());
+    }
+
     #[test]
     fn test_log_rules_default() {
         let rules = LogRules::default();
         assert_eq!(rules.rules.len(), 3);
-        assert_eq!(format!("{:?}",rules),"LogRules { rules: [LogRule { pattern: ^err/, exclude: false, color: Some(\"red\") }, LogRule { pattern: ^debug/dna, exclude: false, color: Some(\"white\") }, LogRule { pattern: .*, exclude: false, color: None }] }".to_string());
+        assert_eq!(format!("{:?}",rules),"LogRules { rules: [LogRule { pattern: ^err/, exclude: false, color: Some(\"red\"), instance_id: None }, LogRule { pattern: ^debug/dna, exclude: false, color: Some(\"white\"), instance_id: None }, LogRule { pattern: .*, exclude: false, color: None, instance_id: None }] }".to_string());
+    }
+
+    #[test]
+    fn test_log_message_json() {
+        let message = LogMessage::new("instance".to_string(), "err/container: broken".to_string());
+        let json: serde_json::Value = serde_json::from_str(&message.to_json()).unwrap();
+        assert_eq!(json["instance_id"], "instance");
+        assert_eq!(json["level"], "error");
+        assert_eq!(json["message"], "err/container: broken");
+        assert!(json["timestamp"].is_string());
+
+        let message = LogMessage::new("instance".to_string(), "hello".to_string());
+        assert_eq!(message.level(), "info");
+    }
+
+    #[test]
+    fn test_rotating_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("container.log");
+        let mut file = RotatingFile::open(path.clone(), Some(10)).unwrap();
+
+        file.write_all(b"1234\n").unwrap();
+        file.write_all(b"1234\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1234\n1234\n");
+
+        file.write_all(b"x\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "x\n");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("container.log.1")).unwrap(),
+            "1234\n1234\n"
+        );
     }
 
     #[test]
@@ -0,0 +1,229 @@
+//! Zome call metrics of the container's instances, see `ZomeCallMetrics`, and their
+//! rendering in the Prometheus text format for the metrics interface.
+use holochain_core::metrics::LatencyHistogram;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+
+/// Upper bounds (in milliseconds) of the buckets of the zome call latency histogram.
+/// Latencies above the last bound end up in an additional overflow bucket.
+pub const CALL_LATENCY_BUCKETS_MS: [u64; 8] = [1, 5, 10, 50, 100, 500, 1000, 5000];
+
+/// Counters of the calls of one zome function
+struct FunctionCallMetrics {
+    calls: AtomicUsize,
+    errors: AtomicUsize,
+    latency: LatencyHistogram,
+}
+
+impl FunctionCallMetrics {
+    fn new() -> Self {
+        FunctionCallMetrics {
+            calls: AtomicUsize::new(0),
+            errors: AtomicUsize::new(0),
+            latency: LatencyHistogram::new(&CALL_LATENCY_BUCKETS_MS),
+        }
+    }
+}
+
+/// Zome call counters of all instances of a container, per instance and zome function,
+/// that `Holochain::call()` records into if the container has them enabled.
+/// The counters are atomic and the map of functions only gets write-locked for the first
+/// call of each function, so recording does not serialize concurrent calls, and reading
+/// them does not need the lock of any instance.
+#[derive(Default)]
+pub struct ZomeCallMetrics {
+    functions: RwLock<HashMap<(String, String), Arc<FunctionCallMetrics>>>,
+}
+
+/// Serializable point-in-time copy of the counters of one zome function.
+/// `latency_ms` holds one count per bucket of CALL_LATENCY_BUCKETS_MS,
+/// followed by the count of the overflow bucket.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FunctionCallMetricsSnapshot {
+    pub calls: usize,
+    pub errors: usize,
+    pub latency_sum_ms: usize,
+    pub latency_buckets_ms: Vec<u64>,
+    pub latency_ms: Vec<usize>,
+}
+
+/// Snapshots of the functions of one instance, keyed by "{zome}/{function}"
+pub type InstanceCallMetricsSnapshot = BTreeMap<String, FunctionCallMetricsSnapshot>;
+
+/// Snapshots of all instances that got called, keyed by instance ID
+pub type ZomeCallMetricsSnapshot = BTreeMap<String, InstanceCallMetricsSnapshot>;
+
+impl ZomeCallMetrics {
+    pub fn new() -> Self {
+        ZomeCallMetrics::default()
+    }
+
+    /// A call of the given function of the given instance took `latency` and failed unless
+    /// `success` is set. Calls that got answered with an error the zome function returned
+    /// itself count as successful, since the call went through.
+    pub fn record_call(
+        &self,
+        instance_id: &str,
+        zome: &str,
+        function: &str,
+        latency: Duration,
+        success: bool,
+    ) {
+        let key = (instance_id.to_string(), format!("{}/{}", zome, function));
+        let maybe_function = self.functions.read().unwrap().get(&key).cloned();
+        let function = maybe_function.unwrap_or_else(|| {
+            self.functions
+                .write()
+                .unwrap()
+                .entry(key)
+                .or_insert_with(|| Arc::new(FunctionCallMetrics::new()))
+                .clone()
+        });
+        function.calls.fetch_add(1, Ordering::Relaxed);
+        if !success {
+            function.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        function.latency.record(latency);
+    }
+
+    /// Forgets the counters of the given instance, e.g. once it got removed
+    pub fn remove_instance(&self, instance_id: &str) {
+        self.functions
+            .write()
+            .unwrap()
+            .retain(|(id, _), _| id != instance_id);
+    }
+
+    pub fn snapshot(&self) -> ZomeCallMetricsSnapshot {
+        let mut snapshot = ZomeCallMetricsSnapshot::new();
+        for ((instance_id, name), function) in self.functions.read().unwrap().iter() {
+            let function_snapshot = FunctionCallMetricsSnapshot {
+                calls: function.calls.load(Ordering::Relaxed),
+                errors: function.errors.load(Ordering::Relaxed),
+                latency_sum_ms: function.latency.sum_ms(),
+                latency_buckets_ms: function.latency.bounds_ms(),
+                latency_ms: function.latency.counts(),
+            };
+            snapshot
+                .entry(instance_id.clone())
+                .or_insert_with(BTreeMap::new)
+                .insert(name.clone(), function_snapshot);
+        }
+        snapshot
+    }
+}
+
+/// Renders the given snapshot as Prometheus metrics
+pub fn render_prometheus(instances: &ZomeCallMetricsSnapshot) -> String {
+    let mut output = String::new();
+    let functions: Vec<(&String, &String, &FunctionCallMetricsSnapshot)> = instances
+        .iter()
+        .flat_map(|(id, snapshot)| {
+            snapshot
+                .iter()
+                .map(move |(function, metrics)| (id, function, metrics))
+        })
+        .collect();
+
+    let _ = writeln!(output, "# TYPE holochain_zome_calls_total counter");
+    for (id, function, metrics) in functions.iter() {
+        let _ = writeln!(
+            output,
+            "holochain_zome_calls_total{{instance=\"{}\",function=\"{}\"}} {}",
+            id, function, metrics.calls
+        );
+    }
+    let _ = writeln!(output, "# TYPE holochain_zome_call_errors_total counter");
+    for (id, function, metrics) in functions.iter() {
+        let _ = writeln!(
+            output,
+            "holochain_zome_call_errors_total{{instance=\"{}\",function=\"{}\"}} {}",
+            id, function, metrics.errors
+        );
+    }
+    let _ = writeln!(output, "# TYPE holochain_zome_call_duration_ms histogram");
+    for (id, function, metrics) in functions.iter() {
+        let labels = format!("instance=\"{}\",function=\"{}\"", id, function);
+        // Prometheus buckets are cumulative
+        let mut count = 0;
+        for (bound, bucket_count) in metrics.latency_buckets_ms.iter().zip(&metrics.latency_ms) {
+            count += bucket_count;
+            let _ = writeln!(
+                output,
+                "holochain_zome_call_duration_ms_bucket{{{},le=\"{}\"}} {}",
+                labels, bound, count
+            );
+        }
+        let _ = writeln!(
+            output,
+            "holochain_zome_call_duration_ms_bucket{{{},le=\"+Inf\"}} {}",
+            labels, metrics.calls
+        );
+        let _ = writeln!(
+            output,
+            "holochain_zome_call_duration_ms_sum{{{}}} {}",
+            labels, metrics.latency_sum_ms
+        );
+        let _ = writeln!(
+            output,
+            "holochain_zome_call_duration_ms_count{{{}}} {}",
+            labels, metrics.calls
+        );
+    }
+    output
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zome_call_metrics() {
+        let metrics = ZomeCallMetrics::new();
+        let record = |instance_id, function, millis, success| {
+            metrics.record_call(
+                instance_id,
+                "greeter",
+                function,
+                Duration::from_millis(millis),
+                success,
+            )
+        };
+        record("instance-1", "hello", 3, true);
+        record("instance-1", "hello", 70, false);
+        record("instance-1", "bye", 10000, true);
+        record("instance-2", "hello", 3, true);
+        metrics.remove_instance("instance-2");
+
+        let snapshot = metrics.snapshot();
+        assert!(!snapshot.contains_key("instance-2"));
+        let instance_snapshot = &snapshot["instance-1"];
+        assert_eq!(
+            instance_snapshot["greeter/hello"],
+            FunctionCallMetricsSnapshot {
+                calls: 2,
+                errors: 1,
+                latency_sum_ms: 73,
+                latency_buckets_ms: CALL_LATENCY_BUCKETS_MS.to_vec(),
+                latency_ms: vec![0, 1, 0, 0, 1, 0, 0, 0, 0],
+            }
+        );
+        assert_eq!(instance_snapshot["greeter/bye"].latency_ms[8], 1);
+
+        let rendered = render_prometheus(&snapshot);
+        assert!(rendered.contains(
+            "holochain_zome_calls_total{instance=\"instance-1\",function=\"greeter/hello\"} 2\n"
+        ));
+        assert!(rendered.contains(
+            "holochain_zome_call_duration_ms_bucket{instance=\"instance-1\",function=\"greeter/hello\",le=\"100\"} 2\n"
+        ));
+        assert!(!rendered.contains("instance-2"));
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{
+    call_queue::CallQueue, container::instance_poisoned_error, error::HolochainInstanceError,
+    Holochain,
+};
+use holochain_core::{context::Context, nucleus::ribosome::api::call::check_zome_function};
+use holochain_core_types::{
+    dna::capabilities::CapabilityCall, error::HolochainError, json::JsonString,
+};
+use std::sync::{Arc, RwLock, Weak};
+
+/// Handle to a zome function of a container instance, as returned by
+/// `Container::prepare_call()`.
+/// The zome function and capability get checked against the DNA when preparing, so that a
+/// wrong target fails right away instead of on the first call. Repeated calls of the same
+/// function then only need to pass their params to `execute()`.
+/// Executing goes through `Holochain::call()` like any other zome call, which validates
+/// the call again, so this is not any faster than `Container::call_instance_function()`.
+/// It also waits for its turn in the instance's call queue the same way.
+///
+/// The handle is bound to the instance it was prepared on. Once that instance gets removed,
+/// reloaded or swapped, executing fails and the call has to be prepared again.
+pub struct PreparedCall {
+    instance_id: String,
+    instance: Weak<RwLock<Holochain>>,
+    call_queue: Option<Arc<CallQueue>>,
+    context: Weak<Context>,
+    zome: String,
+    cap: Option<CapabilityCall>,
+    function: String,
+}
+
+impl PreparedCall {
+    /// Checks that the DNA of the given instance has the given zome function and that it is
+    /// part of the given capability, or of a public one if there is no capability call.
+    /// Capability tokens still get checked on every call.
+    pub(crate) fn new(
+        instance_id: &str,
+        instance: &Arc<RwLock<Holochain>>,
+        call_queue: Option<Arc<CallQueue>>,
+        zome: &str,
+        cap: Option<CapabilityCall>,
+        function: &str,
+    ) -> Result<Self, HolochainInstanceError> {
+        let context = {
+            let hc = instance
+                .read()
+                .map_err(|_| instance_poisoned_error(instance_id))?;
+            let dna = hc.context().get_dna().ok_or(HolochainError::DnaMissing)?;
+            check_zome_function(&dna, zome, &cap, function)?;
+            Arc::downgrade(hc.context())
+        };
+        Ok(PreparedCall {
+            instance_id: instance_id.to_string(),
+            instance: Arc::downgrade(instance),
+            call_queue,
+            context,
+            zome: zome.to_string(),
+            cap,
+            function: function.to_string(),
+        })
+    }
+
+    /// Calls the prepared zome function with the given params and returns its result.
+    pub fn execute(&self, params: &str) -> Result<JsonString, HolochainInstanceError> {
+        let instance = self
+            .instance
+            .upgrade()
+            .ok_or_else(|| self.invalidated_error())?;
+        let _turn = self.call_queue.as_ref().map(|queue| queue.wait_for_turn(0));
+        let mut hc = instance
+            .write()
+            .map_err(|_| instance_poisoned_error(&self.instance_id))?;
+        if !self.is_prepared_on(&hc) {
+            return Err(self.invalidated_error());
+        }
+        hc.call(&self.zome, self.cap.clone(), &self.function, params)
+    }
+
+    /// False once the instance the call was prepared on got removed, reloaded or swapped,
+    /// and while it is stopped.
+    pub fn is_valid(&self) -> bool {
+        self.instance
+            .upgrade()
+            .and_then(|instance| {
+                instance
+                    .read()
+                    .ok()
+                    .map(|hc| hc.active() && self.is_prepared_on(&hc))
+            })
+            .unwrap_or(false)
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// Reloading or swapping replaces the Holochain behind the instance's lock,
+    /// which always comes with a new context.
+    fn is_prepared_on(&self, hc: &Holochain) -> bool {
+        self.context
+            .upgrade()
+            .map(|context| Arc::ptr_eq(&context, hc.context()))
+            .unwrap_or(false)
+    }
+
+    fn invalidated_error(&self) -> HolochainInstanceError {
+        HolochainInstanceError::InternalFailure(HolochainError::ErrorGeneric(format!(
+            "Prepared call {}/{} is no longer valid since instance {} was removed or reloaded",
+            self.zome, self.function, self.instance_id
+        )))
+    }
+}
@@ -0,0 +1,182 @@
+use config::RemoteBridge;
+use interface::ZomeFunctionInfo;
+use jsonrpc_http_server::hyper::{
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    rt::{self, Future, Stream},
+    Body, Client, Request,
+};
+use jsonrpc_ws_server::jsonrpc_core::{self, Output, Value};
+use serde_json;
+use std::{sync::mpsc::channel, thread, time::Duration};
+
+/// How long a remote bridge waits for the callee's container to answer.
+const REMOTE_CALL_TIMEOUT_MS: u64 = 10_000;
+
+/// Asks the callee's container which zome functions the callee instance exposes,
+/// using its "info/functions" method.
+pub fn remote_functions(bridge: &RemoteBridge) -> Result<Vec<ZomeFunctionInfo>, String> {
+    let result = call(
+        bridge,
+        "info/functions",
+        json!({ "instance_id": bridge.callee_instance }),
+    )
+    .map_err(|e| {
+        format!(
+            "Could not get zome functions of remote bridge callee \"{}\" at {}: {}",
+            bridge.callee_instance, bridge.callee_url, e.message
+        )
+    })?;
+    let functions_string = result
+        .as_str()
+        .ok_or_else(|| format!("Unexpected \"info/functions\" result: {}", result))?;
+    serde_json::from_str(functions_string).map_err(|e| e.to_string())
+}
+
+/// Calls the given method on the callee's container and returns the result,
+/// or the JSON-RPC error the callee's container responded with.
+pub fn call(
+    bridge: &RemoteBridge,
+    method: &str,
+    params: Value,
+) -> Result<Value, jsonrpc_core::Error> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": params,
+        "id": 1,
+    });
+    let response =
+        post(&bridge.callee_url, &bridge.auth_token, request.to_string()).map_err(|e| {
+            jsonrpc_core::Error::invalid_params(format!(
+                "Remote bridge call to {} failed: {}",
+                bridge.callee_url, e
+            ))
+        })?;
+    match serde_json::from_str::<Output>(&response) {
+        Ok(Output::Success(success)) => Ok(success.result),
+        Ok(Output::Failure(failure)) => Err(failure.error),
+        Err(_) => Err(jsonrpc_core::Error::invalid_params(format!(
+            "Invalid response from remote bridge callee: {}",
+            response
+        ))),
+    }
+}
+
+/// Sends an HTTP POST request with the given JSON body and returns the response body.
+///
+/// The caller's zome call keeps its instance locked while waiting, so the request gets
+/// abandoned after `REMOTE_CALL_TIMEOUT_MS`.
+fn post(url: &str, auth_token: &Option<String>, body: String) -> Result<String, String> {
+    let mut builder = Request::post(url);
+    builder.header(CONTENT_TYPE, "application/json");
+    if let Some(ref token) = *auth_token {
+        builder.header(AUTHORIZATION, format!("Bearer {}", token).as_str());
+    }
+    let request = builder.body(Body::from(body)).map_err(|e| e.to_string())?;
+
+    let (response_tx, response_rx) = channel();
+    let response = rt::lazy(move || {
+        Client::builder()
+            .keep_alive(false)
+            .build_http()
+            .request(request)
+            .and_then(|response| {
+                let status = response.status();
+                response
+                    .into_body()
+                    .concat2()
+                    .map(move |body| (status, body))
+            })
+            .then(move |result| -> Result<(), ()> {
+                let _ = response_tx.send(result.map_err(|e| e.to_string()));
+                Ok(())
+            })
+    });
+    thread::spawn(move || rt::run(response));
+
+    let (status, body) = response_rx
+        .recv_timeout(Duration::from_millis(REMOTE_CALL_TIMEOUT_MS))
+        .map_err(|_| String::from("Timed out waiting for the response"))??;
+    if !status.is_success() {
+        return Err(format!("HTTP error: {}", status));
+    }
+    String::from_utf8(body.to_vec()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use container::tests::test_container;
+    use interface::{with_auth, ContainerApiBuilder, Interface};
+    use interface_impls::http::HttpInterface;
+    use std::net::TcpListener;
+
+    fn remote_bridge(port: u16, auth_token: &str) -> RemoteBridge {
+        RemoteBridge {
+            caller_id: String::from("caller"),
+            callee_url: format!("http://127.0.0.1:{}", port),
+            callee_instance: String::from("test-instance-1"),
+            handle: String::from("remote-callee"),
+            auth_token: Some(String::from(auth_token)),
+        }
+    }
+
+    /// A port nothing listens on right now
+    fn ephemeral_port() -> u16 {
+        TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    #[test]
+    fn test_remote_bridge_call() {
+        let port = ephemeral_port();
+        let container = test_container();
+        container.instances()["test-instance-1"]
+            .write()
+            .unwrap()
+            .start()
+            .unwrap();
+        let handler = ContainerApiBuilder::new()
+            .with_instances(container.instances().clone())
+            .with_instance_configs(container.config().instances)
+            .spawn();
+        let mut interface = HttpInterface::new(port, Some(String::from("secret")));
+        interface.start(with_auth(handler, false)).unwrap();
+        let (kill_switch, kill_switch_rx) = channel();
+        thread::spawn(move || interface.run(kill_switch_rx).unwrap());
+
+        let bridge = remote_bridge(port, "secret");
+        let mut functions = remote_functions(&bridge);
+        for _ in 0..50 {
+            if functions.is_ok() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+            functions = remote_functions(&bridge);
+        }
+        let functions = functions.unwrap();
+        assert!(functions.contains(&ZomeFunctionInfo {
+            zome: String::from("greeter"),
+            capability: String::from("public"),
+            function: String::from("hello"),
+        }));
+
+        let api = ContainerApiBuilder::new()
+            .with_remote_bridge(bridge, functions)
+            .spawn();
+        let request = r#"{"jsonrpc": "2.0", "method": "remote-callee/greeter/public/hello", "params": {}, "id": 1}"#;
+        let response = api.handle_request_sync(request).unwrap();
+        assert_eq!(
+            response,
+            r#"{"jsonrpc":"2.0","result":"Holo World","id":1}"#
+        );
+
+        let error = remote_functions(&remote_bridge(port, "wrong")).unwrap_err();
+        assert!(error.contains("Unauthorized"));
+
+        kill_switch.send(()).unwrap();
+    }
+}
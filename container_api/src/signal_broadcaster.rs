@@ -0,0 +1,469 @@
+use holochain_core::signal::{signal_channel, Signal, SignalReceiver, SignalSender};
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{
+        mpsc::{channel, Sender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+pub type SubscriptionId = u64;
+
+/// How signals get sent to the container's signal channel, which is bounded.
+/// Instances hand their signals to the broadcaster from the thread that reduces their
+/// actions, so as soon as sending blocks, the instances stall.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SignalDelivery {
+    /// Signals that don't fit into the channel get dropped and counted, see
+    /// `SignalBroadcaster::dropped_signals()`. Instances never wait for the consumer.
+    Drop,
+    /// Signals that don't fit into the channel get queued without limit, so none get lost
+    /// and instances never wait, but a consumer that doesn't keep up makes memory grow.
+    Unbounded,
+    /// Sending waits for the consumer, so no signal gets lost but a slow consumer stalls
+    /// all instances.
+    Block,
+}
+
+impl Default for SignalDelivery {
+    fn default() -> SignalDelivery {
+        SignalDelivery::Drop
+    }
+}
+
+/// The container's signal channel as set up for its SignalDelivery
+enum External {
+    Direct(SignalSender),
+    /// Feeds a thread that forwards to the container's signal channel
+    Queued(Sender<Signal>),
+}
+
+/// Decides which signals get passed on to the container's signal channel
+pub type SignalFilter = Box<Fn(&Signal) -> bool + Send>;
+
+/// Fans out the signals of all instances of a container to the container's signal
+/// channel (see `Container::with_signal_channel()`) and to all subscribers.
+/// Keeps the last `history_size` signals so that new subscribers first get those
+/// replayed before receiving live signals.
+pub struct SignalBroadcaster {
+    history: VecDeque<(Option<String>, Signal)>,
+    history_size: usize,
+    subscribers: Vec<Subscriber>,
+    next_subscription_id: SubscriptionId,
+    external: Option<External>,
+    external_filter: Option<SignalFilter>,
+    delivery: SignalDelivery,
+    dropped_signals: usize,
+}
+
+struct Subscriber {
+    id: SubscriptionId,
+    /// Only signals of these instances get passed on, all signals if None
+    instance_ids: Option<HashSet<String>>,
+    /// Only signals this accepts get passed on, all signals if None
+    filter: Option<SignalFilter>,
+    signal_tx: SignalSender,
+}
+
+impl Subscriber {
+    fn wants(&self, instance_id: &Option<String>, signal: &Signal) -> bool {
+        let wanted_instance = match (&self.instance_ids, instance_id) {
+            (None, _) => true,
+            (Some(instance_ids), Some(instance_id)) => instance_ids.contains(instance_id),
+            (Some(_), None) => false,
+        };
+        let wanted_signal = match self.filter {
+            Some(ref filter) => filter(signal),
+            None => true,
+        };
+        wanted_instance && wanted_signal
+    }
+}
+
+impl SignalBroadcaster {
+    pub fn new(history_size: usize) -> Self {
+        SignalBroadcaster {
+            history: VecDeque::with_capacity(history_size),
+            history_size,
+            subscribers: Vec::new(),
+            next_subscription_id: 0,
+            external: None,
+            external_filter: None,
+            delivery: SignalDelivery::default(),
+            dropped_signals: 0,
+        }
+    }
+
+    /// Sets how signals get sent to the external channel set afterwards
+    pub fn with_delivery(mut self, delivery: SignalDelivery) -> Self {
+        self.delivery = delivery;
+        self
+    }
+
+    pub fn set_external(&mut self, signal_tx: SignalSender) {
+        self.external = Some(self.external_for(signal_tx));
+        self.external_filter = None;
+    }
+
+    /// Like `set_external()` but only passes on the signals the given filter accepts.
+    /// Other signals are dropped before being cloned.
+    pub fn set_filtered_external(&mut self, signal_tx: SignalSender, filter: SignalFilter) {
+        self.external = Some(self.external_for(signal_tx));
+        self.external_filter = Some(filter);
+    }
+
+    fn external_for(&self, signal_tx: SignalSender) -> External {
+        match self.delivery {
+            SignalDelivery::Drop | SignalDelivery::Block => External::Direct(signal_tx),
+            SignalDelivery::Unbounded => {
+                let (queue_tx, queue_rx) = channel();
+                thread::spawn(move || {
+                    while let Ok(signal) = queue_rx.recv() {
+                        if signal_tx.send(signal).is_err() {
+                            break;
+                        }
+                    }
+                });
+                External::Queued(queue_tx)
+            }
+        }
+    }
+
+    /// Whether any signal would get passed on or kept, i.e. whether there is an external
+    /// channel, a subscriber or a history.
+    pub fn has_receivers(&self) -> bool {
+        self.external.is_some() || !self.subscribers.is_empty() || self.history_size > 0
+    }
+
+    /// Number of signals the external channel missed because its consumer didn't keep up,
+    /// which only happens with SignalDelivery::Drop.
+    pub fn dropped_signals(&self) -> usize {
+        self.dropped_signals
+    }
+
+    /// Returns a receiver that first gets the retained signals and then all new ones.
+    pub fn subscribe(&mut self) -> SignalReceiver {
+        self.add_subscriber(None, None).1
+    }
+
+    /// Like `subscribe()` but only for the signals of the given instance.
+    /// The subscription ends once the receiver is dropped or `unsubscribe()` gets called.
+    pub fn subscribe_instance(&mut self, instance_id: String) -> (SubscriptionId, SignalReceiver) {
+        let instance_ids = vec![instance_id].into_iter().collect();
+        self.add_subscriber(Some(instance_ids), None)
+    }
+
+    /// Like `subscribe()` but only for the signals of the given instances that the given
+    /// filter accepts, so that signals the subscriber doesn't care about can't fill up
+    /// its channel and make it miss the ones it waits for.
+    pub fn subscribe_filtered(
+        &mut self,
+        instance_ids: HashSet<String>,
+        filter: SignalFilter,
+    ) -> SignalReceiver {
+        self.add_subscriber(Some(instance_ids), Some(filter)).1
+    }
+
+    /// Drops the sender of the given subscription so that its receiver hangs up.
+    /// Returns false if there is no such subscription.
+    pub fn unsubscribe(&mut self, subscription_id: SubscriptionId) -> bool {
+        let count = self.subscribers.len();
+        self.subscribers
+            .retain(|subscriber| subscriber.id != subscription_id);
+        self.subscribers.len() < count
+    }
+
+    fn add_subscriber(
+        &mut self,
+        instance_ids: Option<HashSet<String>>,
+        filter: Option<SignalFilter>,
+    ) -> (SubscriptionId, SignalReceiver) {
+        let (signal_tx, signal_rx) = signal_channel();
+        let subscriber = Subscriber {
+            id: self.next_subscription_id,
+            instance_ids,
+            filter,
+            signal_tx,
+        };
+        self.next_subscription_id += 1;
+        for (signal_instance_id, signal) in self.history.iter() {
+            if subscriber.wants(signal_instance_id, signal) {
+                let _ = subscriber.signal_tx.try_send(signal.clone());
+            }
+        }
+        let id = subscriber.id;
+        self.subscribers.push(subscriber);
+        (id, signal_rx)
+    }
+
+    /// Passes on a signal that does not belong to a specific instance
+    pub fn broadcast(&mut self, signal: Signal) -> Option<PendingSignal> {
+        self.broadcast_from(None, signal)
+    }
+
+    /// Passes on a signal of the given instance.
+    /// With SignalDelivery::Block, the signal for the external channel is returned instead
+    /// of sent, since sending waits for the consumer and must not happen while holding the
+    /// broadcaster's lock, see `broadcast_through()`.
+    pub fn broadcast_from(
+        &mut self,
+        instance_id: Option<String>,
+        signal: Signal,
+    ) -> Option<PendingSignal> {
+        let mut pending = None;
+        if let Some(ref external) = self.external {
+            let accepted = match self.external_filter {
+                Some(ref filter) => filter(&signal),
+                None => true,
+            };
+            if accepted {
+                match (external, self.delivery) {
+                    (External::Queued(queue_tx), _) => {
+                        let _ = queue_tx.send(signal.clone());
+                    }
+                    (External::Direct(signal_tx), SignalDelivery::Block) => {
+                        pending = Some(PendingSignal {
+                            signal_tx: signal_tx.clone(),
+                            signal: signal.clone(),
+                        });
+                    }
+                    (External::Direct(signal_tx), _) => {
+                        if let Err(TrySendError::Full(_)) = signal_tx.try_send(signal.clone()) {
+                            self.dropped_signals += 1;
+                        }
+                    }
+                }
+            }
+        }
+        // Subscribers that can't keep up miss signals instead of blocking all others.
+        // Subscribers that hung up get removed.
+        self.subscribers.retain(|subscriber| {
+            !subscriber.wants(&instance_id, &signal)
+                || match subscriber.signal_tx.try_send(signal.clone()) {
+                    Err(TrySendError::Disconnected(_)) => false,
+                    _ => true,
+                }
+        });
+        if self.history_size > 0 {
+            if self.history.len() == self.history_size {
+                self.history.pop_front();
+            }
+            self.history.push_back((instance_id, signal));
+        }
+        pending
+    }
+}
+
+/// A signal for the external channel that still has to be sent, waiting for the consumer
+#[must_use]
+pub struct PendingSignal {
+    signal_tx: SignalSender,
+    signal: Signal,
+}
+
+impl PendingSignal {
+    pub fn send(self) {
+        let _ = self.signal_tx.send(self.signal);
+    }
+}
+
+/// Passes on a signal through the broadcaster behind the given lock.
+/// Waiting for the consumer of the external channel only happens after releasing the lock,
+/// so that it doesn't hold up subscribers and the signals of other instances.
+pub fn broadcast_through(
+    broadcaster: &Mutex<SignalBroadcaster>,
+    instance_id: Option<String>,
+    signal: Signal,
+) {
+    let pending = broadcaster
+        .lock()
+        .unwrap()
+        .broadcast_from(instance_id, signal);
+    if let Some(pending) = pending {
+        pending.send();
+    }
+}
+
+/// Spawns a thread that hands every signal sent to the returned sender to the broadcaster
+/// as a signal of the given instance.
+/// The thread ends once all clones of the sender are dropped.
+pub fn spawn_signal_relay(
+    broadcaster: Arc<Mutex<SignalBroadcaster>>,
+    instance_id: String,
+) -> SignalSender {
+    let (signal_tx, signal_rx) = signal_channel();
+    thread::spawn(move || {
+        while let Ok(signal) = signal_rx.recv() {
+            broadcast_through(&broadcaster, Some(instance_id.clone()), signal);
+        }
+    });
+    signal_tx
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::{sync::mpsc::sync_channel, time::Duration};
+
+    fn received_count(signal_rx: &SignalReceiver) -> usize {
+        signal_rx.try_iter().count()
+    }
+
+    #[test]
+    fn replays_bounded_history_to_new_subscribers() {
+        let mut broadcaster = SignalBroadcaster::new(2);
+        let early_rx = broadcaster.subscribe();
+        for _ in 0..3 {
+            broadcaster.broadcast(Signal::User);
+        }
+        assert_eq!(received_count(&early_rx), 3);
+
+        let late_rx = broadcaster.subscribe();
+        assert_eq!(received_count(&late_rx), 2);
+
+        broadcaster.broadcast(Signal::User);
+        assert_eq!(received_count(&early_rx), 1);
+        assert_eq!(received_count(&late_rx), 1);
+    }
+
+    #[test]
+    fn instance_subscribers_only_get_their_instance_signals() {
+        let mut broadcaster = SignalBroadcaster::new(2);
+        broadcaster.broadcast_from(Some("app1".to_string()), Signal::User);
+        broadcaster.broadcast_from(Some("app2".to_string()), Signal::User);
+
+        let (subscription_id, app1_rx) = broadcaster.subscribe_instance("app1".to_string());
+        assert_eq!(received_count(&app1_rx), 1);
+
+        broadcaster.broadcast_from(Some("app1".to_string()), Signal::User);
+        broadcaster.broadcast_from(Some("app2".to_string()), Signal::User);
+        broadcaster.broadcast(Signal::User);
+        assert_eq!(received_count(&app1_rx), 1);
+
+        assert!(broadcaster.unsubscribe(subscription_id));
+        assert!(!broadcaster.unsubscribe(subscription_id));
+        assert!(app1_rx.recv().is_err());
+    }
+
+    #[test]
+    fn filtered_subscribers_only_get_accepted_signals_of_their_instances() {
+        let mut broadcaster = SignalBroadcaster::new(2);
+        broadcaster.broadcast_from(Some("app1".to_string()), Signal::User);
+        broadcaster.broadcast_from(
+            Some("app2".to_string()),
+            Signal::InstanceStarted("app2".to_string()),
+        );
+
+        let filtered_rx = broadcaster.subscribe_filtered(
+            vec!["app2".to_string(), "app3".to_string()]
+                .into_iter()
+                .collect(),
+            Box::new(|signal| match signal {
+                Signal::InstanceStarted(_) => true,
+                _ => false,
+            }),
+        );
+        assert_eq!(received_count(&filtered_rx), 1);
+
+        broadcaster.broadcast_from(
+            Some("app1".to_string()),
+            Signal::InstanceStarted("app1".to_string()),
+        );
+        broadcaster.broadcast_from(Some("app3".to_string()), Signal::User);
+        broadcaster.broadcast_from(
+            Some("app3".to_string()),
+            Signal::InstanceStarted("app3".to_string()),
+        );
+        broadcaster.broadcast(Signal::InstanceStarted("app4".to_string()));
+        assert_eq!(received_count(&filtered_rx), 1);
+    }
+
+    #[test]
+    fn no_history_without_history_size() {
+        let (external_tx, external_rx) = signal_channel();
+        let mut broadcaster = SignalBroadcaster::new(0);
+        broadcaster.set_external(external_tx);
+        broadcaster.broadcast(Signal::User);
+        assert_eq!(received_count(&external_rx), 1);
+        assert_eq!(received_count(&broadcaster.subscribe()), 0);
+    }
+
+    #[test]
+    fn dropping_delivery_counts_what_the_external_channel_misses() {
+        let (external_tx, external_rx) = sync_channel(1);
+        let mut broadcaster = SignalBroadcaster::new(0);
+        broadcaster.set_external(external_tx);
+        for _ in 0..3 {
+            broadcaster.broadcast(Signal::User);
+        }
+        assert_eq!(broadcaster.dropped_signals(), 2);
+        assert_eq!(received_count(&external_rx), 1);
+    }
+
+    #[test]
+    fn unbounded_delivery_queues_what_does_not_fit() {
+        let (external_tx, external_rx) = sync_channel(1);
+        let mut broadcaster = SignalBroadcaster::new(0).with_delivery(SignalDelivery::Unbounded);
+        broadcaster.set_external(external_tx);
+        for _ in 0..3 {
+            broadcaster.broadcast(Signal::User);
+        }
+        assert_eq!(broadcaster.dropped_signals(), 0);
+        for _ in 0..3 {
+            assert!(external_rx.recv_timeout(Duration::from_secs(1)).is_ok());
+        }
+    }
+
+    #[test]
+    fn filtered_external_only_gets_accepted_signals() {
+        let (external_tx, external_rx) = signal_channel();
+        let mut broadcaster = SignalBroadcaster::new(0);
+        broadcaster.set_filtered_external(
+            external_tx,
+            Box::new(|signal| match signal {
+                Signal::InstanceStarted(_) => true,
+                _ => false,
+            }),
+        );
+        let subscriber_rx = broadcaster.subscribe();
+        broadcaster.broadcast(Signal::User);
+        broadcaster.broadcast(Signal::InstanceStarted("app1".to_string()));
+        broadcaster.broadcast(Signal::User);
+
+        assert_eq!(received_count(&external_rx), 1);
+        assert_eq!(received_count(&subscriber_rx), 3);
+    }
+
+    #[test]
+    fn blocking_delivery_waits_without_holding_the_lock() {
+        let (external_tx, external_rx) = sync_channel(1);
+        let broadcaster = Arc::new(Mutex::new(
+            SignalBroadcaster::new(0).with_delivery(SignalDelivery::Block),
+        ));
+        broadcaster.lock().unwrap().set_external(external_tx);
+        broadcast_through(&broadcaster, None, Signal::User);
+
+        // The external channel is full, so the second signal waits for the consumer
+        let blocked_broadcaster = broadcaster.clone();
+        let blocked =
+            thread::spawn(move || broadcast_through(&blocked_broadcaster, None, Signal::User));
+        thread::sleep(Duration::from_millis(100));
+        let subscriber_rx = broadcaster.try_lock().unwrap().subscribe();
+        assert_eq!(received_count(&subscriber_rx), 0);
+
+        assert!(external_rx.recv_timeout(Duration::from_secs(1)).is_ok());
+        blocked.join().unwrap();
+        assert!(external_rx.recv_timeout(Duration::from_secs(1)).is_ok());
+    }
+
+    #[test]
+    fn has_receivers() {
+        let mut broadcaster = SignalBroadcaster::new(0);
+        assert!(!broadcaster.has_receivers());
+        let _subscriber_rx = broadcaster.subscribe();
+        assert!(broadcaster.has_receivers());
+        assert!(SignalBroadcaster::new(1).has_receivers());
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{error::HolochainInstanceError, Holochain};
+use holochain_core_types::{cas::content::Address, crud_status::CrudStatus};
+
+/// Differences between the local DHT shards of two instances, as reported by
+/// `Container::diff_instances()`.
+/// Meant for debugging DHT consistency problems between instances that should be in sync.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct StateDiff {
+    /// Entries only held by the first instance
+    pub only_in_a: Vec<Address>,
+    /// Entries only held by the second instance
+    pub only_in_b: Vec<Address>,
+    /// Entries held by both instances but with a different CRUD status,
+    /// given as (address, status in a, status in b)
+    pub crud_status_mismatches: Vec<(Address, CrudStatus, CrudStatus)>,
+}
+
+impl StateDiff {
+    /// Compares the entries held in the local DHT shards of the two given instances.
+    pub fn between(a: &Holochain, b: &Holochain) -> Result<Self, HolochainInstanceError> {
+        let statuses_a = a.state()?.dht().crud_statuses()?;
+        let statuses_b = b.state()?.dht().crud_statuses()?;
+
+        let mut diff = StateDiff::default();
+        for (address, status_a) in statuses_a.iter() {
+            match statuses_b.get(address) {
+                None => diff.only_in_a.push(address.clone()),
+                Some(status_b) if status_b != status_a => {
+                    diff.crud_status_mismatches
+                        .push((address.clone(), *status_a, *status_b))
+                }
+                Some(_) => (),
+            }
+        }
+        diff.only_in_b = statuses_b
+            .keys()
+            .filter(|address| !statuses_a.contains_key(address))
+            .cloned()
+            .collect();
+        Ok(diff)
+    }
+
+    /// True if both shards hold the same entries with the same CRUD status
+    pub fn is_empty(&self) -> bool {
+        self.only_in_a.is_empty()
+            && self.only_in_b.is_empty()
+            && self.crud_status_mismatches.is_empty()
+    }
+}
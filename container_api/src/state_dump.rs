@@ -0,0 +1,84 @@
+use crate::{error::HolochainInstanceError, Holochain};
+use holochain_core::state::State;
+use holochain_core_types::{
+    chain_header::ChainHeader, entry::Entry, error::HolochainError, json::JsonString,
+};
+use serde_json;
+use std::io::Write;
+
+/// Writes the source chain of the given instance as JSON to the given writer, as
+/// `{"chain":[{"header":<ChainHeader>,"entry":<entry content or null>},...]}`
+/// with the most recent header first.
+///
+/// Works on a snapshot of the instance's state, so the caller only needs to hold a
+/// read lock on the instance to get a consistent dump.
+/// Headers and entries are written one after another instead of building the whole
+/// dump as one value first.
+pub fn dump_source_chain<W: Write>(
+    holochain: &Holochain,
+    writer: W,
+) -> Result<(), HolochainInstanceError> {
+    dump_source_chain_page(&holochain.state()?, 0, None, writer)
+}
+
+/// Like `dump_source_chain()`, but only writes the headers from the `offset`th most recent
+/// one on, at most `limit` of them. Takes a snapshot of the instance's state, so no lock on
+/// the instance needs to be held while writing.
+pub fn dump_source_chain_page<W: Write>(
+    state: &State,
+    offset: usize,
+    limit: Option<usize>,
+    mut writer: W,
+) -> Result<(), HolochainInstanceError> {
+    let agent = state.agent();
+    let chain = agent.chain();
+    let content_storage = chain.content_storage();
+    let content_storage = content_storage.read().map_err(HolochainError::from)?;
+
+    write!(writer, "{{\"chain\":[").map_err(HolochainError::from)?;
+    let headers = chain
+        .iter(&agent.top_chain_header())
+        .skip(offset)
+        .take(limit.unwrap_or(usize::max_value()));
+    for (index, header) in headers.enumerate() {
+        if index > 0 {
+            write!(writer, ",").map_err(HolochainError::from)?;
+        }
+        let entry = content_storage
+            .fetch(header.entry_address())?
+            .unwrap_or_else(JsonString::null);
+        write!(writer, "{{\"header\":").map_err(HolochainError::from)?;
+        serde_json::to_writer(&mut writer, &header).map_err(HolochainError::from)?;
+        write!(writer, ",\"entry\":{}}}", String::from(entry)).map_err(HolochainError::from)?;
+    }
+    write!(writer, "]}}").map_err(HolochainError::from)?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct SourceChainDump {
+    chain: Vec<SourceChainDumpItem>,
+}
+
+#[derive(Deserialize)]
+struct SourceChainDumpItem {
+    header: ChainHeader,
+    entry: Option<Entry>,
+}
+
+/// Reads a source chain written by `dump_source_chain()` back into its headers and entries,
+/// oldest first. Fails if an entry is missing, since such a chain can't be imported.
+pub fn read_source_chain_dump(dump: &str) -> Result<Vec<(ChainHeader, Entry)>, HolochainError> {
+    let dump: SourceChainDump = serde_json::from_str(dump).map_err(HolochainError::from)?;
+    dump.chain
+        .into_iter()
+        .rev()
+        .map(|item| match item.entry {
+            Some(entry) => Ok((item.header, entry)),
+            None => Err(HolochainError::ErrorGeneric(format!(
+                "Source chain dump is missing the entry {}",
+                item.header.entry_address()
+            ))),
+        })
+        .collect()
+}
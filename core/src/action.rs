@@ -87,6 +87,10 @@ pub enum Action {
     /// Does not validate, assumes entry is valid.
     Commit((Entry, Option<Address>)),
 
+    /// Replaces the source chain with the given headers and entries, oldest first.
+    /// Does not validate, assumes the chain is valid.
+    ImportChain(Vec<(ChainHeader, Entry)>),
+
     // -------------
     // DHT actions:
     // -------------
@@ -94,9 +98,10 @@ pub enum Action {
     /// Does not validate, assumes entry is valid.
     Hold(Entry),
 
-    /// Adds a link to the local DHT shard's meta/EAV storage
+    /// Adds a link to the local DHT shard's meta/EAV storage, together with the time it got
+    /// added in milliseconds since the Unix epoch, see DhtStore::link_added_at()
     /// Does not validate, assumes link is valid.
-    AddLink(Link),
+    AddLink((Link, u64)),
 
     // ----------------
     // Network actions:
@@ -130,10 +135,12 @@ pub enum Action {
     ///
     GetEntryTimeout(Address),
 
-    /// get links from entry address and tag name
+    /// get links from entry address and meta attribute, see links_attribute() and
+    /// links_page_attribute()
     GetLinks((Address, String)),
     GetLinksTimeout((Address, String)),
     RespondGetLinks((GetDhtMetaData, Vec<Address>)),
+    /// the meta data the network returned for the request with the given attribute
     HandleGetLinksResult((DhtMetaData, String)),
 
     /// Makes the network module send a direct (node-to-node) message
@@ -188,6 +195,10 @@ pub enum Action {
     /// and the hash of the entry that was validated
     ReturnValidationResult(((snowflake::ProcessUniqueId, Address), ValidationResult)),
 
+    /// Removes the validation result with the given key, if any,
+    /// once nobody is waiting for it anymore
+    RemoveValidationResult((snowflake::ProcessUniqueId, Address)),
+
     /// A validation package was created locally and is reported back
     /// to be added to the state
     ReturnValidationPackage(
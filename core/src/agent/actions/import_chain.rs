@@ -0,0 +1,63 @@
+extern crate futures;
+use crate::{
+    action::{Action, ActionWrapper},
+    agent::state::ActionResponse,
+    context::Context,
+    instance::dispatch_action,
+};
+use futures::{
+    future::Future,
+    task::{LocalWaker, Poll},
+};
+use holochain_core_types::{
+    cas::content::Address, chain_header::ChainHeader, entry::Entry, error::HolochainError,
+};
+use std::{pin::Pin, sync::Arc};
+
+/// ImportChain Action Creator
+/// Replaces the source chain with the given headers and entries, oldest first.
+/// Does not validate anything, see import_chain_workflow() for that.
+///
+/// Returns a future that resolves to the address of the new top chain header.
+pub async fn import_chain(
+    chain: Vec<(ChainHeader, Entry)>,
+    context: &Arc<Context>,
+) -> Result<Address, HolochainError> {
+    let action_wrapper = ActionWrapper::new(Action::ImportChain(chain));
+    dispatch_action(context.action_channel(), action_wrapper.clone());
+    await!(ImportChainFuture {
+        context: context.clone(),
+        action: action_wrapper,
+    })
+}
+
+/// ImportChainFuture resolves to the address of the new top chain header
+/// Tracks the state for a response to its ActionWrapper
+pub struct ImportChainFuture {
+    context: Arc<Context>,
+    action: ActionWrapper,
+}
+
+impl Future for ImportChainFuture {
+    type Output = Result<Address, HolochainError>;
+
+    fn poll(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        //
+        // TODO: connect the waker to state updates for performance reasons
+        // See: https://github.com/holochain/holochain-rust/issues/314
+        //
+        lw.wake();
+        match self
+            .context
+            .state()
+            .unwrap()
+            .agent()
+            .actions()
+            .get(&self.action)
+        {
+            Some(ActionResponse::ImportChain(result)) => Poll::Ready(result.clone()),
+            Some(_) => unreachable!(),
+            None => Poll::Pending,
+        }
+    }
+}
@@ -1,2 +1,3 @@
 pub mod commit;
+pub mod import_chain;
 pub mod update_entry;
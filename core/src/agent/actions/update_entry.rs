@@ -35,6 +35,8 @@ pub fn update_entry(
 
 /// RemoveEntryFuture resolves to ActionResponse
 /// Tracks the state for a response to its ActionWrapper
+/// and invalidates the old revision in the entry cache once the action got reduced,
+/// since a cached old revision would hide the new one.
 pub struct UpdateEntryFuture {
     context: Arc<Context>,
     action: ActionWrapper,
@@ -50,11 +52,15 @@ impl Future for UpdateEntryFuture {
         //
         lw.wake();
         if let Some(state) = self.context.state() {
-            match state.dht().actions().get(&self.action) {
-                Some(Ok(address)) => Poll::Ready(Ok(address.clone())),
-                Some(Err(e)) => Poll::Ready(Err(e.clone())),
-                None => Poll::Pending,
+            let result = match state.dht().actions().get(&self.action) {
+                Some(result) => result.clone(),
+                None => return Poll::Pending,
+            };
+            if let Some(ref entry_cache) = self.context.entry_cache {
+                let (old_address, _) = unwrap_to!(self.action.action() => Action::UpdateEntry);
+                entry_cache.invalidate(old_address);
             }
+            Poll::Ready(result)
         } else {
             Poll::Pending
         }
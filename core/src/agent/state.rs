@@ -7,7 +7,10 @@ use crate::{
 };
 use holochain_core_types::{
     agent::AgentId,
-    cas::content::{Address, AddressableContent, Content},
+    cas::{
+        content::{Address, AddressableContent, Content},
+        storage::ContentAddressableStorage,
+    },
     chain_header::ChainHeader,
     entry::{entry_type::EntryType, Entry},
     error::{HcResult, HolochainError},
@@ -17,7 +20,11 @@ use holochain_core_types::{
 };
 use holochain_wasm_utils::api_serialization::get_entry::*;
 use serde_json;
-use std::{collections::HashMap, convert::TryFrom, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    sync::Arc,
+};
 
 /// The state-slice for the Agent.
 /// Holds the agent's source chain and keys.
@@ -151,6 +158,7 @@ impl AddressableContent for AgentStateSnapshot {
 // @see https://github.com/holochain/holochain-rust/issues/196
 pub enum ActionResponse {
     Commit(Result<Address, HolochainError>),
+    ImportChain(Result<Address, HolochainError>),
     GetEntry(Option<Entry>),
     GetLinks(Result<Vec<Address>, HolochainError>),
     LinkEntries(Result<Entry, HolochainError>),
@@ -232,10 +240,100 @@ fn reduce_commit_entry(
         .insert(action_wrapper.clone(), ActionResponse::Commit(result));
 }
 
+/// Do an ImportChain Action against an agent state.
+/// Adds all headers and entries to the chain's storage and only then moves the top of the
+/// chain to the last imported header, so a failed import leaves the chain as it was and
+/// removes whatever it added to the storage.
+/// Headers of the replaced chain that are not part of the imported one get removed as well,
+/// their entries stay since they are held in the DHT.
+/// Responds with the address of the new top header.
+fn reduce_import_chain(
+    context: Arc<Context>,
+    state: &mut AgentState,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let chain = unwrap_to!(action => Action::ImportChain);
+
+    fn add_new(
+        storage: &mut dyn ContentAddressableStorage,
+        content: &dyn AddressableContent,
+        added: &mut Vec<Address>,
+    ) -> Result<(), HolochainError> {
+        let address = content.address();
+        if !storage.contains(&address)? {
+            storage.add(content)?;
+            added.push(address);
+        }
+        Ok(())
+    }
+
+    fn response(
+        state: &mut AgentState,
+        chain: &[(ChainHeader, Entry)],
+    ) -> Result<ChainHeader, HolochainError> {
+        let top_chain_header = chain
+            .last()
+            .map(|(chain_header, _)| chain_header.clone())
+            .ok_or_else(|| HolochainError::ErrorGeneric("Can not import an empty chain".into()))?;
+        let storage = &state.chain.content_storage().clone();
+        let mut added = Vec::new();
+        {
+            let mut storage = storage.write().unwrap();
+            for (chain_header, entry) in chain {
+                let result = add_new(&mut *storage, entry, &mut added)
+                    .and_then(|_| add_new(&mut *storage, chain_header, &mut added));
+                if let Err(error) = result {
+                    // the error that made the import fail is the one to report
+                    for address in added {
+                        let _ = storage.remove(&address);
+                    }
+                    return Err(error);
+                }
+            }
+        }
+
+        let imported_headers = chain
+            .iter()
+            .map(|(chain_header, _)| chain_header.address())
+            .collect::<HashSet<_>>();
+        let replaced_headers = state
+            .chain
+            .iter(&state.top_chain_header)
+            .map(|chain_header| chain_header.address())
+            .filter(|address| !imported_headers.contains(address))
+            .collect::<Vec<_>>();
+        let mut storage = storage.write().unwrap();
+        for address in replaced_headers {
+            storage.remove(&address)?;
+        }
+        Ok(top_chain_header)
+    }
+    let result = response(state, chain).map(|chain_header| {
+        let address = chain_header.address();
+        state.top_chain_header = Some(chain_header);
+        address
+    });
+
+    if result.is_ok() {
+        #[allow(unused_must_use)]
+        context.state().map(|global_state_lock| {
+            let persis_lock = context.persister.clone();
+            let persister = &mut *persis_lock.lock().unwrap();
+            persister.save(global_state_lock.clone());
+        });
+    }
+
+    state
+        .actions
+        .insert(action_wrapper.clone(), ActionResponse::ImportChain(result));
+}
+
 /// maps incoming action to the correct handler
 fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<AgentReduceFn> {
     match action_wrapper.action() {
         Action::Commit(_) => Some(reduce_commit_entry),
+        Action::ImportChain(_) => Some(reduce_import_chain),
         _ => None,
     }
 }
@@ -260,10 +358,14 @@ pub fn reduce(
 #[cfg(test)]
 pub mod tests {
     extern crate tempfile;
-    use super::{reduce_commit_entry, ActionResponse, AgentState, AgentStateSnapshot};
+    use super::{
+        reduce_commit_entry, reduce_import_chain, ActionResponse, AgentState, AgentStateSnapshot,
+    };
     use crate::{
-        action::tests::test_action_wrapper_commit, agent::chain_store::tests::test_chain_store,
-        instance::tests::test_context, state::State,
+        action::{tests::test_action_wrapper_commit, Action, ActionWrapper},
+        agent::chain_store::tests::test_chain_store,
+        instance::tests::test_context,
+        state::State,
     };
     use holochain_core_types::{
         cas::content::AddressableContent,
@@ -320,6 +422,49 @@ pub mod tests {
         );
     }
 
+    #[test]
+    /// test for reducing import chain
+    fn test_reduce_import_chain() {
+        let mut agent_state = test_agent_state();
+        let context = test_context("bob");
+        let state = State::new_with_agent(context, Arc::new(agent_state.clone()));
+        let mut context = test_context("bob");
+        Arc::get_mut(&mut context)
+            .unwrap()
+            .set_state(Arc::new(RwLock::new(state)));
+        let action_wrapper = ActionWrapper::new(Action::ImportChain(vec![(
+            test_chain_header(),
+            test_entry(),
+        )]));
+
+        reduce_import_chain(context.clone(), &mut agent_state, &action_wrapper);
+
+        assert_eq!(
+            agent_state.actions().get(&action_wrapper),
+            Some(&ActionResponse::ImportChain(Ok(
+                test_chain_header().address()
+            ))),
+        );
+        assert_eq!(agent_state.top_chain_header(), Some(test_chain_header()));
+        assert_eq!(
+            agent_state
+                .chain()
+                .iter(&agent_state.top_chain_header())
+                .count(),
+            1
+        );
+
+        let action_wrapper = ActionWrapper::new(Action::ImportChain(Vec::new()));
+        reduce_import_chain(context, &mut agent_state, &action_wrapper);
+        assert_eq!(
+            agent_state.actions().get(&action_wrapper),
+            Some(&ActionResponse::ImportChain(Err(
+                HolochainError::ErrorGeneric(String::from("Can not import an empty chain"))
+            ))),
+        );
+        assert_eq!(agent_state.top_chain_header(), Some(test_chain_header()));
+    }
+
     #[test]
     /// test response to json
     fn test_commit_response_to_json() {
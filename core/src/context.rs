@@ -1,7 +1,9 @@
 use crate::{
     action::ActionWrapper,
+    entry_cache::EntryCache,
     instance::Observer,
     logger::Logger,
+    metrics::GetEntryMetrics,
     persister::Persister,
     signal::{Signal, SignalSender},
     state::State,
@@ -16,8 +18,10 @@ use holochain_core_types::{
     eav::EntityAttributeValueStorage,
     error::{HcResult, HolochainError},
     json::JsonString,
+    signature::Signature,
 };
 use holochain_net::p2p_config::P2pConfig;
+use holochain_sodium::{secbuf::SecBuf, sign};
 use jsonrpc_ws_server::jsonrpc_core::IoHandler;
 use std::{
     sync::{mpsc::SyncSender, Arc, Mutex, RwLock, RwLockReadGuard},
@@ -43,6 +47,14 @@ pub struct Context {
     pub network_config: JsonString,
     pub container_api: Option<Arc<RwLock<IoHandler>>>,
     pub signal_tx: Option<SyncSender<Signal>>,
+    /// Only set if metrics are enabled, so disabled metrics cost nothing but a check
+    pub get_entry_metrics: Option<Arc<GetEntryMetrics>>,
+    /// Read-through cache of the get entry workflows, disabled (None) by default
+    pub entry_cache: Option<Arc<EntryCache>>,
+    /// How long validation callbacks may run before the validation fails, no limit if None
+    pub validation_timeout: Option<Duration>,
+    /// The agent's secret signing key, only set if it got loaded from the agent's keystore
+    agent_secret_key: Option<Arc<Mutex<SecBuf>>>,
 }
 
 impl Context {
@@ -74,6 +86,10 @@ impl Context {
             eav_storage: eav,
             network_config,
             container_api,
+            get_entry_metrics: None,
+            entry_cache: None,
+            validation_timeout: None,
+            agent_secret_key: None,
         }
     }
 
@@ -101,6 +117,10 @@ impl Context {
             eav_storage: eav,
             network_config,
             container_api: None,
+            get_entry_metrics: None,
+            entry_cache: None,
+            validation_timeout: None,
+            agent_secret_key: None,
         })
     }
 
@@ -114,6 +134,47 @@ impl Context {
         logger.log(msg.into());
     }
 
+    pub fn set_get_entry_metrics(&mut self, get_entry_metrics: Arc<GetEntryMetrics>) {
+        self.get_entry_metrics = Some(get_entry_metrics);
+    }
+
+    pub fn set_entry_cache(&mut self, entry_cache: Arc<EntryCache>) {
+        self.entry_cache = Some(entry_cache);
+    }
+
+    pub fn set_validation_timeout(&mut self, validation_timeout: Duration) {
+        self.validation_timeout = Some(validation_timeout);
+    }
+
+    pub fn set_agent_secret_key(&mut self, secret_key: SecBuf) {
+        self.agent_secret_key = Some(Arc::new(Mutex::new(secret_key)));
+    }
+
+    /// Replaces the agent this context acts as, together with its secret key.
+    /// Without a secret key, signing fails until one gets set.
+    pub fn set_agent(&mut self, agent_id: AgentId, secret_key: Option<SecBuf>) {
+        self.agent_id = agent_id;
+        self.agent_secret_key = secret_key.map(|secret_key| Arc::new(Mutex::new(secret_key)));
+    }
+
+    /// Signs the given message with the agent's secret key and returns the base64 encoded
+    /// signature. Fails if the context got built without the agent's secret key.
+    pub fn sign(&self, message: String) -> Result<Signature, HolochainError> {
+        let secret_key = self.agent_secret_key.as_ref().ok_or_else(|| {
+            HolochainError::new("Can not sign without the secret key of the agent")
+        })?;
+        let mut secret_key = secret_key
+            .lock()
+            .map_err(|_| HolochainError::new("Secret key of the agent is poisoned"))?;
+        let mut message_buf = SecBuf::with_insecure(message.len());
+        message_buf.write_lock().copy_from_slice(message.as_bytes());
+        let mut signature_buf = SecBuf::with_insecure(sign::BYTES);
+        sign::sign(&mut message_buf, &mut secret_key, &mut signature_buf)
+            .map_err(|error| HolochainError::ErrorGeneric(format!("{:?}", error)))?;
+        let signature = signature_buf.read_lock();
+        Ok(Signature::from(base64::encode(&**signature)))
+    }
+
     pub fn set_state(&mut self, state: Arc<RwLock<State>>) {
         self.state = Some(state);
     }
@@ -288,4 +349,44 @@ pub mod tests {
             context.state();
         }
     }
+
+    #[test]
+    fn sign_test() {
+        let file_storage = Arc::new(RwLock::new(
+            FilesystemStorage::new(tempdir().unwrap().path().to_str().unwrap()).unwrap(),
+        ));
+        let mut context = Context::new(
+            AgentId::generate_fake("Terence"),
+            test_logger(),
+            Arc::new(Mutex::new(SimplePersister::new(file_storage.clone()))),
+            file_storage.clone(),
+            file_storage.clone(),
+            Arc::new(RwLock::new(
+                EavFileStorage::new(tempdir().unwrap().path().to_str().unwrap().to_string())
+                    .unwrap(),
+            )),
+            mock_network_config(),
+            None,
+            None,
+        );
+        assert!(context.sign(String::from("message")).is_err());
+
+        let mut seed = SecBuf::with_secure(sign::SEEDBYTES);
+        let mut public_key = SecBuf::with_insecure(sign::PUBLICKEYBYTES);
+        let mut secret_key = SecBuf::with_secure(sign::SECRETKEYBYTES);
+        holochain_sodium::random::random_secbuf(&mut seed);
+        sign::seed_keypair(&mut public_key, &mut secret_key, &mut seed).unwrap();
+        context.set_agent_secret_key(secret_key);
+
+        let signature = context.sign(String::from("message")).unwrap();
+        let signature_bytes = base64::decode(&String::from(signature)).unwrap();
+        let mut signature_buf = SecBuf::with_insecure(sign::BYTES);
+        signature_buf.write_lock().copy_from_slice(&signature_bytes);
+        let mut message_buf = SecBuf::with_insecure(7);
+        message_buf.write_lock().copy_from_slice(b"message");
+        assert_eq!(
+            sign::verify(&mut signature_buf, &mut message_buf, &mut public_key),
+            0
+        );
+    }
 }
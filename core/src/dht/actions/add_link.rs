@@ -10,7 +10,11 @@ use futures::{
     task::{LocalWaker, Poll},
 };
 use holochain_core_types::{error::HolochainError, link::Link};
-use std::{pin::Pin, sync::Arc};
+use std::{
+    pin::Pin,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 /// AddLink Action Creator
 /// This action creator dispatches an AddLink action which is consumed by the DHT reducer.
@@ -19,9 +23,15 @@ use std::{pin::Pin, sync::Arc};
 /// local storage and will return an error that the AddLinkFuture resolves to
 /// if that is not the case.
 ///
+/// The link gets added with the current time, which get_links() can sort by.
+///
 /// Returns a future that resolves to an Ok(()) or an Err(HolochainError).
 pub fn add_link(link: &Link, context: &Arc<Context>) -> AddLinkFuture {
-    let action_wrapper = ActionWrapper::new(Action::AddLink(link.clone()));
+    let added_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs() * 1000 + u64::from(since_epoch.subsec_millis()))
+        .unwrap_or(0);
+    let action_wrapper = ActionWrapper::new(Action::AddLink((link.clone(), added_at)));
     dispatch_action(context.action_channel(), action_wrapper.clone());
 
     AddLinkFuture {
@@ -35,6 +35,7 @@ pub fn remove_entry(
 
 /// RemoveEntryFuture resolves to ActionResponse
 /// Tracks the state for a response to its ActionWrapper
+/// and invalidates the deleted entry in the entry cache once the action got reduced.
 pub struct RemoveEntryFuture {
     context: Arc<Context>,
     action: ActionWrapper,
@@ -50,11 +51,15 @@ impl Future for RemoveEntryFuture {
         //
         lw.wake();
         if let Some(state) = self.context.state() {
-            match state.dht().actions().get(&self.action) {
-                Some(Ok(_)) => Poll::Ready(Ok(())),
-                Some(Err(e)) => Poll::Ready(Err(e.clone())),
-                None => Poll::Pending,
+            let result = match state.dht().actions().get(&self.action) {
+                Some(result) => result.clone(),
+                None => return Poll::Pending,
+            };
+            if let Some(ref entry_cache) = self.context.entry_cache {
+                let (deleted_address, _) = unwrap_to!(self.action.action() => Action::RemoveEntry);
+                entry_cache.invalidate(deleted_address);
             }
+            Poll::Ready(result.map(|_| ()))
         } else {
             Poll::Pending
         }
@@ -3,7 +3,7 @@
 use crate::{
     action::{Action, ActionWrapper},
     context::Context,
-    dht::dht_store::DhtStore,
+    dht::dht_store::{link_added_attribute, DhtStore},
 };
 use holochain_core_types::{
     cas::content::{Address, AddressableContent},
@@ -106,7 +106,7 @@ pub(crate) fn reduce_add_link(
 ) -> Option<DhtStore> {
     // Get Action's input data
     let action = action_wrapper.action();
-    let link = unwrap_to!(action => Action::AddLink);
+    let (link, added_at) = unwrap_to!(action => Action::AddLink);
 
     let mut new_store = (*old_store).clone();
     let storage = &old_store.content_storage().clone();
@@ -119,11 +119,24 @@ pub(crate) fn reduce_add_link(
         );
         Some(new_store)
     } else {
-        let eav =
-            EntityAttributeValue::new(link.base(), &format!("link__{}", link.tag()), link.target());
-        eav.map(|e| {
+        let eavs =
+            EntityAttributeValue::new(link.base(), &format!("link__{}", link.tag()), link.target())
+                .and_then(|link_eav| {
+                    let added_at_eav = EntityAttributeValue::new(
+                        link.base(),
+                        &link_added_attribute(link.tag(), link.target()),
+                        &Address::from(added_at.to_string()),
+                    )?;
+                    Ok((link_eav, added_at_eav))
+                });
+        eavs.map(|(link_eav, added_at_eav)| {
             let storage = new_store.meta_storage();
-            let result = storage.write().unwrap().add_eav(&e);
+            let result = {
+                let mut storage = storage.write().unwrap();
+                storage
+                    .add_eav(&link_eav)
+                    .and_then(|_| storage.add_eav(&added_at_eav))
+            };
             new_store
                 .actions_mut()
                 .insert(action_wrapper.clone(), result.map(|_| link.base().clone()));
@@ -360,7 +373,7 @@ pub mod tests {
         let context = Arc::new(context);
 
         let link = Link::new(&entry.address(), &entry.address(), "test-tag");
-        let action = ActionWrapper::new(Action::AddLink(link.clone()));
+        let action = ActionWrapper::new(Action::AddLink((link.clone(), 1234)));
 
         let new_dht_store: DhtStore;
         {
@@ -369,10 +382,11 @@ pub mod tests {
             new_dht_store = (*reduce(Arc::clone(&context), state.dht(), &action)).clone();
         }
         let storage = new_dht_store.meta_storage();
-        let fetched = storage
-            .read()
-            .unwrap()
-            .fetch_eav(Some(entry.address()), None, None);
+        let fetched = storage.read().unwrap().fetch_eav(
+            Some(entry.address()),
+            Some(format!("link__{}", link.tag())),
+            None,
+        );
 
         assert!(fetched.is_ok());
         let hash_set = fetched.unwrap();
@@ -381,6 +395,10 @@ pub mod tests {
         assert_eq!(eav.entity(), *link.base());
         assert_eq!(eav.value(), *link.target());
         assert_eq!(eav.attribute(), format!("link__{}", link.tag()));
+        assert_eq!(
+            new_dht_store.link_added_at(link.base(), link.tag(), link.target()),
+            Ok(Some(1234))
+        );
     }
 
     #[test]
@@ -396,7 +414,7 @@ pub mod tests {
         let context = Arc::new(context);
 
         let link = Link::new(&entry.address(), &entry.address(), "test-tag");
-        let action = ActionWrapper::new(Action::AddLink(link.clone()));
+        let action = ActionWrapper::new(Action::AddLink((link.clone(), 1234)));
 
         let new_dht_store: DhtStore;
         {
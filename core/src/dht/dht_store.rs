@@ -1,14 +1,22 @@
 use crate::action::ActionWrapper;
 use holochain_core_types::{
     cas::{content::Address, storage::ContentAddressableStorage},
+    crud_status::{CrudStatus, STATUS_NAME},
     eav::{EntityAttributeValue, EntityAttributeValueStorage},
     error::HolochainError,
 };
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
+    str::FromStr,
     sync::{Arc, RwLock},
 };
 
+/// EAV attribute of the time a link got added, stored with the link's base as entity,
+/// see DhtStore::link_added_at()
+pub(crate) fn link_added_attribute(tag: &str, target: &Address) -> String {
+    format!("link_added__{}__{}", tag, target)
+}
+
 /// The state-slice for the DHT.
 /// Holds the agent's local shard and interacts with the network module
 #[derive(Clone, Debug)]
@@ -57,6 +65,46 @@ impl DhtStore {
             .fetch_eav(Some(address), Some(format!("link__{}", tag)), None)
     }
 
+    /// When the link with the given base, tag and target got added to this shard first,
+    /// in milliseconds since the Unix epoch, or None for links added before that got recorded
+    pub fn link_added_at(
+        &self,
+        base: &Address,
+        tag: &str,
+        target: &Address,
+    ) -> Result<Option<u64>, HolochainError> {
+        let eavs = self.meta_storage.read()?.fetch_eav(
+            Some(base.clone()),
+            Some(link_added_attribute(tag, target)),
+            None,
+        )?;
+        Ok(eavs
+            .into_iter()
+            .filter_map(|eav| String::from(eav.value()).parse::<u64>().ok())
+            .min())
+    }
+
+    /// Returns the addresses of all entries held in the local shard together with
+    /// their current CRUD status.
+    /// Like get_entry_crud_meta_from_dht(), this picks the status by life-cycle
+    /// order (Deleted, Modified, Live) as long as there can be several status EAVs per entry.
+    pub fn crud_statuses(&self) -> Result<BTreeMap<Address, CrudStatus>, HolochainError> {
+        let status_eavs = self.meta_storage.read()?.fetch_eav(
+            None,
+            Some(STATUS_NAME.to_string()),
+            None,
+        )?;
+        let mut crud_statuses = BTreeMap::new();
+        for eav in status_eavs {
+            let status = CrudStatus::from_str(String::from(eav.value()).as_ref())?;
+            let current = crud_statuses.entry(eav.entity()).or_insert(status);
+            if crud_status_precedence(status) > crud_status_precedence(*current) {
+                *current = status;
+            }
+        }
+        Ok(crud_statuses)
+    }
+
     // Getters (for reducers)
     // =======
     pub(crate) fn content_storage(&self) -> Arc<RwLock<ContentAddressableStorage>> {
@@ -74,3 +122,11 @@ impl DhtStore {
         &mut self.actions
     }
 }
+
+fn crud_status_precedence(status: CrudStatus) -> u8 {
+    match status {
+        CrudStatus::Deleted => 2,
+        CrudStatus::Modified => 1,
+        _ => 0,
+    }
+}
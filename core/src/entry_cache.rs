@@ -0,0 +1,190 @@
+use holochain_core_types::{cas::content::Address, crud_status::CrudStatus, entry::EntryWithMeta};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// How long an EntryCache serves Live entries if not configured otherwise
+pub const DEFAULT_LIVE_ENTRY_MAX_AGE_MS: u64 = 5000;
+
+/// Least recently used cache of the entries get_entry_with_meta_workflow() resolved,
+/// which the workflow consults first if the context has one set.
+/// Entries that are not Live anymore can't change, so they stay until they get evicted.
+/// Live entries can get new revisions anywhere on the network, so they only get served for
+/// `live_entry_max_age` and are invalidated as soon as the local DHT sees them updated
+/// or removed.
+pub struct EntryCache {
+    capacity: usize,
+    live_entry_max_age: Duration,
+    inner: Mutex<CacheState>,
+}
+
+struct CachedEntry {
+    entry_with_meta: EntryWithMeta,
+    cached_at: Instant,
+    last_use: u64,
+}
+
+struct CacheState {
+    entries: HashMap<Address, CachedEntry>,
+    /// Addresses by the tick of their last use, least recently used first
+    usage: BTreeMap<u64, Address>,
+    clock: u64,
+}
+
+impl CacheState {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn remove(&mut self, address: &Address) {
+        if let Some(cached) = self.entries.remove(address) {
+            self.usage.remove(&cached.last_use);
+        }
+    }
+}
+
+impl EntryCache {
+    pub fn new(capacity: usize, live_entry_max_age: Duration) -> Self {
+        EntryCache {
+            capacity,
+            live_entry_max_age,
+            inner: Mutex::new(CacheState {
+                entries: HashMap::new(),
+                usage: BTreeMap::new(),
+                clock: 0,
+            }),
+        }
+    }
+
+    /// The cached entry of the given address, unless it is a Live entry older than
+    /// `live_entry_max_age`.
+    pub fn get(&self, address: &Address) -> Option<EntryWithMeta> {
+        let mut state = self.inner.lock().unwrap();
+        let expired = {
+            let cached = state.entries.get(address)?;
+            cached.entry_with_meta.crud_status == CrudStatus::Live
+                && cached.cached_at.elapsed() > self.live_entry_max_age
+        };
+        if expired {
+            state.remove(address);
+            return None;
+        }
+        let tick = state.tick();
+        let cached = state.entries.get_mut(address)?;
+        let previous_use = cached.last_use;
+        cached.last_use = tick;
+        let entry_with_meta = cached.entry_with_meta.clone();
+        state.usage.remove(&previous_use);
+        state.usage.insert(tick, address.clone());
+        Some(entry_with_meta)
+    }
+
+    /// Caches the entry resolved for the given address, evicting the least recently used
+    /// entry if the cache is full.
+    pub fn insert(&self, address: Address, entry_with_meta: EntryWithMeta) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut state = self.inner.lock().unwrap();
+        state.remove(&address);
+        if state.entries.len() >= self.capacity {
+            let least_recently_used = state.usage.values().next().cloned();
+            if let Some(least_recently_used) = least_recently_used {
+                state.remove(&least_recently_used);
+            }
+        }
+        let tick = state.tick();
+        state.usage.insert(tick, address.clone());
+        state.entries.insert(
+            address,
+            CachedEntry {
+                entry_with_meta,
+                cached_at: Instant::now(),
+                last_use: tick,
+            },
+        );
+    }
+
+    /// Drops the cached entry of the given address, e.g. because a newer revision showed up
+    pub fn invalidate(&self, address: &Address) {
+        self.inner.lock().unwrap().remove(address);
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core_types::{
+        cas::content::AddressableContent,
+        entry::{test_entry_a, test_entry_b, test_entry_c, Entry},
+    };
+    use std::thread;
+
+    fn entry_with_meta(entry: Entry, crud_status: CrudStatus) -> EntryWithMeta {
+        EntryWithMeta {
+            entry,
+            crud_status,
+            maybe_crud_link: None,
+        }
+    }
+
+    #[test]
+    fn entry_cache_evicts_least_recently_used() {
+        let cache = EntryCache::new(2, Duration::from_secs(60));
+        let (a, b, c) = (test_entry_a(), test_entry_b(), test_entry_c());
+        cache.insert(a.address(), entry_with_meta(a.clone(), CrudStatus::Live));
+        cache.insert(b.address(), entry_with_meta(b.clone(), CrudStatus::Live));
+        // Using a makes b the least recently used entry
+        assert!(cache.get(&a.address()).is_some());
+        cache.insert(c.address(), entry_with_meta(c.clone(), CrudStatus::Live));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&a.address()).is_some());
+        assert!(cache.get(&b.address()).is_none());
+        assert!(cache.get(&c.address()).is_some());
+
+        cache.invalidate(&a.address());
+        assert!(cache.get(&a.address()).is_none());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn entry_cache_expires_live_entries_only() {
+        let cache = EntryCache::new(10, Duration::from_millis(10));
+        let (live, modified) = (test_entry_a(), test_entry_b());
+        cache.insert(
+            live.address(),
+            entry_with_meta(live.clone(), CrudStatus::Live),
+        );
+        cache.insert(
+            modified.address(),
+            entry_with_meta(modified.clone(), CrudStatus::Modified),
+        );
+        thread::sleep(Duration::from_millis(20));
+
+        assert!(cache.get(&live.address()).is_none());
+        assert_eq!(
+            cache.get(&modified.address()),
+            Some(entry_with_meta(modified, CrudStatus::Modified))
+        );
+    }
+
+    #[test]
+    fn entry_cache_with_zero_capacity_caches_nothing() {
+        let cache = EntryCache::new(0, Duration::from_secs(60));
+        let entry = test_entry_a();
+        cache.insert(entry.address(), entry_with_meta(entry, CrudStatus::Live));
+        assert_eq!(cache.len(), 0);
+    }
+}
@@ -15,6 +15,8 @@ extern crate snowflake;
 #[cfg(test)]
 extern crate test_utils;
 extern crate wasmi;
+extern crate parity_wasm;
+extern crate pwasm_utils;
 #[macro_use]
 extern crate unwrap_to;
 #[macro_use]
@@ -33,6 +35,7 @@ extern crate holochain_core_types_derive;
 extern crate base64;
 extern crate globset;
 extern crate holochain_net_connection;
+extern crate holochain_sodium;
 #[macro_use]
 extern crate lazy_static;
 
@@ -40,10 +43,12 @@ pub mod action;
 pub mod agent;
 pub mod context;
 pub mod dht;
+pub mod entry_cache;
 pub mod instance;
 #[cfg(test)]
 pub mod link_tests;
 pub mod logger;
+pub mod metrics;
 pub mod network;
 pub mod nucleus;
 pub mod persister;
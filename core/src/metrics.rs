@@ -0,0 +1,146 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+/// Upper bounds (in milliseconds) of the buckets of the network latency histogram.
+/// Latencies above the last bound end up in an additional overflow bucket.
+pub const NETWORK_LATENCY_BUCKETS_MS: [u64; 8] = [10, 50, 100, 250, 500, 1000, 5000, 30000];
+
+/// Histogram of latencies with the given upper bucket bounds (in milliseconds) and an
+/// additional overflow bucket, which also sums up all recorded latencies.
+/// The counters are atomic, so recording does not need a lock.
+pub struct LatencyHistogram {
+    bounds_ms: &'static [u64],
+    counts: Vec<AtomicUsize>,
+    sum_ms: AtomicUsize,
+}
+
+impl LatencyHistogram {
+    pub fn new(bounds_ms: &'static [u64]) -> Self {
+        LatencyHistogram {
+            bounds_ms,
+            counts: (0..=bounds_ms.len()).map(|_| AtomicUsize::new(0)).collect(),
+            sum_ms: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn record(&self, latency: Duration) {
+        let millis = latency.as_secs() * 1000 + u64::from(latency.subsec_millis());
+        let bucket = self
+            .bounds_ms
+            .iter()
+            .position(|bound| millis <= *bound)
+            .unwrap_or(self.bounds_ms.len());
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_ms.fetch_add(millis as usize, Ordering::Relaxed);
+    }
+
+    pub fn bounds_ms(&self) -> Vec<u64> {
+        self.bounds_ms.to_vec()
+    }
+
+    /// One count per bucket, followed by the count of the overflow bucket
+    pub fn counts(&self) -> Vec<usize> {
+        self.counts
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    pub fn sum_ms(&self) -> usize {
+        self.sum_ms.load(Ordering::Relaxed)
+    }
+}
+
+/// Counters that get_entry_with_meta_workflow() records into if the context has them set.
+/// All counters are atomic so that the workflows of an instance can share them
+/// without locking.
+pub struct GetEntryMetrics {
+    cache_hits: AtomicUsize,
+    local_hits: AtomicUsize,
+    network_fallbacks: AtomicUsize,
+    network_timeouts: AtomicUsize,
+    network_latency: LatencyHistogram,
+}
+
+/// Serializable point-in-time copy of GetEntryMetrics.
+/// `network_latency_ms` holds one count per bucket of NETWORK_LATENCY_BUCKETS_MS,
+/// followed by the count of the overflow bucket.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GetEntryMetricsSnapshot {
+    pub cache_hits: usize,
+    pub local_hits: usize,
+    pub network_fallbacks: usize,
+    pub network_timeouts: usize,
+    pub network_latency_buckets_ms: Vec<u64>,
+    pub network_latency_ms: Vec<usize>,
+}
+
+impl GetEntryMetrics {
+    pub fn new() -> Self {
+        GetEntryMetrics {
+            cache_hits: AtomicUsize::new(0),
+            local_hits: AtomicUsize::new(0),
+            network_fallbacks: AtomicUsize::new(0),
+            network_timeouts: AtomicUsize::new(0),
+            network_latency: LatencyHistogram::new(&NETWORK_LATENCY_BUCKETS_MS),
+        }
+    }
+
+    /// The entry was served from the context's entry cache
+    pub fn record_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The entry was found in the local DHT shard
+    pub fn record_local_hit(&self) {
+        self.local_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The entry was looked up on the network, which took `latency`
+    pub fn record_network_fallback(&self, latency: Duration) {
+        self.network_fallbacks.fetch_add(1, Ordering::Relaxed);
+        self.network_latency.record(latency);
+    }
+
+    /// The network look-up ended with a timeout
+    pub fn record_network_timeout(&self) {
+        self.network_timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> GetEntryMetricsSnapshot {
+        GetEntryMetricsSnapshot {
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            local_hits: self.local_hits.load(Ordering::Relaxed),
+            network_fallbacks: self.network_fallbacks.load(Ordering::Relaxed),
+            network_timeouts: self.network_timeouts.load(Ordering::Relaxed),
+            network_latency_buckets_ms: self.network_latency.bounds_ms(),
+            network_latency_ms: self.network_latency.counts(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn get_entry_metrics_snapshot_test() {
+        let metrics = GetEntryMetrics::new();
+        metrics.record_cache_hit();
+        metrics.record_local_hit();
+        metrics.record_local_hit();
+        metrics.record_network_fallback(Duration::from_millis(5));
+        metrics.record_network_fallback(Duration::from_millis(300));
+        metrics.record_network_fallback(Duration::from_secs(60));
+        metrics.record_network_timeout();
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.cache_hits, 1);
+        assert_eq!(snapshot.local_hits, 2);
+        assert_eq!(snapshot.network_fallbacks, 3);
+        assert_eq!(snapshot.network_timeouts, 1);
+        assert_eq!(snapshot.network_latency_ms, vec![1, 0, 0, 0, 1, 0, 0, 0, 1]);
+    }
+}
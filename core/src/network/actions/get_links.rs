@@ -9,29 +9,137 @@ use futures::{
     task::{LocalWaker, Poll},
 };
 use holochain_core_types::{cas::content::Address, error::HcResult};
+use holochain_wasm_utils::api_serialization::get_links::LinksSortOrder;
+use regex::Regex;
+use serde_json;
 use std::{pin::Pin, sync::Arc, thread::sleep, time::Duration};
 
+lazy_static! {
+    static ref LINK: Regex =
+        Regex::new(r"^link__(.*)$").expect("This string literal is a valid regex");
+    static ref LINK_PAGE: Regex =
+        Regex::new(r"^link_page__(.*)$").expect("This string literal is a valid regex");
+}
+
+/// Page of the links of a base, see get_links_page()
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LinksPage {
+    /// Number of links to skip
+    pub offset: usize,
+    /// Maximum number of links, None for all after the offset
+    pub limit: Option<usize>,
+    /// Order of the links the page gets cut from
+    pub sort_order: LinksSortOrder,
+}
+
+impl LinksPage {
+    /// Sorts the links by the page's sort order and returns the ones of this page.
+    /// `added_at` gives the time a link got added, see DhtStore::link_added_at().
+    pub fn of<F>(&self, mut links: Vec<Address>, added_at: F) -> Vec<Address>
+    where
+        F: Fn(&Address) -> Option<u64>,
+    {
+        match self.sort_order {
+            LinksSortOrder::Unsorted => (),
+            LinksSortOrder::Address => links.sort(),
+            LinksSortOrder::Timestamp => {
+                let mut timed_links = links
+                    .into_iter()
+                    .map(|target| (added_at(&target).unwrap_or(u64::max_value()), target))
+                    .collect::<Vec<_>>();
+                timed_links.sort();
+                links = timed_links.into_iter().map(|(_, target)| target).collect();
+            }
+        }
+        let page = links.into_iter().skip(self.offset);
+        match self.limit {
+            Some(limit) => page.take(limit).collect(),
+            None => page.collect(),
+        }
+    }
+}
+
+/// DHT meta attribute that requests all links with the given tag
+pub fn links_attribute(tag: &str) -> String {
+    format!("link__{}", tag)
+}
+
+/// DHT meta attribute that requests the given page of the links with the given tag,
+/// sorted as the page says
+pub fn links_page_attribute(tag: &str, page: &LinksPage) -> String {
+    format!(
+        "link_page__{}",
+        serde_json::to_string(&(tag, page)).expect("Tag and page must serialize")
+    )
+}
+
+/// Tag and, if only a page got requested, page of a links request attribute,
+/// or None if the attribute requests something else
+pub fn parse_links_attribute(attribute: &str) -> Option<(String, Option<LinksPage>)> {
+    let captured = |regex: &Regex| {
+        regex
+            .captures(attribute)
+            .and_then(|captures| captures.get(1))
+            .map(|capture| capture.as_str().to_string())
+    };
+    match (captured(&LINK), captured(&LINK_PAGE)) {
+        (Some(tag), _) => Some((tag, None)),
+        (None, Some(query)) => serde_json::from_str::<(String, LinksPage)>(&query)
+            .ok()
+            .map(|(tag, page)| (tag, Some(page))),
+        (None, None) => None,
+    }
+}
+
 /// GetLinks Action Creator
 /// This is the network version of get_links that makes the network module start
 /// a look-up process.
+/// The links come in the order the node holding them has them in.
 pub async fn get_links<'a>(
     context: &'a Arc<Context>,
     address: &'a Address,
     tag: String,
 ) -> HcResult<Vec<Address>> {
-    let action_wrapper = ActionWrapper::new(Action::GetLinks((address.clone(), tag.clone())));
+    await!(get_links_with_attribute(
+        context,
+        address,
+        links_attribute(&tag)
+    ))
+}
+
+/// Like get_links(), but the node holding the links sorts them as the page says and
+/// only sends the given page of them.
+pub async fn get_links_page<'a>(
+    context: &'a Arc<Context>,
+    address: &'a Address,
+    tag: String,
+    page: LinksPage,
+) -> HcResult<Vec<Address>> {
+    await!(get_links_with_attribute(
+        context,
+        address,
+        links_page_attribute(&tag, &page)
+    ))
+}
+
+async fn get_links_with_attribute<'a>(
+    context: &'a Arc<Context>,
+    address: &'a Address,
+    attribute: String,
+) -> HcResult<Vec<Address>> {
+    let key = (address.clone(), attribute);
+    let action_wrapper = ActionWrapper::new(Action::GetLinks(key.clone()));
     dispatch_action(context.action_channel(), action_wrapper.clone());
 
     let _ = async {
         sleep(Duration::from_secs(60));
-        let action_wrapper =
-            ActionWrapper::new(Action::GetLinksTimeout((address.clone(), tag.clone())));
+        let action_wrapper = ActionWrapper::new(Action::GetLinksTimeout(key.clone()));
         dispatch_action(context.action_channel(), action_wrapper.clone());
     };
 
     await!(GetLinksFuture {
         context: context.clone(),
-        key: (address.clone(), tag.clone())
+        key,
     })
 }
 
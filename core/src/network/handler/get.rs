@@ -2,20 +2,15 @@ use crate::{
     action::{Action, ActionWrapper},
     context::Context,
     instance::dispatch_action,
+    network::actions::get_links::parse_links_attribute,
     nucleus,
 };
 use holochain_core_types::cas::content::Address;
 use holochain_net_connection::protocol_wrapper::{
     DhtData, DhtMetaData, GetDhtData, GetDhtMetaData,
 };
-use regex::Regex;
 use std::{collections::HashSet, sync::Arc};
 
-lazy_static! {
-    static ref LINK: Regex =
-        Regex::new(r"^link__(.*)$").expect("This string literal is a valid regex");
-}
-
 /// The network has requested a DHT entry from us.
 /// Lets try to get it and trigger a response.
 pub fn handle_get_dht(get_dht_data: GetDhtData, context: Arc<Context>) {
@@ -39,27 +34,23 @@ pub fn handle_get_dht_result(dht_data: DhtData, context: Arc<Context>) {
     dispatch_action(context.action_channel(), action_wrapper.clone());
 }
 
+/// The network has requested links from us, either all of them or a sorted page of them,
+/// see `get_links_page()`.
 pub fn handle_get_dht_meta(get_dht_meta_data: GetDhtMetaData, context: Arc<Context>) {
-    if LINK.is_match(&get_dht_meta_data.attribute) {
-        let tag = LINK
-            .captures(&get_dht_meta_data.attribute)
-            .unwrap()
-            .get(1)
-            .unwrap()
-            .as_str()
-            .to_string();
-        let links = context
-            .state()
-            .unwrap()
-            .dht()
-            .get_links(
-                Address::from(get_dht_meta_data.address.clone()),
-                tag.clone(),
-            )
+    if let Some((tag, page)) = parse_links_attribute(&get_dht_meta_data.attribute) {
+        let base = Address::from(get_dht_meta_data.address.clone());
+        let dht = context.state().unwrap().dht();
+        let mut links = dht
+            .get_links(base.clone(), tag.clone())
             .unwrap_or(HashSet::new())
             .into_iter()
             .map(|eav| eav.value())
             .collect::<Vec<_>>();
+        if let Some(page) = page {
+            links = page.of(links, |target| {
+                dht.link_added_at(&base, &tag, target).unwrap_or(None)
+            });
+        }
         let action_wrapper =
             ActionWrapper::new(Action::RespondGetLinks((get_dht_meta_data, links)));
         dispatch_action(context.action_channel(), action_wrapper.clone());
@@ -68,15 +59,10 @@ pub fn handle_get_dht_meta(get_dht_meta_data: GetDhtMetaData, context: Arc<Conte
 
 /// The network comes back with a result to our previous GET META request.
 pub fn handle_get_dht_meta_result(dht_meta_data: DhtMetaData, context: Arc<Context>) {
-    if LINK.is_match(&dht_meta_data.attribute) {
-        let tag = LINK
-            .captures(&dht_meta_data.attribute)
-            .unwrap()
-            .get(1)
-            .unwrap()
-            .as_str()
-            .to_string();
-        let action_wrapper = ActionWrapper::new(Action::HandleGetLinksResult((dht_meta_data, tag)));
+    if parse_links_attribute(&dht_meta_data.attribute).is_some() {
+        let attribute = dht_meta_data.attribute.clone();
+        let action_wrapper =
+            ActionWrapper::new(Action::HandleGetLinksResult((dht_meta_data, attribute)));
         dispatch_action(context.action_channel(), action_wrapper.clone());
     }
 }
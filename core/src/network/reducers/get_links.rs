@@ -10,7 +10,7 @@ use std::sync::Arc;
 fn inner(
     network_state: &mut NetworkState,
     address: &Address,
-    tag: &String,
+    attribute: &String,
 ) -> Result<(), HolochainError> {
     network_state.initialized()?;
 
@@ -21,7 +21,7 @@ fn inner(
             dna_address: network_state.dna_address.clone().unwrap(),
             from_agent_id: network_state.agent_id.clone().unwrap(),
             address: address.to_string(),
-            attribute: format!("link__{}", tag),
+            attribute: attribute.clone(),
         }),
     )
 }
@@ -32,16 +32,16 @@ pub fn reduce_get_links(
     action_wrapper: &ActionWrapper,
 ) {
     let action = action_wrapper.action();
-    let (address, tag) = unwrap_to!(action => crate::action::Action::GetLinks);
+    let (address, attribute) = unwrap_to!(action => crate::action::Action::GetLinks);
 
-    let result = match inner(network_state, &address, tag) {
+    let result = match inner(network_state, &address, attribute) {
         Ok(()) => None,
         Err(err) => Some(Err(err)),
     };
 
     network_state
         .get_links_results
-        .insert((address.clone(), tag.clone()), result);
+        .insert((address.clone(), attribute.clone()), result);
 }
 
 pub fn reduce_get_links_timeout(
@@ -70,6 +70,7 @@ mod tests {
         action::{Action, ActionWrapper, NetworkSettings},
         context::mock_network_config,
         instance::tests::test_context,
+        network::actions::get_links::links_attribute,
         state::test_store,
     };
     use holochain_core_types::error::HolochainError;
@@ -81,8 +82,7 @@ mod tests {
         let store = test_store(context.clone());
 
         let entry = test_entry();
-        let tag = String::from("test-tag");
-        let key = (entry.address(), tag.clone());
+        let key = (entry.address(), links_attribute("test-tag"));
         let action_wrapper = ActionWrapper::new(Action::GetLinks(key.clone()));
 
         let store = store.reduce(context.clone(), action_wrapper);
@@ -114,8 +114,7 @@ mod tests {
         let store = store.reduce(context.clone(), action_wrapper);
 
         let entry = test_entry();
-        let tag = String::from("test-tag");
-        let key = (entry.address(), tag.clone());
+        let key = (entry.address(), links_attribute("test-tag"));
         let action_wrapper = ActionWrapper::new(Action::GetLinks(key.clone()));
 
         let store = store.reduce(context.clone(), action_wrapper);
@@ -147,8 +146,7 @@ mod tests {
         }
 
         let entry = test_entry();
-        let tag = String::from("test-tag");
-        let key = (entry.address(), tag.clone());
+        let key = (entry.address(), links_attribute("test-tag"));
         let action_wrapper = ActionWrapper::new(Action::GetLinks(key.clone()));
 
         {
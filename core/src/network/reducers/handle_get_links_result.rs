@@ -25,12 +25,16 @@ pub fn reduce_handle_get_links_result(
     action_wrapper: &ActionWrapper,
 ) {
     let action = action_wrapper.action();
-    let (dht_meta_data, tag) = unwrap_to!(action => crate::action::Action::HandleGetLinksResult);
+    let (dht_meta_data, attribute) =
+        unwrap_to!(action => crate::action::Action::HandleGetLinksResult);
 
     let result = inner(network_state, dht_meta_data);
 
     network_state.get_links_results.insert(
-        (Address::from(dht_meta_data.address.clone()), tag.clone()),
+        (
+            Address::from(dht_meta_data.address.clone()),
+            attribute.clone(),
+        ),
         Some(result),
     );
 }
@@ -3,6 +3,7 @@ extern crate serde_json;
 use crate::{
     action::{Action, ActionWrapper},
     context::Context,
+    instance::dispatch_action,
     nucleus::ribosome::callback::{self, CallbackResult},
 };
 use futures::{
@@ -17,7 +18,12 @@ use holochain_core_types::{
     validation::ValidationData,
 };
 use snowflake;
-use std::{pin::Pin, sync::Arc, thread};
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    thread,
+    time::Instant,
+};
 
 /// ValidateEntry Action Creator
 /// This is the high-level validate function that wraps the whole validation process and is what should
@@ -77,11 +83,13 @@ pub fn validate_entry<'a>(
         }
     }
 
+    let timed_out = Arc::new(Mutex::new(false));
     {
         let id = id.clone();
         let address = address.clone();
         let entry = entry.clone();
         let context = context.clone();
+        let timed_out = timed_out.clone();
         thread::spawn(move || {
             let maybe_validation_result = callback::validate_entry::validate_entry(
                 entry.clone(),
@@ -102,27 +110,39 @@ pub fn validate_entry<'a>(
                 Err(error) => Err(error.to_string()),
             };
 
-            context
-                .action_channel()
-                .send(ActionWrapper::new(Action::ReturnValidationResult((
-                    (id, address),
-                    result,
-                ))))
-                .expect("action channel to be open in reducer");
+            // Nobody waits for the result anymore once the future timed out
+            let timed_out = timed_out.lock().unwrap();
+            if !*timed_out {
+                context
+                    .action_channel()
+                    .send(ActionWrapper::new(Action::ReturnValidationResult((
+                        (id, address),
+                        result,
+                    ))))
+                    .expect("action channel to be open in reducer");
+            }
         });
     };
 
     FutureObj::new(Box::new(ValidationFuture {
         context: context.clone(),
         key: (id, address),
+        started: Instant::now(),
+        timed_out,
     }))
 }
 
 /// ValidationFuture resolves to an Ok(ActionWrapper) or an Err(error_message:String).
 /// Tracks the state for ValidationResults.
+/// Fails once the context's validation timeout passed without a result. The validation
+/// callback gets aborted by the ribosome at the same deadline, and its result is dropped
+/// instead of being added to the state. A result that arrived just before the timeout
+/// gets removed from the state again.
 pub struct ValidationFuture {
     context: Arc<Context>,
     key: (snowflake::ProcessUniqueId, HashString),
+    started: Instant,
+    timed_out: Arc<Mutex<bool>>,
 }
 
 impl Future for ValidationFuture {
@@ -134,14 +154,78 @@ impl Future for ValidationFuture {
         // See: https://github.com/holochain/holochain-rust/issues/314
         //
         lw.wake();
-        if let Some(state) = self.context.state() {
-            match state.nucleus().validation_results.get(&self.key) {
-                Some(Ok(())) => Poll::Ready(Ok(self.key.1.clone())),
-                Some(Err(e)) => Poll::Ready(Err(HolochainError::ValidationFailed(e.clone()))),
-                None => Poll::Pending,
+        let maybe_result = match self.context.state() {
+            Some(state) => state.nucleus().validation_results.get(&self.key).cloned(),
+            None => return Poll::Pending,
+        };
+        match maybe_result {
+            Some(Ok(())) => Poll::Ready(Ok(self.key.1.clone())),
+            Some(Err(e)) => Poll::Ready(Err(HolochainError::ValidationFailed(e))),
+            None => match self.context.validation_timeout {
+                Some(timeout) if self.started.elapsed() >= timeout => {
+                    // Holding the lock while dispatching makes sure a result sent before
+                    // gets removed, and no result gets sent after
+                    let mut timed_out = self.timed_out.lock().unwrap();
+                    *timed_out = true;
+                    dispatch_action(
+                        self.context.action_channel(),
+                        ActionWrapper::new(Action::RemoveValidationResult(self.key.clone())),
+                    );
+                    Poll::Ready(Err(HolochainError::ValidationFailed(format!(
+                        "Validation of entry {} timed out after {} ms",
+                        self.key.1,
+                        timeout.as_secs() * 1000 + u64::from(timeout.subsec_millis())
+                    ))))
+                }
+                _ => Poll::Pending,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{instance::tests::test_context_with_channels, state::State};
+    use futures::executor::block_on;
+    use holochain_core_types::entry::test_entry;
+    use std::{
+        sync::{mpsc::sync_channel, RwLock},
+        time::Duration,
+    };
+
+    #[test]
+    fn validation_fails_after_timeout() {
+        let (action_tx, action_rx) = sync_channel(10);
+        let (observer_tx, _observer_rx) = sync_channel(10);
+        let mut context =
+            (*test_context_with_channels("Florence", &action_tx, &observer_tx)).clone();
+        let state = State::new(Arc::new(context.clone()));
+        context.set_state(Arc::new(RwLock::new(state)));
+        context.set_validation_timeout(Duration::from_millis(50));
+        let key = (snowflake::ProcessUniqueId::new(), test_entry().address());
+        let timed_out = Arc::new(Mutex::new(false));
+        let future = ValidationFuture {
+            context: Arc::new(context),
+            key: key.clone(),
+            started: Instant::now(),
+            timed_out: timed_out.clone(),
+        };
+        match block_on(future) {
+            Err(HolochainError::ValidationFailed(message)) => {
+                assert!(message.contains("timed out after 50 ms"))
             }
-        } else {
-            Poll::Pending
+            other => panic!("Expected validation to time out, got {:?}", other),
+        }
+
+        // a late result won't be sent, and one sent before gets removed
+        assert!(*timed_out.lock().unwrap());
+        match action_rx.try_recv().unwrap().action() {
+            Action::RemoveValidationResult(removed_key) => assert_eq!(removed_key, &key),
+            other => panic!(
+                "Expected the validation result to be removed, got {:?}",
+                other
+            ),
         }
     }
 }
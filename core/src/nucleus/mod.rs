@@ -15,11 +15,7 @@ use crate::{
 };
 use holochain_core_types::{
     cas::content::Address,
-    dna::{
-        capabilities::{CapabilityCall, CapabilityType},
-        wasm::DnaWasm,
-        Dna,
-    },
+    dna::{capabilities::CapabilityCall, wasm::DnaWasm},
     error::{HcResult, HolochainError},
     json::JsonString,
 };
@@ -323,6 +319,16 @@ fn reduce_return_validation_result(
         .insert((id.clone(), hash.clone()), validation_result.clone());
 }
 
+fn reduce_remove_validation_result(
+    _context: Arc<Context>,
+    state: &mut NucleusState,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let key = unwrap_to!(action => Action::RemoveValidationResult);
+    state.validation_results.remove(key);
+}
+
 /// Reduce ReturnZomeFunctionResult Action.
 /// Simply drops function call into zome_calls state.
 #[allow(unknown_lints)]
@@ -360,6 +366,7 @@ fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<NucleusReduceFn> {
         Action::ReturnZomeFunctionResult(_) => Some(reduce_return_zome_function_result),
         Action::Call(_) => Some(reduce_call),
         Action::ReturnValidationResult(_) => Some(reduce_return_validation_result),
+        Action::RemoveValidationResult(_) => Some(reduce_remove_validation_result),
         Action::ReturnValidationPackage(_) => Some(reduce_return_validation_package),
         _ => None,
     }
@@ -383,16 +390,6 @@ pub fn reduce(
     }
 }
 
-// Helper function for finding out if a given function call is public
-fn is_fn_public(dna: &Dna, zome_call: &ZomeFnCall) -> Result<bool, HolochainError> {
-    // Get Capability from DNA
-    let res = dna.get_capability_with_zome_name(&zome_call.zome_name, &zome_call.cap_name());
-    match res {
-        Err(e) => Err(HolochainError::Dna(e)),
-        Ok(cap) => Ok(cap.cap_type == CapabilityType::Public),
-    }
-}
-
 #[cfg(test)]
 pub mod tests {
     extern crate test_utils;
@@ -3,14 +3,17 @@ use crate::{
     context::Context,
     instance::RECV_DEFAULT_TIMEOUT_MS,
     nucleus::{
-        is_fn_public, launch_zome_fn_call,
+        launch_zome_fn_call,
         ribosome::{api::ZomeApiResult, Runtime},
         state::NucleusState,
         ZomeFnCall,
     },
 };
 use holochain_core_types::{
-    dna::{capabilities::CapabilityCall, Dna},
+    dna::{
+        capabilities::{Capability, CapabilityCall, CapabilityType},
+        Dna,
+    },
     entry::cap_entries::CapTokenGrant,
     error::{DnaError, HolochainError},
     json::JsonString,
@@ -165,12 +168,29 @@ pub fn validate_call(
     }
     let dna = state.dna.clone().unwrap();
 
+    let capability = check_zome_function(&dna, &fn_call.zome_name, &fn_call.cap, &fn_call.fn_name)?;
+    let public = capability.cap_type == CapabilityType::Public;
+    if !public && !check_capability(context.clone(), &fn_call.clone()) {
+        return Err(HolochainError::CapabilityCheckFailed);
+    }
+    Ok(dna)
+}
+
+/// Checks that the DNA has the given zome function and that it is part of the given
+/// capability, or of a public one if there is no capability call, and returns that capability.
+/// Capability tokens are not checked here since they depend on the chain of the callee.
+pub fn check_zome_function<'a>(
+    dna: &'a Dna,
+    zome_name: &str,
+    cap: &Option<CapabilityCall>,
+    fn_name: &str,
+) -> Result<&'a Capability, HolochainError> {
     // Get zome
-    let zome = match dna.zomes.get(&fn_call.zome_name) {
+    let zome = match dna.zomes.get(zome_name) {
         None => {
             return Err(HolochainError::Dna(DnaError::ZomeNotFound(format!(
                 "Zome '{}' not found",
-                fn_call.zome_name.clone()
+                zome_name
             ))));
         }
         Some(zome) => zome,
@@ -178,32 +198,41 @@ pub fn validate_call(
 
     // Get capability
     // NOTE, this will go away soon because function won't be inside the capability.
-    let capability = match zome.capabilities.get(&fn_call.cap_name()) {
-        None => {
-            return Err(HolochainError::Dna(DnaError::CapabilityNotFound(format!(
-                "Capability '{}' not found in Zome '{}'",
-                fn_call.cap_name().clone(),
-                fn_call.zome_name.clone()
-            ))));
-        }
-        Some(capability) => capability,
+    // Calls without a CapabilityCall go to the public capability that has the function,
+    // if there is one.
+    let capability = match cap {
+        Some(ref cap_call) => match zome.capabilities.get(&cap_call.cap_name) {
+            None => {
+                return Err(HolochainError::Dna(DnaError::CapabilityNotFound(format!(
+                    "Capability '{}' not found in Zome '{}'",
+                    cap_call.cap_name.clone(),
+                    zome_name
+                ))));
+            }
+            Some(capability) => capability,
+        },
+        None => match zome.capabilities.values().find(|capability| {
+            capability.cap_type == CapabilityType::Public
+                && capability
+                    .functions
+                    .iter()
+                    .any(|fn_declaration| fn_declaration.name == fn_name)
+        }) {
+            None => return Err(HolochainError::CapabilityCheckFailed),
+            Some(capability) => capability,
+        },
     };
     // Get ZomeFn
     let maybe_fn = capability
         .functions
         .iter()
-        .find(|&fn_declaration| fn_declaration.name == fn_call.fn_name);
+        .find(|&fn_declaration| fn_declaration.name == fn_name);
     if maybe_fn.is_none() {
         return Err(HolochainError::Dna(DnaError::ZomeFunctionNotFound(
-            format!("Zome function '{}' not found", fn_call.fn_name.clone()),
+            format!("Zome function '{}' not found", fn_name),
         )));
     }
-
-    let public = is_fn_public(&dna, &fn_call)?;
-    if !public && !check_capability(context.clone(), &fn_call.clone()) {
-        return Err(HolochainError::CapabilityCheckFailed);
-    }
-    Ok(dna)
+    Ok(capability)
 }
 
 /// Reduce Call Action
@@ -368,16 +397,17 @@ pub mod tests {
         _caller: Address,
         expected: Result<Result<JsonString, HolochainError>, RecvTimeoutError>,
     ) {
-        let zome_call = ZomeFnCall::new(
-            "test_zome",
-            Some(CapabilityCall::new(
-                "test_cap".to_string(),
-                Address::from(token_str),
-                None,
-            )),
-            "test",
-            "{}",
-        );
+        let cap = CapabilityCall::new("test_cap".to_string(), Address::from(token_str), None);
+        test_reduce_zome_call(test_setup, Some(cap), expected);
+    }
+
+    #[cfg_attr(tarpaulin, skip)]
+    fn test_reduce_zome_call(
+        test_setup: &TestSetup,
+        cap: Option<CapabilityCall>,
+        expected: Result<Result<JsonString, HolochainError>, RecvTimeoutError>,
+    ) {
+        let zome_call = ZomeFnCall::new("test_zome", cap, "test", "{}");
         let zome_call_action = ActionWrapper::new(Action::Call(zome_call.clone()));
 
         // process the action
@@ -447,7 +477,9 @@ pub mod tests {
         let test_setup = setup_test(dna);
         // Expecting timeout since there is no function in wasm to call
         let expected = Err(RecvTimeoutError::Disconnected);
-        test_reduce_call(&test_setup, "", Address::from("caller"), expected);
+        test_reduce_call(&test_setup, "", Address::from("caller"), expected.clone());
+        // Public functions can be called without a CapabilityCall
+        test_reduce_zome_call(&test_setup, None, expected);
     }
 
     #[test]
@@ -455,7 +487,13 @@ pub mod tests {
         let dna = setup_dna_for_cap_test(CapabilityType::Transferable);
         let test_setup = setup_test(dna);
         let expected_failure = Ok(Err(HolochainError::CapabilityCheckFailed));
-        test_reduce_call(&test_setup, "", Address::from("caller"), expected_failure);
+        test_reduce_call(
+            &test_setup,
+            "",
+            Address::from("caller"),
+            expected_failure.clone(),
+        );
+        test_reduce_zome_call(&test_setup, None, expected_failure);
 
         // Expecting timeout since there is no function in wasm to call
         let expected = Err(RecvTimeoutError::Disconnected);
@@ -489,6 +527,7 @@ pub mod tests {
             Address::from("any caller"),
             expected_failure.clone(),
         );
+        test_reduce_zome_call(&test_setup, None, expected_failure.clone());
 
         // Expecting timeout since there is no function in wasm to call
         let expected = Err(RecvTimeoutError::Disconnected);
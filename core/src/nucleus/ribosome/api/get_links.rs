@@ -1,11 +1,9 @@
 use crate::{
-    network::actions::get_links::get_links,
     nucleus::ribosome::{api::ZomeApiResult, Runtime},
+    workflows::get_links_result::get_links_result_workflow,
 };
 use futures::executor::block_on;
-use holochain_wasm_utils::api_serialization::get_links::{
-    GetLinksArgs, GetLinksResult, LinksStatusRequestKind,
-};
+use holochain_wasm_utils::api_serialization::get_links::{GetLinksArgs, LinksStatusRequestKind};
 use std::convert::TryFrom;
 use wasmi::{RuntimeArgs, RuntimeValue};
 
@@ -41,13 +39,10 @@ pub fn invoke_get_links(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiRes
         return ribosome_error_code!(Unspecified);
     }
 
-    // Get links from DHT
-    let maybe_links = block_on(get_links(&runtime.context, &input.entry_address, input.tag));
+    // Get the requested page of links from the DHT
+    let result = block_on(get_links_result_workflow(&runtime.context, &input));
 
-    runtime.store_result(match maybe_links {
-        Ok(links) => Ok(GetLinksResult::new(links)),
-        Err(hc_err) => Err(hc_err),
-    })
+    runtime.store_result(result)
 }
 
 #[cfg(test)]
@@ -21,7 +21,7 @@ use holochain_core_types::{
 use holochain_wasm_utils::api_serialization::validation::{
     EntryValidationArgs, LinkValidationArgs,
 };
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 /// This function determines and runs the appropriate validation callback for the given entry
 /// with the given validation data (which includes the validation package).
@@ -172,18 +172,24 @@ fn build_validation_call(
     ))
 }
 
+/// Runs the given validation callback, aborting it once the context's validation timeout
+/// passed so it doesn't keep running after `ValidationFuture` gave up on it.
 fn run_validation_callback(
     context: Arc<Context>,
     fc: ZomeFnCall,
     wasm: &DnaWasm,
     dna_name: String,
 ) -> CallbackResult {
-    match ribosome::run_dna(
+    let deadline = context
+        .validation_timeout
+        .map(|timeout| Instant::now() + timeout);
+    match ribosome::run_dna_with_deadline(
         &dna_name,
         context,
         wasm.code.clone(),
         &fc,
         Some(fc.clone().parameters.into_bytes()),
+        deadline,
     ) {
         Ok(call_result) => match call_result.is_null() {
             true => CallbackResult::Pass,
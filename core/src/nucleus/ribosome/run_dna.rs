@@ -1,7 +1,10 @@
 use crate::{
     context::Context,
     nucleus::{
-        ribosome::{api::ZomeApiFunction, memory::SinglePageManager, Runtime},
+        ribosome::{
+            api::ZomeApiFunction, memory::SinglePageManager, Runtime, GAS_FUNCTION_INDEX,
+            GAS_FUNCTION_NAME,
+        },
         ZomeFnCall, ZomeFnResult,
     },
 };
@@ -10,7 +13,8 @@ use holochain_core_types::{
     json::JsonString,
 };
 use holochain_wasm_utils::memory_allocation::decode_encoded_allocation;
-use std::{str::FromStr, sync::Arc};
+use pwasm_utils::{self, rules};
+use std::{str::FromStr, sync::Arc, time::Instant};
 use wasmi::{
     self, Error as InterpreterError, FuncInstance, FuncRef, ImportsBuilder, ModuleImportResolver,
     ModuleInstance, NopExternals, RuntimeValue, Signature, ValueType,
@@ -25,10 +29,35 @@ pub fn run_dna(
     wasm: Vec<u8>,
     zome_call: &ZomeFnCall,
     parameters: Option<Vec<u8>>,
+) -> ZomeFnResult {
+    run_dna_with_deadline(dna_name, context, wasm, zome_call, parameters, None)
+}
+
+/// Like `run_dna()`, but aborts the zome function with an error once the given deadline
+/// passed. For this, the wasm binary gets instrumented with a gas counter which calls back
+/// into the host, so code that loops without calling any Zome API function gets stopped too.
+pub fn run_dna_with_deadline(
+    dna_name: &str,
+    context: Arc<Context>,
+    wasm: Vec<u8>,
+    zome_call: &ZomeFnCall,
+    parameters: Option<Vec<u8>>,
+    deadline: Option<Instant>,
 ) -> ZomeFnResult {
     // Create wasm module from wasm binary
-    let module =
-        wasmi::Module::from_buffer(wasm).map_err(|e| HolochainError::ErrorGeneric(e.into()))?;
+    let module = match deadline {
+        None => wasmi::Module::from_buffer(wasm),
+        Some(_) => {
+            let module = parity_wasm::deserialize_buffer(&wasm)
+                .map_err(|e| HolochainError::ErrorGeneric(e.to_string()))?;
+            let module =
+                pwasm_utils::inject_gas_counter(module, &rules::Set::default()).map_err(|_| {
+                    HolochainError::ErrorGeneric("Failed to inject gas counter".to_string())
+                })?;
+            wasmi::Module::from_parity_wasm_module(module)
+        }
+    }
+    .map_err(|e| HolochainError::ErrorGeneric(e.into()))?;
 
     // invoke_index and resolve_func work together to enable callable host functions
     // within WASM modules, which is how the core API functions
@@ -43,6 +72,13 @@ pub fn run_dna(
             field_name: &str,
             _signature: &Signature,
         ) -> Result<FuncRef, InterpreterError> {
+            // Imported by the gas counter injected into modules run with a deadline
+            if field_name == GAS_FUNCTION_NAME {
+                return Ok(FuncInstance::alloc_host(
+                    Signature::new(&[ValueType::I32][..], None),
+                    GAS_FUNCTION_INDEX,
+                ));
+            }
             let api_fn = match ZomeApiFunction::from_str(&field_name) {
                 Ok(api_fn) => api_fn,
                 Err(_) => {
@@ -96,6 +132,8 @@ pub fn run_dna(
         context,
         zome_call: zome_call.clone(),
         dna_name: dna_name.to_string(),
+        deadline,
+        gas_since_deadline_check: 0,
     };
 
     // Write input arguments in wasm memory
@@ -174,3 +212,47 @@ pub fn run_dna(
     ));
     return return_result;
 }
+
+#[cfg(test)]
+pub mod tests {
+    extern crate wabt;
+    use self::wabt::Wat2Wasm;
+    use super::*;
+    use crate::instance::tests::test_context;
+    use std::time::Duration;
+
+    #[test]
+    fn run_dna_with_deadline_aborts_looping_code() {
+        let wasm = Wat2Wasm::new()
+            .convert(
+                r#"
+(module
+    (memory 1)
+    (export "memory" (memory 0))
+    (func (export "loop_forever") (param $allocation i32) (result i32)
+        (loop $forever (br $forever))
+        (i32.const 0)
+    )
+)
+"#,
+            )
+            .unwrap()
+            .as_ref()
+            .to_vec();
+        let zome_call = ZomeFnCall::new("test_zome", None, "loop_forever", "");
+        let started = Instant::now();
+        let result = run_dna_with_deadline(
+            "test_dna",
+            test_context("jimmy"),
+            wasm,
+            &zome_call,
+            None,
+            Some(started + Duration::from_millis(50)),
+        );
+        match result {
+            Err(HolochainError::RibosomeFailed(_)) => (),
+            other => panic!("Expected the zome function to be aborted, got {:?}", other),
+        }
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -14,8 +14,28 @@ use holochain_core_types::{
     json::JsonString,
 };
 use holochain_wasm_utils::memory_allocation::decode_encoded_allocation;
-use std::sync::Arc;
-use wasmi::{Externals, RuntimeArgs, RuntimeValue};
+use std::{fmt, sync::Arc, time::Instant};
+use wasmi::{Externals, HostError, RuntimeArgs, RuntimeValue, Trap, TrapKind};
+
+/// Name of the host function that the gas counter injected by `run_dna_with_deadline()`
+/// calls with the cost of each block of code before running it
+pub const GAS_FUNCTION_NAME: &str = "gas";
+/// Index of the gas host function, outside of the range of Zome API functions
+pub const GAS_FUNCTION_INDEX: usize = usize::max_value();
+/// Amount of gas that may be used before the deadline gets checked again
+const GAS_PER_DEADLINE_CHECK: u64 = 10_000;
+
+/// Traps a zome function that ran past its deadline
+#[derive(Debug)]
+struct DeadlineExceeded;
+
+impl fmt::Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Zome function aborted after its deadline passed")
+    }
+}
+
+impl HostError for DeadlineExceeded {}
 
 /// Object holding data to pass around to invoked Zome API functions
 #[derive(Clone)]
@@ -28,9 +48,30 @@ pub struct Runtime {
     pub dna_name: String,
     /// The zome function call that initiated the Ribosome.
     pub zome_call: ZomeFnCall,
+    /// Point in time after which the zome function gets trapped, if any.
+    pub deadline: Option<Instant>,
+    /// Gas used since the deadline was last checked.
+    pub gas_since_deadline_check: u64,
 }
 
 impl Runtime {
+    /// Accounts for the given amount of gas used by instrumented wasm code and traps it
+    /// if its deadline passed.
+    /// The clock only gets read every GAS_PER_DEADLINE_CHECK units of gas.
+    fn use_gas(&mut self, amount: u32) -> ZomeApiResult {
+        self.gas_since_deadline_check += u64::from(amount);
+        if self.gas_since_deadline_check >= GAS_PER_DEADLINE_CHECK {
+            self.gas_since_deadline_check = 0;
+            if self
+                .deadline
+                .map_or(false, |deadline| Instant::now() >= deadline)
+            {
+                return Err(Trap::new(TrapKind::Host(Box::new(DeadlineExceeded))));
+            }
+        }
+        Ok(None)
+    }
+
     /// Load a JsonString stored in wasm memory.
     /// Input RuntimeArgs should only have one input which is the encoded allocation holding
     /// the complex data as an utf8 string.
@@ -92,6 +133,9 @@ impl Runtime {
 // by implementing the Externals trait from Wasmi.
 impl Externals for Runtime {
     fn invoke_index(&mut self, index: usize, args: RuntimeArgs) -> ZomeApiResult {
+        if index == GAS_FUNCTION_INDEX {
+            return self.use_gas(args.nth_checked(0)?);
+        }
         let zf = ZomeApiFunction::from_index(index);
         match zf {
             ZomeApiFunction::MissingNo => panic!("unknown function index"),
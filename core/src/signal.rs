@@ -5,10 +5,14 @@ use std::{
 
 use crate::action::Action;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Signal {
     Internal(Action),
     User,
+    /// Emitted by the container after it started the instance with the given ID
+    InstanceStarted(String),
+    /// Emitted by the container after it stopped the instance with the given ID
+    InstanceStopped(String),
 }
 
 pub type SignalSender = SyncSender<Signal>;
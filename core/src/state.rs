@@ -142,6 +142,17 @@ impl State {
         Arc::clone(&self.network)
     }
 
+    /// Returns a copy of this state that has the given DNA in its nucleus
+    /// but keeps the agent state, i.e. the source chain, and the DHT state.
+    pub fn with_dna(&self, dna: Dna) -> Self {
+        let mut nucleus = (*self.nucleus).clone();
+        nucleus.dna = Some(dna);
+        State {
+            nucleus: Arc::new(nucleus),
+            ..self.clone()
+        }
+    }
+
     pub fn try_from_agent_snapshot(
         context: Arc<Context>,
         snapshot: AgentStateSnapshot,
@@ -1,26 +1,198 @@
 use crate::{context::Context, network, nucleus};
 
 use holochain_core_types::{
-    cas::content::Address, crud_status::CrudStatus, entry::EntryWithMeta, error::HolochainError,
+    cas::content::Address,
+    crud_status::{CrudStatus, LINK_NAME},
+    entry::EntryWithMeta,
+    error::HolochainError,
 };
 use holochain_wasm_utils::api_serialization::get_entry::{
-    GetEntryArgs, GetEntryResult, StatusRequestKind,
+    CrudLinkPolicy, GetEntryArgs, GetEntryOptions, GetEntryResult, GetStrategy, StatusRequestKind,
+};
+use std::{
+    collections::HashSet,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
 };
-use std::sync::Arc;
 
 /// Get Entry workflow
+/// Looks the entry up in the local DHT shard and/or on the network, as given by the
+/// `strategy` of `options`. Network look-ups that find nothing get repeated as configured
+/// by the network retry options.
+/// If the context has an entry cache, LocalFirst look-ups get served from it first and
+/// every resolved entry gets cached.
 pub async fn get_entry_with_meta_workflow<'a>(
     context: &'a Arc<Context>,
     address: &'a Address,
+    options: &'a GetEntryOptions,
+) -> Result<Option<EntryWithMeta>, HolochainError> {
+    let entry_cache = match context.entry_cache {
+        Some(ref entry_cache) => entry_cache.clone(),
+        None => return await!(resolve_entry_with_meta(context, address, options)),
+    };
+    if options.strategy == GetStrategy::LocalFirst {
+        if let Some(entry_with_meta) = entry_cache.get(address) {
+            if let Some(ref metrics) = context.get_entry_metrics {
+                metrics.record_cache_hit();
+            }
+            return Ok(Some(entry_with_meta));
+        }
+    }
+    let maybe_entry_with_meta = await!(resolve_entry_with_meta(context, address, options))?;
+    if let Some(ref entry_with_meta) = maybe_entry_with_meta {
+        entry_cache.insert(address.clone(), entry_with_meta.clone());
+    }
+    Ok(maybe_entry_with_meta)
+}
+
+async fn resolve_entry_with_meta<'a>(
+    context: &'a Arc<Context>,
+    address: &'a Address,
+    options: &'a GetEntryOptions,
+) -> Result<Option<EntryWithMeta>, HolochainError> {
+    match options.strategy {
+        GetStrategy::LocalFirst => {
+            // 1. Try to get the entry locally (i.e. local DHT shard)
+            let maybe_entry_with_meta =
+                nucleus::actions::get_entry::get_entry_with_meta(context, address.clone())?;
+            if maybe_entry_with_meta.is_some() {
+                if let Some(ref metrics) = context.get_entry_metrics {
+                    metrics.record_local_hit();
+                }
+                return Ok(maybe_entry_with_meta);
+            }
+            // 2. No result, so try on the network
+            await!(get_entry_from_network_with_retries(context, &address, options))
+        }
+        GetStrategy::NetworkFirst => {
+            let maybe_entry_with_meta =
+                await!(get_entry_from_network_with_retries(context, &address, options))?;
+            if maybe_entry_with_meta.is_some() {
+                return Ok(maybe_entry_with_meta);
+            }
+            nucleus::actions::get_entry::get_entry_with_meta(context, address.clone())
+        }
+        GetStrategy::LocalOnly => {
+            nucleus::actions::get_entry::get_entry_with_meta(context, address.clone())
+        }
+        GetStrategy::NetworkOnly => {
+            await!(get_entry_from_network_with_retries(context, &address, options))
+        }
+    }
+}
+
+/// Looks the entry up on the network and repeats the look-up up to `network_retries` times,
+/// waiting `network_retry_delay_ms` in between, as long as the entry was not found.
+/// Errors are returned right away.
+async fn get_entry_from_network_with_retries<'a>(
+    context: &'a Arc<Context>,
+    address: &'a Address,
+    options: &'a GetEntryOptions,
 ) -> Result<Option<EntryWithMeta>, HolochainError> {
-    // 1. Try to get the entry locally (i.e. local DHT shard)
-    let maybe_entry_with_meta =
-        nucleus::actions::get_entry::get_entry_with_meta(context, address.clone())?;
-    if maybe_entry_with_meta.is_some() {
-        return Ok(maybe_entry_with_meta);
-    }
-    // 2. No result, so try on the network
-    await!(network::actions::get_entry::get_entry(context, &address))
+    let mut retries = options.network_retries;
+    loop {
+        let maybe_entry_with_meta = await!(get_entry_from_network(context, address))?;
+        if maybe_entry_with_meta.is_some() || retries == 0 {
+            return Ok(maybe_entry_with_meta);
+        }
+        retries -= 1;
+        context.log(format!(
+            "debug/workflow/get_entry: entry {} not found on the network, retrying",
+            address
+        ));
+        // Callers run this workflow with block_on() on their own thread, so sleeping only
+        // holds up this look-up
+        thread::sleep(Duration::from_millis(options.network_retry_delay_ms));
+    }
+}
+
+/// Looks the entry up on the network and records the look-up in the context's
+/// metrics, if enabled.
+async fn get_entry_from_network<'a>(
+    context: &'a Arc<Context>,
+    address: &'a Address,
+) -> Result<Option<EntryWithMeta>, HolochainError> {
+    let metrics = match context.get_entry_metrics {
+        Some(ref metrics) => metrics.clone(),
+        None => return await!(network::actions::get_entry::get_entry(context, &address)),
+    };
+    let start = Instant::now();
+    let result = await!(network::actions::get_entry::get_entry(context, &address));
+    metrics.record_network_fallback(start.elapsed());
+    if let Err(HolochainError::Timeout) = result {
+        metrics.record_network_timeout();
+    }
+    result
+}
+
+/// Checks whether `to` can be the successor of `from` in a crud chain, i.e. whether it is
+/// consistent to follow the crud-link of `from` to `to`.
+/// Returns the reason if it is not.
+fn crud_link_inconsistency(from: &EntryWithMeta, to: &EntryWithMeta) -> Option<String> {
+    if from.crud_status != CrudStatus::Modified {
+        return Some(format!(
+            "entry with crud-link has status {:?} instead of Modified",
+            from.crud_status
+        ));
+    }
+    if from.entry.entry_type() != to.entry.entry_type() {
+        return Some(format!(
+            "entry type changed from {:?} to {:?}",
+            from.entry.entry_type(),
+            to.entry.entry_type()
+        ));
+    }
+    None
+}
+
+/// Makes sure that following crud-links from `address` reaches `revision`, i.e. that it is
+/// `address` itself or one of its later revisions, and returns the index of `revision` in
+/// the crud chain starting at `address`.
+/// The crud-links get followed backwards from `revision` through the local DHT shard's
+/// metadata, so `start_from` doesn't cost walking the chain up to it. The network can't be
+/// asked for predecessors, so this fails if the local DHT shard doesn't hold the links.
+fn check_is_revision_of(
+    context: &Arc<Context>,
+    address: &Address,
+    revision: &Address,
+) -> Result<usize, HolochainError> {
+    revision_index_locally(context, address, revision)?.ok_or_else(|| {
+        HolochainError::ErrorGeneric(format!(
+            "Cannot start from {}: the local DHT shard does not know it as a revision of {}",
+            revision, address
+        ))
+    })
+}
+
+/// Number of crud-links the local DHT shard holds that lead from `revision` back to
+/// `address`, if they lead there at all
+fn revision_index_locally(
+    context: &Arc<Context>,
+    address: &Address,
+    revision: &Address,
+) -> Result<Option<usize>, HolochainError> {
+    let meta_storage = match context.state() {
+        Some(state) => state.dht().meta_storage(),
+        None => return Ok(None),
+    };
+    let mut current = revision.clone();
+    let mut visited_addresses = HashSet::new();
+    while &current != address {
+        if !visited_addresses.insert(current.clone()) {
+            return Ok(None);
+        }
+        let predecessor_eavs = meta_storage.read().unwrap().fetch_eav(
+            None,
+            Some(LINK_NAME.to_string()),
+            Some(current.clone()),
+        )?;
+        current = match predecessor_eavs.into_iter().next() {
+            Some(eav) => eav.entity(),
+            None => return Ok(None),
+        };
+    }
+    Ok(Some(visited_addresses.len()))
 }
 
 /// Get GetEntryResult workflow
@@ -36,25 +208,78 @@ pub async fn get_entry_result_workflow<'a>(
     // Setup
     let mut entry_result = GetEntryResult::new(args.options.status_request.clone(), None);
     let mut maybe_address = Some(args.address.clone());
+    // Position in the crud chain of the next entry found, counting skipped entries too
+    let mut next_revision = 0;
+    if let Some(ref start_from) = args.options.start_from {
+        if args.options.status_request != StatusRequestKind::Initial {
+            next_revision = check_is_revision_of(context, &args.address, start_from)?;
+            maybe_address = Some(start_from.clone());
+        }
+    }
+    let mut visited_addresses = HashSet::new();
+    // Last entry of the crud chain that was found to be consistent
+    let mut maybe_previous: Option<EntryWithMeta> = None;
 
     // Accumulate entry history in a loop unless only request initial.
     while maybe_address.is_some() {
         let address = maybe_address.unwrap();
         maybe_address = None;
+        // A crud-link pointing back into the chain can never be consistent
+        if !visited_addresses.insert(address.clone()) {
+            if args.options.crud_link_policy == CrudLinkPolicy::Error {
+                return Err(HolochainError::ErrorGeneric(format!(
+                    "Inconsistent crud-link: cycle at {}",
+                    address
+                )));
+            }
+            break;
+        }
         // Try to get entry
-        let maybe_entry_with_meta = await!(get_entry_with_meta_workflow(context, &address))?;
+        let maybe_entry_with_meta = await!(get_entry_with_meta_workflow(
+            context,
+            &address,
+            &args.options,
+        ))?;
         // Entry found
         if let Some(entry_with_meta) = maybe_entry_with_meta {
-            // Erase history if request is for latest
-            if args.options.status_request == StatusRequestKind::Latest {
-                if entry_with_meta.crud_status == CrudStatus::Deleted {
-                    entry_result.clear();
-                    break;
+            let revision = next_revision;
+            next_revision += 1;
+            // Check the crud-link we followed to get here
+            let maybe_inconsistency = maybe_previous
+                .as_ref()
+                .and_then(|previous| crud_link_inconsistency(previous, &entry_with_meta));
+            if let Some(inconsistency) = maybe_inconsistency {
+                match args.options.crud_link_policy {
+                    CrudLinkPolicy::Stop => break,
+                    CrudLinkPolicy::Error => {
+                        return Err(HolochainError::ErrorGeneric(format!(
+                            "Inconsistent crud-link to {}: {}",
+                            address, inconsistency
+                        )));
+                    }
+                    CrudLinkPolicy::Skip => {
+                        if entry_with_meta.crud_status != CrudStatus::Deleted {
+                            maybe_address = entry_with_meta.maybe_crud_link;
+                        }
+                        continue;
+                    }
                 }
             }
 
+            // Erase history if request is for latest, unless deleted entries were asked for,
+            // or for a revision count
+            let erase_deleted = match args.options.status_request {
+                StatusRequestKind::Latest => !args.options.include_deleted,
+                StatusRequestKind::Count => true,
+                _ => false,
+            };
+            if erase_deleted && entry_with_meta.crud_status == CrudStatus::Deleted {
+                entry_result.clear();
+                break;
+            }
+
             // Add entry
-            entry_result.push(&entry_with_meta);
+            entry_result.push_revision(&entry_with_meta, revision);
 
             if args.options.status_request == StatusRequestKind::Initial {
                 break;
@@ -65,45 +290,333 @@ pub async fn get_entry_result_workflow<'a>(
                 && entry_with_meta.crud_status != CrudStatus::Deleted
                 && args.options.status_request != StatusRequestKind::Initial
             {
-                maybe_address = Some(entry_with_meta.maybe_crud_link.unwrap());
+                maybe_address = entry_with_meta.maybe_crud_link.clone();
             }
+            maybe_previous = Some(entry_with_meta);
         }
     }
     Ok(entry_result)
 }
 
-//#[cfg(test)]
-//pub mod tests {
-//    use crate::instance::tests::test_context_with_state;
-//    use futures::executor::block_on;
-//    use holochain_core_types::{
-//        cas::content::AddressableContent,
-//        crud_status::{create_crud_status_eav, CrudStatus},
-//        entry::test_entry,
-//    };
-//    use holochain_wasm_utils::api_serialization::get_entry::*;
-//
-//    #[test]
-//    fn can_get_entry_result_workflow() {
-//        let entry = test_entry();
-//        let context = test_context_with_state();
-//        let args = GetEntryArgs {
-//            address: entry.address(),
-//            options: GetEntryOptions {
-//                status_request: StatusRequestKind::Latest,
-//            },
-//        };
-//        let maybe_entry_history = block_on(super::get_entry_result_workflow(&context, &args));
-////        assert_eq!(0, maybe_entry_history.unwrap().entries.len());
-////        let content_storage = &context.state().unwrap().dht().content_storage().clone();
-////        (*content_storage.write().unwrap()).add(&entry).unwrap();
-////        let status_eav = create_crud_status_eav(&entry.address(), CrudStatus::Live);
-////        let meta_storage = &context.state().unwrap().dht().meta_storage().clone();
-////        (*meta_storage.write().unwrap())
-////            .add_eav(&status_eav)
-////            .unwrap();
-////        let maybe_entry_history = block_on(super::get_entry_result_workflow(&context, &args));
-////        let entry_history = maybe_entry_history.unwrap();
-////        assert_eq!(&entry, entry_history.entries.iter().next().unwrap());
-//    }
-//}
+#[cfg(test)]
+pub mod tests {
+    use super::{crud_link_inconsistency, get_entry_with_meta_workflow};
+    use crate::{
+        context::Context,
+        entry_cache::EntryCache,
+        instance::tests::{test_context_with_state, test_instance_and_context_by_name},
+    };
+    use futures::executor::block_on;
+    use holochain_core_types::{
+        cas::content::{Address, AddressableContent},
+        crud_status::{create_crud_link_eav, create_crud_status_eav, CrudStatus},
+        entry::{
+            entry_type::test_app_entry_type_b, test_entry, test_entry_b, test_entry_c,
+            test_sys_entry, Entry, EntryWithMeta,
+        },
+        json::RawString,
+    };
+    use holochain_wasm_utils::api_serialization::get_entry::{
+        CrudLinkPolicy, GetEntryArgs, GetEntryOptions, GetEntryResultType, GetStrategy,
+        StatusRequestKind,
+    };
+    use std::{sync::Arc, thread, time::Duration};
+    use test_utils::create_test_dna_with_wat;
+
+    #[test]
+    fn local_only_returns_none_if_entry_is_not_local() {
+        let context = test_context_with_state();
+        let address = test_entry().address();
+        let result = block_on(get_entry_with_meta_workflow(
+            &context,
+            &address,
+            &GetEntryOptions::default().with_strategy(GetStrategy::LocalOnly),
+        ));
+        assert_eq!(result, Ok(None));
+
+        let args = GetEntryArgs {
+            address,
+            options: GetEntryOptions::default().with_strategy(GetStrategy::LocalOnly),
+        };
+        let result = block_on(super::get_entry_result_workflow(&context, &args)).unwrap();
+        assert!(!result.found());
+    }
+
+    #[test]
+    fn local_first_reads_through_entry_cache() {
+        let mut context = (*test_context_with_state()).clone();
+        let entry_cache = Arc::new(EntryCache::new(10, Duration::from_secs(60)));
+        context.set_entry_cache(entry_cache.clone());
+        let context = Arc::new(context);
+        let entry = test_entry();
+        let cached = EntryWithMeta {
+            entry: entry.clone(),
+            crud_status: CrudStatus::Modified,
+            maybe_crud_link: None,
+        };
+        entry_cache.insert(entry.address(), cached.clone());
+
+        let result = block_on(get_entry_with_meta_workflow(
+            &context,
+            &entry.address(),
+            &GetEntryOptions::default().with_strategy(GetStrategy::LocalFirst),
+        ));
+        assert_eq!(result, Ok(Some(cached)));
+
+        // Explicit strategies bypass the cache
+        let result = block_on(get_entry_with_meta_workflow(
+            &context,
+            &entry.address(),
+            &GetEntryOptions::default().with_strategy(GetStrategy::LocalOnly),
+        ));
+        assert_eq!(result, Ok(None));
+    }
+
+    #[test]
+    fn network_retries_find_entry_that_arrives_late() {
+        let mut dna = create_test_dna_with_wat("test_zome", "test_cap", None);
+        dna.uuid = String::from("network_retries_find_entry_that_arrives_late");
+        let (_, context1) = test_instance_and_context_by_name(dna.clone(), "alice1").unwrap();
+        let (_, context2) = test_instance_and_context_by_name(dna.clone(), "bob1").unwrap();
+        let entry = test_entry();
+        let options = GetEntryOptions::default().with_strategy(GetStrategy::NetworkOnly);
+
+        // Nobody holds the entry yet
+        let result = block_on(get_entry_with_meta_workflow(
+            &context2,
+            &entry.address(),
+            &options,
+        ));
+        assert_eq!(result, Ok(None));
+
+        // Alice only receives the entry after bob started looking for it
+        let delayed_entry = entry.clone();
+        let delayed_context = context1.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            delayed_context
+                .dht_storage
+                .write()
+                .unwrap()
+                .add(&delayed_entry)
+                .unwrap();
+            let status_eav =
+                create_crud_status_eav(&delayed_entry.address(), CrudStatus::Live).unwrap();
+            delayed_context
+                .eav_storage
+                .write()
+                .unwrap()
+                .add_eav(&status_eav)
+                .unwrap();
+        });
+
+        let result = block_on(get_entry_with_meta_workflow(
+            &context2,
+            &entry.address(),
+            &options.with_network_retries(20, 100),
+        ))
+        .unwrap();
+        handle.join().unwrap();
+        let entry_with_meta = result.expect("Entry should be found after retrying");
+        assert_eq!(entry_with_meta.entry, entry);
+        assert_eq!(entry_with_meta.crud_status, CrudStatus::Live);
+    }
+
+    #[test]
+    fn include_deleted_returns_deleted_latest() {
+        let context = test_context_with_state();
+        let entry = test_entry();
+        let dht = context.state().unwrap().dht();
+        dht.content_storage().write().unwrap().add(&entry).unwrap();
+        let status_eav = create_crud_status_eav(&entry.address(), CrudStatus::Deleted).unwrap();
+        dht.meta_storage()
+            .write()
+            .unwrap()
+            .add_eav(&status_eav)
+            .unwrap();
+
+        let options = GetEntryOptions::default().with_strategy(GetStrategy::LocalOnly);
+        let args = GetEntryArgs {
+            address: entry.address(),
+            options: options.clone(),
+        };
+        let result = block_on(super::get_entry_result_workflow(&context, &args)).unwrap();
+        assert!(!result.found());
+
+        let args = GetEntryArgs {
+            address: entry.address(),
+            options: options.with_include_deleted(true),
+        };
+        let result = block_on(super::get_entry_result_workflow(&context, &args)).unwrap();
+        assert_eq!(result.latest(), Some(entry));
+        match result.result {
+            GetEntryResultType::Single(item) => {
+                assert_eq!(item.meta.unwrap().crud_status, CrudStatus::Deleted)
+            }
+            _ => panic!("Latest should give a single result"),
+        }
+    }
+
+    /// Stores three revisions of an entry, the last one deleted, in the local DHT shard
+    fn store_revisions(context: &Arc<Context>) -> Vec<(Entry, CrudStatus)> {
+        store_chain(
+            context,
+            vec![
+                (test_entry_b(), CrudStatus::Modified),
+                (test_entry_c(), CrudStatus::Modified),
+                (
+                    Entry::App(test_app_entry_type_b(), RawString::from("value D").into()),
+                    CrudStatus::Deleted,
+                ),
+            ],
+        )
+    }
+
+    /// Stores the given entries in the local DHT shard, crud-linking each to the next one
+    fn store_chain(
+        context: &Arc<Context>,
+        revisions: Vec<(Entry, CrudStatus)>,
+    ) -> Vec<(Entry, CrudStatus)> {
+        let dht = context.state().unwrap().dht();
+        for (index, (entry, crud_status)) in revisions.iter().enumerate() {
+            dht.content_storage().write().unwrap().add(entry).unwrap();
+            let meta_storage = dht.meta_storage();
+            let mut meta_storage = meta_storage.write().unwrap();
+            meta_storage
+                .add_eav(&create_crud_status_eav(&entry.address(), *crud_status).unwrap())
+                .unwrap();
+            if let Some((next_entry, _)) = revisions.get(index + 1) {
+                meta_storage
+                    .add_eav(
+                        &create_crud_link_eav(&entry.address(), &next_entry.address()).unwrap(),
+                    )
+                    .unwrap();
+            }
+        }
+        revisions
+    }
+
+    #[test]
+    fn all_revisions_are_ordered_oldest_first() {
+        let context = test_context_with_state();
+        let revisions = store_revisions(&context);
+
+        let args = GetEntryArgs {
+            address: revisions[0].0.address(),
+            options: GetEntryOptions::new(StatusRequestKind::All, true, false, false)
+                .with_strategy(GetStrategy::LocalOnly),
+        };
+        let result = block_on(super::get_entry_result_workflow(&context, &args)).unwrap();
+        let history = match result.result {
+            GetEntryResultType::All(history) => history,
+            _ => panic!("All should give a history"),
+        };
+        assert_eq!(history.current, Some(2));
+        assert_eq!(history.items.len(), 3);
+        for (index, item) in history.items.iter().enumerate() {
+            assert_eq!(item.revision, Some(index));
+            assert_eq!(item.entry, Some(revisions[index].0.clone()));
+            assert_eq!(item.meta.as_ref().unwrap().crud_status, revisions[index].1);
+        }
+    }
+
+    #[test]
+    fn start_from_skips_earlier_revisions() {
+        let context = test_context_with_state();
+        let revisions = store_revisions(&context);
+        let get_all = |start_from: Address| {
+            let args = GetEntryArgs {
+                address: revisions[0].0.address(),
+                options: GetEntryOptions::new(StatusRequestKind::All, true, false, false)
+                    .with_strategy(GetStrategy::LocalOnly)
+                    .with_start_from(start_from),
+            };
+            block_on(super::get_entry_result_workflow(&context, &args))
+        };
+
+        let history = match get_all(revisions[1].0.address()).unwrap().result {
+            GetEntryResultType::All(history) => history,
+            _ => panic!("All should give a history"),
+        };
+        assert_eq!(history.items.len(), 2);
+        assert_eq!(history.items[0].entry, Some(revisions[1].0.clone()));
+        assert_eq!(history.items[1].entry, Some(revisions[2].0.clone()));
+        // revision indices stay relative to the requested address
+        assert_eq!(history.items[0].revision, Some(1));
+        assert_eq!(history.items[1].revision, Some(2));
+
+        let error = get_all(test_entry().address()).unwrap_err();
+        assert!(error.to_string().contains("does not know it as a revision of"));
+    }
+
+    #[test]
+    fn skipped_revisions_keep_their_index() {
+        let context = test_context_with_state();
+        // The crud-link from the first revision leads to an entry of another type
+        let revisions = store_chain(
+            &context,
+            vec![
+                (test_entry_b(), CrudStatus::Modified),
+                (test_sys_entry(), CrudStatus::Modified),
+                (test_entry_c(), CrudStatus::Live),
+            ],
+        );
+
+        let args = GetEntryArgs {
+            address: revisions[0].0.address(),
+            options: GetEntryOptions::new(StatusRequestKind::All, true, false, false)
+                .with_strategy(GetStrategy::LocalOnly)
+                .with_crud_link_policy(CrudLinkPolicy::Skip),
+        };
+        let result = block_on(super::get_entry_result_workflow(&context, &args)).unwrap();
+        let history = match result.result {
+            GetEntryResultType::All(history) => history,
+            _ => panic!("All should give a history"),
+        };
+        assert_eq!(history.items.len(), 2);
+        assert_eq!(history.items[0].revision, Some(0));
+        assert_eq!(history.items[1].revision, Some(2));
+        assert_eq!(history.items[1].entry, Some(revisions[2].0.clone()));
+        assert_eq!(history.current, Some(1));
+    }
+
+    #[test]
+    fn count_of_deleted_entry_ignores_include_deleted() {
+        let context = test_context_with_state();
+        let revisions = store_revisions(&context);
+
+        let args = GetEntryArgs {
+            address: revisions[0].0.address(),
+            options: GetEntryOptions::new(StatusRequestKind::Count, true, false, false)
+                .with_strategy(GetStrategy::LocalOnly)
+                .with_include_deleted(true),
+        };
+        let result = block_on(super::get_entry_result_workflow(&context, &args)).unwrap();
+        assert!(!result.found());
+    }
+
+    fn entry_with_meta(entry: Entry, crud_status: CrudStatus) -> EntryWithMeta {
+        EntryWithMeta {
+            entry,
+            crud_status,
+            maybe_crud_link: None,
+        }
+    }
+
+    #[test]
+    fn crud_link_inconsistency_test() {
+        let modified = entry_with_meta(test_entry_b(), CrudStatus::Modified);
+        let successor = entry_with_meta(test_entry_c(), CrudStatus::Live);
+        assert_eq!(crud_link_inconsistency(&modified, &successor), None);
+
+        let live = entry_with_meta(test_entry_b(), CrudStatus::Live);
+        assert!(crud_link_inconsistency(&live, &successor)
+            .unwrap()
+            .contains("instead of Modified"));
+
+        let other_type = entry_with_meta(test_sys_entry(), CrudStatus::Live);
+        assert!(crud_link_inconsistency(&modified, &other_type)
+            .unwrap()
+            .contains("entry type changed"));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{
+    context::Context,
+    network::actions::get_links::{get_links, get_links_page, LinksPage},
+};
+
+use holochain_core_types::{cas::content::Address, error::HolochainError};
+use holochain_wasm_utils::api_serialization::get_links::{
+    GetLinksArgs, GetLinksOptions, GetLinksResult, LinksSortOrder,
+};
+use std::sync::Arc;
+
+/// Get GetLinksResult workflow
+/// Looks the links up on the network and returns the page of them the options ask for,
+/// i.e. up to `limit` links from `offset` on, in `sort_order`.
+/// Sorted links get sorted and cut by the node holding them, which is the only one that
+/// knows when they got added, and only the page gets transferred. Unsorted pages get cut
+/// from all links of the base here.
+/// Requests for an empty page skip the look-up.
+pub async fn get_links_result_workflow<'a>(
+    context: &'a Arc<Context>,
+    args: &'a GetLinksArgs,
+) -> Result<GetLinksResult, HolochainError> {
+    let options = &args.options;
+    if options.limit == Some(0) {
+        return Ok(GetLinksResult::new(Vec::new()));
+    }
+    let links = if options.sort_order == LinksSortOrder::Unsorted {
+        let links = await!(get_links(context, &args.entry_address, args.tag.clone()))?;
+        page(options).of(links, |_| None)
+    } else {
+        await!(get_links_page(
+            context,
+            &args.entry_address,
+            args.tag.clone(),
+            page(options)
+        ))?
+    };
+    Ok(GetLinksResult::new(links))
+}
+
+fn page(options: &GetLinksOptions) -> LinksPage {
+    LinksPage {
+        offset: options.offset,
+        limit: options.limit,
+        sort_order: options.sort_order.clone(),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        agent::actions::commit::commit_entry, dht::actions::add_link::add_link,
+        instance::tests::test_instance_and_context_by_name,
+        network::test_utils::test_wat_always_valid,
+    };
+    use futures::executor::block_on;
+    use holochain_core_types::{
+        entry::{entry_type::test_app_entry_type, Entry},
+        link::Link,
+    };
+    use std::{thread, time::Duration};
+    use test_utils::*;
+
+    fn links() -> Vec<Address> {
+        vec!["QmC", "QmA", "QmE", "QmB", "QmD"]
+            .into_iter()
+            .map(Address::from)
+            .collect()
+    }
+
+    fn options(offset: usize, limit: Option<usize>) -> GetLinksOptions {
+        GetLinksOptions {
+            offset,
+            limit,
+            sort_order: LinksSortOrder::Address,
+            ..GetLinksOptions::default()
+        }
+    }
+
+    fn addresses(addresses: Vec<&str>) -> Vec<Address> {
+        addresses.into_iter().map(Address::from).collect()
+    }
+
+    /// The page of links of a node that did not record when it added them
+    fn page_of_links(links: Vec<Address>, options: &GetLinksOptions) -> Vec<Address> {
+        page(options).of(links, |_| None)
+    }
+
+    #[test]
+    fn page_of_links_sorts_by_address() {
+        assert_eq!(
+            page_of_links(links(), &options(0, None)),
+            addresses(vec!["QmA", "QmB", "QmC", "QmD", "QmE"])
+        );
+        assert_eq!(
+            page_of_links(links(), &options(1, Some(2))),
+            addresses(vec!["QmB", "QmC"])
+        );
+    }
+
+    #[test]
+    fn page_of_links_sorts_by_time_added() {
+        let by_timestamp = GetLinksOptions {
+            sort_order: LinksSortOrder::Timestamp,
+            ..GetLinksOptions::default()
+        };
+        let added_at = |target: &Address| match String::from(target.clone()).as_ref() {
+            "QmA" => Some(30),
+            "QmB" => Some(10),
+            "QmC" => Some(20),
+            _ => None,
+        };
+        assert_eq!(
+            page(&by_timestamp).of(links(), added_at),
+            addresses(vec!["QmB", "QmC", "QmA", "QmD", "QmE"])
+        );
+    }
+
+    #[test]
+    fn page_of_links_keeps_the_order_unsorted() {
+        let unsorted = GetLinksOptions {
+            offset: 1,
+            limit: Some(2),
+            ..GetLinksOptions::default()
+        };
+        assert_eq!(
+            page_of_links(links(), &unsorted),
+            addresses(vec!["QmA", "QmE"])
+        );
+    }
+
+    #[test]
+    fn page_of_links_of_empty_result_is_empty() {
+        assert!(page_of_links(Vec::new(), &options(0, Some(10))).is_empty());
+    }
+
+    #[test]
+    fn page_of_links_returns_partial_last_page() {
+        assert_eq!(
+            page_of_links(links(), &options(4, Some(2))),
+            addresses(vec!["QmE"])
+        );
+    }
+
+    #[test]
+    fn page_of_links_beyond_the_end_is_empty() {
+        assert!(page_of_links(links(), &options(5, Some(2))).is_empty());
+        assert!(page_of_links(links(), &options(10, None)).is_empty());
+    }
+
+    #[test]
+    fn get_links_result_workflow_gets_pages_from_the_network() {
+        let wat = &test_wat_always_valid();
+        let mut dna = create_test_dna_with_wat("test_zome", "test_cap", Some(wat));
+        dna.uuid = String::from("get_links_result_workflow_gets_pages_from_the_network");
+        let (_, context1) = test_instance_and_context_by_name(dna.clone(), "alice1").unwrap();
+
+        let mut targets: Vec<Address> = (0..5)
+            .map(|i| {
+                let entry = Entry::App(test_app_entry_type(), format!("entry{} value", i).into());
+                block_on(commit_entry(entry, None, &context1))
+                    .expect("Could not commit entry for testing")
+            })
+            .collect();
+        let base = targets.remove(0);
+        for target in targets.iter() {
+            let link = Link::new(&base, target, "test-tag");
+            assert!(block_on(add_link(&link, &context1)).is_ok());
+            // Gives each link its own time added
+            thread::sleep(Duration::from_millis(5));
+        }
+        let added_order = targets.clone();
+        targets.sort();
+
+        let (_, context2) = test_instance_and_context_by_name(dna.clone(), "bob1").unwrap();
+        let get_links = |options: GetLinksOptions| {
+            let args = GetLinksArgs {
+                entry_address: base.clone(),
+                tag: String::from("test-tag"),
+                options,
+            };
+            block_on(get_links_result_workflow(&context2, &args))
+                .unwrap()
+                .addresses()
+                .clone()
+        };
+
+        // All links by default, in any order
+        let mut all = get_links(GetLinksOptions::default());
+        all.sort();
+        assert_eq!(all, targets);
+        assert_eq!(get_links(options(0, None)), targets);
+
+        assert_eq!(get_links(options(1, Some(2))), targets[1..3].to_vec());
+        assert_eq!(get_links(options(3, Some(2))), targets[3..].to_vec());
+        assert!(get_links(options(4, Some(2))).is_empty());
+        assert!(get_links(options(0, Some(0))).is_empty());
+
+        let unsorted_page = GetLinksOptions {
+            limit: Some(3),
+            ..GetLinksOptions::default()
+        };
+        assert_eq!(get_links(unsorted_page).len(), 3);
+
+        let by_timestamp = |offset: usize, limit: Option<usize>| GetLinksOptions {
+            sort_order: LinksSortOrder::Timestamp,
+            ..options(offset, limit)
+        };
+        assert_eq!(get_links(by_timestamp(0, None)), added_order);
+        assert_eq!(get_links(by_timestamp(1, Some(2))), added_order[1..3].to_vec());
+    }
+
+}
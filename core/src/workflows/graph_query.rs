@@ -0,0 +1,179 @@
+use crate::{
+    context::Context, network::actions::get_links::get_links,
+    workflows::get_entry_result::get_entry_with_meta_workflow,
+};
+use futures::executor::block_on;
+use holochain_core_types::{
+    cas::content::Address,
+    crud_status::CrudStatus,
+    entry::{Entry, EntryWithMeta},
+    error::HolochainError,
+    json::JsonString,
+};
+use holochain_wasm_utils::api_serialization::get_entry::GetEntryOptions;
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+};
+
+/// Maximum number of link hops a single graph query may traverse
+pub const MAX_GRAPH_QUERY_DEPTH: u32 = 8;
+
+/// Declarative description of an object graph to fetch in one go:
+/// starting at `base`, follow all links with one of the given `link_tags`
+/// up to `depth` hops, only keeping linked entries of the given `entry_types`
+/// (all types if empty).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, DefaultJson)]
+pub struct GraphQuery {
+    pub base: Address,
+    #[serde(default)]
+    pub link_tags: Vec<String>,
+    #[serde(default)]
+    pub entry_types: Vec<String>,
+    #[serde(default)]
+    pub depth: u32,
+}
+
+impl GraphQuery {
+    pub fn check(&self) -> Result<(), HolochainError> {
+        if self.depth > MAX_GRAPH_QUERY_DEPTH {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Graph query depth {} exceeds the maximum of {}",
+                self.depth, MAX_GRAPH_QUERY_DEPTH
+            )));
+        }
+        Ok(())
+    }
+
+    fn includes_entry_type(&self, entry: &Entry) -> bool {
+        self.entry_types.is_empty() || self.entry_types.contains(&String::from(entry.entry_type()))
+    }
+}
+
+/// One resolved entry of a graph query result together with the
+/// entries it links to, grouped by link tag.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, DefaultJson)]
+pub struct GraphNode {
+    pub address: Address,
+    pub entry: Option<Entry>,
+    pub crud_status: Option<CrudStatus>,
+    pub links: BTreeMap<String, Vec<GraphNode>>,
+}
+
+/// Resolves the given query against the local shard and the network and
+/// returns the nested result with the query's base at its root.
+///
+/// Blocks on the underlying get workflows, so this must not be called from
+/// within an action or observer callback.
+pub fn graph_query(
+    context: &Arc<Context>,
+    query: &GraphQuery,
+) -> Result<GraphNode, HolochainError> {
+    query.check()?;
+    let maybe_entry_with_meta = block_on(get_entry_with_meta_workflow(
+        context,
+        &query.base,
+        &GetEntryOptions::default(),
+    ))?;
+    let mut path = HashSet::new();
+    resolve_node(
+        context,
+        query,
+        query.base.clone(),
+        maybe_entry_with_meta,
+        query.depth,
+        &mut path,
+    )
+}
+
+fn resolve_node(
+    context: &Arc<Context>,
+    query: &GraphQuery,
+    address: Address,
+    maybe_entry_with_meta: Option<EntryWithMeta>,
+    remaining_depth: u32,
+    path: &mut HashSet<Address>,
+) -> Result<GraphNode, HolochainError> {
+    let mut links = BTreeMap::new();
+    // Don't descend into entries we are already resolving further up, to not loop forever
+    if remaining_depth > 0 && maybe_entry_with_meta.is_some() && path.insert(address.clone()) {
+        for tag in query.link_tags.iter() {
+            let targets = block_on(get_links(context, &address, tag.clone()))?;
+            let mut nodes = Vec::new();
+            for target in targets {
+                let maybe_target = block_on(get_entry_with_meta_workflow(
+                    context,
+                    &target,
+                    &GetEntryOptions::default(),
+                ))?;
+                let included = match maybe_target {
+                    Some(ref target) => query.includes_entry_type(&target.entry),
+                    None => query.entry_types.is_empty(),
+                };
+                if included {
+                    nodes.push(resolve_node(
+                        context,
+                        query,
+                        target,
+                        maybe_target,
+                        remaining_depth - 1,
+                        path,
+                    )?);
+                }
+            }
+            links.insert(tag.clone(), nodes);
+        }
+        path.remove(&address);
+    }
+
+    Ok(GraphNode {
+        address,
+        crud_status: maybe_entry_with_meta
+            .as_ref()
+            .map(|entry_with_meta| entry_with_meta.crud_status),
+        entry: maybe_entry_with_meta.map(|entry_with_meta| entry_with_meta.entry),
+        links,
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core_types::entry::{test_entry, test_sys_entry};
+
+    fn test_query() -> GraphQuery {
+        GraphQuery {
+            base: Address::from("QmBase"),
+            link_tags: vec![String::from("comments")],
+            entry_types: Vec::new(),
+            depth: 2,
+        }
+    }
+
+    #[test]
+    fn graph_query_check_test() {
+        let mut query = test_query();
+        assert!(query.check().is_ok());
+        query.depth = MAX_GRAPH_QUERY_DEPTH + 1;
+        assert!(query.check().is_err());
+    }
+
+    #[test]
+    fn graph_query_entry_type_filter_test() {
+        let mut query = test_query();
+        assert!(query.includes_entry_type(&test_entry()));
+        assert!(query.includes_entry_type(&test_sys_entry()));
+
+        query.entry_types = vec![String::from(test_entry().entry_type())];
+        assert!(query.includes_entry_type(&test_entry()));
+        assert!(!query.includes_entry_type(&test_sys_entry()));
+    }
+
+    #[test]
+    fn graph_query_deserialize_defaults_test() {
+        let query: GraphQuery = serde_json::from_str(r#"{"base": "QmBase"}"#).unwrap();
+        assert_eq!(query.depth, 0);
+        assert!(query.link_tags.is_empty());
+        assert!(query.entry_types.is_empty());
+    }
+}
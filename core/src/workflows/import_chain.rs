@@ -0,0 +1,269 @@
+use crate::{
+    agent::actions::{import_chain::import_chain, update_entry::update_entry},
+    context::Context,
+    dht::actions::{hold::hold_entry, remove_entry::remove_entry},
+    nucleus::actions::validate::validate_entry,
+};
+
+use holochain_core_types::{
+    agent::AgentId,
+    cas::content::{Address, AddressableContent},
+    chain_header::ChainHeader,
+    entry::{entry_type::EntryType, Entry},
+    error::HolochainError,
+    signature::Signature,
+    validation::{EntryAction, EntryLifecycle, ValidationData, ValidationPackage},
+};
+use holochain_sodium::{secbuf::SecBuf, sign};
+use std::sync::Arc;
+
+/// Replaces the source chain of the given context's instance with the given headers and
+/// entries, oldest first, e.g. one exported from another container.
+///
+/// The chain has to be well formed, start with this instance's DNA and be authored by this
+/// instance's agent. Every header has to be signed by the agent, unless `allow_unsigned` is
+/// set, which is needed for chains authored before headers got signed.
+/// Every entry gets validated by the DNA like it would have been when it got committed,
+/// with the imported chain before it as validation package.
+/// The entries get held in the local DHT shard like committed ones, including the meta data
+/// of updates and deletions. Nothing gets published: the entries got published when they were
+/// authored.
+/// Returns the address of the new top chain header.
+pub async fn import_chain_workflow<'a>(
+    chain: Vec<(ChainHeader, Entry)>,
+    allow_unsigned: bool,
+    context: &'a Arc<Context>,
+) -> Result<Address, HolochainError> {
+    // 1. Check that the chain is intact and belongs to this instance
+    check_chain(&chain, allow_unsigned, context)?;
+
+    // 2. Validate the entries
+    for (index, (header, entry)) in chain.iter().enumerate() {
+        if entry.entry_type() == EntryType::Dna {
+            continue;
+        }
+        context.log(format!(
+            "debug/workflow/import_chain/{}: validating...",
+            entry.address()
+        ));
+        let previous_public = chain[..index]
+            .iter()
+            .rev()
+            .filter(|(header, _)| header.entry_type().can_publish());
+        let validation_data = ValidationData {
+            package: ValidationPackage {
+                chain_header: Some(header.clone()),
+                source_chain_entries: Some(
+                    previous_public
+                        .clone()
+                        .map(|(_, entry)| entry.clone())
+                        .collect(),
+                ),
+                source_chain_headers: Some(
+                    previous_public.map(|(header, _)| header.clone()).collect(),
+                ),
+                custom: None,
+            },
+            sources: header.sources().clone(),
+            lifecycle: EntryLifecycle::Chain,
+            action: match (entry, header.link_crud()) {
+                (Entry::Deletion(_), _) => EntryAction::Delete,
+                (_, Some(_)) => EntryAction::Modify,
+                (_, None) => EntryAction::Create,
+            },
+        };
+        await!(validate_entry(entry.clone(), validation_data, &context)).map_err(|error| {
+            HolochainError::ValidationFailed(format!(
+                "Entry {} of the imported chain is invalid: {}",
+                index, error
+            ))
+        })?;
+    }
+
+    // 3. Replace the chain
+    let top_chain_header_address = await!(import_chain(chain.clone(), &context))?;
+
+    // 4. Hold the entries in the local DHT shard and record updates and deletions
+    for (_, entry) in chain.iter() {
+        await!(hold_entry(entry, &context))?;
+    }
+    for (header, entry) in chain.iter() {
+        match (entry, header.link_crud()) {
+            // Deletions of entries authored by other agents are up to the DHT holding them
+            (Entry::Deletion(deletion), _) => {
+                let deleted_address = deletion.clone().deleted_entry_address();
+                if chain
+                    .iter()
+                    .any(|(_, entry)| entry.address() == deleted_address)
+                {
+                    await!(remove_entry(
+                        &context,
+                        context.action_channel(),
+                        deleted_address,
+                        entry.address(),
+                    ))?;
+                }
+            }
+            // Updates link to the header of the old revision, see check_chain()
+            (_, Some(old_header_address)) => {
+                let old_address = chain
+                    .iter()
+                    .find(|(header, _)| header.address() == old_header_address)
+                    .map(|(header, _)| header.entry_address().clone())
+                    .expect("check_chain() makes sure updates link to a previous header");
+                await!(update_entry(
+                    &context,
+                    context.action_channel(),
+                    old_address,
+                    entry.address(),
+                ))?;
+            }
+            _ => (),
+        }
+    }
+
+    Ok(top_chain_header_address)
+}
+
+/// Checks that the headers link up and match their entries, the chain starts with the DNA of
+/// the instance followed by its agent, the agent is the source and signer of all headers, and
+/// updates link to the header of an earlier entry.
+fn check_chain(
+    chain: &[(ChainHeader, Entry)],
+    allow_unsigned: bool,
+    context: &Arc<Context>,
+) -> Result<(), HolochainError> {
+    let dna = context
+        .get_dna()
+        .ok_or_else(|| HolochainError::ErrorGeneric(String::from("Instance has no DNA")))?;
+    let dna_address = Entry::Dna(dna).address();
+    match chain.get(0) {
+        Some((_, entry @ Entry::Dna(_))) if entry.address() == dna_address => (),
+        _ => {
+            return Err(HolochainError::ValidationFailed(String::from(
+                "Imported chain does not start with the DNA of this instance",
+            )))
+        }
+    }
+    match chain.get(1) {
+        Some((_, Entry::AgentId(agent_id))) if *agent_id == context.agent_id => (),
+        _ => {
+            return Err(HolochainError::ValidationFailed(String::from(
+                "Imported chain does not belong to the agent of this instance",
+            )))
+        }
+    }
+
+    let agent_address = context.agent_id.address();
+    let mut previous: Option<&ChainHeader> = None;
+    for (index, (header, entry)) in chain.iter().enumerate() {
+        let invalid = |reason: &str| {
+            Err(HolochainError::ValidationFailed(format!(
+                "Header {} of the imported chain {}",
+                index, reason
+            )))
+        };
+        if *header.entry_address() != entry.address() || *header.entry_type() != entry.entry_type()
+        {
+            return invalid("does not match its entry");
+        }
+        if header.link() != previous.map(|previous| previous.address()) {
+            return invalid("does not link to the header before it");
+        }
+        let previous_same_type = chain[..index]
+            .iter()
+            .rev()
+            .find(|(previous, _)| previous.entry_type() == header.entry_type())
+            .map(|(previous, _)| previous.address());
+        if header.link_same_type() != previous_same_type {
+            return invalid("does not link to the header of the same type before it");
+        }
+        if *header.sources() != vec![agent_address.clone()] {
+            return invalid("has another source than the agent of this instance");
+        }
+        match (entry, header.link_crud()) {
+            (Entry::Deletion(_), _) | (_, None) => (),
+            (_, Some(old_header_address)) => {
+                if !chain[..index]
+                    .iter()
+                    .any(|(previous, _)| previous.address() == old_header_address)
+                {
+                    return invalid("updates an entry that is not on the chain before it");
+                }
+            }
+        }
+        let message = String::from(header.entry_address().clone());
+        let signatures = header
+            .entry_signatures()
+            .iter()
+            .filter(|signature| !String::from((*signature).clone()).is_empty())
+            .collect::<Vec<_>>();
+        if signatures.is_empty() {
+            if !allow_unsigned {
+                return invalid("is not signed, import with allow_unsigned to accept that");
+            }
+        } else if !signatures
+            .iter()
+            .all(|signature| is_valid_signature(signature, &message, &context.agent_id))
+        {
+            return invalid("has an invalid signature");
+        }
+        previous = Some(header);
+    }
+    Ok(())
+}
+
+/// A valid signature is the agent's signature of the entry address.
+/// Source chains don't get signed yet (see create_new_chain_header()), so their headers only
+/// carry an empty placeholder, which is no valid signature: check_chain() only accepts those
+/// headers as unsigned if the import allows that.
+fn is_valid_signature(signature: &Signature, message: &str, agent_id: &AgentId) -> bool {
+    let signature = String::from(signature.clone());
+    let signature_bytes = match base64::decode(&signature) {
+        Ok(ref bytes) if bytes.len() == sign::BYTES => bytes.clone(),
+        _ => return false,
+    };
+    let mut signature_buf = SecBuf::with_insecure(sign::BYTES);
+    signature_buf.write_lock().copy_from_slice(&signature_bytes);
+    let mut message_buf = SecBuf::with_insecure(message.len());
+    message_buf.write_lock().copy_from_slice(message.as_bytes());
+    let public_key_bytes = agent_id.to_buffer();
+    let public_key_bytes = public_key_bytes.get_sig();
+    let mut public_key = SecBuf::with_insecure(public_key_bytes.len());
+    public_key.write_lock().copy_from_slice(public_key_bytes);
+    sign::verify(&mut signature_buf, &mut message_buf, &mut public_key) == 0
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core_types::agent::KeyBuffer;
+
+    #[test]
+    fn import_chain_accepts_agent_signatures_only() {
+        let mut seed = SecBuf::with_secure(sign::SEEDBYTES);
+        let mut public_key = SecBuf::with_insecure(sign::PUBLICKEYBYTES);
+        let mut secret_key = SecBuf::with_secure(sign::SECRETKEYBYTES);
+        holochain_sodium::random::random_secbuf(&mut seed);
+        sign::seed_keypair(&mut public_key, &mut secret_key, &mut seed).unwrap();
+        let mut key = [0; 64];
+        key[..32].copy_from_slice(&public_key.read_lock());
+        let agent_id = AgentId::new("bob", &KeyBuffer::with_raw(&key));
+
+        let message = "QmEntryAddress";
+        let mut message_buf = SecBuf::with_insecure(message.len());
+        message_buf.write_lock().copy_from_slice(message.as_bytes());
+        let mut signature_buf = SecBuf::with_insecure(sign::BYTES);
+        sign::sign(&mut message_buf, &mut secret_key, &mut signature_buf).unwrap();
+        let signature = Signature::from(base64::encode(&**signature_buf.read_lock()));
+
+        assert!(is_valid_signature(&signature, message, &agent_id));
+        assert!(!is_valid_signature(&Signature::from(""), message, &agent_id));
+        assert!(!is_valid_signature(&signature, "QmOtherAddress", &agent_id));
+        assert!(!is_valid_signature(
+            &Signature::from("fake-signature"),
+            message,
+            &agent_id
+        ));
+    }
+}
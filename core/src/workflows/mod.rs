@@ -1,7 +1,10 @@
 pub mod application;
 pub mod author_entry;
 pub mod get_entry_result;
+pub mod get_links_result;
+pub mod graph_query;
 pub mod handle_custom_direct_message;
 pub mod hold_entry;
 pub mod hold_link;
+pub mod import_chain;
 pub mod respond_validation_package_request;
@@ -32,6 +32,13 @@ impl KeyBuffer {
     pub fn with_corrected(s: &str) -> Result<KeyBuffer, HolochainError> {
         let s = s.replace("-", "+").replace("_", "/");
         let base64 = base64::decode(&s)?;
+        if base64.len() != KeyBuffer::KEY_LEN + KeyBuffer::PARITY_LEN {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Identity key has {} bytes instead of {}",
+                base64.len(),
+                KeyBuffer::KEY_LEN + KeyBuffer::PARITY_LEN
+            )));
+        }
         let dec = Decoder::new(KeyBuffer::PARITY_LEN);
         let dec = *dec.correct(base64.as_slice(), None)?;
         Ok(KeyBuffer::with_raw(array_ref![dec, 0, KeyBuffer::KEY_LEN]))
@@ -191,6 +198,12 @@ mod tests {
         assert!(res.is_err())
     }
 
+    #[test]
+    fn it_fails_if_wrong_length() {
+        let res = test_base64_to_agent_id(&GOOD_ID[4..]);
+        assert!(res.is_err())
+    }
+
     #[test]
     /// show ToString implementation for Agent
     fn agent_to_string_test() {
@@ -23,7 +23,7 @@ use uuid::Uuid;
 /// content addressable store (CAS)
 /// implements storage in memory or persistently
 /// anything implementing AddressableContent can be added and fetched by address
-/// CAS is append only
+/// CAS is append only, except for remove() which rolls back content nothing refers to
 pub trait ContentAddressableStorage: objekt::Clone + Send + Sync + Debug {
     /// adds AddressableContent to the ContentAddressableStorage by its Address as Content
     fn add(&mut self, content: &AddressableContent) -> Result<(), HolochainError>;
@@ -34,6 +34,10 @@ pub trait ContentAddressableStorage: objekt::Clone + Send + Sync + Debug {
     /// AddressableContent::from_content() can be used to allow the compiler to infer the type
     /// @see the fetch implementation for ExampleCas in the cas module tests
     fn fetch(&self, address: &Address) -> Result<Option<Content>, HolochainError>;
+    /// removes the Content at the given Address, if there is any.
+    /// only meant for content that got added by a failed or superseded operation and that
+    /// nothing else refers to, e.g. the headers of a replaced source chain.
+    fn remove(&mut self, address: &Address) -> Result<(), HolochainError>;
     //needed to find a way to compare two different CAS for partialord derives.
     //easiest solution was to just compare two ids which are based on uuids
     fn get_id(&self) -> Uuid;
@@ -83,6 +87,10 @@ impl ContentAddressableStorage for ExampleContentAddressableStorage {
         Ok(self.content.read()?.unthreadable_fetch(address)?)
     }
 
+    fn remove(&mut self, address: &Address) -> Result<(), HolochainError> {
+        self.content.write()?.unthreadable_remove(address)
+    }
+
     fn get_id(&self) -> Uuid {
         Uuid::new_v4()
     }
@@ -117,6 +125,11 @@ impl ExampleContentAddressableStorageContent {
     fn unthreadable_fetch(&self, address: &Address) -> Result<Option<Content>, HolochainError> {
         Ok(self.storage.get(address).cloned())
     }
+
+    fn unthreadable_remove(&mut self, address: &Address) -> Result<(), HolochainError> {
+        self.storage.remove(address);
+        Ok(())
+    }
 }
 
 // A struct for our test suite that infers a type of ContentAddressableStorage
@@ -191,6 +204,17 @@ where
             );
         }
 
+        // removed content is gone for all clones, other content stays
+        assert_eq!(Ok(()), self.cas.remove(&other_content.address()));
+        // removing missing content is not an error
+        assert_eq!(Ok(()), self.cas_clone.remove(&other_content.address()));
+
+        for cas in both_cas.iter() {
+            assert_eq!(Ok(true), cas.contains(&content.address()));
+            assert_eq!(Ok(false), cas.contains(&other_content.address()));
+            assert_eq!(Ok(None), cas.fetch(&other_content.address()));
+        }
+
         // show consistent view on data across threads
 
         let entry = test_entry_unique();
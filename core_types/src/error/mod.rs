@@ -4,5 +4,6 @@
 mod dna_error;
 pub mod error;
 mod ribosome_error;
+mod zome_function_error;
 
-pub use self::{dna_error::*, error::*, ribosome_error::*};
+pub use self::{dna_error::*, error::*, ribosome_error::*, zome_function_error::*};
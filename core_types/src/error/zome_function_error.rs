@@ -0,0 +1,91 @@
+use crate::json::JsonString;
+use serde_json::{self, Value};
+use std::{error::Error, fmt};
+
+/// Structured application error a zome function can return to its caller.
+///
+/// Zome functions return a `ZomeApiResult<T>`. If the error side of that result is a
+/// `ZomeApiError::Structured(ZomeFunctionError)`, the JSON the zome function returns is:
+///
+/// `{"Err":{"Structured":{"code":<i64>,"message":<string>,"data":<any JSON or null>}}}`
+///
+/// Containers can recognize this shape with `from_zome_function_result()` and hand the
+/// fields to clients as-is (e.g. as a JSON-RPC error object) so that clients can branch
+/// on application defined error codes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ZomeFunctionError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default)]
+    pub data: Option<Value>,
+}
+
+impl ZomeFunctionError {
+    pub fn new(code: i64, message: &str) -> Self {
+        ZomeFunctionError {
+            code,
+            message: message.to_string(),
+            data: None,
+        }
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Extracts the structured error from the JSON result of a zome function call.
+    /// Returns None if the result is not an error in the shape described above.
+    pub fn from_zome_function_result(result: &JsonString) -> Option<Self> {
+        let value: Value = serde_json::from_str(&String::from(result)).ok()?;
+        let structured = value.get("Err")?.get("Structured")?;
+        serde_json::from_value(structured.clone()).ok()
+    }
+}
+
+impl Error for ZomeFunctionError {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ZomeFunctionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (code {})", self.message, self.code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_zome_function_result_test() {
+        let result = JsonString::from(
+            "{\"Err\":{\"Structured\":{\"code\":404,\"message\":\"not found\",\"data\":{\"id\":1}}}}",
+        );
+        assert_eq!(
+            ZomeFunctionError::from_zome_function_result(&result),
+            Some(ZomeFunctionError::new(404, "not found").with_data(json!({"id": 1}))),
+        );
+
+        let result = JsonString::from("{\"Err\":{\"Structured\":{\"code\":1,\"message\":\"m\"}}}");
+        assert_eq!(
+            ZomeFunctionError::from_zome_function_result(&result),
+            Some(ZomeFunctionError::new(1, "m")),
+        );
+
+        for result in vec![
+            "{\"Ok\":\"foo\"}",
+            "{\"Err\":{\"Internal\":\"foo\"}}",
+            "{\"Err\":\"FunctionNotImplemented\"}",
+            "\"foo\"",
+            "not json",
+        ] {
+            assert_eq!(
+                ZomeFunctionError::from_zome_function_result(&JsonString::from(result)),
+                None,
+            );
+        }
+    }
+}
@@ -14,6 +14,18 @@ impl From<&'static str> for Signature {
     }
 }
 
+impl From<String> for Signature {
+    fn from(s: String) -> Signature {
+        Signature(s)
+    }
+}
+
+impl From<Signature> for String {
+    fn from(signature: Signature) -> String {
+        signature.0
+    }
+}
+
 pub fn test_signatures() -> Vec<Signature> {
     vec![Signature::from("fake-signature")]
 }
@@ -1,7 +1,7 @@
 //! This file contains defitions for Zome errors and also Zome Results.
 
 use crate::holochain_core_types::{
-    error::{HolochainError, RibosomeErrorCode},
+    error::{HolochainError, RibosomeErrorCode, ZomeFunctionError},
     json::{JsonError, JsonString},
 };
 use std::{error::Error, fmt};
@@ -15,6 +15,9 @@ pub enum ZomeApiError {
     HashNotFound,
     ValidationFailed(String),
     Timeout,
+    /// Application defined error with a code, message and optional data.
+    /// Containers can be configured to pass these on to clients as structured errors.
+    Structured(ZomeFunctionError),
 }
 
 impl JsonError for ZomeApiError {}
@@ -71,6 +74,7 @@ impl Error for ZomeApiError {
             ZomeApiError::HashNotFound            => "Hash not found",
             ZomeApiError::ValidationFailed(msg)   => &msg,
             ZomeApiError::Timeout                 => "Timeout",
+            ZomeApiError::Structured(error)       => &error.message,
         }
     }
 }
@@ -91,7 +95,7 @@ pub type ZomeApiResult<T> = Result<T, ZomeApiError>;
 mod tests {
 
     use error::{ZomeApiError, ZomeApiResult};
-    use holochain_core_types::json::JsonString;
+    use holochain_core_types::{error::ZomeFunctionError, json::JsonString};
 
     #[test]
     fn zome_api_result_json_result_round_trip_test() {
@@ -102,4 +106,18 @@ mod tests {
             JsonString::from("{\"Err\":\"FunctionNotImplemented\"}"),
         );
     }
+
+    #[test]
+    fn zome_api_result_structured_error_json_test() {
+        let result: ZomeApiResult<String> = Err(ZomeApiError::Structured(
+            ZomeFunctionError::new(42, "Invalid post"),
+        ));
+
+        assert_eq!(
+            JsonString::from(result),
+            JsonString::from(
+                "{\"Err\":{\"Structured\":{\"code\":42,\"message\":\"Invalid post\",\"data\":null}}}"
+            ),
+        );
+    }
 }
@@ -202,6 +202,7 @@ pub fn handle_remove_entry_ok() -> JsonString {
         Ok(result) => match result.result {
             GetEntryResultType::Single(item) => item.into(),
             GetEntryResultType::All(history) => history.into(),
+            GetEntryResultType::Count(revisions) => revisions.into(),
         },
         Err(e) => e.into(),
     }
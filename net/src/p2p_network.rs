@@ -13,7 +13,8 @@ use super::{ipc_net_worker::IpcNetWorker, mock_worker::MockWorker, p2p_config::*
 
 /// The p2p network instance
 pub struct P2pNetwork {
-    connection: NetConnectionThread,
+    /// None once the network got stopped
+    connection: Option<NetConnectionThread>,
 }
 
 impl std::fmt::Debug for P2pNetwork {
@@ -25,7 +26,10 @@ impl std::fmt::Debug for P2pNetwork {
 impl NetConnection for P2pNetwork {
     /// send a Protocol message to the p2p network instance
     fn send(&mut self, data: Protocol) -> NetResult<()> {
-        self.connection.send(data)
+        match self.connection {
+            Some(ref mut connection) => connection.send(data),
+            None => bail!("P2pNetwork has been stopped"),
+        }
     }
 }
 
@@ -55,16 +59,25 @@ impl P2pNetwork {
                 None,
             )?,
         };
-        Ok(P2pNetwork { connection })
+        Ok(P2pNetwork {
+            connection: Some(connection),
+        })
     }
 
     /// stop the network module (disconnect any sockets, join any threads, etc)
-    pub fn stop(self) -> NetResult<()> {
-        self.connection.stop()
+    /// Sending fails afterwards, stopping again does nothing.
+    pub fn stop(&mut self) -> NetResult<()> {
+        match self.connection.take() {
+            Some(connection) => connection.stop(),
+            None => Ok(()),
+        }
     }
 
     pub fn endpoint(&self) -> String {
-        self.connection.endpoint.clone()
+        self.connection
+            .as_ref()
+            .map(|connection| connection.endpoint.clone())
+            .unwrap_or_default()
     }
 }
 
@@ -88,5 +101,7 @@ mod tests {
         let mut res = P2pNetwork::new(Box::new(|_r| Ok(())), &P2pConfig::unique_mock()).unwrap();
         res.send(Protocol::P2pReady).unwrap();
         res.stop().unwrap();
+        assert!(res.send(Protocol::P2pReady).is_err());
+        res.stop().unwrap();
     }
 }
@@ -28,7 +28,7 @@ pub fn ipc_spawn(
         .envs(&env)
         .current_dir(work_dir);
 
-    println!("SPAWN ({:?})", child);
+    eprintln!("SPAWN ({:?})", child);
 
     let mut child = child.spawn()?;
 
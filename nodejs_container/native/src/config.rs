@@ -3,9 +3,10 @@ use holochain_container_api::{
         AgentConfiguration, Configuration, DnaConfiguration, InstanceConfiguration,
         LoggerConfiguration, StorageConfiguration,
     },
-    logger::LogRules,
+    container::Container,
+    logger::{LogFormat, LogRules},
 };
-use holochain_core_types::agent::AgentId;
+use holochain_core_types::{agent::AgentId, cas::content::AddressableContent};
 use neon::prelude::*;
 use std::{collections::HashMap, path::PathBuf};
 
@@ -46,6 +47,7 @@ fn make_config(instance_data: Vec<InstanceData>) -> Configuration {
                 name: agent_name.clone(),
                 public_address: agent_key.key,
                 key_file: format!("fake/key/{}", agent_name),
+                keystore_passphrase: None,
             }
         });
         let dna_config = dna_configs
@@ -59,6 +61,10 @@ fn make_config(instance_data: Vec<InstanceData>) -> Configuration {
             agent: agent_id,
             dna: dna_id,
             storage: StorageConfiguration::Memory,
+            network: None,
+            priority: 0,
+            validation_timeout_ms: None,
+            prioritized_calls: false,
         };
         instance_configs.push(instance);
     }
@@ -72,6 +78,9 @@ fn make_config(instance_data: Vec<InstanceData>) -> Configuration {
         logger: LoggerConfiguration {
             logger_type: "debug".to_string(),
             rules: LogRules::new(),
+            format: LogFormat::Text,
+            file: None,
+            max_size_bytes: None,
         },
         ..Default::default()
     };
@@ -84,14 +93,10 @@ fn instance_id(agent_id: &str, dna_id: &str) -> String {
 
 fn make_dna_config(dna: DnaData) -> Result<DnaConfiguration, String> {
     let path = dna.path.to_string_lossy().to_string();
+    let dna = Container::load_dna(&path).map_err(|e| e.to_string())?;
     Ok(DnaConfiguration {
         id: path.clone(),
-        hash: String::from("DONTCARE"),
+        hash: dna.address().to_string(),
         file: path,
     })
-    // eventually can get actual file content to calculate hash and stuff,
-    // but for now it doesn't matter so don't care...
-
-    // let temp = DnaConfiguration {id: "", hash: "", file: dna_path};
-    // let dna = Dna::try_from(temp).map_err(|e| e.to_string())?;
 }
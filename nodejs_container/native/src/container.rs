@@ -50,9 +50,10 @@ declare_types! {
             let start_result: Result<(), String> = {
                 let guard = cx.lock();
                 let hab = &mut *this.borrow_mut(&guard);
-                hab.container.load_config().and_then(|_| {
-                    hab.container.start_all_instances().map_err(|e| e.to_string())
-                })
+                hab.container
+                    .load_config()
+                    .map_err(|e| e.to_string())
+                    .and_then(|_| hab.container.start_all_instances().map_err(|e| e.to_string()))
             };
 
             start_result.or_else(|e| {
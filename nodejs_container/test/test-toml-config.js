@@ -3,6 +3,8 @@ const test = require('tape');
 const { Container } = require('..')
 
 const toml = `
+warn_on_dna_hash_mismatch = true
+
 [[agents]]
 id = "test/agent/1"
 name = "Holo Tester 1"
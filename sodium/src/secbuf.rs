@@ -7,7 +7,7 @@ use super::check_init;
 
 /// a trait for structures that can be used as a backing store for SecBuf
 pub trait Bufferable {
-    fn new(s: usize) -> Box<Bufferable>
+    fn new(s: usize) -> Box<Bufferable + Send>
     where
         Self: Sized;
     fn len(&self) -> usize;
@@ -25,7 +25,7 @@ struct RustBuf {
 }
 
 impl Bufferable for RustBuf {
-    fn new(s: usize) -> Box<Bufferable> {
+    fn new(s: usize) -> Box<Bufferable + Send> {
         let b = vec![0; s].into_boxed_slice();
         Box::new(RustBuf { b })
     }
@@ -55,9 +55,13 @@ struct SodiumBuf {
     s: usize,
 }
 
+/// A SodiumBuf exclusively owns the memory behind its pointer, so it can be moved
+/// to other threads.
+unsafe impl Send for SodiumBuf {}
+
 impl Bufferable for SodiumBuf {
     /// warning: funky sizes may result in mis-alignment
-    fn new(s: usize) -> Box<Bufferable> {
+    fn new(s: usize) -> Box<Bufferable + Send> {
         if s != 8 && s != 16 && s != 32 && s != 64 {
             panic!("bad buffer size: {}, disallowing this for safety", s);
         }
@@ -124,7 +128,7 @@ pub enum ProtectState {
 /// It can be backed by insecure (raw) memory for things like public keys,
 /// or secure (mlocked / mprotected) memory for things like private keys.
 pub struct SecBuf {
-    b: Box<Bufferable>,
+    b: Box<Bufferable + Send>,
     p: ProtectState,
 }
 
@@ -1,6 +1,10 @@
 //! This module provides access to libsodium
 use super::{check_init, secbuf::SecBuf};
 use crate::error::SodiumResult;
+pub const PUBLICKEYBYTES: usize = rust_sodium_sys::crypto_sign_PUBLICKEYBYTES as usize;
+pub const SECRETKEYBYTES: usize = rust_sodium_sys::crypto_sign_SECRETKEYBYTES as usize;
+pub const SEEDBYTES: usize = rust_sodium_sys::crypto_sign_SEEDBYTES as usize;
+pub const BYTES: usize = rust_sodium_sys::crypto_sign_BYTES as usize;
 
 /// Generate a signing keypair from a seed buffer
 ///
@@ -12,6 +12,8 @@ pub enum StatusRequestKind {
     Initial,
     Latest,
     All,
+    /// Follow the whole crud chain like Latest, but only count the revisions
+    Count,
 }
 impl Default for StatusRequestKind {
     fn default() -> Self {
@@ -19,6 +21,41 @@ impl Default for StatusRequestKind {
     }
 }
 
+/// What to do when following a crud-link leads to an entry that is inconsistent with
+/// the entry the link originates from (i.e. corrupted or malicious CRUD metadata).
+#[derive(Deserialize, Debug, Serialize, DefaultJson, Clone, PartialEq)]
+pub enum CrudLinkPolicy {
+    /// End the history walk and return the entries found so far
+    Stop,
+    /// Fail the whole request with an error
+    Error,
+    /// Leave out the inconsistent entry but keep following its crud-link
+    Skip,
+}
+impl Default for CrudLinkPolicy {
+    fn default() -> Self {
+        CrudLinkPolicy::Stop
+    }
+}
+
+/// Where to look for an entry and in which order.
+#[derive(Deserialize, Debug, Serialize, DefaultJson, Clone, PartialEq)]
+pub enum GetStrategy {
+    /// Look in the local DHT shard and only ask the network if it isn't there
+    LocalFirst,
+    /// Ask the network and only fall back to the local DHT shard if it doesn't have it
+    NetworkFirst,
+    /// Only look in the local DHT shard, never touching the network
+    LocalOnly,
+    /// Only ask the network, ignoring any local copy
+    NetworkOnly,
+}
+impl Default for GetStrategy {
+    fn default() -> Self {
+        GetStrategy::LocalFirst
+    }
+}
+
 /// Structure used to specify what should be returned to a call to get_entry_result()
 /// The default is to return the latest entry.
 #[derive(Deserialize, Debug, Serialize, DefaultJson, PartialEq, Clone)]
@@ -27,6 +64,34 @@ pub struct GetEntryOptions {
     pub entry: bool,
     pub header: bool,
     pub sources: bool,
+    #[serde(default)]
+    pub crud_link_policy: CrudLinkPolicy,
+    #[serde(default)]
+    pub strategy: GetStrategy,
+    /// StatusRequestKind::Latest only: return the latest revision even if it is deleted,
+    /// with its Deleted status, instead of reporting the entry as not found.
+    /// A Count of a deleted entry still counts as not found.
+    #[serde(default)]
+    pub include_deleted: bool,
+    /// How often a network look-up that found nothing gets repeated before the entry counts
+    /// as not found, for entries that might not have reached the DHT yet, e.g. ones that
+    /// just got published. Look-ups in the local DHT shard are not repeated. Default is 0.
+    #[serde(default)]
+    pub network_retries: u32,
+    /// How long to wait before repeating a network look-up
+    #[serde(default = "default_network_retry_delay_ms")]
+    pub network_retry_delay_ms: u64,
+    /// Address of a known later revision of the requested entry to start following the
+    /// crud chain from, instead of the requested address, when the history before it is
+    /// not of interest. Getting fails unless the local DHT shard holds the crud-links from
+    /// it back to the requested entry. Revision indices still count from the requested entry.
+    /// Ignored for StatusRequestKind::Initial.
+    #[serde(default)]
+    pub start_from: Option<Address>,
+}
+
+pub fn default_network_retry_delay_ms() -> u64 {
+    500
 }
 
 impl Default for GetEntryOptions {
@@ -36,6 +101,12 @@ impl Default for GetEntryOptions {
             entry: true,
             header: false,
             sources: false,
+            crud_link_policy: CrudLinkPolicy::default(),
+            strategy: GetStrategy::default(),
+            include_deleted: false,
+            network_retries: 0,
+            network_retry_delay_ms: default_network_retry_delay_ms(),
+            start_from: None,
         }
     }
 }
@@ -52,8 +123,40 @@ impl GetEntryOptions {
             entry,
             header,
             sources,
+            crud_link_policy: CrudLinkPolicy::default(),
+            strategy: GetStrategy::default(),
+            include_deleted: false,
+            network_retries: 0,
+            network_retry_delay_ms: default_network_retry_delay_ms(),
+            start_from: None,
         }
     }
+
+    pub fn with_crud_link_policy(mut self, crud_link_policy: CrudLinkPolicy) -> Self {
+        self.crud_link_policy = crud_link_policy;
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: GetStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn with_include_deleted(mut self, include_deleted: bool) -> Self {
+        self.include_deleted = include_deleted;
+        self
+    }
+
+    pub fn with_network_retries(mut self, retries: u32, delay_ms: u64) -> Self {
+        self.network_retries = retries;
+        self.network_retry_delay_ms = delay_ms;
+        self
+    }
+
+    pub fn with_start_from(mut self, start_from: Address) -> Self {
+        self.start_from = Some(start_from);
+        self
+    }
 }
 
 #[derive(Deserialize, Debug, Serialize, DefaultJson)]
@@ -77,6 +180,11 @@ pub struct EntryResultMeta {
 pub struct GetEntryResultItem {
     pub meta: Option<EntryResultMeta>,
     pub entry: Option<Entry>,
+    /// Position of the item in the crud chain, 0 being the requested entry. Revisions left
+    /// out by CrudLinkPolicy::Skip keep their position, so indices can have gaps.
+    /// Only set for the items of an EntryHistory.
+    #[serde(default)]
+    pub revision: Option<usize>,
 }
 impl GetEntryResultItem {
     pub fn new(maybe_entry_with_meta: Option<&EntryWithMeta>) -> Self {
@@ -88,33 +196,60 @@ impl GetEntryResultItem {
                     crud_status: entry_with_meta.crud_status,
                 }),
                 entry: Some(entry_with_meta.entry.clone()),
+                revision: None,
             },
             _ => GetEntryResultItem {
                 meta: None,
                 entry: None,
+                revision: None,
             },
         }
     }
 }
 
 /// Structure that holds a whole crud status history if the status request
-/// in the GetEntryOptions was set to StatusRequestKind::All
+/// in the GetEntryOptions was set to StatusRequestKind::All.
+/// Items are always ordered oldest first, i.e. the requested entry followed by its
+/// revisions in the order of the crud-links, and carry their `revision` index. Entries
+/// don't have a timestamp, the revision index is what tells their chronology.
+/// `current` is the index into `items` of the item the crud chain ends with, i.e. the
+/// first item that is not Modified, which has the Deleted status if the entry got deleted.
+/// It is None if the walk stopped before reaching the end of the chain.
 #[derive(Deserialize, Debug, Serialize, DefaultJson, Clone)]
 pub struct EntryHistory {
     pub items: Vec<GetEntryResultItem>,
     pub crud_links: HashMap<Address, Address>,
+    #[serde(default)]
+    pub current: Option<usize>,
 }
 impl EntryHistory {
     pub fn new() -> Self {
         EntryHistory {
             items: Vec::new(),
             crud_links: HashMap::new(),
+            current: None,
         }
     }
 
+    /// Adds the revision following the last item
     pub fn push(&mut self, entry_with_meta: &EntryWithMeta) {
+        let revision = self
+            .items
+            .last()
+            .and_then(|item| item.revision)
+            .map(|revision| revision + 1)
+            .unwrap_or(0);
+        self.push_revision(entry_with_meta, revision);
+    }
+
+    /// Adds the item at the given position in the crud chain
+    pub fn push_revision(&mut self, entry_with_meta: &EntryWithMeta, revision: usize) {
         let address = entry_with_meta.entry.address();
-        let item = GetEntryResultItem::new(Some(entry_with_meta));
+        let mut item = GetEntryResultItem::new(Some(entry_with_meta));
+        item.revision = Some(revision);
+        if self.current.is_none() && entry_with_meta.crud_status != CrudStatus::Modified {
+            self.current = Some(self.items.len());
+        }
         self.items.push(item);
         if let Some(new_address) = entry_with_meta.maybe_crud_link.clone() {
             self.crud_links.insert(address, new_address);
@@ -122,10 +257,34 @@ impl EntryHistory {
     }
 }
 
+/// Structure that holds the number of revisions found if the status request
+/// in the GetEntryOptions was set to StatusRequestKind::Count.
+/// As with StatusRequestKind::Latest, a crud chain ending in a deleted entry
+/// counts as not found.
+#[derive(Deserialize, Debug, Serialize, DefaultJson, Clone, PartialEq)]
+pub struct RevisionCount {
+    pub count: usize,
+    pub latest_address: Option<Address>,
+}
+impl RevisionCount {
+    pub fn new() -> Self {
+        RevisionCount {
+            count: 0,
+            latest_address: None,
+        }
+    }
+
+    pub fn push(&mut self, entry_with_meta: &EntryWithMeta) {
+        self.count += 1;
+        self.latest_address = Some(entry_with_meta.entry.address());
+    }
+}
+
 #[derive(Deserialize, Debug, Serialize, DefaultJson, Clone)]
 pub enum GetEntryResultType {
     Single(GetEntryResultItem),
     All(EntryHistory),
+    Count(RevisionCount),
 }
 
 #[derive(Deserialize, Debug, Serialize, DefaultJson, Clone)]
@@ -149,6 +308,15 @@ impl GetEntryResult {
                 }
                 entry_result
             }
+            StatusRequestKind::Count => {
+                let mut entry_result = GetEntryResult {
+                    result: GetEntryResultType::Count(RevisionCount::new()),
+                };
+                if let Some(entry_with_meta) = maybe_entry_with_meta {
+                    entry_result.push(entry_with_meta);
+                }
+                entry_result
+            }
             _ => GetEntryResult {
                 result: GetEntryResultType::Single(GetEntryResultItem::new(maybe_entry_with_meta)),
             },
@@ -158,6 +326,7 @@ impl GetEntryResult {
         match self.result {
             GetEntryResultType::Single(ref item) => item.meta.is_some(),
             GetEntryResultType::All(ref history) => !history.items.is_empty(),
+            GetEntryResultType::Count(ref revisions) => revisions.count > 0,
         }
     }
 
@@ -167,7 +336,8 @@ impl GetEntryResult {
             GetEntryResultType::Single(_) => {
                 self.result = GetEntryResultType::Single(GetEntryResultItem::new(None))
             }
-            GetEntryResultType::All(ref mut history) => history.items.clear(),
+            GetEntryResultType::All(ref mut history) => *history = EntryHistory::new(),
+            GetEntryResultType::Count(ref mut revisions) => *revisions = RevisionCount::new(),
         };
     }
 
@@ -179,12 +349,23 @@ impl GetEntryResult {
                     GetEntryResultType::Single(GetEntryResultItem::new(Some(entry_with_meta)))
             }
             GetEntryResultType::All(ref mut history) => history.push(entry_with_meta),
+            GetEntryResultType::Count(ref mut revisions) => revisions.push(entry_with_meta),
+        };
+    }
+
+    /// Same as `push()`, but gives a history item the given position in the crud chain
+    pub fn push_revision(&mut self, entry_with_meta: &EntryWithMeta, revision: usize) {
+        match self.result {
+            GetEntryResultType::All(ref mut history) => {
+                history.push_revision(entry_with_meta, revision)
+            }
+            _ => self.push(entry_with_meta),
         };
     }
 
     /// returns the entry searched for.  Note that if the GetEntryOptions did not
     /// include a request for the entry value, this function will return None even if the
-    /// entry was found.
+    /// entry was found.  A Count result never holds entries and always returns None.
     pub fn latest(&self) -> Option<Entry> {
         match self.result {
             GetEntryResultType::Single(ref item) => item.entry.clone(),
@@ -192,6 +373,7 @@ impl GetEntryResult {
                 let last = history.items.last()?;
                 last.entry.clone()
             }
+            GetEntryResultType::Count(_) => None,
         }
     }
 }
@@ -209,6 +391,37 @@ mod tests {
         assert!(!result.found());
         let result = GetEntryResult::new(StatusRequestKind::All, None);
         assert!(!result.found());
+        let result = GetEntryResult::new(StatusRequestKind::Count, None);
+        assert!(!result.found());
+    }
+
+    #[test]
+    fn test_get_entry_count() {
+        let mut result = GetEntryResult::new(StatusRequestKind::Count, None);
+        result.push(&EntryWithMeta {
+            entry: test_entry_a(),
+            crud_status: CrudStatus::Modified,
+            maybe_crud_link: Some(test_entry_b().address()),
+        });
+        result.push(&EntryWithMeta {
+            entry: test_entry_b(),
+            crud_status: CrudStatus::Live,
+            maybe_crud_link: None,
+        });
+        assert!(result.found());
+        assert_eq!(result.latest(), None);
+        match result.result {
+            GetEntryResultType::Count(ref revisions) => assert_eq!(
+                revisions,
+                &RevisionCount {
+                    count: 2,
+                    latest_address: Some(test_entry_b().address()),
+                }
+            ),
+            _ => panic!("expected a Count result"),
+        }
+        result.clear();
+        assert!(!result.found());
     }
 
     #[test]
@@ -19,16 +19,49 @@ impl Default for LinksStatusRequestKind {
     }
 }
 
+/// Order in which get_links() returns the links it found
+#[derive(Deserialize, Debug, Serialize, DefaultJson, Clone, PartialEq, Eq, Hash)]
+pub enum LinksSortOrder {
+    /// In the order the node holding the links returns them, which can differ between calls
+    Unsorted,
+    /// By the address of the link target
+    Address,
+    /// By the time the node holding the links added them, oldest first.
+    /// Links it added before it recorded that time come last.
+    Timestamp,
+}
+
+impl Default for LinksSortOrder {
+    fn default() -> Self {
+        LinksSortOrder::Unsorted
+    }
+}
+
+/// Structure used to specify what should be returned to a call to get_links().
+/// The default is to return all live links, unsorted.
+/// With `offset` and `limit`, the links can be fetched page by page, which only gives
+/// consistent pages when sorted.
 #[derive(Deserialize, Debug, Serialize, DefaultJson, Clone, PartialEq, Hash, Eq)]
 pub struct GetLinksOptions {
     pub status_request: LinksStatusRequestKind,
     pub sources: bool,
+    /// Number of links to skip, after sorting
+    #[serde(default)]
+    pub offset: usize,
+    /// Maximum number of links to return. Default is None, i.e. all.
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub sort_order: LinksSortOrder,
 }
 impl Default for GetLinksOptions {
     fn default() -> Self {
         GetLinksOptions {
             status_request: LinksStatusRequestKind::default(),
             sources: false,
+            offset: 0,
+            limit: None,
+            sort_order: LinksSortOrder::default(),
         }
     }
 }